@@ -319,6 +319,15 @@ pub(crate) fn expand_test_or_bench(
                                     field("compile_fail", cx.expr_bool(sp, false)),
                                     // no_run: true | false
                                     field("no_run", cx.expr_bool(sp, false)),
+                                    // timeout: Some(seconds) | None
+                                    field(
+                                        "timeout",
+                                        if let Some(secs) = test_timeout(cx, &item) {
+                                            cx.expr_some(sp, cx.expr_u32(sp, secs))
+                                        } else {
+                                            cx.expr_none(sp)
+                                        },
+                                    ),
                                     // should_panic: ...
                                     field(
                                         "should_panic",
@@ -512,6 +521,20 @@ fn should_panic(cx: &ExtCtxt<'_>, i: &ast::Item) -> ShouldPanic {
     }
 }
 
+fn test_timeout(cx: &ExtCtxt<'_>, i: &ast::Item) -> Option<u32> {
+    let attr = attr::find_by_name(&i.attrs, sym::test_timeout)?;
+    // Handle #[test_timeout = "seconds"]
+    let secs =
+        attr.value_str().and_then(|secs| secs.as_str().parse().ok()).filter(|&secs| secs > 0);
+    if secs.is_none() {
+        cx.dcx().span_err(
+            attr.span,
+            "`#[test_timeout]` must be a positive number of seconds, like `#[test_timeout = \"60\"]`",
+        );
+    }
+    secs
+}
+
 enum TestType {
     UnitTest,
     IntegrationTest,
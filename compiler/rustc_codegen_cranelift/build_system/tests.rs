@@ -9,10 +9,11 @@
 use crate::prepare::{apply_patches, GitRepo};
 use crate::rustc_info::get_default_sysroot;
 use crate::shared_utils::rustflags_from_env;
-use crate::utils::{spawn_and_wait, CargoProject, Compiler, LogGroup};
+use crate::utils::{spawn_and_wait, spawn_and_wait_with_output, CargoProject, Compiler, LogGroup};
 use crate::{CodegenBackend, SysrootKind};
 
 static BUILD_EXAMPLE_OUT_DIR: RelPath = RelPath::BUILD.join("example");
+static PROFILER_RT_OBJECTS_DIR: RelPath = RelPath::BUILD.join("profiler_rt");
 
 struct TestCase {
     config: &'static str,
@@ -108,6 +109,42 @@ const fn jit_bin(config: &'static str, source: &'static str, args: &'static str)
         ]);
         runner.run_out_command("gen_block_iterate", &[]);
     }),
+    TestCase::custom("aot.debuginfo", &|runner| {
+        runner.run_rustc(["example/debuginfo.rs"]);
+        runner.run_out_command("debuginfo", &[]);
+        runner.run_rustc(["example/debuginfo.rs", "-O", "--crate-name", "debuginfo_opt"]);
+        runner.run_out_command("debuginfo_opt", &[]);
+    }),
+    TestCase::custom("aot.coverage", &|runner| {
+        // The profiler runtime and the LLVM tools are taken from the bootstrap toolchain.
+        if !runner.is_native || !runner.target_compiler.triple.contains("linux") {
+            eprintln!("Only supported natively on Linux: Not running test");
+            return;
+        }
+        runner.build_profiler_builtins();
+
+        let out_dir = BUILD_EXAMPLE_OUT_DIR.to_path(&runner.dirs);
+        let profraw = out_dir.join("coverage.profraw");
+        let profdata = out_dir.join("coverage.profdata");
+        runner.run_rustc(["example/coverage.rs", "-Cinstrument-coverage"]);
+        let mut run_cmd = Command::new(out_dir.join("coverage"));
+        run_cmd.env("LLVM_PROFILE_FILE", &profraw);
+        spawn_and_wait(run_cmd);
+
+        let mut merge_cmd = Command::new(runner.llvm_tool("llvm-profdata"));
+        merge_cmd.arg("merge").arg("-o").arg(&profdata).arg(&profraw);
+        spawn_and_wait(merge_cmd);
+
+        let mut show_cmd = Command::new(runner.llvm_tool("llvm-cov"));
+        show_cmd
+            .arg("show")
+            .arg("--show-instantiations=false")
+            .arg("--instr-profile")
+            .arg(&profdata);
+        show_cmd.arg(out_dir.join("coverage"));
+        let report = spawn_and_wait_with_output(show_cmd);
+        check_coverage_report(&report);
+    }),
 ];
 
 pub(crate) static RAND_REPO: GitRepo = GitRepo::github(
@@ -243,6 +280,32 @@ const fn jit_bin(config: &'static str, source: &'static str, args: &'static str)
     }),
 ];
 
+/// Checks the execution counts of the lines of `example/coverage.rs` which end with a
+/// `// count: N` comment.
+fn check_coverage_report(report: &str) {
+    let mut checked_lines = 0;
+    for line in report.lines() {
+        // Lines of the report look like `    9|      8|        steps += 1; // count: 8`.
+        let mut columns = line.splitn(3, '|');
+        let (Some(_line_number), Some(count), Some(source)) =
+            (columns.next(), columns.next(), columns.next())
+        else {
+            continue;
+        };
+        let Some((_, expected_count)) = source.split_once("// count: ") else {
+            continue;
+        };
+        assert_eq!(
+            count.trim(),
+            expected_count.trim(),
+            "wrong execution count for `{}`",
+            source.trim(),
+        );
+        checked_lines += 1;
+    }
+    assert!(checked_lines > 0, "no execution counts found in coverage report:\n{report}");
+}
+
 pub(crate) fn run_tests(
     dirs: &Dirs,
     channel: &str,
@@ -254,8 +317,8 @@ pub(crate) fn run_tests(
     rustup_toolchain_name: Option<&str>,
     target_triple: String,
 ) {
-    let stdlib_source =
-        get_default_sysroot(&bootstrap_host_compiler.rustc).join("lib/rustlib/src/rust");
+    let bootstrap_sysroot = get_default_sysroot(&bootstrap_host_compiler.rustc);
+    let stdlib_source = bootstrap_sysroot.join("lib/rustlib/src/rust");
     assert!(stdlib_source.exists());
 
     if config::get_bool("testsuite.no_sysroot") && !skip_tests.contains(&"testsuite.no_sysroot") {
@@ -275,6 +338,7 @@ pub(crate) fn run_tests(
             use_unstable_features,
             skip_tests,
             bootstrap_host_compiler.triple == target_triple,
+            bootstrap_sysroot.clone(),
             stdlib_source.clone(),
         );
 
@@ -306,6 +370,7 @@ pub(crate) fn run_tests(
             use_unstable_features,
             skip_tests,
             bootstrap_host_compiler.triple == target_triple,
+            bootstrap_sysroot,
             stdlib_source,
         );
 
@@ -333,6 +398,7 @@ struct TestRunner<'a> {
     skip_tests: &'a [&'a str],
     dirs: Dirs,
     target_compiler: Compiler,
+    bootstrap_sysroot: PathBuf,
     stdlib_source: PathBuf,
 }
 
@@ -343,6 +409,7 @@ fn new(
         use_unstable_features: bool,
         skip_tests: &'a [&'a str],
         is_native: bool,
+        bootstrap_sysroot: PathBuf,
         stdlib_source: PathBuf,
     ) -> Self {
         target_compiler.rustflags.extend(rustflags_from_env("RUSTFLAGS"));
@@ -368,6 +435,7 @@ fn new(
             skip_tests,
             dirs,
             target_compiler,
+            bootstrap_sysroot,
             stdlib_source,
         }
     }
@@ -486,6 +554,59 @@ fn run_rustc<I, S>(&self, args: I)
         spawn_and_wait(self.rustc_command(args));
     }
 
+    fn llvm_tool(&self, name: &str) -> PathBuf {
+        let rustlib = self.bootstrap_sysroot.join("lib/rustlib").join(&self.target_compiler.triple);
+        rustlib.join("bin").join(name)
+    }
+
+    /// Builds `profiler_builtins` with cg_clif into the example output directory, where it is
+    /// found by `-Cinstrument-coverage`. The C part of it is taken from the `profiler_builtins` of
+    /// the bootstrap toolchain, as compiler-rt isn't part of the stdlib source.
+    fn build_profiler_builtins(&self) {
+        let rustlib_lib = self
+            .bootstrap_sysroot
+            .join("lib/rustlib")
+            .join(&self.target_compiler.triple)
+            .join("lib");
+        let bootstrap_profiler_builtins = fs::read_dir(&rustlib_lib)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .find(|path| {
+                let file_name = path.file_name().unwrap().to_str().unwrap();
+                file_name.starts_with("libprofiler_builtins-") && file_name.ends_with(".rlib")
+            })
+            .unwrap_or_else(|| panic!("no profiler_builtins in {}", rustlib_lib.display()));
+
+        PROFILER_RT_OBJECTS_DIR.ensure_fresh(&self.dirs);
+        let objects_dir = PROFILER_RT_OBJECTS_DIR.to_path(&self.dirs);
+        let mut extract_cmd = Command::new(self.llvm_tool("llvm-ar"));
+        extract_cmd.arg("x").arg(bootstrap_profiler_builtins).current_dir(&objects_dir);
+        spawn_and_wait(extract_cmd);
+
+        // Only keep the object files of compiler-rt, not the ones of the Rust code or the metadata.
+        let out_dir = BUILD_EXAMPLE_OUT_DIR.to_path(&self.dirs);
+        let mut archive_cmd = Command::new(self.llvm_tool("llvm-ar"));
+        archive_cmd.arg("rcs").arg(out_dir.join("libprofiler_rt.a"));
+        for entry in fs::read_dir(&objects_dir).unwrap() {
+            let path = entry.unwrap().path();
+            let file_name = path.file_name().unwrap().to_str().unwrap();
+            if file_name.ends_with(".o") && !file_name.starts_with("profiler_builtins-") {
+                archive_cmd.arg(path);
+            }
+        }
+        spawn_and_wait(archive_cmd);
+
+        self.run_rustc([
+            self.stdlib_source.join("library/profiler_builtins/src/lib.rs").as_os_str(),
+            OsStr::new("--crate-type=rlib"),
+            OsStr::new("--crate-name=profiler_builtins"),
+            OsStr::new("--edition=2021"),
+            OsStr::new("-lstatic=profiler_rt"),
+            OsStr::new("-L"),
+            format!("native={}", out_dir.display()).as_ref(),
+        ]);
+    }
+
     fn run_out_command(&self, name: &str, args: &[&str]) {
         let mut full_cmd = vec![];
 
@@ -2,7 +2,7 @@
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
-use std::process::{self, Command};
+use std::process::{self, Command, Stdio};
 use std::sync::atomic::{AtomicBool, Ordering};
 
 use crate::path::{Dirs, RelPath};
@@ -214,6 +214,17 @@ pub(crate) fn spawn_and_wait(mut cmd: Command) {
     }
 }
 
+/// Like [`spawn_and_wait`], but returns the stdout of the command.
+#[must_use]
+pub(crate) fn spawn_and_wait_with_output(mut cmd: Command) -> String {
+    let output = cmd.stderr(Stdio::inherit()).output().unwrap();
+    if !output.status.success() {
+        eprintln!("{cmd:?} exited with status {:?}", output.status);
+        process::exit(1);
+    }
+    String::from_utf8(output.stdout).unwrap()
+}
+
 // Based on the retry function in rust's src/ci/shared.sh
 #[track_caller]
 pub(crate) fn retry_spawn_and_wait(tries: u64, mut cmd: Command) {
@@ -0,0 +1,32 @@
+// Checked by the `aot.coverage` test, which compares the execution counts reported by `llvm-cov`
+// with the ones given in a comment at the end of some lines.
+
+fn collatz_steps(mut n: u64) -> u32 {
+    let mut steps = 0; // count: 1
+    while n != 1 {
+        if n % 2 == 0 {
+            n /= 2; // count: 6
+        } else {
+            n = 3 * n + 1; // count: 2
+        }
+        steps += 1; // count: 8
+    }
+    steps
+}
+
+fn generic<T: Default>() -> T {
+    T::default() // count: 2
+}
+
+fn never_called() {
+    println!("unreachable"); // count: 0
+}
+
+fn main() {
+    assert_eq!(collatz_steps(6), 8);
+    assert_eq!(generic::<u8>(), 0);
+    assert_eq!(generic::<u64>(), 0);
+    if std::env::args().count() > 100 {
+        never_called(); // count: 0
+    }
+}
@@ -0,0 +1,247 @@
+// Checks the locations of variables in the debuginfo of this executable, as read back by gimli.
+// Variables are used after a call, so that they are still alive once the call returns.
+
+#![feature(rustc_private)]
+
+extern crate gimli;
+extern crate object;
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hint::black_box;
+use std::num::NonZeroU32;
+
+use gimli::{Operation, RunTimeEndian};
+use object::{Object, ObjectSection};
+
+struct Point {
+    x: u16,
+    y: u64,
+}
+
+enum Shape {
+    Circle(u32),
+    Rect { w: u32, h: u32 },
+}
+
+#[inline(never)]
+fn adt(point: Point, shape: Shape) -> u64 {
+    black_box(());
+    let area = match shape {
+        Shape::Circle(r) => r * r * 3,
+        Shape::Rect { w, h } => w * h,
+    };
+    let local_point = Point { x: point.x + 1, y: black_box(2) };
+    black_box(());
+    black_box(point.y + area as u64 + local_point.x as u64 + local_point.y)
+}
+
+#[inline(never)]
+fn niche(niche_opt: Option<NonZeroU32>, niche_ref: Option<&u8>) -> u32 {
+    black_box(());
+    niche_opt.map_or(0, |n| n.get()) + niche_ref.map_or(0, |&r| r as u32)
+}
+
+#[inline(never)]
+fn slice(slice_arg: &[u8]) -> usize {
+    black_box(());
+    black_box(slice_arg).len()
+}
+
+#[inline(never)]
+fn trait_object(dyn_arg: &dyn Debug) -> String {
+    let dyn_local: &dyn Debug = &black_box(8u8);
+    black_box(());
+    format!("{:?} {:?}", black_box(dyn_arg), black_box(dyn_local))
+}
+
+#[inline(never)]
+fn closure(captured: u32) -> u32 {
+    let add_captured = |x: u32| {
+        black_box(());
+        x + captured
+    };
+    black_box(add_captured(1))
+}
+
+fn main() {
+    assert_eq!(adt(Point { x: 1, y: 2 }, Shape::Circle(1)), 9);
+    assert_eq!(adt(Point { x: 1, y: 2 }, Shape::Rect { w: 3, h: 4 }), 18);
+    assert_eq!(niche(NonZeroU32::new(5), Some(&6)), 11);
+    assert_eq!(slice(&[1, 2, 3]), 3);
+    assert_eq!(trait_object(&7), "7 8");
+    assert_eq!(closure(8), 9);
+
+    // Only ELF executables contain the debuginfo themselves.
+    if cfg!(target_os = "linux") {
+        check_debuginfo();
+    }
+}
+
+struct Variable {
+    type_name: String,
+    /// The location expression of each range in which the variable is known.
+    locations: Vec<Vec<Operation<gimli::EndianSlice<'static, RunTimeEndian>>>>,
+}
+
+fn check_debuginfo() {
+    let variables = read_variables();
+    let variable = |function: &str, name: &str| {
+        let Some(variable) = variables.get(&(function.to_owned(), name.to_owned())) else {
+            panic!("no debuginfo for `{}` in `{}`", name, function);
+        };
+        assert!(
+            variable.locations.iter().any(|ops| !ops.is_empty()),
+            "no location for `{}` in `{}`",
+            name,
+            function,
+        );
+        variable
+    };
+    let pointer_size = std::mem::size_of::<usize>() as u64;
+    let first_location = |variable: &Variable| {
+        variable.locations.iter().find(|ops| !ops.is_empty()).unwrap().clone()
+    };
+    let piece_sizes = |variable: &Variable| {
+        first_location(variable)
+            .iter()
+            .filter_map(|op| match op {
+                Operation::Piece { size_in_bits, bit_offset: None } => Some(size_in_bits / 8),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+    let is_deref = |variable: &Variable| {
+        first_location(variable).iter().any(|op| matches!(op, Operation::Deref { .. }))
+    };
+
+    // An ADT passed by reference, whose location is behind the pointer passed as argument.
+    let shape = variable("adt", "shape");
+    assert_eq!(shape.type_name, "Shape");
+    assert!(is_deref(shape), "{:?}", shape.locations);
+
+    assert_eq!(variable("adt", "point").type_name, "Point");
+    assert_eq!(variable("niche", "niche_ref").type_name, "Option<&u8>");
+    assert_eq!(
+        variable("niche", "niche_opt").type_name,
+        "Option<core::num::nonzero::NonZero<u32>>",
+    );
+
+    if cfg!(debug_assertions) {
+        // Captured variables are behind the pointer to the closure environment. With
+        // optimizations, the closure is inlined.
+        assert!(variable("closure", "add_captured").type_name.contains("{closure_env#0}"));
+        let captured = variable("{closure#0}", "captured");
+        assert!(is_deref(captured), "{:?}", captured.locations);
+
+        // Wide pointers, which are in a pair of registers.
+        let slice_arg = variable("slice", "slice_arg");
+        assert_eq!(slice_arg.type_name, "&[u8]");
+        assert_eq!(piece_sizes(slice_arg), [pointer_size, pointer_size]);
+
+        let dyn_arg = variable("trait_object", "dyn_arg");
+        assert_eq!(dyn_arg.type_name, "&dyn core::fmt::Debug");
+        assert_eq!(piece_sizes(dyn_arg), [pointer_size, pointer_size]);
+        assert_eq!(variable("trait_object", "dyn_local").type_name, "&dyn core::fmt::Debug");
+
+        // The vtables created by unsizing, which tell debuggers the concrete type behind a trait
+        // object.
+        assert_eq!(
+            variable("", "<u8 as core::fmt::Debug>::{vtable}").type_name,
+            "<u8 as core::fmt::Debug>::{vtable_type}",
+        );
+        assert_eq!(
+            variable("", "<i32 as core::fmt::Debug>::{vtable}").type_name,
+            "<i32 as core::fmt::Debug>::{vtable_type}",
+        );
+    } else {
+        // Split into one local per field by the MIR optimizations.
+        let local_point = variable("adt", "local_point");
+        assert_eq!(piece_sizes(local_point).last(), Some(&2), "{:?}", local_point.locations);
+    }
+}
+
+/// Returns the variables of the functions of this crate by function and variable name. Global
+/// variables have an empty function name.
+fn read_variables() -> HashMap<(String, String), Variable> {
+    let data = std::fs::read(std::env::current_exe().unwrap()).unwrap();
+    let data: &'static [u8] = Box::leak(data.into_boxed_slice());
+    let file = object::File::parse(data).unwrap();
+    let endian = if file.is_little_endian() { RunTimeEndian::Little } else { RunTimeEndian::Big };
+    let dwarf = gimli::Dwarf::load(|id| -> Result<_, gimli::Error> {
+        let data = file.section_by_name(id.name()).and_then(|section| section.data().ok());
+        Ok(gimli::EndianSlice::new(data.unwrap_or(&[]), endian))
+    })
+    .unwrap();
+
+    let mut variables = HashMap::new();
+    let mut units = dwarf.units();
+    while let Some(header) = units.next().unwrap() {
+        let unit = dwarf.unit(header).unwrap();
+        let name = |entry: &gimli::DebuggingInformationEntry<'_, '_, _>| {
+            let name = entry.attr_value(gimli::DW_AT_name).unwrap()?;
+            Some(dwarf.attr_string(&unit, name).unwrap().to_string_lossy().into_owned())
+        };
+
+        let unit_root = unit.entries().next_dfs().unwrap().unwrap().1.clone();
+        if !name(&unit_root).is_some_and(|name| name.contains("debuginfo.rs/@/")) {
+            continue;
+        }
+
+        // The functions containing the current entry, with their depth.
+        let mut functions: Vec<(isize, String)> = vec![];
+        let mut depth = 0;
+        let mut entries = unit.entries();
+        while let Some((delta_depth, entry)) = entries.next_dfs().unwrap() {
+            depth += delta_depth;
+            while functions.last().is_some_and(|&(function_depth, _)| function_depth >= depth) {
+                functions.pop();
+            }
+            match entry.tag() {
+                gimli::DW_TAG_subprogram => {
+                    functions.push((depth, name(entry).unwrap_or_default()));
+                }
+                gimli::DW_TAG_variable | gimli::DW_TAG_formal_parameter => {
+                    let function = functions.last().map_or("", |(_, function)| function);
+                    let Some(gimli::AttributeValue::UnitRef(type_offset)) =
+                        entry.attr_value(gimli::DW_AT_type).unwrap()
+                    else {
+                        continue;
+                    };
+                    let type_name = name(&unit.entry(type_offset).unwrap()).unwrap_or_default();
+
+                    let mut expressions = vec![];
+                    match entry.attr_value(gimli::DW_AT_location).unwrap() {
+                        Some(gimli::AttributeValue::Exprloc(expr)) => expressions.push(expr),
+                        Some(location) => {
+                            let mut locations =
+                                dwarf.attr_locations(&unit, location).unwrap().unwrap();
+                            while let Some(location) = locations.next().unwrap() {
+                                expressions.push(location.data);
+                            }
+                        }
+                        None => {}
+                    }
+                    let locations = expressions
+                        .into_iter()
+                        .map(|expr| {
+                            let mut ops = vec![];
+                            let mut operations = expr.operations(unit.encoding());
+                            while let Some(op) = operations.next().unwrap() {
+                                ops.push(op);
+                            }
+                            ops
+                        })
+                        .collect();
+
+                    variables.insert(
+                        (function.to_owned(), name(entry).unwrap()),
+                        Variable { type_name, locations },
+                    );
+                }
+                _ => {}
+            }
+        }
+    }
+    variables
+}
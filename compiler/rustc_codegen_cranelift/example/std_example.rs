@@ -1,9 +1,11 @@
 #![feature(
+    asm_goto,
     core_intrinsics,
     coroutines,
     stmt_expr_attributes,
     coroutine_trait,
     is_sorted,
+    linkage,
     repr_simd,
     tuple_trait,
     unboxed_closures
@@ -124,6 +126,9 @@ fn main() {
         test_simd();
     }
 
+    #[cfg(all(not(jit), target_arch = "x86_64", target_os = "linux"))]
+    test_asm_sym_and_label();
+
     Box::pin(
         #[coroutine]
         move |mut _task_context| {
@@ -468,6 +473,44 @@ fn test_checked_mul() {
     assert_eq!(i64::MIN.checked_mul(i64::MIN), None);
 }
 
+#[cfg(all(not(jit), target_arch = "x86_64", target_os = "linux"))]
+fn test_asm_sym_and_label() {
+    use std::arch::asm;
+
+    // Neither of these is exported, so they are local to the object file of this codegen unit.
+    extern "C" fn asm_sym_fn() -> u64 {
+        42
+    }
+    static mut ASM_SYM_STATIC: u64 = 0;
+
+    let ret: u64;
+    unsafe {
+        asm!("call {}", sym asm_sym_fn, out("rax") ret, clobber_abi("C"));
+        asm!("mov qword ptr [rip + {}], 1", sym ASM_SYM_STATIC);
+    }
+    assert_eq!(ret, 42);
+    assert_eq!(unsafe { ASM_SYM_STATIC }, 1);
+
+    // With internal linkage the static is local to the codegen unit. It is defined after this
+    // function, so the `sym` operand is codegened before the static.
+    let value: u64;
+    unsafe {
+        asm!("mov {}, qword ptr [rip + {}]", out(reg) value, sym ASM_SYM_INTERNAL_STATIC);
+    }
+    assert_eq!(value, 43);
+
+    let mut jumped = false;
+    unsafe {
+        asm!("jmp {}", label { jumped = true; });
+        asm!("/* {} */", label { unreachable!(); });
+    }
+    assert!(jumped);
+}
+
+#[cfg(all(not(jit), target_arch = "x86_64", target_os = "linux"))]
+#[linkage = "internal"]
+static ASM_SYM_INTERNAL_STATIC: u64 = 43;
+
 #[derive(PartialEq)]
 enum LoopState {
     Continue(()),
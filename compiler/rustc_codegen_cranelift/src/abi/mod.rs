@@ -288,6 +288,16 @@ enum ArgKind<'tcx> {
         // not mutated by the current function, this is necessary to support unsized arguments.
         if let ArgKind::Normal(Some(val)) = arg_kind {
             if let Some((addr, meta)) = val.try_to_ptr() {
+                // Store the address of the argument, so that a debugger can find it for the whole
+                // function.
+                if fx.func_debug_cx.is_some() && meta.is_none() {
+                    let pointer_size = fx.pointer_type.bytes();
+                    let address_slot = fx.create_stack_slot(pointer_size, pointer_size);
+                    let address = addr.get_addr(fx);
+                    address_slot.store(fx, address, MemFlags::trusted());
+                    fx.func_debug_cx.as_mut().unwrap().define_indirect_arg(local, address_slot);
+                }
+
                 // Ownership of the value at the backing storage for an argument is passed to the
                 // callee per the ABI, so it is fine to borrow the backing storage of this argument
                 // to prevent a copy.
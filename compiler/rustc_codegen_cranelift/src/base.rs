@@ -13,7 +13,7 @@
 use rustc_monomorphize::is_call_from_compiler_builtins_to_upstream_monomorphization;
 
 use crate::constant::ConstantCx;
-use crate::debuginfo::{FunctionDebugContext, TypeDebugContext};
+use crate::debuginfo::{FunctionDebugContext, TypeDebugContext, VariableLocation};
 use crate::prelude::*;
 use crate::pretty_clif::CommentWriter;
 
@@ -88,6 +88,7 @@ pub(crate) fn codegen_fn<'tcx>(
         pointer_type,
         constants_cx: ConstantCx::new(),
         func_debug_cx,
+        vtables: FxIndexMap::default(),
 
         instance,
         symbol_name,
@@ -104,6 +105,13 @@ pub(crate) fn codegen_fn<'tcx>(
     };
 
     tcx.prof.generic_activity("codegen clif ir").run(|| codegen_fn_body(&mut fx, start_block));
+    if fx.func_debug_cx.is_some() {
+        define_locals_debuginfo(&mut fx, type_dbg);
+        let debug_context = fx.cx.debug_context.as_mut().unwrap();
+        for (&data_id, &(ty, trait_ref)) in &fx.vtables {
+            debug_context.define_vtable(tcx, type_dbg, ty, trait_ref, data_id);
+        }
+    }
     fx.bcx.seal_all_blocks();
     fx.bcx.finalize();
 
@@ -131,6 +139,136 @@ pub(crate) fn codegen_fn<'tcx>(
     CodegenedFunction { symbol_name, func_id, func, clif_comments, func_debug_cx }
 }
 
+fn define_locals_debuginfo<'tcx>(
+    fx: &mut FunctionCx<'_, '_, 'tcx>,
+    type_dbg: &mut TypeDebugContext<'tcx>,
+) {
+    let mut variables: Vec<(&'tcx VarDebugInfo<'tcx>, Ty<'tcx>, VariableLocation)> = vec![];
+    for var in &fx.mir.var_debug_info {
+        // FIXME support variables that are a constant
+        let VarDebugInfoContents::Place(place) = var.value else { continue };
+        let Some((layout, location)) = debuginfo_place_location(fx, place) else { continue };
+
+        let Some(fragment) = &var.composite else {
+            variables.push((var, layout.ty, location));
+            continue;
+        };
+
+        // The variable was split into several locals by SROA. The fragments of a variable
+        // are consecutive.
+        let ty = fx.monomorphize(fragment.ty);
+        let mut fragment_layout = fx.layout_of(ty);
+        let mut offset = Size::ZERO;
+        for elem in &fragment.projection {
+            let PlaceElem::Field(field, _) = *elem else {
+                bug!("unexpected projection in debuginfo fragment: {elem:?}")
+            };
+            offset += fragment_layout.fields.offset(field.index());
+            fragment_layout = fragment_layout.field(fx, field.index());
+        }
+        let pieces = match variables.last_mut() {
+            Some((prev_var, prev_ty, VariableLocation::Pieces(pieces)))
+                if prev_var.composite.is_some()
+                    && prev_var.name == var.name
+                    && prev_var.source_info.scope == var.source_info.scope
+                    && *prev_ty == ty =>
+            {
+                pieces
+            }
+            _ => {
+                variables.push((var, ty, VariableLocation::Pieces(vec![])));
+                let Some((_, _, VariableLocation::Pieces(pieces))) = variables.last_mut() else {
+                    unreachable!()
+                };
+                pieces
+            }
+        };
+        match location {
+            VariableLocation::Pieces(inner_pieces) => pieces.extend(
+                inner_pieces
+                    .into_iter()
+                    .map(|(inner_offset, size, location)| (offset + inner_offset, size, location)),
+            ),
+            location => pieces.push((offset, layout.size, location)),
+        }
+    }
+
+    let func_debug_cx = fx.func_debug_cx.as_mut().unwrap();
+    let debug_context = fx.cx.debug_context.as_mut().unwrap();
+    for (var, ty, mut location) in variables {
+        if let VariableLocation::Pieces(pieces) = &mut location {
+            pieces.sort_by_key(|&(offset, _, _)| offset);
+        }
+        func_debug_cx.define_local(debug_context, fx.tcx, type_dbg, fx.mir.span, var, ty, location);
+    }
+}
+
+/// Returns the layout of a place used by the debuginfo of a variable and where a debugger can
+/// find its value, if that can be described.
+fn debuginfo_place_location<'tcx>(
+    fx: &FunctionCx<'_, '_, 'tcx>,
+    place: Place<'tcx>,
+) -> Option<(TyAndLayout<'tcx>, VariableLocation)> {
+    // There are no locals if the arguments of the function are uninhabited.
+    let local_place = *fx.local_map.get(place.local)?;
+    let mut layout = local_place.layout();
+    let mut location = match local_place.debuginfo_location(fx) {
+        Some(location) => location,
+        None => fx.func_debug_cx.as_ref().unwrap().indirect_arg_location(place.local)?,
+    };
+    for elem in place.projection {
+        match elem {
+            PlaceElem::Field(field, _) => {
+                let field_layout = layout.field(fx, field.index());
+                let offset = layout.fields.offset(field.index());
+                location = part_location(location, layout.size, offset, field_layout.size)?;
+                layout = field_layout;
+            }
+            PlaceElem::Downcast(_, variant) => layout = layout.for_variant(fx, variant),
+            PlaceElem::Deref => {
+                let pointee_layout = fx.layout_of(layout.ty.builtin_deref(true)?.ty);
+                if pointee_layout.is_unsized() {
+                    return None;
+                }
+                // The data pointer of a wide pointer comes first.
+                let pointer_size = fx.tcx.data_layout.pointer_size;
+                let pointer = part_location(location, layout.size, Size::ZERO, pointer_size)?;
+                location = VariableLocation::Deref(Box::new(pointer), 0);
+                layout = pointee_layout;
+            }
+            _ => return None,
+        }
+    }
+    Some((layout, location))
+}
+
+/// Returns the location of the `size` bytes at `offset` of a value of `whole_size` bytes.
+fn part_location(
+    location: VariableLocation,
+    whole_size: Size,
+    offset: Size,
+    size: Size,
+) -> Option<VariableLocation> {
+    if offset == Size::ZERO && size == whole_size {
+        return Some(location);
+    }
+    match location {
+        VariableLocation::Var(_) => None,
+        VariableLocation::Stack(stack_slot, stack_offset) => Some(VariableLocation::Stack(
+            stack_slot,
+            stack_offset + i32::try_from(offset.bytes()).unwrap(),
+        )),
+        VariableLocation::Deref(pointer, deref_offset) => Some(VariableLocation::Deref(
+            pointer,
+            deref_offset + i64::try_from(offset.bytes()).unwrap(),
+        )),
+        VariableLocation::Pieces(pieces) => pieces
+            .into_iter()
+            .find(|&(piece_offset, piece_size, _)| piece_offset == offset && piece_size == size)
+            .map(|(_, _, location)| location),
+    }
+}
+
 pub(crate) fn compile_fn(
     cx: &mut crate::CodegenCx,
     cached_context: &mut Context,
@@ -225,6 +363,7 @@ pub(crate) fn compile_fn(
                 debug_context,
                 codegened_func.func_id,
                 context,
+                isa,
             );
         }
         unwind_context.add_function(codegened_func.func_id, &context, isa);
@@ -476,25 +615,13 @@ fn codegen_fn_body(fx: &mut FunctionCx<'_, '_, '_>, start_block: Block) {
                     );
                 }
 
-                let have_labels = if options.contains(InlineAsmOptions::NORETURN) {
-                    !targets.is_empty()
-                } else {
-                    targets.len() > 1
-                };
-                if have_labels {
-                    fx.tcx.dcx().span_fatal(
-                        source_info.span,
-                        "cranelift doesn't support labels in inline assembly.",
-                    );
-                }
-
                 crate::inline_asm::codegen_inline_asm_terminator(
                     fx,
                     source_info.span,
                     template,
                     operands,
                     *options,
-                    targets.get(0).copied(),
+                    targets,
                 );
             }
             TerminatorKind::UnwindTerminate(reason) => {
@@ -871,7 +998,9 @@ fn is_fat_ptr<'tcx>(fx: &FunctionCx<'_, '_, 'tcx>, ty: Ty<'tcx>) -> bool {
         | StatementKind::PlaceMention(..)
         | StatementKind::AscribeUserType(..) => {}
 
-        StatementKind::Coverage { .. } => fx.tcx.dcx().fatal("-Zcoverage is unimplemented"),
+        StatementKind::Coverage(kind) => {
+            crate::coverage::codegen_coverage(fx, kind, stmt.source_info.scope)
+        }
         StatementKind::Intrinsic(ref intrinsic) => match &**intrinsic {
             // We ignore `assume` intrinsics, they are only useful for optimizations
             NonDivergingIntrinsic::Assume(_) => {}
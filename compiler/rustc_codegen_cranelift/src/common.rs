@@ -1,5 +1,6 @@
 use cranelift_codegen::isa::TargetFrontendConfig;
 use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext};
+use cranelift_module::DataId;
 use rustc_index::IndexVec;
 use rustc_middle::ty::layout::{
     self, FnAbiError, FnAbiOfHelpers, FnAbiRequest, LayoutError, LayoutOfHelpers,
@@ -289,6 +290,9 @@ pub(crate) struct FunctionCx<'m, 'clif, 'tcx: 'm> {
     pub(crate) pointer_type: Type,                  // Cached from module
     pub(crate) constants_cx: ConstantCx,
     pub(crate) func_debug_cx: Option<FunctionDebugContext>,
+    /// The vtables used by this function, which are described in the debuginfo once the function
+    /// has been codegened.
+    pub(crate) vtables: FxIndexMap<DataId, (Ty<'tcx>, Option<ty::PolyExistentialTraitRef<'tcx>>)>,
 
     pub(crate) instance: Instance<'tcx>,
     pub(crate) symbol_name: String,
@@ -43,6 +43,15 @@ pub(crate) fn codegen_static(tcx: TyCtxt<'_>, module: &mut dyn Module, def_id: D
     )
 }
 
+/// Declares a static of the current codegen unit with the linkage of its definition, so that
+/// references to it which are codegened before the static itself, like `sym` operands of inline
+/// assembly, know whether it is local to the codegen unit.
+pub(crate) fn predefine_static(tcx: TyCtxt<'_>, module: &mut dyn Module, def_id: DefId) {
+    // Whether the static is writable is only known once its initializer has been evaluated. The
+    // definition updates the declaration accordingly.
+    data_id_for_static(tcx, module, def_id, true, false);
+}
+
 pub(crate) fn codegen_tls_ref<'tcx>(
     fx: &mut FunctionCx<'_, '_, 'tcx>,
     def_id: DefId,
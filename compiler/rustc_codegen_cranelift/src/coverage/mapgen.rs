@@ -0,0 +1,441 @@
+//! Encoding of the profile data and coverage mapping sections.
+//!
+//! This follows what `InstrProfiling.cpp` and `CoverageMappingWriter.cpp` produce for the LLVM
+//! version cg_llvm is built against:
+//!
+//! * Raw profile version 9 (see `InstrProfData.inc`) for `__llvm_prf_data` and `__llvm_prf_names`.
+//! * [Coverage mapping format][format] version 7 (encoded as 6) for `__llvm_covmap` and
+//!   `__llvm_covfun`.
+//!
+//! Unlike LLVM we never compress the filenames and function names and don't remove unused
+//! counter expressions. Both are optional.
+//!
+//! [format]: https://github.com/rust-lang/llvm-project/blob/rustc/18.0-2024-02-13/llvm/docs/CoverageMappingFormat.rst
+
+use cranelift_module::DataId;
+use gimli::write::{EndianVec, Writer};
+use gimli::RunTimeEndian;
+use rustc_data_structures::fx::{FxHashSet, FxIndexSet};
+use rustc_hir::def_id::LocalDefId;
+use rustc_middle::mir::coverage::{
+    CovTerm, Expression, ExpressionId, FunctionCoverageInfo, MappingKind, Op,
+};
+use rustc_middle::mir::mono::MonoItem;
+use rustc_session::config::RemapPathScopeComponents;
+use rustc_session::RemapFileNameExt;
+use rustc_span::Symbol;
+
+use super::FunctionCoverage;
+use crate::prelude::*;
+
+/// `CovMapVersion::Version7`, encoded as n-1.
+const COVMAP_VERSION: u32 = 6;
+
+/// Offset of the `CounterPtr` field in a `__llvm_prf_data` record.
+pub(super) const PRF_DATA_COUNTER_PTR_OFFSET: u32 = 16;
+
+/// Size of a `__llvm_prf_data` record on a 64bit target, including the trailing padding.
+const PRF_DATA_RECORD_SIZE: usize = 64;
+
+/// Separator between the function names in `__llvm_prf_names`.
+const PRF_NAME_SEPARATOR: &str = "\x01";
+
+pub(super) struct CoverageSections {
+    pub(super) prf_data: Vec<u8>,
+    /// The offset of every `CounterPtr` field in `prf_data` together with the counters it has to
+    /// point to.
+    pub(super) prf_data_relocs: Vec<(u64, DataId)>,
+    pub(super) prf_names: Vec<u8>,
+    pub(super) covmap: Vec<u8>,
+    pub(super) covfun: Vec<u8>,
+}
+
+pub(super) fn generate(
+    tcx: TyCtxt<'_>,
+    endian: RunTimeEndian,
+    functions: &FxIndexMap<String, FunctionCoverage>,
+) -> CoverageSections {
+    let mut file_names = functions
+        .values()
+        .flat_map(|function| function.info.mappings.iter())
+        .map(|mapping| mapping.code_region.file_name)
+        .collect::<FxIndexSet<Symbol>>();
+    // Sort the file table by its actual string values, not the arbitrary ordering of its symbols.
+    file_names.sort_unstable_by(|a, b| a.as_str().cmp(b.as_str()));
+
+    let filenames = encode_filenames(tcx, &file_names);
+    let filenames_ref = md5_hash(&filenames);
+
+    let mut covmap = EndianVec::new(endian);
+    covmap.write_u32(0).unwrap(); // Number of records, always zero since version 4
+    covmap.write_u32(filenames.len().try_into().unwrap()).unwrap();
+    covmap.write_u32(0).unwrap(); // Coverage size, always zero since version 4
+    covmap.write_u32(COVMAP_VERSION).unwrap();
+    covmap.write(&filenames).unwrap();
+    align_to_8(&mut covmap);
+
+    let mut covfun = EndianVec::new(endian);
+    let mut prf_data = EndianVec::new(endian);
+    let mut prf_data_relocs = vec![];
+    let mut names = vec![];
+
+    for (name, function) in functions {
+        let mapping = encode_mapping(&file_names, function);
+        if mapping.is_empty() {
+            assert!(!function.is_used, "a used function should have coverage mappings: {name}");
+            continue;
+        }
+
+        let name_ref = md5_hash(name.as_bytes());
+        let source_hash = if function.is_used { function.info.function_source_hash } else { 0 };
+
+        covfun.write_u64(name_ref).unwrap();
+        covfun.write_u32(mapping.len().try_into().unwrap()).unwrap();
+        covfun.write_u64(source_hash).unwrap();
+        covfun.write_u64(filenames_ref).unwrap();
+        covfun.write(&mapping).unwrap();
+        align_to_8(&mut covfun);
+
+        if !function.is_used {
+            // Unused functions only need their name to be present in the profile.
+            names.push(&**name);
+            continue;
+        }
+
+        let Some((counters, num_counters)) = function.counters else {
+            // All counter increments of this function were optimized out.
+            continue;
+        };
+        names.push(&**name);
+
+        let record_start = prf_data.len();
+        prf_data.write_u64(name_ref).unwrap();
+        prf_data.write_u64(source_hash).unwrap();
+        assert_eq!(prf_data.len() - record_start, PRF_DATA_COUNTER_PTR_OFFSET as usize);
+        prf_data_relocs.push((prf_data.len() as u64, counters));
+        prf_data.write_u64(0).unwrap(); // CounterPtr, filled in by a relocation
+        prf_data.write_u64(0).unwrap(); // BitmapPtr, only used by MC/DC
+        prf_data.write_u64(0).unwrap(); // FunctionPointer, only used by value profiling
+        prf_data.write_u64(0).unwrap(); // Values, only used by value profiling
+        prf_data.write_u32(num_counters).unwrap();
+        prf_data.write_u16(0).unwrap(); // NumValueSites[IPVK_IndirectCallTarget]
+        prf_data.write_u16(0).unwrap(); // NumValueSites[IPVK_MemOPSize]
+        prf_data.write_u32(0).unwrap(); // NumBitmapBytes
+        align_to_8(&mut prf_data);
+        assert_eq!(prf_data.len() - record_start, PRF_DATA_RECORD_SIZE);
+    }
+
+    let mut prf_names = vec![];
+    if !names.is_empty() {
+        let names = names.join(PRF_NAME_SEPARATOR);
+        gimli::leb128::write::unsigned(&mut prf_names, names.len() as u64).unwrap();
+        gimli::leb128::write::unsigned(&mut prf_names, 0).unwrap(); // Not compressed
+        prf_names.extend_from_slice(names.as_bytes());
+    }
+
+    CoverageSections {
+        prf_data: prf_data.into_vec(),
+        prf_data_relocs,
+        prf_names,
+        covmap: covmap.into_vec(),
+        covfun: covfun.into_vec(),
+    }
+}
+
+fn align_to_8(w: &mut EndianVec<RunTimeEndian>) {
+    while w.len() % 8 != 0 {
+        w.write_u8(0).unwrap();
+    }
+}
+
+fn encode_filenames(tcx: TyCtxt<'_>, file_names: &FxIndexSet<Symbol>) -> Vec<u8> {
+    // The first filename must be the compilation directory. The other filenames are relative to
+    // it.
+    let working_dir = tcx
+        .sess
+        .opts
+        .working_dir
+        .for_scope(tcx.sess, RemapPathScopeComponents::MACRO)
+        .to_string_lossy()
+        .into_owned();
+
+    let mut filenames = vec![];
+    for file_name in std::iter::once(&*working_dir).chain(file_names.iter().map(Symbol::as_str)) {
+        gimli::leb128::write::unsigned(&mut filenames, file_name.len() as u64).unwrap();
+        filenames.extend_from_slice(file_name.as_bytes());
+    }
+
+    let mut buf = vec![];
+    gimli::leb128::write::unsigned(&mut buf, file_names.len() as u64 + 1).unwrap();
+    gimli::leb128::write::unsigned(&mut buf, filenames.len() as u64).unwrap();
+    gimli::leb128::write::unsigned(&mut buf, 0).unwrap(); // Not compressed
+    buf.extend_from_slice(&filenames);
+    buf
+}
+
+/// Encodes the file mapping, counter expressions and mapping regions of a function. Returns an
+/// empty buffer if the function doesn't have any mappings.
+fn encode_mapping(file_names: &FxIndexSet<Symbol>, function: &FunctionCoverage) -> Vec<u8> {
+    let mut buf = vec![];
+    if function.info.mappings.is_empty() {
+        return buf;
+    }
+
+    let zero_expressions = zero_expressions(function);
+    let is_zero_term = |term: CovTerm| match term {
+        CovTerm::Zero => true,
+        CovTerm::Counter(id) => !function.counters_seen.contains(id),
+        CovTerm::Expression(id) => zero_expressions.contains(&id),
+    };
+    let encode_term = |buf: &mut Vec<u8>, term: CovTerm| {
+        // The two lowest bits are the counter kind. For expressions the kind also says whether
+        // it is a subtraction or an addition.
+        let encoded = match term {
+            _ if is_zero_term(term) => 0,
+            CovTerm::Zero => unreachable!(),
+            CovTerm::Counter(id) => 1 | id.as_u32() << 2,
+            CovTerm::Expression(id) => match function.info.expressions[id].op {
+                Op::Subtract => 2 | id.as_u32() << 2,
+                Op::Add => 3 | id.as_u32() << 2,
+            },
+        };
+        gimli::leb128::write::unsigned(buf, encoded.into()).unwrap();
+    };
+
+    // Map the filenames of this function to local file ids in order of first use. Regions must be
+    // sorted by their local file id and then by their start location.
+    let mut virtual_file_mapping = FxIndexSet::default();
+    let mut regions = function
+        .info
+        .mappings
+        .iter()
+        .map(|mapping| {
+            let (local_file_id, _) = virtual_file_mapping.insert_full(
+                // The working dir has global file id 0.
+                file_names.get_index_of(&mapping.code_region.file_name).unwrap() + 1,
+            );
+            (local_file_id, mapping)
+        })
+        .collect::<Vec<_>>();
+    regions.sort_by_key(|&(local_file_id, mapping)| {
+        let region = &mapping.code_region;
+        (local_file_id, region.start_line, region.start_col)
+    });
+
+    gimli::leb128::write::unsigned(&mut buf, virtual_file_mapping.len() as u64).unwrap();
+    for &global_file_id in &virtual_file_mapping {
+        gimli::leb128::write::unsigned(&mut buf, global_file_id as u64).unwrap();
+    }
+
+    gimli::leb128::write::unsigned(&mut buf, function.info.expressions.len() as u64).unwrap();
+    for &Expression { lhs, op: _, rhs } in &function.info.expressions {
+        encode_term(&mut buf, lhs);
+        encode_term(&mut buf, rhs);
+    }
+
+    for (i, &(local_file_id, mapping)) in regions.iter().enumerate() {
+        if i == 0 || regions[i - 1].0 != local_file_id {
+            let count = regions[i..].iter().take_while(|&&(id, _)| id == local_file_id).count();
+            gimli::leb128::write::unsigned(&mut buf, count as u64).unwrap();
+        }
+        let prev_start_line = match regions[..i].last() {
+            Some(&(prev_file_id, prev)) if prev_file_id == local_file_id => {
+                prev.code_region.start_line
+            }
+            _ => 0,
+        };
+
+        match mapping.kind {
+            MappingKind::Code(term) => encode_term(&mut buf, term),
+            MappingKind::Branch { true_term, false_term } => {
+                // `RegionKind::BranchRegion` shifted past the counter tag and the expansion bit.
+                gimli::leb128::write::unsigned(&mut buf, 4 << 3).unwrap();
+                encode_term(&mut buf, true_term);
+                encode_term(&mut buf, false_term);
+            }
+            MappingKind::MCDCBranch { .. } | MappingKind::MCDCDecision(_) => {
+                bug!("MC/DC coverage mappings are not supported by cg_clif")
+            }
+        }
+
+        let region = &mapping.code_region;
+        gimli::leb128::write::unsigned(&mut buf, (region.start_line - prev_start_line).into())
+            .unwrap();
+        gimli::leb128::write::unsigned(&mut buf, region.start_col.into()).unwrap();
+        gimli::leb128::write::unsigned(&mut buf, (region.end_line - region.start_line).into())
+            .unwrap();
+        gimli::leb128::write::unsigned(&mut buf, region.end_col.into()).unwrap();
+    }
+
+    buf
+}
+
+/// Identify expressions that will always have a value of zero because they were optimized out or
+/// because both of their operands are zero. Mappings and expression operands that refer to them
+/// can refer to a constant zero instead.
+fn zero_expressions(function: &FunctionCoverage) -> FxHashSet<ExpressionId> {
+    let mut zero_expressions = FxHashSet::default();
+
+    // Expressions can only refer to expressions with lower ids, so one pass is sufficient.
+    for (id, &Expression { lhs, op, rhs }) in function.info.expressions.iter_enumerated() {
+        if !function.expressions_seen.contains(id) {
+            zero_expressions.insert(id);
+            continue;
+        }
+
+        let is_zero_term = |term: CovTerm| match term {
+            CovTerm::Zero => true,
+            CovTerm::Counter(id) => !function.counters_seen.contains(id),
+            CovTerm::Expression(id) => zero_expressions.contains(&id),
+        };
+        let lhs_is_zero = is_zero_term(lhs);
+        // Coverage counter values can't be negative, so if an expression subtracts from zero the
+        // rhs must be zero too.
+        let rhs_is_zero = (lhs_is_zero && op == Op::Subtract) || is_zero_term(rhs);
+        if lhs_is_zero && rhs_is_zero {
+            zero_expressions.insert(id);
+        }
+    }
+
+    zero_expressions
+}
+
+/// Returns functions that were instrumented for coverage, but are not codegened anywhere. These
+/// still need coverage mappings to show up as unused in coverage reports.
+pub(super) fn unused_functions<'tcx>(
+    tcx: TyCtxt<'tcx>,
+) -> Vec<(Instance<'tcx>, &'tcx FunctionCoverageInfo)> {
+    let (all_mono_items, cgus) = tcx.collect_and_partition_mono_items(());
+
+    // Functions whose coverage statements were found inlined into other functions.
+    let mut used_via_inlining = FxHashSet::default();
+    // Functions that were instrumented, but had all of their coverage statements removed by later
+    // MIR transforms (e.g. UnreachablePropagation).
+    let mut missing_own_coverage = FxHashSet::default();
+
+    let mut def_ids_seen = FxHashSet::default();
+    for instance in cgus.iter().flat_map(|cgu| cgu.items().keys()).filter_map(|item| match item {
+        MonoItem::Fn(instance) => Some(instance),
+        MonoItem::Static(_) | MonoItem::GlobalAsm(_) => None,
+    }) {
+        // We only need one arbitrary instance per definition.
+        if !def_ids_seen.insert(instance.def_id()) {
+            continue;
+        }
+
+        let body = tcx.instance_mir(instance.def);
+        let mut saw_own_coverage = false;
+        for stmt in body
+            .basic_blocks
+            .iter()
+            .flat_map(|block| &block.statements)
+            .filter(|stmt| matches!(stmt.kind, StatementKind::Coverage(_)))
+        {
+            if let Some(inlined) = stmt.source_info.scope.inlined_instance(&body.source_scopes) {
+                used_via_inlining.insert(inlined.def_id());
+            } else {
+                saw_own_coverage = true;
+            }
+        }
+
+        if !saw_own_coverage && body.function_coverage_info.is_some() {
+            missing_own_coverage.insert(instance.def_id());
+        }
+    }
+
+    let is_unused_fn = |def_id: LocalDefId| {
+        let def_id = def_id.to_def_id();
+        tcx.def_kind(def_id).is_fn_like()
+            && (!all_mono_items.contains(&def_id) || missing_own_coverage.contains(&def_id))
+            && !used_via_inlining.contains(&def_id)
+    };
+
+    let mut unused_functions = vec![];
+    for &def_id in tcx.mir_keys(()).iter() {
+        if !is_unused_fn(def_id) {
+            continue;
+        }
+        let Some(info) = tcx.optimized_mir(def_id).function_coverage_info.as_deref() else {
+            continue;
+        };
+
+        // Make a dummy instance that fills in all generics with placeholders.
+        let def_id = def_id.to_def_id();
+        let instance = Instance::new(
+            def_id,
+            ty::GenericArgs::for_item(tcx, def_id, |param, _| {
+                if let ty::GenericParamDefKind::Lifetime = param.kind {
+                    tcx.lifetimes.re_erased.into()
+                } else {
+                    tcx.mk_param_from_def(param)
+                }
+            }),
+        );
+        unused_functions.push((instance, info));
+    }
+
+    unused_functions
+}
+
+/// The lower 64 bits of the MD5 hash of `data`, interpreted as little endian. This is what LLVM
+/// uses to refer to function names and filename tables.
+fn md5_hash(data: &[u8]) -> u64 {
+    u64::from_le_bytes(md5(data)[..8].try_into().unwrap())
+}
+
+fn md5(data: &[u8]) -> [u8; 16] {
+    const K: [u32; 64] = [
+        0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613,
+        0xfd469501, 0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193,
+        0xa679438e, 0x49b40821, 0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d,
+        0x02441453, 0xd8a1e681, 0xe7d3fbc8, 0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed,
+        0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a, 0xfffa3942, 0x8771f681, 0x6d9d6122,
+        0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70, 0x289b7ec6, 0xeaa127fa,
+        0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665, 0xf4292244,
+        0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+        0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb,
+        0xeb86d391,
+    ];
+    const SHIFTS: [u32; 64] = [
+        7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 5, 9, 14, 20, 5, 9, 14, 20, 5,
+        9, 14, 20, 5, 9, 14, 20, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 6, 10,
+        15, 21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+    ];
+
+    let mut state: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64).wrapping_mul(8).to_le_bytes());
+
+    for chunk in message.chunks_exact(64) {
+        let words: Vec<u32> =
+            chunk.chunks_exact(4).map(|w| u32::from_le_bytes(w.try_into().unwrap())).collect();
+        let [mut a, mut b, mut c, mut d] = state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let f = f.wrapping_add(a).wrapping_add(K[i]).wrapping_add(words[g]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(f.rotate_left(SHIFTS[i]));
+        }
+        for (s, v) in state.iter_mut().zip([a, b, c, d]) {
+            *s = s.wrapping_add(v);
+        }
+    }
+
+    let mut digest = [0; 16];
+    for (bytes, s) in digest.chunks_exact_mut(4).zip(state) {
+        bytes.copy_from_slice(&s.to_le_bytes());
+    }
+    digest
+}
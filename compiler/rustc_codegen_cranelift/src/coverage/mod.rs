@@ -0,0 +1,254 @@
+//! Source-based code coverage (`-Cinstrument-coverage`)
+//!
+//! Coverage statements inserted by the `InstrumentCoverage` MIR pass are lowered to increments of
+//! a per-function array of 64-bit counters. At the end of every codegen unit the counters are
+//! described to LLVM's profiler runtime (`profiler_builtins`) and to `llvm-cov` using the same
+//! sections that cg_llvm emits:
+//!
+//! * `__llvm_prf_cnts` holds the counters themselves.
+//! * `__llvm_prf_data` holds a profile data record per instrumented function, which the runtime
+//!   uses to find the counters of each function when writing the `.profraw` file.
+//! * `__llvm_prf_names` holds the mangled names of all instrumented functions.
+//! * `__llvm_covmap` holds the filenames used by the coverage mappings of the codegen unit.
+//! * `__llvm_covfun` holds the coverage mappings of each function.
+//!
+//! See [`mapgen`] for the encoding of these sections. Linking the profiler runtime is handled by
+//! `rustc_codegen_ssa` for all backends.
+
+use cranelift_codegen::ir::Endianness;
+use cranelift_codegen::isa::TargetIsa;
+use cranelift_module::DataId;
+use cranelift_object::ObjectProduct;
+use gimli::RunTimeEndian;
+use object::write::{Relocation, StandardSegment};
+use object::{RelocationFlags, SectionKind};
+use rustc_index::bit_set::BitSet;
+use rustc_middle::mir::coverage::{
+    CounterId, CovTerm, CoverageKind, ExpressionId, FunctionCoverageInfo,
+};
+use rustc_middle::mir::mono::CodegenUnit;
+
+use crate::prelude::*;
+
+mod mapgen;
+
+/// Per-codegen-unit state for coverage instrumentation.
+pub(crate) struct CoverageContext {
+    endian: RunTimeEndian,
+    /// The ELF relocation type of the 64-bit PC-relative counter pointers in `__llvm_prf_data`.
+    counter_ptr_reloc: u32,
+    /// Coverage data for every function instrumented in this codegen unit, keyed by the mangled
+    /// symbol name of the function.
+    functions: FxIndexMap<String, FunctionCoverage>,
+    /// The encoded coverage sections. Filled in by [`CoverageContext::finalize`].
+    sections: Option<mapgen::CoverageSections>,
+}
+
+/// Coverage data for a single function instance.
+struct FunctionCoverage {
+    /// Coverage info that was attached to this function by the instrumentor.
+    info: FunctionCoverageInfo,
+    /// False for functions that are not codegened at all, but still need to show up as unused in
+    /// coverage reports.
+    is_used: bool,
+    /// The `__llvm_prf_cnts` array and the number of counters in it. This is only created once
+    /// the first counter increment of the function is codegened.
+    counters: Option<(DataId, u32)>,
+    /// Counters that have been seen in a counter-increment statement. Mappings to counters that
+    /// were optimized out will be set to zero.
+    counters_seen: BitSet<CounterId>,
+    /// Expressions that have been seen in an expression-used statement, plus all expressions that
+    /// aren't directly used by any mappings. Expressions missing from this set must have had their
+    /// statements deleted by MIR opts.
+    expressions_seen: BitSet<ExpressionId>,
+}
+
+impl FunctionCoverage {
+    fn new(info: &FunctionCoverageInfo, is_used: bool) -> Self {
+        // Expressions that are not directly used by any mapping never get an `ExpressionUsed`
+        // statement, so treat them as seen.
+        let mut expressions_seen = BitSet::new_filled(info.expressions.len());
+        for term in info.mappings.iter().flat_map(|m| m.kind.terms()) {
+            if let CovTerm::Expression(id) = term {
+                expressions_seen.remove(id);
+            }
+        }
+
+        FunctionCoverage {
+            info: info.clone(),
+            is_used,
+            counters: None,
+            counters_seen: BitSet::new_empty(info.num_counters),
+            expressions_seen,
+        }
+    }
+}
+
+impl CoverageContext {
+    pub(crate) fn new(tcx: TyCtxt<'_>, isa: &dyn TargetIsa) -> Self {
+        // FIXME support Mach-O and COFF. They use different section names and Mach-O needs
+        // relocation pairs for the relative counter pointers in `__llvm_prf_data`.
+        if tcx.sess.target.is_like_osx || tcx.sess.target.is_like_windows {
+            tcx.dcx().fatal("-Cinstrument-coverage is only supported for ELF targets by cg_clif");
+        }
+        if tcx.data_layout.pointer_size.bits() != 64 {
+            tcx.dcx().fatal("-Cinstrument-coverage is only supported for 64bit targets by cg_clif");
+        }
+        let counter_ptr_reloc = match &*tcx.sess.target.arch {
+            "x86_64" => object::elf::R_X86_64_PC64,
+            "aarch64" => object::elf::R_AARCH64_PREL64,
+            "s390x" => object::elf::R_390_PC64,
+            // FIXME support riscv64, which needs a pair of R_RISCV_ADD64 and R_RISCV_SUB64
+            // relocations instead
+            arch => tcx
+                .dcx()
+                .fatal(format!("-Cinstrument-coverage is not yet supported on {arch} by cg_clif")),
+        };
+        if tcx.sess.instrument_coverage_mcdc() {
+            tcx.dcx().fatal("MC/DC coverage instrumentation is not yet supported by cg_clif");
+        }
+
+        let endian = match isa.endianness() {
+            Endianness::Little => RunTimeEndian::Little,
+            Endianness::Big => RunTimeEndian::Big,
+        };
+
+        CoverageContext {
+            endian,
+            counter_ptr_reloc,
+            functions: FxIndexMap::default(),
+            sections: None,
+        }
+    }
+
+    /// Adds the functions that weren't codegened anywhere (if this is the designated codegen unit
+    /// for them) and encodes all coverage sections. This needs access to the `TyCtxt` and must
+    /// therefore happen before the codegen unit is sent to a worker thread.
+    pub(crate) fn finalize(&mut self, tcx: TyCtxt<'_>, cgu: &CodegenUnit<'_>) {
+        if cgu.is_code_coverage_dead_code_cgu() {
+            for (instance, info) in mapgen::unused_functions(tcx) {
+                self.functions
+                    .entry(tcx.symbol_name(instance).name.to_owned())
+                    .or_insert_with(|| FunctionCoverage::new(info, false));
+            }
+        }
+
+        if !self.functions.is_empty() {
+            self.sections = Some(mapgen::generate(tcx, self.endian, &self.functions));
+        }
+    }
+
+    pub(crate) fn emit(self, product: &mut ObjectProduct) {
+        let Some(sections) = self.sections else {
+            return;
+        };
+
+        let segment = product.object.segment_name(StandardSegment::Data).to_vec();
+
+        let prf_data = product.object.add_section(
+            segment.clone(),
+            b"__llvm_prf_data".to_vec(),
+            SectionKind::Data,
+        );
+        product.object.section_mut(prf_data).set_data(sections.prf_data, 8);
+        for &(offset, data_id) in &sections.prf_data_relocs {
+            // The counter pointer is relative to the start of the profile data record.
+            let symbol_id = product.data_symbol(data_id);
+            let (symbol, symbol_offset) =
+                product.object.symbol_section_and_offset(symbol_id).unwrap_or((symbol_id, 0));
+            product
+                .object
+                .add_relocation(
+                    prf_data,
+                    Relocation {
+                        offset,
+                        symbol,
+                        flags: RelocationFlags::Elf { r_type: self.counter_ptr_reloc },
+                        addend: i64::try_from(symbol_offset).unwrap()
+                            + i64::from(mapgen::PRF_DATA_COUNTER_PTR_OFFSET),
+                    },
+                )
+                .unwrap();
+        }
+
+        // `__llvm_covmap` and `__llvm_covfun` are only read by `llvm-cov` and are not loaded at
+        // runtime.
+        for (name, data, kind, align) in [
+            ("__llvm_prf_names", sections.prf_names, SectionKind::ReadOnlyData, 1),
+            ("__llvm_covmap", sections.covmap, SectionKind::Other, 8),
+            ("__llvm_covfun", sections.covfun, SectionKind::Other, 8),
+        ] {
+            if data.is_empty() {
+                continue;
+            }
+            let section_id =
+                product.object.add_section(segment.clone(), name.as_bytes().to_vec(), kind);
+            product.object.section_mut(section_id).set_data(data, align);
+        }
+    }
+}
+
+pub(crate) fn codegen_coverage<'tcx>(
+    fx: &mut FunctionCx<'_, '_, 'tcx>,
+    kind: &CoverageKind,
+    scope: SourceScope,
+) {
+    // Determine the instance that coverage data was originally generated for.
+    let instance = if let Some(inlined) = scope.inlined_instance(&fx.mir.source_scopes) {
+        fx.monomorphize(inlined)
+    } else {
+        fx.instance
+    };
+
+    let Some(info) = fx.tcx.instance_mir(instance.def).function_coverage_info.as_deref() else {
+        return;
+    };
+
+    let Some(coverage_context) = &mut fx.cx.coverage_context else {
+        fx.tcx.dcx().fatal("-Cinstrument-coverage is not supported in jit mode");
+    };
+
+    let func_coverage = coverage_context
+        .functions
+        .entry(fx.tcx.symbol_name(instance).name.to_owned())
+        .or_insert_with(|| FunctionCoverage::new(info, true));
+
+    match *kind {
+        CoverageKind::SpanMarker | CoverageKind::BlockMarker { .. } => {
+            bug!("marker statement {kind:?} should have been removed by CleanupPostBorrowck")
+        }
+        CoverageKind::CounterIncrement { id } => {
+            func_coverage.counters_seen.insert(id);
+
+            let (counters, _) = *func_coverage.counters.get_or_insert_with(|| {
+                // Some high-numbered counter increments may have been removed by MIR
+                // optimizations, in which case fewer physical counters are needed.
+                let num_counters =
+                    fx.tcx.coverage_ids_info(instance.def).max_counter_id.as_u32() + 1;
+                assert!(num_counters as usize <= info.num_counters);
+
+                let data_id = fx.module.declare_anonymous_data(true, false).unwrap();
+                let mut data = DataDescription::new();
+                data.set_align(8);
+                data.set_segment_section("", "__llvm_prf_cnts");
+                data.define(vec![0; num_counters as usize * 8].into_boxed_slice());
+                fx.module.define_data(data_id, &data).unwrap();
+
+                (data_id, num_counters)
+            });
+
+            let counters = fx.module.declare_data_in_func(counters, fx.bcx.func);
+            let counters = fx.bcx.ins().global_value(fx.pointer_type, counters);
+            let offset = i32::try_from(id.as_u32() * 8).unwrap();
+            let count = fx.bcx.ins().load(types::I64, MemFlags::trusted(), counters, offset);
+            let count = fx.bcx.ins().iadd_imm(count, 1);
+            fx.bcx.ins().store(MemFlags::trusted(), count, counters, offset);
+        }
+        CoverageKind::ExpressionUsed { id } => {
+            func_coverage.expressions_seen.insert(id);
+        }
+        CoverageKind::CondBitmapUpdate { .. } | CoverageKind::TestVectorBitmapUpdate { .. } => {
+            fx.tcx.dcx().fatal("MC/DC coverage instrumentation is not yet supported by cg_clif");
+        }
+    }
+}
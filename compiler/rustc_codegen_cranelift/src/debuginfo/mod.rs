@@ -6,12 +6,13 @@
 mod types;
 mod unwind;
 
-use cranelift_codegen::ir::Endianness;
+use cranelift_codegen::ir::{Endianness, ValueLabel};
 use cranelift_codegen::isa::TargetIsa;
+use cranelift_codegen::{CompiledCode, LabelValueLoc};
 use cranelift_module::DataId;
 use gimli::write::{
-    Address, AttributeValue, DwarfUnit, Expression, FileId, LineProgram, LineString, Range,
-    RangeList, UnitEntryId,
+    Address, AttributeValue, DwarfUnit, Expression, FileId, LineProgram, LineString, Location,
+    LocationList, Range, RangeList, UnitEntryId,
 };
 use gimli::{AArch64, Encoding, Format, LineEncoding, Register, RiscV, RunTimeEndian, X86_64};
 use indexmap::IndexSet;
@@ -26,6 +27,7 @@
 pub(crate) use self::types::TypeDebugContext;
 pub(crate) use self::unwind::UnwindContext;
 use crate::debuginfo::emit::{address_for_data, address_for_func};
+use crate::pointer::PointerBase;
 use crate::prelude::*;
 
 pub(crate) fn producer(sess: &Session) -> String {
@@ -49,6 +51,23 @@ pub(crate) struct FunctionDebugContext {
     entry_id: UnitEntryId,
     function_source_loc: (FileId, u64, u64),
     source_loc_set: IndexSet<(FileId, u64, u64)>,
+    variables: Vec<(UnitEntryId, VariableLocation)>,
+    /// The locations of the arguments which are passed by reference.
+    indirect_args: FxHashMap<Local, VariableLocation>,
+}
+
+/// Where the value of a local variable lives.
+#[derive(Clone, Debug)]
+pub(crate) enum VariableLocation {
+    /// In the Cranelift values with the given label.
+    Var(ValueLabel),
+    /// In a stack slot, at the given offset.
+    Stack(StackSlot, i32),
+    /// Behind the pointer stored at the given location, at the given offset.
+    Deref(Box<VariableLocation>, i64),
+    /// Split into pieces, each given by its offset and size within the value. The pieces are
+    /// sorted by offset and don't overlap. Bytes not covered by any piece are unknown.
+    Pieces(Vec<(Size, Size, VariableLocation)>),
 }
 
 impl DebugContext {
@@ -254,6 +273,8 @@ pub(crate) fn define_function<'tcx>(
             entry_id,
             function_source_loc: (file_id, line, column),
             source_loc_set: IndexSet::new(),
+            variables: Vec::new(),
+            indirect_args: FxHashMap::default(),
         }
     }
 
@@ -312,14 +333,91 @@ pub(crate) fn define_static<'tcx>(
             entry.set(gimli::DW_AT_linkage_name, AttributeValue::StringRef(linkage_name_id));
         }
     }
+
+    // Adapted from `create_vtable_di_node` in rustc_codegen_llvm/src/debuginfo/metadata.rs
+    /// Describes the vtable in `data_id` as a `<T as Trait>::{vtable}` static. Debuggers use it to
+    /// find the concrete type behind a trait object.
+    pub(crate) fn define_vtable<'tcx>(
+        &mut self,
+        tcx: TyCtxt<'tcx>,
+        type_dbg: &mut TypeDebugContext<'tcx>,
+        ty: Ty<'tcx>,
+        trait_ref: Option<ty::PolyExistentialTraitRef<'tcx>>,
+        data_id: DataId,
+    ) {
+        let type_id = self.vtable_type(tcx, type_dbg, ty, trait_ref);
+        let name = type_names::compute_debuginfo_vtable_name(
+            tcx,
+            ty,
+            trait_ref,
+            type_names::VTableNameKind::GlobalVariable,
+        );
+
+        let entry_id = self.dwarf.unit.add(self.dwarf.unit.root(), gimli::DW_TAG_variable);
+        let entry = self.dwarf.unit.get_mut(entry_id);
+        entry.set(gimli::DW_AT_name, AttributeValue::StringRef(self.dwarf.strings.add(name)));
+        entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(type_id));
+
+        let mut expr = Expression::new();
+        expr.op_addr(address_for_data(data_id));
+        entry.set(gimli::DW_AT_location, AttributeValue::Exprloc(expr));
+    }
 }
 
 impl FunctionDebugContext {
+    /// Records that the argument `local` is passed by reference, with the address of its value
+    /// stored at `address_slot`.
+    pub(crate) fn define_indirect_arg(&mut self, local: Local, address_slot: Pointer) {
+        let (PointerBase::Stack(stack_slot), offset) = address_slot.debug_base_and_offset() else {
+            bug!("address of argument not stored in a stack slot");
+        };
+        let address_location = VariableLocation::Stack(stack_slot, offset.into());
+        self.indirect_args.insert(local, VariableLocation::Deref(Box::new(address_location), 0));
+    }
+
+    pub(crate) fn indirect_arg_location(&self, local: Local) -> Option<VariableLocation> {
+        self.indirect_args.get(&local).cloned()
+    }
+
+    /// Defines a local variable or argument of this function. Its location is filled in by
+    /// `finalize`, once the function has been compiled.
+    pub(crate) fn define_local<'tcx>(
+        &mut self,
+        debug_context: &mut DebugContext,
+        tcx: TyCtxt<'tcx>,
+        type_dbg: &mut TypeDebugContext<'tcx>,
+        function_span: Span,
+        var: &VarDebugInfo<'tcx>,
+        ty: Ty<'tcx>,
+        location: VariableLocation,
+    ) {
+        let (file_id, line, _column) =
+            debug_context.get_span_loc(tcx, function_span, var.source_info.span);
+        let type_id = debug_context.debug_type(tcx, type_dbg, ty);
+
+        // FIXME put variables in the lexical block of their scope
+        let tag = if var.argument_index.is_some() {
+            gimli::DW_TAG_formal_parameter
+        } else {
+            gimli::DW_TAG_variable
+        };
+        let var_id = debug_context.dwarf.unit.add(self.entry_id, tag);
+        let name_id = debug_context.dwarf.strings.add(var.name.as_str());
+        let var_entry = debug_context.dwarf.unit.get_mut(var_id);
+        var_entry.set(gimli::DW_AT_name, AttributeValue::StringRef(name_id));
+        var_entry.set(gimli::DW_AT_decl_file, AttributeValue::FileIndex(Some(file_id)));
+        var_entry.set(gimli::DW_AT_decl_line, AttributeValue::Udata(line));
+        var_entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(type_id));
+
+        self.variables.push((var_id, location));
+    }
+
     pub(crate) fn finalize(
         mut self,
         debug_context: &mut DebugContext,
         func_id: FuncId,
         context: &Context,
+        isa: &dyn TargetIsa,
     ) {
         let end = self.create_debug_lines(debug_context, func_id, context);
 
@@ -333,5 +431,218 @@ pub(crate) fn finalize(
         func_entry.set(gimli::DW_AT_low_pc, AttributeValue::Address(address_for_func(func_id)));
         // Using Udata for DW_AT_high_pc requires at least DWARF4
         func_entry.set(gimli::DW_AT_high_pc, AttributeValue::Udata(u64::from(end)));
+
+        for (var_id, location) in self.variables {
+            let location = variable_location(debug_context, func_id, context, isa, end, location);
+            let var_entry = debug_context.dwarf.unit.get_mut(var_id);
+            var_entry.set(gimli::DW_AT_location, location);
+        }
+    }
+}
+
+fn variable_location(
+    debug_context: &mut DebugContext,
+    func_id: FuncId,
+    context: &Context,
+    isa: &dyn TargetIsa,
+    func_len: u32,
+    location: VariableLocation,
+) -> AttributeValue {
+    let compiled_code = context.compiled_code().unwrap();
+    let mut ranges = match location {
+        VariableLocation::Pieces(pieces) => {
+            let pieces = pieces
+                .into_iter()
+                .map(|(offset, size, location)| {
+                    (offset, size, part_locations(compiled_code, isa, func_len, location))
+                })
+                .collect::<Vec<_>>();
+            piece_locations(&pieces)
+        }
+        location => part_locations(compiled_code, isa, func_len, location)
+            .into_iter()
+            .map(|(start, end, part)| {
+                let mut expr = Expression::new();
+                part.write(&mut expr);
+                (start, end, expr)
+            })
+            .collect(),
+    };
+    ranges.retain(|&(start, end, _)| start < end);
+
+    match &*ranges {
+        // The variable was optimized out.
+        [] => AttributeValue::Exprloc(Expression::new()),
+        [(0, end, _)] if *end == func_len => AttributeValue::Exprloc(ranges.pop().unwrap().2),
+        _ => {
+            let Address::Symbol { symbol, addend } = address_for_func(func_id) else {
+                unreachable!()
+            };
+            let locations = ranges
+                .into_iter()
+                .map(|(start, end, data)| Location::StartEnd {
+                    begin: Address::Symbol { symbol, addend: addend + i64::from(start) },
+                    end: Address::Symbol { symbol, addend: addend + i64::from(end) },
+                    data,
+                })
+                .collect();
+            let location_list_id = debug_context.dwarf.unit.locations.add(LocationList(locations));
+            AttributeValue::LocationListRef(location_list_id)
+        }
+    }
+}
+
+/// The location of a variable, or of a piece of it, which doesn't need to be split further.
+#[derive(Clone, PartialEq, Eq)]
+enum PartLocation {
+    Reg(Register),
+    /// In memory at the address computed by `base`. Then, for each entry of `derefs`, the
+    /// pointer at that address is loaded and the entry is added to it.
+    Memory {
+        base: AddressBase,
+        derefs: Vec<i64>,
+    },
+}
+
+#[derive(Clone, PartialEq, Eq)]
+enum AddressBase {
+    FrameBase(i64),
+    Cfa(i64),
+    Reg(Register, i64),
+}
+
+impl PartLocation {
+    fn deref(self, offset: i64) -> PartLocation {
+        match self {
+            PartLocation::Reg(reg) => {
+                PartLocation::Memory { base: AddressBase::Reg(reg, offset), derefs: vec![] }
+            }
+            PartLocation::Memory { base, mut derefs } => {
+                derefs.push(offset);
+                PartLocation::Memory { base, derefs }
+            }
+        }
+    }
+
+    fn write(&self, expr: &mut Expression) {
+        match self {
+            PartLocation::Reg(reg) => expr.op_reg(*reg),
+            PartLocation::Memory { base, derefs } => {
+                match *base {
+                    AddressBase::FrameBase(offset) => expr.op_fbreg(offset),
+                    AddressBase::Cfa(offset) => {
+                        // The value is spilled to the stack.
+                        expr.op(gimli::DW_OP_call_frame_cfa);
+                        expr.op_consts(offset);
+                        expr.op(gimli::DW_OP_plus);
+                    }
+                    AddressBase::Reg(reg, offset) => expr.op_breg(reg, offset),
+                }
+                for &offset in derefs {
+                    expr.op_deref();
+                    if offset != 0 {
+                        expr.op_consts(offset);
+                        expr.op(gimli::DW_OP_plus);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns the code ranges of the function in which the location of a variable or piece is
+/// known, together with that location.
+fn part_locations(
+    compiled_code: &CompiledCode,
+    isa: &dyn TargetIsa,
+    func_len: u32,
+    location: VariableLocation,
+) -> Vec<(u32, u32, PartLocation)> {
+    match location {
+        VariableLocation::Var(label) => {
+            let Some(ranges) = compiled_code.value_labels_ranges.get(&label) else {
+                return vec![];
+            };
+            ranges
+                .iter()
+                .filter_map(|range| {
+                    Some((range.start, range.end, translate_value_loc(isa, range.loc)?))
+                })
+                .collect()
+        }
+        VariableLocation::Stack(stack_slot, offset) => {
+            // The frame base is the stack pointer after the prologue, which is what stack slot
+            // offsets are relative to.
+            let stack_slot_offset = compiled_code.sized_stackslot_offsets[stack_slot];
+            let base = AddressBase::FrameBase(i64::from(stack_slot_offset) + i64::from(offset));
+            vec![(0, func_len, PartLocation::Memory { base, derefs: vec![] })]
+        }
+        VariableLocation::Deref(pointer, offset) => {
+            part_locations(compiled_code, isa, func_len, *pointer)
+                .into_iter()
+                .map(|(start, end, pointer)| (start, end, pointer.deref(offset)))
+                .collect()
+        }
+        // Pointers are never split into pieces.
+        VariableLocation::Pieces(_) => vec![],
+    }
+}
+
+/// Combines the locations of the pieces of a variable into `DW_OP_piece` expressions, one for
+/// each code range in which the locations of all pieces stay the same.
+fn piece_locations(
+    pieces: &[(Size, Size, Vec<(u32, u32, PartLocation)>)],
+) -> Vec<(u32, u32, Expression)> {
+    let mut boundaries = pieces
+        .iter()
+        .flat_map(|(_, _, ranges)| ranges.iter().flat_map(|&(start, end, _)| [start, end]))
+        .collect::<Vec<_>>();
+    boundaries.sort_unstable();
+    boundaries.dedup();
+
+    let mut locations: Vec<(u32, u32, Expression)> = vec![];
+    for window in boundaries.windows(2) {
+        let (start, end) = (window[0], window[1]);
+        let mut expr = Expression::new();
+        let mut next_offset = Size::ZERO;
+        let mut any_known = false;
+        for (offset, size, ranges) in pieces {
+            let Some((_, _, part)) = ranges
+                .iter()
+                .find(|&&(part_start, part_end, _)| part_start <= start && end <= part_end)
+            else {
+                continue;
+            };
+            if *offset > next_offset {
+                // An empty piece, for the bytes whose location is unknown.
+                expr.op_piece((*offset - next_offset).bytes());
+            }
+            part.write(&mut expr);
+            expr.op_piece(size.bytes());
+            next_offset = *offset + *size;
+            any_known = true;
+        }
+        if !any_known {
+            continue;
+        }
+        match locations.last_mut() {
+            Some((_, last_end, last_expr)) if *last_end == start && *last_expr == expr => {
+                *last_end = end;
+            }
+            _ => locations.push((start, end, expr)),
+        }
+    }
+    locations
+}
+
+fn translate_value_loc(isa: &dyn TargetIsa, loc: LabelValueLoc) -> Option<PartLocation> {
+    match loc {
+        LabelValueLoc::Reg(reg) => {
+            let reg = isa.map_regalloc_reg_to_dwarf(reg).ok()?;
+            Some(PartLocation::Reg(Register(reg)))
+        }
+        LabelValueLoc::CFAOffset(offset) => {
+            Some(PartLocation::Memory { base: AddressBase::Cfa(offset), derefs: vec![] })
+        }
     }
 }
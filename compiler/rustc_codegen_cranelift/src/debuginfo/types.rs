@@ -1,16 +1,23 @@
 // Adapted from https://github.com/rust-lang/rust/blob/10a7aa14fed9b528b74b0f098c4899c37c09a9c7/compiler/rustc_codegen_llvm/src/debuginfo/metadata.rs
 
 use gimli::write::{AttributeValue, UnitEntryId};
-use rustc_codegen_ssa::debuginfo::type_names;
+use rustc_codegen_ssa::debuginfo::type_names::{self, VTableNameKind};
+use rustc_codegen_ssa::debuginfo::wants_c_like_enum_debuginfo;
 use rustc_data_structures::fx::FxHashMap;
-use rustc_middle::ty::layout::LayoutOf;
-use rustc_middle::ty::{self, Ty, TyCtxt};
+use rustc_hir::def::CtorKind;
+use rustc_hir::def_id::DefId;
+use rustc_middle::ty::layout::{
+    IntegerExt, LayoutOf, PrimitiveExt, TyAndLayout, FAT_PTR_ADDR, FAT_PTR_EXTRA,
+};
+use rustc_middle::ty::{self, AdtDef, Ty, TyCtxt};
+use rustc_target::abi::{HasDataLayout, Primitive, Size, TagEncoding, VariantIdx, Variants};
 
 use crate::{has_ptr_meta, DebugContext, RevealAllLayoutCx};
 
 #[derive(Default)]
 pub(crate) struct TypeDebugContext<'tcx> {
     type_map: FxHashMap<Ty<'tcx>, UnitEntryId>,
+    vtable_type_map: FxHashMap<(Ty<'tcx>, Option<ty::PolyExistentialTraitRef<'tcx>>), UnitEntryId>,
 }
 
 /// Returns from the enclosing function if the type debuginfo node with the given
@@ -46,19 +53,33 @@ pub(crate) fn debug_type<'tcx>(
                 *elem_ty,
                 len.eval_target_usize(tcx, ty::ParamEnv::reveal_all()),
             ),
-            // ty::Slice(_) | ty::Str
-            // ty::Dynamic
-            // ty::Foreign
+            // Like the LLVM backend, describe `[T]` and `str` as their element type, so that the
+            // data pointer of a fat pointer to them is a `*const T`.
+            ty::Slice(elem_ty) => self.debug_type(tcx, type_dbg, *elem_ty),
+            ty::Str => self.debug_type(tcx, type_dbg, tcx.types.u8),
+            ty::Dynamic(..) => self.opaque_type(tcx, ty, None),
+            ty::Foreign(def_id) => self.opaque_type(tcx, ty, Some(*def_id)),
             ty::RawPtr(pointee_type, _) | ty::Ref(_, pointee_type, _) => {
                 self.pointer_type(tcx, type_dbg, ty, *pointee_type)
             }
-            // ty::Adt(def, args) if def.is_box() && args.get(1).map_or(true, |arg| cx.layout_of(arg.expect_ty()).is_1zst())
-            // ty::FnDef(..) | ty::FnPtr(..)
-            // ty::Closure(..)
-            // ty::Adt(def, ..)
+            // Some `Box` are newtyped pointers, make debuginfo aware of that.
+            // Only works if the allocator argument is a 1-ZST and hence irrelevant for layout
+            // (or if there is no allocator argument).
+            ty::Adt(def, args)
+                if def.is_box()
+                    && args.get(1).map_or(true, |arg| {
+                        RevealAllLayoutCx(tcx).layout_of(arg.expect_ty()).is_1zst()
+                    }) =>
+            {
+                self.pointer_type(tcx, type_dbg, ty, ty.boxed_ty())
+            }
+            ty::FnDef(..) | ty::FnPtr(..) => self.subroutine_type(tcx, type_dbg, ty),
+            ty::Closure(..) | ty::CoroutineClosure(..) => self.closure_type(tcx, type_dbg, ty),
+            ty::Adt(def, _) if def.is_struct() => self.struct_type(tcx, type_dbg, ty, *def),
+            ty::Adt(def, _) if def.is_union() => self.union_type(tcx, type_dbg, ty, *def),
+            ty::Adt(def, _) => self.enum_type(tcx, type_dbg, ty, *def),
             ty::Tuple(components) => self.tuple_type(tcx, type_dbg, ty, *components),
-            // ty::Param(_)
-            // FIXME implement remaining types and add unreachable!() to the fallback branch
+            // FIXME implement coroutines and add unreachable!() to the fallback branch
             _ => self.placeholder_for_type(tcx, type_dbg, ty),
         };
 
@@ -129,7 +150,7 @@ fn pointer_type<'tcx>(
 
         let name = type_names::compute_debuginfo_type_name(tcx, ptr_type, true);
 
-        if !has_ptr_meta(tcx, ptr_type) {
+        if !has_ptr_meta(tcx, pointee_type) {
             let pointer_type_id =
                 self.dwarf.unit.add(self.dwarf.unit.root(), gimli::DW_TAG_pointer_type);
             let pointer_entry = self.dwarf.unit.get_mut(pointer_type_id);
@@ -137,11 +158,195 @@ fn pointer_type<'tcx>(
             pointer_entry
                 .set(gimli::DW_AT_name, AttributeValue::StringRef(self.dwarf.strings.add(name)));
 
-            pointer_type_id
+            return pointer_type_id;
+        }
+
+        // Fat pointers are described as a struct of the data pointer and the metadata. `Box` is
+        // laid out like a raw pointer here, so use the layout of one for all of them.
+        let layout = RevealAllLayoutCx(tcx).layout_of(Ty::new_mut_ptr(tcx, pointee_type));
+        let (addr_field_name, extra_field_name) = match tcx
+            .struct_tail_erasing_lifetimes(pointee_type, ty::ParamEnv::reveal_all())
+            .kind()
+        {
+            ty::Dynamic(..) => ("pointer", "vtable"),
+            _ => ("data_ptr", "length"),
+        };
+        let extra_field = layout.field(&RevealAllLayoutCx(tcx), FAT_PTR_EXTRA);
+        let extra_dw_ty = self.debug_type(tcx, type_dbg, extra_field.ty);
+
+        let pointer_type_id = self.struct_entry(self.dwarf.unit.root(), &name, layout);
+
+        // The data pointer is a regular, thin pointer, regardless of whether this is a slice or a
+        // trait object.
+        let data_ptr_id = self.dwarf.unit.add(self.dwarf.unit.root(), gimli::DW_TAG_pointer_type);
+        let data_ptr_entry = self.dwarf.unit.get_mut(data_ptr_id);
+        data_ptr_entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(pointee_dw_ty));
+
+        let addr_field = layout.field(&RevealAllLayoutCx(tcx), FAT_PTR_ADDR);
+        self.add_member(
+            pointer_type_id,
+            addr_field_name,
+            data_ptr_id,
+            addr_field,
+            layout.fields.offset(FAT_PTR_ADDR),
+        );
+        self.add_member(
+            pointer_type_id,
+            extra_field_name,
+            extra_dw_ty,
+            extra_field,
+            layout.fields.offset(FAT_PTR_EXTRA),
+        );
+
+        pointer_type_id
+    }
+
+    /// Describes the vtable of `ty` for the given trait as a struct with a member for each of its
+    /// entries. Like the LLVM backend, all function pointers are described as `*const ()`.
+    pub(crate) fn vtable_type<'tcx>(
+        &mut self,
+        tcx: TyCtxt<'tcx>,
+        type_dbg: &mut TypeDebugContext<'tcx>,
+        ty: Ty<'tcx>,
+        trait_ref: Option<ty::PolyExistentialTraitRef<'tcx>>,
+    ) -> UnitEntryId {
+        if let Some(&type_id) = type_dbg.vtable_type_map.get(&(ty, trait_ref)) {
+            return type_id;
+        }
+
+        let vtable_entries = if let Some(trait_ref) = trait_ref {
+            tcx.vtable_entries(tcx.erase_regions(trait_ref.with_self_ty(tcx, ty)))
         } else {
-            // FIXME implement debuginfo for fat pointers
-            self.placeholder_for_type(tcx, type_dbg, ptr_type)
+            TyCtxt::COMMON_VTABLE_ENTRIES
+        };
+
+        let void_pointer_ty = Ty::new_imm_ptr(tcx, tcx.types.unit);
+        let void_pointer_dw_ty = self.debug_type(tcx, type_dbg, void_pointer_ty);
+        let usize_dw_ty = self.debug_type(tcx, type_dbg, tcx.types.usize);
+        // This is used as the layout of every entry, so `usize` must be pointer-sized too.
+        let entry_layout = RevealAllLayoutCx(tcx).layout_of(tcx.types.usize);
+        assert_eq!(entry_layout.size, RevealAllLayoutCx(tcx).layout_of(void_pointer_ty).size);
+
+        // The containing type allows debuggers to find the concrete type behind a trait object
+        // from its vtable.
+        let holder_dw_ty = self.debug_type(tcx, type_dbg, ty);
+
+        let name =
+            type_names::compute_debuginfo_vtable_name(tcx, ty, trait_ref, VTableNameKind::Type);
+        let vtable_type_id =
+            self.dwarf.unit.add(self.dwarf.unit.root(), gimli::DW_TAG_structure_type);
+        let vtable_type_entry = self.dwarf.unit.get_mut(vtable_type_id);
+        vtable_type_entry
+            .set(gimli::DW_AT_name, AttributeValue::StringRef(self.dwarf.strings.add(name)));
+        vtable_type_entry.set(
+            gimli::DW_AT_byte_size,
+            AttributeValue::Udata(entry_layout.size.bytes() * vtable_entries.len() as u64),
+        );
+        vtable_type_entry
+            .set(gimli::DW_AT_alignment, AttributeValue::Udata(entry_layout.align.pref.bytes()));
+        vtable_type_entry.set(gimli::DW_AT_containing_type, AttributeValue::UnitRef(holder_dw_ty));
+        vtable_type_entry.set(gimli::DW_AT_artificial, AttributeValue::FlagPresent);
+        type_dbg.vtable_type_map.insert((ty, trait_ref), vtable_type_id);
+
+        for (index, vtable_entry) in vtable_entries.iter().enumerate() {
+            let (name, dw_ty) = match vtable_entry {
+                ty::VtblEntry::MetadataDropInPlace => {
+                    ("drop_in_place".to_owned(), void_pointer_dw_ty)
+                }
+                ty::VtblEntry::MetadataSize => ("size".to_owned(), usize_dw_ty),
+                ty::VtblEntry::MetadataAlign => ("align".to_owned(), usize_dw_ty),
+                // Methods are not named after the method, as several traits may have methods
+                // with the same name.
+                ty::VtblEntry::Method(_) => (format!("__method{index}"), void_pointer_dw_ty),
+                ty::VtblEntry::TraitVPtr(_) => {
+                    (format!("__super_trait_ptr{index}"), void_pointer_dw_ty)
+                }
+                ty::VtblEntry::Vacant => continue,
+            };
+            self.add_member(
+                vtable_type_id,
+                &name,
+                dw_ty,
+                entry_layout,
+                entry_layout.size * index as u64,
+            );
         }
+
+        vtable_type_id
+    }
+
+    /// Describes `dyn Trait` and extern types. These are described as empty structs with the
+    /// correct name.
+    fn opaque_type<'tcx>(
+        &mut self,
+        tcx: TyCtxt<'tcx>,
+        ty: Ty<'tcx>,
+        def_id: Option<DefId>,
+    ) -> UnitEntryId {
+        let name = type_names::compute_debuginfo_type_name(tcx, ty, false);
+        let scope = match def_id {
+            Some(def_id) => self.item_namespace(tcx, tcx.parent(def_id)),
+            None => self.dwarf.unit.root(),
+        };
+
+        let type_id = self.dwarf.unit.add(scope, gimli::DW_TAG_structure_type);
+        let type_entry = self.dwarf.unit.get_mut(type_id);
+        type_entry.set(gimli::DW_AT_name, AttributeValue::StringRef(self.dwarf.strings.add(name)));
+        if def_id.is_some() {
+            type_entry.set(gimli::DW_AT_declaration, AttributeValue::FlagPresent);
+        } else {
+            type_entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(0));
+        }
+
+        type_id
+    }
+
+    /// Describes function pointers and function items as a pointer to a subroutine type. Function
+    /// items are zero-sized.
+    fn subroutine_type<'tcx>(
+        &mut self,
+        tcx: TyCtxt<'tcx>,
+        type_dbg: &mut TypeDebugContext<'tcx>,
+        fn_ty: Ty<'tcx>,
+    ) -> UnitEntryId {
+        let name = type_names::compute_debuginfo_type_name(tcx, fn_ty, false);
+        let size = match fn_ty.kind() {
+            ty::FnDef(..) => 0,
+            _ => tcx.data_layout().pointer_size.bytes(),
+        };
+
+        let subroutine_type_id =
+            self.dwarf.unit.add(self.dwarf.unit.root(), gimli::DW_TAG_subroutine_type);
+
+        let pointer_type_id =
+            self.dwarf.unit.add(self.dwarf.unit.root(), gimli::DW_TAG_pointer_type);
+        let pointer_entry = self.dwarf.unit.get_mut(pointer_type_id);
+        pointer_entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(subroutine_type_id));
+        pointer_entry
+            .set(gimli::DW_AT_name, AttributeValue::StringRef(self.dwarf.strings.add(name)));
+        pointer_entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(size));
+
+        // A function item can be part of its own signature (`fn foo() -> impl Copy { foo }`), so
+        // register it before describing the signature.
+        type_dbg.type_map.insert(fn_ty, pointer_type_id);
+
+        let signature =
+            tcx.normalize_erasing_late_bound_regions(ty::ParamEnv::reveal_all(), fn_ty.fn_sig(tcx));
+
+        if !signature.output().is_unit() {
+            let return_dw_ty = self.debug_type(tcx, type_dbg, signature.output());
+            let subroutine_entry = self.dwarf.unit.get_mut(subroutine_type_id);
+            subroutine_entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(return_dw_ty));
+        }
+
+        for &arg_ty in signature.inputs() {
+            let arg_dw_ty = self.debug_type(tcx, type_dbg, arg_ty);
+            let param_id = self.dwarf.unit.add(subroutine_type_id, gimli::DW_TAG_formal_parameter);
+            let param_entry = self.dwarf.unit.get_mut(param_id);
+            param_entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(arg_dw_ty));
+        }
+
+        pointer_type_id
     }
 
     fn tuple_type<'tcx>(
@@ -161,32 +366,296 @@ fn tuple_type<'tcx>(
         let name = type_names::compute_debuginfo_type_name(tcx, tuple_type, false);
         let layout = RevealAllLayoutCx(tcx).layout_of(tuple_type);
 
-        let tuple_type_id =
-            self.dwarf.unit.add(self.dwarf.unit.root(), gimli::DW_TAG_structure_type);
-        let tuple_entry = self.dwarf.unit.get_mut(tuple_type_id);
-        tuple_entry.set(gimli::DW_AT_name, AttributeValue::StringRef(self.dwarf.strings.add(name)));
-        tuple_entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(layout.size.bytes()));
-        tuple_entry.set(gimli::DW_AT_alignment, AttributeValue::Udata(layout.align.pref.bytes()));
+        let tuple_type_id = self.struct_entry(self.dwarf.unit.root(), &name, layout);
 
         for (i, (ty, dw_ty)) in components.into_iter().enumerate() {
-            let member_id = self.dwarf.unit.add(tuple_type_id, gimli::DW_TAG_member);
-            let member_entry = self.dwarf.unit.get_mut(member_id);
-            member_entry.set(
-                gimli::DW_AT_name,
-                AttributeValue::StringRef(self.dwarf.strings.add(format!("__{i}"))),
+            self.add_member(
+                tuple_type_id,
+                &format!("__{i}"),
+                dw_ty,
+                RevealAllLayoutCx(tcx).layout_of(ty),
+                layout.fields.offset(i),
             );
-            member_entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(dw_ty));
-            member_entry.set(
-                gimli::DW_AT_alignment,
-                AttributeValue::Udata(RevealAllLayoutCx(tcx).layout_of(ty).align.pref.bytes()),
+        }
+
+        tuple_type_id
+    }
+
+    fn closure_type<'tcx>(
+        &mut self,
+        tcx: TyCtxt<'tcx>,
+        type_dbg: &mut TypeDebugContext<'tcx>,
+        closure_type: Ty<'tcx>,
+    ) -> UnitEntryId {
+        let (def_id, upvar_tys) = match *closure_type.kind() {
+            ty::Closure(def_id, args) => (def_id, args.as_closure().upvar_tys()),
+            ty::CoroutineClosure(def_id, args) => (def_id, args.as_coroutine_closure().upvar_tys()),
+            _ => unreachable!(),
+        };
+
+        let scope = self.item_namespace(tcx, tcx.parent(def_id));
+        let name = type_names::compute_debuginfo_type_name(tcx, closure_type, false);
+        let layout = RevealAllLayoutCx(tcx).layout_of(closure_type);
+
+        let closure_type_id = self.struct_entry(scope, &name, layout);
+        type_dbg.type_map.insert(closure_type, closure_type_id);
+
+        let capture_names = tcx.closure_saved_names_of_captured_variables(def_id);
+        for (i, (upvar_ty, capture_name)) in upvar_tys.into_iter().zip(capture_names).enumerate() {
+            let upvar_dw_ty = self.debug_type(tcx, type_dbg, upvar_ty);
+            self.add_member(
+                closure_type_id,
+                capture_name.as_str(),
+                upvar_dw_ty,
+                RevealAllLayoutCx(tcx).layout_of(upvar_ty),
+                layout.fields.offset(i),
             );
-            member_entry.set(
-                gimli::DW_AT_data_member_location,
-                AttributeValue::Udata(layout.fields.offset(i).bytes()),
+        }
+
+        closure_type_id
+    }
+
+    fn struct_type<'tcx>(
+        &mut self,
+        tcx: TyCtxt<'tcx>,
+        type_dbg: &mut TypeDebugContext<'tcx>,
+        struct_type: Ty<'tcx>,
+        adt_def: AdtDef<'tcx>,
+    ) -> UnitEntryId {
+        let scope = self.item_namespace(tcx, tcx.parent(adt_def.did()));
+        let name = type_names::compute_debuginfo_type_name(tcx, struct_type, false);
+        let layout = RevealAllLayoutCx(tcx).layout_of(struct_type);
+
+        // Register the struct before describing its fields, as they may point back to it.
+        let struct_type_id = self.struct_entry(scope, &name, layout);
+        type_dbg.type_map.insert(struct_type, struct_type_id);
+
+        self.add_variant_fields(tcx, type_dbg, struct_type_id, adt_def, layout, None);
+
+        struct_type_id
+    }
+
+    fn union_type<'tcx>(
+        &mut self,
+        tcx: TyCtxt<'tcx>,
+        type_dbg: &mut TypeDebugContext<'tcx>,
+        union_type: Ty<'tcx>,
+        adt_def: AdtDef<'tcx>,
+    ) -> UnitEntryId {
+        let scope = self.item_namespace(tcx, tcx.parent(adt_def.did()));
+        let name = type_names::compute_debuginfo_type_name(tcx, union_type, false);
+        let layout = RevealAllLayoutCx(tcx).layout_of(union_type);
+
+        let union_type_id = self.dwarf.unit.add(scope, gimli::DW_TAG_union_type);
+        let union_entry = self.dwarf.unit.get_mut(union_type_id);
+        union_entry.set(gimli::DW_AT_name, AttributeValue::StringRef(self.dwarf.strings.add(name)));
+        union_entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(layout.size.bytes()));
+        union_entry.set(gimli::DW_AT_alignment, AttributeValue::Udata(layout.align.pref.bytes()));
+        type_dbg.type_map.insert(union_type, union_type_id);
+
+        self.add_variant_fields(tcx, type_dbg, union_type_id, adt_def, layout, None);
+
+        union_type_id
+    }
+
+    /// Describes an enum. Enums without fields are described as a `DW_TAG_enumeration_type`.
+    /// All other enums are described like the LLVM backend does on non-MSVC targets:
+    ///
+    /// ```txt
+    /// DW_TAG_structure_type              (top-level type for enum)
+    ///   DW_TAG_variant_part              (variant part)
+    ///     DW_AT_discr                    (reference to discriminant DW_TAG_member)
+    ///     DW_TAG_member                  (discriminant member)
+    ///     DW_TAG_variant                 (variant 1)
+    ///       DW_AT_discr_value            (omitted for the untagged variant of a niche layout)
+    ///       DW_TAG_member                (member of the type of variant 1)
+    ///     DW_TAG_variant                 (variant 2)
+    ///       ...
+    ///   DW_TAG_structure_type            (type of variant 1)
+    ///   DW_TAG_structure_type            (type of variant 2)
+    /// ```
+    fn enum_type<'tcx>(
+        &mut self,
+        tcx: TyCtxt<'tcx>,
+        type_dbg: &mut TypeDebugContext<'tcx>,
+        enum_type: Ty<'tcx>,
+        adt_def: AdtDef<'tcx>,
+    ) -> UnitEntryId {
+        let scope = self.item_namespace(tcx, tcx.parent(adt_def.did()));
+        let name = type_names::compute_debuginfo_type_name(tcx, enum_type, false);
+        let layout = RevealAllLayoutCx(tcx).layout_of(enum_type);
+
+        if wants_c_like_enum_debuginfo(layout) {
+            return self.c_like_enum_type(tcx, type_dbg, scope, &name, layout, adt_def);
+        }
+
+        let enum_type_id = self.struct_entry(scope, &name, layout);
+        type_dbg.type_map.insert(enum_type, enum_type_id);
+
+        let variant_part_id = self.dwarf.unit.add(enum_type_id, gimli::DW_TAG_variant_part);
+
+        let tag_base_type = match layout.variants {
+            // A single-variant enum has no discriminant.
+            Variants::Single { .. } => None,
+            Variants::Multiple { tag_field, .. } => {
+                let tag_base_type = tag_base_type(tcx, layout);
+                let tag_dw_ty = self.debug_type(tcx, type_dbg, tag_base_type);
+                let tag_member_id = self.add_member(
+                    variant_part_id,
+                    "",
+                    tag_dw_ty,
+                    RevealAllLayoutCx(tcx).layout_of(tag_base_type),
+                    layout.fields.offset(tag_field),
+                );
+                let tag_member_entry = self.dwarf.unit.get_mut(tag_member_id);
+                tag_member_entry.set(gimli::DW_AT_artificial, AttributeValue::FlagPresent);
+
+                let variant_part_entry = self.dwarf.unit.get_mut(variant_part_id);
+                variant_part_entry.set(gimli::DW_AT_discr, AttributeValue::UnitRef(tag_member_id));
+                Some(tag_base_type)
+            }
+        };
+
+        for variant_index in adt_def.variant_range() {
+            let variant_def = adt_def.variant(variant_index);
+            let variant_layout = layout.for_variant(&RevealAllLayoutCx(tcx), variant_index);
+
+            // The type of a variant is a struct with the name of the variant and a member for
+            // each field, but not for the discriminant. It uses the size of the whole enum.
+            let variant_struct_id =
+                self.struct_entry(enum_type_id, variant_def.name.as_str(), layout);
+            self.add_variant_fields(
+                tcx,
+                type_dbg,
+                variant_struct_id,
+                adt_def,
+                variant_layout,
+                Some(variant_index),
+            );
+
+            let variant_id = self.dwarf.unit.add(variant_part_id, gimli::DW_TAG_variant);
+            if let Some(tag_base_type) = tag_base_type {
+                if let Some(discr_value) =
+                    discriminant_value(tcx, layout, tag_base_type, variant_index)
+                {
+                    let variant_entry = self.dwarf.unit.get_mut(variant_id);
+                    variant_entry.set(gimli::DW_AT_discr_value, discr_value);
+                }
+            }
+            self.add_member(
+                variant_id,
+                variant_def.name.as_str(),
+                variant_struct_id,
+                layout,
+                Size::ZERO,
             );
         }
 
-        tuple_type_id
+        enum_type_id
+    }
+
+    fn c_like_enum_type<'tcx>(
+        &mut self,
+        tcx: TyCtxt<'tcx>,
+        type_dbg: &mut TypeDebugContext<'tcx>,
+        scope: UnitEntryId,
+        name: &str,
+        layout: TyAndLayout<'tcx>,
+        adt_def: AdtDef<'tcx>,
+    ) -> UnitEntryId {
+        let tag_base_type = tag_base_type(tcx, layout);
+        let tag_dw_ty = self.debug_type(tcx, type_dbg, tag_base_type);
+        let tag_size = RevealAllLayoutCx(tcx).layout_of(tag_base_type).size;
+
+        let enum_type_id = self.dwarf.unit.add(scope, gimli::DW_TAG_enumeration_type);
+        let enum_entry = self.dwarf.unit.get_mut(enum_type_id);
+        enum_entry.set(gimli::DW_AT_name, AttributeValue::StringRef(self.dwarf.strings.add(name)));
+        enum_entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(tag_dw_ty));
+        enum_entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(tag_size.bytes()));
+        enum_entry.set(gimli::DW_AT_alignment, AttributeValue::Udata(layout.align.pref.bytes()));
+
+        for (variant_index, discr) in adt_def.discriminants(tcx) {
+            let variant_name = adt_def.variant(variant_index).name.as_str();
+            let enumerator_id = self.dwarf.unit.add(enum_type_id, gimli::DW_TAG_enumerator);
+            let enumerator_entry = self.dwarf.unit.get_mut(enumerator_id);
+            enumerator_entry.set(
+                gimli::DW_AT_name,
+                AttributeValue::StringRef(self.dwarf.strings.add(variant_name)),
+            );
+            enumerator_entry
+                .set(gimli::DW_AT_const_value, constant_value(tag_base_type, tag_size, discr.val));
+        }
+
+        enum_type_id
+    }
+
+    /// Adds a member for each field of a struct, union or enum variant. `layout` is the layout of
+    /// the variant.
+    fn add_variant_fields<'tcx>(
+        &mut self,
+        tcx: TyCtxt<'tcx>,
+        type_dbg: &mut TypeDebugContext<'tcx>,
+        parent: UnitEntryId,
+        adt_def: AdtDef<'tcx>,
+        layout: TyAndLayout<'tcx>,
+        variant_index: Option<VariantIdx>,
+    ) {
+        let variant_def = match variant_index {
+            Some(variant_index) => adt_def.variant(variant_index),
+            None => adt_def.non_enum_variant(),
+        };
+
+        for (i, field_def) in variant_def.fields.iter().enumerate() {
+            let field_layout = layout.field(&RevealAllLayoutCx(tcx), i);
+            let field_dw_ty = self.debug_type(tcx, type_dbg, field_layout.ty);
+            let field_name = if variant_def.ctor_kind() == Some(CtorKind::Fn) {
+                // Tuple-like
+                format!("__{i}")
+            } else {
+                field_def.name.to_string()
+            };
+            self.add_member(
+                parent,
+                &field_name,
+                field_dw_ty,
+                field_layout,
+                layout.fields.offset(i),
+            );
+        }
+    }
+
+    /// Creates a `DW_TAG_structure_type` without any members.
+    fn struct_entry(
+        &mut self,
+        parent: UnitEntryId,
+        name: &str,
+        layout: TyAndLayout<'_>,
+    ) -> UnitEntryId {
+        let struct_type_id = self.dwarf.unit.add(parent, gimli::DW_TAG_structure_type);
+        let struct_entry = self.dwarf.unit.get_mut(struct_type_id);
+        struct_entry
+            .set(gimli::DW_AT_name, AttributeValue::StringRef(self.dwarf.strings.add(name)));
+        struct_entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(layout.size.bytes()));
+        struct_entry.set(gimli::DW_AT_alignment, AttributeValue::Udata(layout.align.pref.bytes()));
+        struct_type_id
+    }
+
+    fn add_member(
+        &mut self,
+        parent: UnitEntryId,
+        name: &str,
+        dw_ty: UnitEntryId,
+        layout: TyAndLayout<'_>,
+        offset: Size,
+    ) -> UnitEntryId {
+        let member_id = self.dwarf.unit.add(parent, gimli::DW_TAG_member);
+        let member_entry = self.dwarf.unit.get_mut(member_id);
+        member_entry
+            .set(gimli::DW_AT_name, AttributeValue::StringRef(self.dwarf.strings.add(name)));
+        member_entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(dw_ty));
+        member_entry.set(gimli::DW_AT_alignment, AttributeValue::Udata(layout.align.pref.bytes()));
+        member_entry.set(gimli::DW_AT_data_member_location, AttributeValue::Udata(offset.bytes()));
+        member_id
     }
 
     fn placeholder_for_type<'tcx>(
@@ -202,3 +671,69 @@ fn placeholder_for_type<'tcx>(
         )
     }
 }
+
+/// The type with which the tag of the given enum is described.
+fn tag_base_type<'tcx>(tcx: TyCtxt<'tcx>, enum_layout: TyAndLayout<'tcx>) -> Ty<'tcx> {
+    match enum_layout.variants {
+        Variants::Single { .. } => {
+            bug!("tag_base_type() called for enum without tag: {:?}", enum_layout)
+        }
+
+        Variants::Multiple { tag_encoding: TagEncoding::Niche { .. }, tag, .. } => {
+            // Niche tags are always normalized to unsigned integers of the correct size.
+            match tag.primitive() {
+                Primitive::Int(t, _) => t,
+                Primitive::F16 => rustc_target::abi::Integer::I16,
+                Primitive::F32 => rustc_target::abi::Integer::I32,
+                Primitive::F64 => rustc_target::abi::Integer::I64,
+                Primitive::F128 => rustc_target::abi::Integer::I128,
+                // The niche is the null value of a pointer, describe it as `usize`.
+                Primitive::Pointer(_) => tcx.data_layout().ptr_sized_integer(),
+            }
+            .to_ty(tcx, false)
+        }
+
+        Variants::Multiple { tag_encoding: TagEncoding::Direct, tag, .. } => {
+            // Direct tags preserve the sign.
+            tag.primitive().to_ty(tcx)
+        }
+    }
+}
+
+/// The value of the tag that identifies the given variant, or `None` if no single value does,
+/// which is the case for the untagged variant of a niche layout.
+fn discriminant_value<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    enum_layout: TyAndLayout<'tcx>,
+    tag_base_type: Ty<'tcx>,
+    variant_index: VariantIdx,
+) -> Option<AttributeValue> {
+    let tag_size = RevealAllLayoutCx(tcx).layout_of(tag_base_type).size;
+    let value = match enum_layout.variants {
+        Variants::Single { .. } => return None,
+        Variants::Multiple { tag_encoding: TagEncoding::Direct, .. } => {
+            enum_layout.ty.discriminant_for_variant(tcx, variant_index).unwrap().val
+        }
+        Variants::Multiple {
+            tag_encoding: TagEncoding::Niche { ref niche_variants, niche_start, untagged_variant },
+            ..
+        } => {
+            if variant_index == untagged_variant {
+                return None;
+            }
+            (variant_index.as_u32() as u128)
+                .wrapping_sub(niche_variants.start().as_u32() as u128)
+                .wrapping_add(niche_start)
+        }
+    };
+    Some(constant_value(tag_base_type, tag_size, tag_size.truncate(value)))
+}
+
+fn constant_value(ty: Ty<'_>, size: Size, value: u128) -> AttributeValue {
+    // FIXME 128bit values are truncated to 64 bits
+    if ty.is_signed() {
+        AttributeValue::Sdata(size.sign_extend(value) as i128 as i64)
+    } else {
+        AttributeValue::Udata(value as u64)
+    }
+}
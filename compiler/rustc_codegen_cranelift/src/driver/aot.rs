@@ -6,6 +6,7 @@
 use std::sync::Arc;
 use std::thread::JoinHandle;
 
+use cranelift_module::FuncOrDataId;
 use cranelift_object::{ObjectBuilder, ObjectModule};
 use rustc_codegen_ssa::assert_module_sources::CguReuse;
 use rustc_codegen_ssa::back::link::ensure_removed;
@@ -311,17 +312,25 @@ fn emit_cgu(
     prof: &SelfProfilerRef,
     name: String,
     module: ObjectModule,
+    asm_symbol_aliases: FxIndexMap<FuncOrDataId, String>,
     debug: Option<DebugContext>,
+    coverage: Option<CoverageContext>,
     unwind_context: UnwindContext,
     global_asm_object_file: Option<PathBuf>,
     producer: &str,
 ) -> Result<ModuleCodegenResult, String> {
     let mut product = module.finish();
 
+    crate::inline_asm::emit_asm_symbol_aliases(&mut product, asm_symbol_aliases);
+
     if let Some(mut debug) = debug {
         debug.emit(&mut product);
     }
 
+    if let Some(coverage) = coverage {
+        coverage.emit(&mut product);
+    }
+
     unwind_context.emit(&mut product);
 
     let module_regular = emit_module(
@@ -498,11 +507,7 @@ fn module_codegen(
                         }
                     }
                     MonoItem::GlobalAsm(item_id) => {
-                        crate::global_asm::codegen_global_asm_item(
-                            tcx,
-                            &mut cx.global_asm,
-                            item_id,
-                        );
+                        crate::global_asm::codegen_global_asm_item(tcx, &module, &mut cx, item_id);
                     }
                 }
             }
@@ -514,6 +519,10 @@ fn module_codegen(
                 cgu.is_primary(),
             );
 
+            if let Some(coverage_context) = &mut cx.coverage_context {
+                coverage_context.finalize(tcx, cgu);
+            }
+
             let cgu_name = cgu.name().as_str().to_owned();
 
             (cgu_name, cx, module, codegened_functions)
@@ -545,7 +554,9 @@ fn module_codegen(
                     &cx.profiler,
                     cgu_name,
                     module,
+                    cx.asm_symbol_aliases,
                     cx.debug_context,
+                    cx.coverage_context,
                     cx.unwind_context,
                     global_asm_object_file,
                     &producer,
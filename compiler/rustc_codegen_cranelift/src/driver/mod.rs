@@ -35,7 +35,8 @@ fn predefine_mono_items<'tcx>(
                     );
                     module.declare_function(name, linkage, &sig).unwrap();
                 }
-                MonoItem::Static(_) | MonoItem::GlobalAsm(_) => {}
+                MonoItem::Static(def_id) => crate::constant::predefine_static(tcx, module, def_id),
+                MonoItem::GlobalAsm(_) => {}
             }
         }
     });
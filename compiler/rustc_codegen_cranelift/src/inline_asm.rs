@@ -3,6 +3,9 @@
 use std::fmt::Write;
 
 use cranelift_codegen::isa::CallConv;
+use cranelift_module::FuncOrDataId;
+use cranelift_object::ObjectProduct;
+use object::{SymbolFlags, SymbolScope};
 use rustc_ast::ast::{InlineAsmOptions, InlineAsmTemplatePiece};
 use rustc_span::sym;
 use rustc_target::asm::*;
@@ -32,6 +35,9 @@ pub(crate) enum CInlineAsmOperand<'tcx> {
     Symbol {
         symbol: String,
     },
+    Label {
+        block: Block,
+    },
 }
 
 pub(crate) fn codegen_inline_asm_terminator<'tcx>(
@@ -40,7 +46,7 @@ pub(crate) fn codegen_inline_asm_terminator<'tcx>(
     template: &[InlineAsmTemplatePiece],
     operands: &[InlineAsmOperand<'tcx>],
     options: InlineAsmOptions,
-    destination: Option<mir::BasicBlock>,
+    targets: &[mir::BasicBlock],
 ) {
     // Used by panic_abort on Windows, but uses a syntax which only happens to work with
     // asm!() by accident and breaks with the GNU assembler as well as global_asm!() for
@@ -83,12 +89,6 @@ pub(crate) fn codegen_inline_asm_terminator<'tcx>(
                 CInlineAsmOperand::Const { value }
             }
             InlineAsmOperand::SymFn { ref value } => {
-                if cfg!(not(feature = "inline_asm_sym")) {
-                    fx.tcx
-                        .dcx()
-                        .span_err(span, "asm! and global_asm! sym operands are not yet supported");
-                }
-
                 let const_ = fx.monomorphize(value.const_);
                 if let ty::FnDef(def_id, args) = *const_.ty().kind() {
                     let instance = ty::Instance::resolve_for_fn_ptr(
@@ -99,28 +99,9 @@ pub(crate) fn codegen_inline_asm_terminator<'tcx>(
                     )
                     .unwrap();
                     let symbol = fx.tcx.symbol_name(instance);
-
-                    // Pass a wrapper rather than the function itself as the function itself may not
-                    // be exported from the main codegen unit and may thus be unreachable from the
-                    // object file created by an external assembler.
-                    let inline_asm_index = fx.cx.inline_asm_index.get();
-                    fx.cx.inline_asm_index.set(inline_asm_index + 1);
-                    let wrapper_name = format!(
-                        "__inline_asm_{}_wrapper_n{}",
-                        fx.cx.cgu_name.as_str().replace('.', "__").replace('-', "_"),
-                        inline_asm_index
-                    );
-                    let sig =
-                        get_function_sig(fx.tcx, fx.target_config.default_call_conv, instance);
-                    create_wrapper_function(
-                        fx.module,
-                        &mut fx.cx.unwind_context,
-                        sig,
-                        &wrapper_name,
-                        symbol.name,
-                    );
-
-                    CInlineAsmOperand::Symbol { symbol: wrapper_name }
+                    CInlineAsmOperand::Symbol {
+                        symbol: asm_symbol_name(&*fx.module, fx.cx, symbol.name),
+                    }
                 } else {
                     span_bug!(span, "invalid type for asm sym (fn)");
                 }
@@ -128,16 +109,23 @@ pub(crate) fn codegen_inline_asm_terminator<'tcx>(
             InlineAsmOperand::SymStatic { def_id } => {
                 assert!(fx.tcx.is_static(def_id));
                 let instance = Instance::mono(fx.tcx, def_id).polymorphize(fx.tcx);
-                CInlineAsmOperand::Symbol { symbol: fx.tcx.symbol_name(instance).name.to_owned() }
+                let symbol = fx.tcx.symbol_name(instance);
+                CInlineAsmOperand::Symbol {
+                    symbol: asm_symbol_name(&*fx.module, fx.cx, symbol.name),
+                }
             }
-            InlineAsmOperand::Label { .. } => {
-                span_bug!(span, "asm! label operands are not yet supported");
+            InlineAsmOperand::Label { target_index } => {
+                CInlineAsmOperand::Label { block: fx.get_block(targets[target_index]) }
             }
         })
         .collect::<Vec<_>>();
 
     codegen_inline_asm_inner(fx, template, &operands, options);
 
+    // For `noreturn` asm all targets are label targets, otherwise the first target is the block
+    // to continue at when the asm falls through.
+    let destination =
+        if options.contains(InlineAsmOptions::NORETURN) { None } else { Some(targets[0]) };
     match destination {
         Some(destination) => {
             let destination_block = fx.get_block(destination);
@@ -168,6 +156,7 @@ pub(crate) fn codegen_inline_asm_inner<'tcx>(
         stack_slots_clobber: Vec::new(),
         stack_slots_input: Vec::new(),
         stack_slots_output: Vec::new(),
+        stack_slot_label: None,
         stack_slot_size: Size::from_bytes(0),
     };
     asm_gen.allocate_registers();
@@ -186,6 +175,7 @@ pub(crate) fn codegen_inline_asm_inner<'tcx>(
 
     let mut inputs = Vec::new();
     let mut outputs = Vec::new();
+    let mut labels = Vec::new();
     for (i, operand) in operands.iter().enumerate() {
         match operand {
             CInlineAsmOperand::In { reg: _, value } => {
@@ -202,11 +192,83 @@ pub(crate) fn codegen_inline_asm_inner<'tcx>(
                     outputs.push((asm_gen.stack_slots_output[i].unwrap(), *out_place));
                 }
             }
+            CInlineAsmOperand::Label { block } => {
+                labels.push(*block);
+            }
             CInlineAsmOperand::Const { value: _ } | CInlineAsmOperand::Symbol { symbol: _ } => {}
         }
     }
 
-    call_inline_asm(fx, &asm_name, asm_gen.stack_slot_size, inputs, outputs);
+    call_inline_asm(
+        fx,
+        &asm_name,
+        asm_gen.stack_slot_size,
+        inputs,
+        outputs,
+        asm_gen.stack_slot_label.map(|slot| (slot, labels)),
+    );
+}
+
+/// Returns the name by which assembly generated for `asm!` or `global_asm!` can refer to the
+/// function or static called `symbol_name`.
+///
+/// This assembly ends up in a separate object file, so it can't reference symbols that are local
+/// to the object file of the current codegen unit. For those a hidden alias is used instead, which
+/// gets defined by [`emit_asm_symbol_aliases`].
+pub(crate) fn asm_symbol_name(
+    module: &dyn Module,
+    cx: &mut crate::CodegenCx,
+    symbol_name: &str,
+) -> String {
+    // Symbols which are not declared yet are defined in another codegen unit and thus not local.
+    let Some(id) = module.get_name(symbol_name) else {
+        return symbol_name.to_owned();
+    };
+    let linkage = match id {
+        FuncOrDataId::Func(func_id) => module.declarations().get_function_decl(func_id).linkage,
+        FuncOrDataId::Data(data_id) => module.declarations().get_data_decl(data_id).linkage,
+    };
+    if linkage != Linkage::Local {
+        return symbol_name.to_owned();
+    }
+
+    cx.asm_symbol_aliases
+        .entry(id)
+        .or_insert_with(|| {
+            let inline_asm_index = cx.inline_asm_index.get();
+            cx.inline_asm_index.set(inline_asm_index + 1);
+            format!(
+                "__inline_asm_{}_sym_n{}",
+                cx.cgu_name.as_str().replace('.', "__").replace('-', "_"),
+                inline_asm_index
+            )
+        })
+        .clone()
+}
+
+/// Defines the aliases returned by [`asm_symbol_name`] for local symbols.
+pub(crate) fn emit_asm_symbol_aliases(
+    product: &mut ObjectProduct,
+    aliases: FxIndexMap<FuncOrDataId, String>,
+) {
+    for (id, alias) in aliases {
+        let symbol_id = match id {
+            FuncOrDataId::Func(func_id) => product.function_symbol(func_id),
+            FuncOrDataId::Data(data_id) => product.data_symbol(data_id),
+        };
+        let symbol = product.object.symbol(symbol_id);
+        let alias = object::write::Symbol {
+            name: alias.into_bytes(),
+            value: symbol.value,
+            size: symbol.size,
+            kind: symbol.kind,
+            scope: SymbolScope::Linkage,
+            weak: false,
+            section: symbol.section,
+            flags: SymbolFlags::None,
+        };
+        product.object.add_symbol(alias);
+    }
 }
 
 struct InlineAssemblyGenerator<'a, 'tcx> {
@@ -220,6 +282,9 @@ struct InlineAssemblyGenerator<'a, 'tcx> {
     stack_slots_clobber: Vec<Option<Size>>,
     stack_slots_input: Vec<Option<Size>>,
     stack_slots_output: Vec<Option<Size>>,
+    /// Stack slot in which the asm wrapper stores which label the asm jumped to, if any. `0` means
+    /// that the asm fell through and `n` that it jumped to the `n`th label operand.
+    stack_slot_label: Option<Size>,
     stack_slot_size: Size,
 }
 
@@ -432,12 +497,26 @@ fn allocate_stack_slots(&mut self) {
 
         slot_size = slot_size.max(slot_size_after_input);
 
+        // Allocate stack slot for the label selector
+        let slot_label = if self.has_labels() {
+            let offset = slot_size.align_to(rustc_target::abi::Align::from_bytes(4).unwrap());
+            slot_size = offset + Size::from_bytes(4);
+            Some(offset)
+        } else {
+            None
+        };
+
         self.stack_slots_clobber = slots_clobber;
         self.stack_slots_input = slots_input;
         self.stack_slots_output = slots_output;
+        self.stack_slot_label = slot_label;
         self.stack_slot_size = slot_size;
     }
 
+    fn has_labels(&self) -> bool {
+        self.operands.iter().any(|operand| matches!(operand, CInlineAsmOperand::Label { .. }))
+    }
+
     fn generate_asm_wrapper(&self, asm_name: &str) -> String {
         let binary_format = crate::target_triple(self.tcx.sess).binary_format;
 
@@ -470,8 +549,9 @@ fn generate_asm_wrapper(&self, asm_name: &str) -> String {
         }
         Self::prologue(&mut generated_asm, self.arch);
 
-        // Save clobbered registers
-        if !self.options.contains(InlineAsmOptions::NORETURN) {
+        // Save clobbered registers. Jumps to labels return from the wrapper, so the registers
+        // need to be restored even for noreturn asm in that case.
+        if !self.options.contains(InlineAsmOptions::NORETURN) || self.has_labels() {
             for (reg, slot) in self
                 .registers
                 .iter()
@@ -543,6 +623,9 @@ fn generate_asm_wrapper(&self, asm_name: &str) -> String {
                             generated_asm.push_str(value);
                         }
                         CInlineAsmOperand::Symbol { ref symbol } => generated_asm.push_str(symbol),
+                        CInlineAsmOperand::Label { block: _ } => {
+                            write!(generated_asm, "{asm_name}_label{operand_idx}").unwrap();
+                        }
                     }
                 }
             }
@@ -564,21 +647,35 @@ fn generate_asm_wrapper(&self, asm_name: &str) -> String {
                 Self::save_register(&mut generated_asm, self.arch, reg, slot);
             }
 
-            // Restore clobbered registers
-            for (reg, slot) in self
-                .registers
-                .iter()
-                .zip(self.stack_slots_clobber.iter().copied())
-                .filter_map(|(r, s)| r.zip(s))
-            {
-                Self::restore_register(&mut generated_asm, self.arch, reg, slot);
+            if let Some(slot) = self.stack_slot_label {
+                Self::write_label_selector(&mut generated_asm, self.arch, slot, 0);
             }
 
-            Self::epilogue(&mut generated_asm, self.arch);
+            self.restore_clobbers_and_return(&mut generated_asm);
         } else {
             Self::epilogue_noreturn(&mut generated_asm, self.arch);
         }
 
+        // Label operands. Each one records which label was jumped to and then returns like the
+        // fallthrough path does. The caller dispatches on the recorded value.
+        for (label_idx, operand_idx) in self
+            .operands
+            .iter()
+            .enumerate()
+            .filter(|(_, operand)| matches!(operand, CInlineAsmOperand::Label { .. }))
+            .map(|(operand_idx, _)| operand_idx)
+            .enumerate()
+        {
+            writeln!(generated_asm, "{asm_name}_label{operand_idx}:").unwrap();
+            Self::write_label_selector(
+                &mut generated_asm,
+                self.arch,
+                self.stack_slot_label.unwrap(),
+                u32::try_from(label_idx + 1).unwrap(),
+            );
+            self.restore_clobbers_and_return(&mut generated_asm);
+        }
+
         if is_x86 {
             generated_asm.push_str(".att_syntax\n");
         }
@@ -600,6 +697,19 @@ fn generate_asm_wrapper(&self, asm_name: &str) -> String {
         generated_asm
     }
 
+    fn restore_clobbers_and_return(&self, generated_asm: &mut String) {
+        for (reg, slot) in self
+            .registers
+            .iter()
+            .zip(self.stack_slots_clobber.iter().copied())
+            .filter_map(|(r, s)| r.zip(s))
+        {
+            Self::restore_register(generated_asm, self.arch, reg, slot);
+        }
+
+        Self::epilogue(generated_asm, self.arch);
+    }
+
     fn prologue(generated_asm: &mut String, arch: InlineAsmArch) {
         match arch {
             InlineAsmArch::X86_64 => {
@@ -664,6 +774,31 @@ fn epilogue_noreturn(generated_asm: &mut String, arch: InlineAsmArch) {
         }
     }
 
+    fn write_label_selector(
+        generated_asm: &mut String,
+        arch: InlineAsmArch,
+        offset: Size,
+        value: u32,
+    ) {
+        match arch {
+            InlineAsmArch::X86_64 => {
+                writeln!(generated_asm, "    mov dword ptr [rbx+0x{:x}], {value}", offset.bytes())
+                    .unwrap();
+            }
+            InlineAsmArch::AArch64 => {
+                // lr is restored by the epilogue, so it is free to use as scratch register here
+                writeln!(generated_asm, "    mov w30, #{}", value).unwrap();
+                writeln!(generated_asm, "    str w30, [x19, 0x{:x}]", offset.bytes()).unwrap();
+            }
+            InlineAsmArch::RiscV64 => {
+                // ra is restored by the epilogue, so it is free to use as scratch register here
+                writeln!(generated_asm, "    li ra, {}", value).unwrap();
+                writeln!(generated_asm, "    sw ra, 0x{:x}(s1)", offset.bytes()).unwrap();
+            }
+            _ => unimplemented!("write_label_selector for {:?}", arch),
+        }
+    }
+
     fn save_register(
         generated_asm: &mut String,
         arch: InlineAsmArch,
@@ -752,6 +887,7 @@ fn call_inline_asm<'tcx>(
     slot_size: Size,
     inputs: Vec<(Size, Value)>,
     outputs: Vec<(Size, CPlace<'tcx>)>,
+    labels: Option<(Size, Vec<Block>)>,
 ) {
     let stack_slot = fx.create_stack_slot(u32::try_from(slot_size.bytes()).unwrap(), 16);
 
@@ -783,6 +919,21 @@ fn call_inline_asm<'tcx>(
     let stack_slot_addr = stack_slot.get_addr(fx);
     fx.bcx.ins().call(inline_asm_func, &[stack_slot_addr]);
 
+    if let Some((offset, labels)) = labels {
+        let selector = stack_slot.offset(fx, i32::try_from(offset.bytes()).unwrap().into()).load(
+            fx,
+            types::I32,
+            MemFlags::trusted(),
+        );
+        let fallthrough_block = fx.bcx.create_block();
+        let mut switch = ::cranelift_frontend::Switch::new();
+        for (i, block) in labels.into_iter().enumerate() {
+            switch.set_entry(u128::try_from(i + 1).unwrap(), block);
+        }
+        switch.emit(&mut fx.bcx, selector, fallthrough_block);
+        fx.bcx.switch_to_block(fallthrough_block);
+    }
+
     for (offset, place) in outputs {
         let ty = if place.layout().ty.is_simd() {
             let (lane_count, lane_type) = place.layout().ty.simd_size_and_type(fx.tcx);
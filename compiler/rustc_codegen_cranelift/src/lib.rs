@@ -61,6 +61,7 @@
 mod concurrency_limiter;
 mod config;
 mod constant;
+mod coverage;
 mod debuginfo;
 mod discriminant;
 mod driver;
@@ -104,6 +105,7 @@ mod prelude {
     pub(crate) use crate::base::{codegen_operand, codegen_place};
     pub(crate) use crate::cast::*;
     pub(crate) use crate::common::*;
+    pub(crate) use crate::coverage::CoverageContext;
     pub(crate) use crate::debuginfo::{DebugContext, UnwindContext};
     pub(crate) use crate::pointer::Pointer;
     pub(crate) use crate::value_and_place::{CPlace, CValue};
@@ -126,7 +128,9 @@ struct CodegenCx {
     should_write_ir: bool,
     global_asm: String,
     inline_asm_index: Cell<usize>,
+    asm_symbol_aliases: FxIndexMap<cranelift_module::FuncOrDataId, String>,
     debug_context: Option<DebugContext>,
+    coverage_context: Option<CoverageContext>,
     unwind_context: UnwindContext,
     cgu_name: Symbol,
 }
@@ -148,13 +152,23 @@ fn new(
         } else {
             None
         };
+        // FIXME support coverage instrumentation in jit mode
+        let coverage_context = if tcx.sess.instrument_coverage()
+            && matches!(backend_config.codegen_mode, CodegenMode::Aot)
+        {
+            Some(CoverageContext::new(tcx, isa))
+        } else {
+            None
+        };
         CodegenCx {
             profiler: tcx.prof.clone(),
             output_filenames: tcx.output_filenames(()).clone(),
             should_write_ir: crate::pretty_clif::should_write_ir(tcx),
             global_asm: String::new(),
             inline_asm_index: Cell::new(0),
+            asm_symbol_aliases: FxIndexMap::default(),
             debug_context,
+            coverage_context,
             unwind_context,
             cgu_name,
         }
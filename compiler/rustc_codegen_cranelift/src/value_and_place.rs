@@ -2,9 +2,11 @@
 
 use cranelift_codegen::entity::EntityRef;
 use cranelift_codegen::ir::immediates::Offset32;
+use cranelift_codegen::ir::ValueLabel;
 use cranelift_frontend::Variable;
 use rustc_middle::ty::FnSig;
 
+use crate::debuginfo::VariableLocation;
 use crate::prelude::*;
 
 fn codegen_field<'tcx>(
@@ -453,14 +455,14 @@ pub(crate) fn to_cvalue(self, fx: &mut FunctionCx<'_, '_, 'tcx>) -> CValue<'tcx>
         match self.inner {
             CPlaceInner::Var(_local, var) => {
                 let val = fx.bcx.use_var(var);
-                //fx.bcx.set_val_label(val, cranelift_codegen::ir::ValueLabel::new(var.index()));
+                fx.bcx.set_val_label(val, ValueLabel::new(var.index()));
                 CValue::by_val(val, layout)
             }
             CPlaceInner::VarPair(_local, var1, var2) => {
                 let val1 = fx.bcx.use_var(var1);
-                //fx.bcx.set_val_label(val1, cranelift_codegen::ir::ValueLabel::new(var1.index()));
+                fx.bcx.set_val_label(val1, ValueLabel::new(var1.index()));
                 let val2 = fx.bcx.use_var(var2);
-                //fx.bcx.set_val_label(val2, cranelift_codegen::ir::ValueLabel::new(var2.index()));
+                fx.bcx.set_val_label(val2, ValueLabel::new(var2.index()));
                 CValue::by_val_pair(val1, val2, layout)
             }
             CPlaceInner::Addr(ptr, extra) => {
@@ -497,6 +499,33 @@ pub(crate) fn debug_comment(self) -> (&'static str, String) {
         }
     }
 
+    /// Where the value of this place can be found by a debugger, if that can be described.
+    pub(crate) fn debuginfo_location(
+        self,
+        fx: &FunctionCx<'_, '_, '_>,
+    ) -> Option<VariableLocation> {
+        match self.inner {
+            CPlaceInner::Var(_local, var) => {
+                Some(VariableLocation::Var(ValueLabel::new(var.index())))
+            }
+            CPlaceInner::VarPair(_local, var1, var2) => {
+                let Abi::ScalarPair(a, b) = self.layout().abi else { unreachable!() };
+                let b_offset = a.size(fx).align_to(b.align(fx).abi);
+                Some(VariableLocation::Pieces(vec![
+                    (Size::ZERO, a.size(fx), VariableLocation::Var(ValueLabel::new(var1.index()))),
+                    (b_offset, b.size(fx), VariableLocation::Var(ValueLabel::new(var2.index()))),
+                ]))
+            }
+            CPlaceInner::Addr(ptr, None) => match ptr.debug_base_and_offset() {
+                (crate::pointer::PointerBase::Stack(stack_slot), offset) => {
+                    Some(VariableLocation::Stack(stack_slot, offset.into()))
+                }
+                _ => None,
+            },
+            CPlaceInner::Addr(_, Some(_)) => None,
+        }
+    }
+
     #[track_caller]
     pub(crate) fn to_ptr(self) -> Pointer {
         match self.inner {
@@ -581,7 +610,7 @@ fn transmute_scalar<'tcx>(
                 // calling `write_cvalue` you need to add a `bint` instruction.
                 _ => unreachable!("write_cvalue_transmute: {:?} -> {:?}", src_ty, dst_ty),
             };
-            //fx.bcx.set_val_label(data, cranelift_codegen::ir::ValueLabel::new(var.index()));
+            fx.bcx.set_val_label(data, ValueLabel::new(var.index()));
             fx.bcx.def_var(var, data);
         }
 
@@ -93,6 +93,9 @@ pub(crate) fn get_vtable<'tcx>(
     trait_ref: Option<ty::PolyExistentialTraitRef<'tcx>>,
 ) -> Value {
     let data_id = data_id_for_vtable(fx.tcx, &mut fx.constants_cx, fx.module, ty, trait_ref);
+    if fx.func_debug_cx.is_some() {
+        fx.vtables.insert(data_id, (ty, trait_ref));
+    }
     let local_data_id = fx.module.declare_data_in_func(data_id, fx.bcx.func);
     if fx.clif_comments.enabled() {
         fx.add_comment(local_data_id, "vtable");
@@ -35,6 +35,10 @@ fn get_runners() -> Runners {
         "--projects",
         ("Run the tests of popular crates", test_projects),
     );
+    runners.insert(
+        "--test-debuginfo",
+        ("Run rustc debuginfo tests with gdb", test_debuginfo),
+    );
     runners.insert("--test-libcore", ("Run libcore tests", test_libcore));
     runners.insert("--clean", ("Empty cargo target directory", clean));
     runners.insert("--build-sysroot", ("Build sysroot", build_sysroot));
@@ -1160,6 +1164,57 @@ fn test_successful_rustc(env: &Env, args: &TestArg) -> Result<(), String> {
     })
 }
 
+fn test_debuginfo(env: &Env, args: &TestArg) -> Result<(), String> {
+    // FIXME: create a function "display_if_not_quiet" or something along the line.
+    println!("[TEST] rust-lang/rust debuginfo");
+    let mut env = env.clone();
+    let rust_path = setup_rustc(&mut env, args)?;
+
+    // Removing the tests which are known to not work with the GCC backend, for instance because
+    // libgccjit doesn't support lexical blocks (see `doc/tests.md`).
+    let path = "tests/failing-debuginfo-tests.txt";
+    if let Ok(files) = std::fs::read_to_string(path) {
+        for file in files
+            .split('\n')
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+        {
+            remove_file(&rust_path.join(file))?;
+        }
+    } else {
+        println!("Failed to read `{}`, running all debuginfo tests", path);
+    }
+
+    // FIXME: create a function "display_if_not_quiet" or something along the line.
+    println!("[TEST] rustc debuginfo test suite");
+    env.insert("COMPILETEST_FORCE_STAGE0".to_string(), "1".to_string());
+
+    let rustc_args = format!(
+        "{} -Zcodegen-backend={} --sysroot {}",
+        env.get("TEST_FLAGS").unwrap_or(&String::new()),
+        args.config_info.cg_backend_path,
+        args.config_info.sysroot_path,
+    );
+
+    env.get_mut("RUSTFLAGS").unwrap().clear();
+    run_command_with_output_and_env(
+        &[
+            &"./x.py",
+            &"test",
+            &"--run",
+            &"always",
+            &"--stage",
+            &"0",
+            &"tests/debuginfo",
+            &"--rustc-args",
+            &rustc_args,
+        ],
+        Some(&rust_path),
+        Some(&env),
+    )?;
+    Ok(())
+}
+
 fn run_all(env: &Env, args: &TestArg) -> Result<(), String> {
     clean(env, args)?;
     mini_tests(env, args)?;
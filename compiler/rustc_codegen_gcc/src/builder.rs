@@ -27,7 +27,8 @@
 };
 use rustc_middle::ty::{ParamEnv, Ty, TyCtxt, Instance};
 use rustc_span::def_id::DefId;
-use rustc_span::Span;
+use rustc_session::config::DebugInfo;
+use rustc_span::{Span, Symbol};
 use rustc_target::abi::{
     self, call::FnAbi, Align, HasDataLayout, Size, TargetDataLayout, WrappingRange,
 };
@@ -911,6 +912,25 @@ fn alloca(&mut self, size: Size, align: Align) -> RValue<'gcc> {
             .get_address(self.location)
     }
 
+    /// libgccjit derives the debuginfo of a variable from the local holding it and can't rename
+    /// a local, so the stack slot of a user variable is a local with the name of the variable and
+    /// its debug type (see `debuginfo/types.rs`), which is at least as large as the variable.
+    fn var_alloca(&mut self, layout: TyAndLayout<'tcx>, name: Symbol) -> RValue<'gcc> {
+        if self.cx.sess().opts.debuginfo != DebugInfo::Full {
+            return self.alloca(layout.size, layout.align.abi);
+        }
+
+        let ty = self.cx.debug_type(layout.ty);
+        // FIXME(antoyo): this check that we don't call get_aligned() a second time on a type.
+        // Ideally, we shouldn't need to do this check.
+        let ty = if ty == self.cx.u128_type || ty == self.cx.i128_type {
+            ty
+        } else {
+            ty.get_aligned(layout.align.abi.bytes())
+        };
+        self.current_func().new_local(self.location, ty, name.as_str()).get_address(self.location)
+    }
+
     fn dynamic_alloca(&mut self, _len: RValue<'gcc>, _align: Align) -> RValue<'gcc> {
         unimplemented!();
     }
@@ -79,6 +79,9 @@ pub struct CodegenCx<'gcc, 'tcx> {
 
     pub struct_types: RefCell<FxHashMap<Vec<Type<'gcc>>, Type<'gcc>>>,
 
+    /// Cache of the types used to describe user variables to the debugger.
+    pub debug_types: RefCell<FxHashMap<Ty<'tcx>, Type<'gcc>>>,
+
     /// Cache instances of monomorphic and polymorphic items
     pub instances: RefCell<FxHashMap<Instance<'tcx>, LValue<'gcc>>>,
     /// Cache function instances of monomorphic and polymorphic items
@@ -325,6 +328,7 @@ pub fn new(
             types: Default::default(),
             tcx,
             struct_types: Default::default(),
+            debug_types: Default::default(),
             local_gen_sym_counter: Cell::new(0),
             eh_personality: Cell::new(None),
             #[cfg(feature="master")]
@@ -412,7 +416,7 @@ impl<'gcc, 'tcx> BackendTypes for CodegenCx<'gcc, 'tcx> {
     type Type = Type<'gcc>;
     type Funclet = (); // TODO(antoyo)
 
-    type DIScope = (); // TODO(antoyo)
+    type DIScope = ();
     type DILocation = Location<'gcc>;
     type DIVariable = (); // TODO(antoyo)
 }
@@ -0,0 +1,91 @@
+// .debug_gdb_scripts binary section.
+
+use gccjit::RValue;
+use rustc_ast::attr;
+use rustc_codegen_ssa::base::collect_debugger_visualizers_transitive;
+use rustc_codegen_ssa::traits::{BaseTypeMethods, BuilderMethods};
+use rustc_hir::def_id::LOCAL_CRATE;
+use rustc_middle::middle::debugger_visualizer::DebuggerVisualizerType;
+use rustc_session::config::{CrateType, DebugInfo};
+use rustc_span::symbol::sym;
+
+use crate::builder::Builder;
+use crate::context::CodegenCx;
+
+const SECTION_VAR_NAME: &str = "__rustc_debug_gdb_scripts_section__";
+
+/// Inserts a side-effect free instruction sequence that makes sure that the
+/// .debug_gdb_scripts global is referenced, so it isn't removed by the linker.
+pub fn insert_reference_to_gdb_debug_scripts_section_global(bx: &mut Builder<'_, '_, '_>) {
+    if needs_gdb_debug_scripts_section(bx.cx) {
+        let gdb_debug_scripts_section = get_or_insert_gdb_debug_scripts_section_global(bx.cx);
+        // Load just the first byte as that's all that's necessary to force
+        // GCC to keep around the reference to the global.
+        let u8_type = bx.type_u8();
+        let volatile_load = bx.volatile_load(u8_type, gdb_debug_scripts_section);
+        // The load has to be evaluated on its own since its value is unused.
+        bx.llbb().add_eval(bx.location, volatile_load);
+    }
+}
+
+/// Allocates the global variable responsible for the .debug_gdb_scripts binary
+/// section.
+///
+/// NOTE: unlike cg_llvm, which emits this section in every codegen unit with a linkonce linkage,
+/// this is only called for the codegen unit containing the entry point, so an internal global
+/// is enough.
+pub fn get_or_insert_gdb_debug_scripts_section_global<'gcc>(
+    cx: &CodegenCx<'gcc, '_>,
+) -> RValue<'gcc> {
+    if let Some(&section_var) = cx.globals.borrow().get(SECTION_VAR_NAME) {
+        return section_var;
+    }
+
+    let mut section_contents = Vec::new();
+
+    // Add the pretty printers for the standard library first.
+    section_contents.extend_from_slice(b"\x01gdb_load_rust_pretty_printers.py\0");
+
+    // Next, add the pretty printers that were specified via the `#[debugger_visualizer]` attribute.
+    let visualizers =
+        collect_debugger_visualizers_transitive(cx.tcx, DebuggerVisualizerType::GdbPrettyPrinter);
+    let crate_name = cx.tcx.crate_name(LOCAL_CRATE);
+    for (index, visualizer) in visualizers.iter().enumerate() {
+        // The initial byte `4` instructs GDB that the following pretty printer
+        // is defined inline as opposed to in a standalone file.
+        section_contents.extend_from_slice(b"\x04");
+        let vis_name = format!("pretty-printer-{crate_name}-{index}\n");
+        section_contents.extend_from_slice(vis_name.as_bytes());
+        section_contents.extend_from_slice(&visualizer.src);
+
+        // The final byte `0` tells GDB that the pretty printer has been
+        // fully defined and can continue searching for additional
+        // pretty printers.
+        section_contents.extend_from_slice(b"\0");
+    }
+
+    let typ = cx.type_array(cx.type_u8(), section_contents.len() as u64).make_const();
+    let section_var = cx.declare_private_global(SECTION_VAR_NAME, typ);
+    section_var.set_link_section(".debug_gdb_scripts");
+    section_var.global_set_initializer_rvalue(cx.const_bytes(&section_contents));
+    // This should make sure that the whole section is not larger than
+    // the string it contains. Otherwise we get a warning from GDB.
+    section_var.set_alignment(1);
+    section_var.get_address(None)
+}
+
+pub fn needs_gdb_debug_scripts_section(cx: &CodegenCx<'_, '_>) -> bool {
+    let omit_gdb_pretty_printer_section =
+        attr::contains_name(cx.tcx.hir().krate_attrs(), sym::omit_gdb_pretty_printer_section);
+
+    // The section is only referenced from the entry point (see
+    // `get_or_insert_gdb_debug_scripts_section_global`), so it is only emitted for executables.
+    // This also avoids emitting it for rlibs, where each rlib could produce a different set of
+    // visualizers.
+    let embed_visualizers = cx.tcx.crate_types().contains(&CrateType::Executable);
+
+    !omit_gdb_pretty_printer_section
+        && cx.sess().opts.debuginfo != DebugInfo::None
+        && cx.sess().target.emit_debug_gdb_scripts
+        && embed_visualizers
+}
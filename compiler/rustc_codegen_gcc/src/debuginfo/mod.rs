@@ -16,12 +16,23 @@
 use crate::builder::Builder;
 use crate::context::CodegenCx;
 
+mod gdb;
+mod types;
+
 pub(super) const UNKNOWN_LINE_NUMBER: u32 = 0;
 pub(super) const UNKNOWN_COLUMN_NUMBER: u32 = 0;
 
 impl<'a, 'gcc, 'tcx> DebugInfoBuilderMethods for Builder<'a, 'gcc, 'tcx> {
     // FIXME(eddyb) find a common convention for all of the debuginfo-related
     // names (choose between `dbg`, `debug`, `debuginfo`, `debug_info` etc.).
+
+    /// libgccjit can't attach a variable to an existing local: a user variable is described by
+    /// its stack slot, which `var_alloca` creates with the name and the type of the variable.
+    ///
+    /// FIXME: variables which don't have a stack slot of their own aren't described. Those are
+    /// the variables at an offset in a local, the variables behind a pointer, like the captures of
+    /// a closure and the arguments passed by reference, and the variables split by SROA. They
+    /// need a DWARF location expression, which libgccjit can't emit.
     fn dbg_var_addr(
         &mut self,
         _dbg_var: Self::DIVariable,
@@ -31,18 +42,16 @@ fn dbg_var_addr(
         _indirect_offsets: &[Size],
         _fragment: Option<Range<Size>>,
     ) {
-        // FIXME(tempdragon): Not sure if this is correct, probably wrong but still keep it here.
         #[cfg(feature = "master")]
         _variable_alloca.set_location(_dbg_loc);
     }
 
     fn insert_reference_to_gdb_debug_scripts_section_global(&mut self) {
-        // TODO(antoyo): insert reference to gdb debug scripts section global.
+        gdb::insert_reference_to_gdb_debug_scripts_section_global(self);
     }
 
-    /// FIXME(tempdragon): Currently, this function is not yet implemented. It seems that the
-    /// debug name and the mangled name should both be included in the LValues.
-    /// Besides, a function to get the rvalue type(m_is_lvalue) should also be included.
+    /// NOTE: The name of a local is given when creating it in libgccjit and can't be changed
+    /// afterwards. The stack slots of user variables are named by `var_alloca` instead.
     fn set_var_name(&mut self, _value: RValue<'gcc>, _name: &str) {}
 
     fn set_dbg_loc(&mut self, dbg_loc: Self::DILocation) {
@@ -203,7 +212,11 @@ fn create_vtable_debuginfo(
         _trait_ref: Option<PolyExistentialTraitRef<'tcx>>,
         _vtable: Self::Value,
     ) {
-        // TODO(antoyo)
+        // NOTE: gdb finds the concrete type of a trait object through a variable named
+        // `<T as Trait>::{vtable}` located at the vtable, with a `DW_AT_containing_type` pointing
+        // to `T`. libgccjit can neither rename the global holding the vtable nor emit this
+        // attribute, so there is nothing useful to emit here. Trait objects are shown with their
+        // data pointer and vtable address (see `types.rs`).
     }
 
     fn create_function_debug_context(
@@ -235,12 +248,13 @@ fn create_function_debug_context(
         Some(fn_debug_context)
     }
 
+    /// NOTE: libgccjit takes the file of every statement from its location, so scopes don't need
+    /// to know about files.
     fn extend_scope_to_file(
         &self,
         _scope_metadata: Self::DIScope,
         _file: &SourceFile,
     ) -> Self::DIScope {
-        // TODO(antoyo): implement.
     }
 
     fn debuginfo_finalize(&self) {
@@ -257,13 +271,16 @@ fn create_dbg_var(
     ) -> Self::DIVariable {
     }
 
+    /// NOTE: libgccjit emits the `DW_TAG_subprogram` of every function by itself and all locals of
+    /// a function are in the scope of that function since libgccjit doesn't support lexical
+    /// blocks. The scopes are thus empty. Shadowed variables show up as several variables with
+    /// the same name in the function.
     fn dbg_scope_fn(
         &self,
         _instance: Instance<'tcx>,
         _fn_abi: &FnAbi<'tcx, Ty<'tcx>>,
         _maybe_definition_llfn: Option<RValue<'gcc>>,
     ) -> Self::DIScope {
-        // TODO(antoyo): implement.
     }
 
     fn dbg_loc(
@@ -0,0 +1,225 @@
+//! Types used to describe user variables to the debugger.
+//!
+//! The types used for codegen (see `type_of.rs`) only reflect the ABI of a Rust type: fields are
+//! called `field_N`, enums are opaque blobs and every type has padding fields. Since libgccjit
+//! derives the DWARF of a variable from its type, this module builds a separate set of types which
+//! mirror the Rust types as closely as possible, with the type names produced by
+//! `compute_debuginfo_type_name` so that the Rust pretty printers of gdb recognize them.
+//!
+//! Those types are only used as the type of the stack slot of user variables (see `var_alloca` in
+//! `builder.rs`) and values are never accessed through them. Their layout has to match the Rust
+//! layout for the debugger to read the right bytes, and they must be at least as large as the Rust
+//! type to hold the variable.
+
+use gccjit::{Field, Type};
+use rustc_codegen_ssa::debuginfo::type_names::compute_debuginfo_type_name;
+use rustc_codegen_ssa::traits::BaseTypeMethods;
+use rustc_middle::ty::layout::{LayoutOf, TyAndLayout};
+use rustc_middle::ty::{self, Ty};
+use rustc_target::abi::{Abi, FieldIdx, FieldsShape, Size, Variants};
+
+use crate::context::CodegenCx;
+
+impl<'gcc, 'tcx> CodegenCx<'gcc, 'tcx> {
+    /// Returns the debug type of `ty`. See the module documentation.
+    pub fn debug_type(&self, ty: Ty<'tcx>) -> Type<'gcc> {
+        if let Some(&typ) = self.debug_types.borrow().get(&ty) {
+            return typ;
+        }
+
+        let layout = self.layout_of(ty);
+        let typ = match *ty.kind() {
+            ty::Bool => self.bool_type,
+            // FIXME: libgccjit only has C base types, so `char` can't be described with the
+            // `DW_ATE_UTF` encoding that makes gdb print it as a character.
+            ty::Char => self.u32_type,
+            ty::Int(int_ty) => match int_ty {
+                ty::IntTy::I8 => self.i8_type,
+                ty::IntTy::I16 => self.i16_type,
+                ty::IntTy::I32 => self.i32_type,
+                ty::IntTy::I64 => self.i64_type,
+                ty::IntTy::I128 => self.i128_type,
+                ty::IntTy::Isize => self.isize_type,
+            },
+            ty::Uint(uint_ty) => match uint_ty {
+                ty::UintTy::U8 => self.u8_type,
+                ty::UintTy::U16 => self.u16_type,
+                ty::UintTy::U32 => self.u32_type,
+                ty::UintTy::U64 => self.u64_type,
+                ty::UintTy::U128 => self.u128_type,
+                ty::UintTy::Usize => self.usize_type,
+            },
+            ty::Float(ty::FloatTy::F32) => self.float_type,
+            ty::Float(ty::FloatTy::F64) => self.double_type,
+            ty::RawPtr(pointee, _) | ty::Ref(_, pointee, _) => self.debug_pointer_type(ty, pointee),
+            ty::Adt(def, _) if def.is_box() => self.debug_pointer_type(ty, ty.boxed_ty()),
+            ty::FnPtr(_) => self.type_void().make_pointer(),
+            ty::Array(element, _) => {
+                let FieldsShape::Array { count, .. } = layout.fields else {
+                    unreachable!("array without an array layout");
+                };
+                self.context.new_array_type(None, self.debug_type(element), count)
+            }
+            ty::Adt(def, _) if def.is_enum() || def.is_union() => self.debug_union_type(layout),
+            ty::Adt(..) | ty::Tuple(_) | ty::Closure(..) if layout.is_sized() => {
+                self.debug_struct_type(layout)
+            }
+            _ => self.opaque(layout),
+        };
+
+        self.debug_types.borrow_mut().insert(ty, typ);
+        typ
+    }
+
+    fn debug_type_name(&self, ty: Ty<'tcx>) -> String {
+        compute_debuginfo_type_name(self.tcx, ty, true)
+    }
+
+    fn debug_pointer_type(&self, ty: Ty<'tcx>, pointee: Ty<'tcx>) -> Type<'gcc> {
+        let pointee_tail = self.tcx.struct_tail_erasing_lifetimes(pointee, self.param_env());
+        let data_ptr = |pointee: Ty<'tcx>| {
+            let field_type = self.debug_type(pointee).make_pointer();
+            self.context.new_field(None, field_type, "data_ptr")
+        };
+        let fields = match *pointee_tail.kind() {
+            _ if pointee.is_sized(self.tcx, self.param_env()) => {
+                return self.debug_type(pointee).make_pointer();
+            }
+            ty::Slice(element) => {
+                vec![data_ptr(element), self.context.new_field(None, self.usize_type, "length")]
+            }
+            ty::Str => {
+                vec![
+                    data_ptr(self.tcx.types.u8),
+                    self.context.new_field(None, self.usize_type, "length"),
+                ]
+            }
+            ty::Dynamic(..) => {
+                vec![
+                    data_ptr(self.tcx.types.unit),
+                    self.context.new_field(None, self.usize_type.make_pointer(), "vtable"),
+                ]
+            }
+            _ => return self.opaque(self.layout_of(ty)),
+        };
+        self.context.new_struct_type(None, self.debug_type_name(ty), &fields).as_type()
+    }
+
+    /// Structs, tuples and closures. Enums and unions are handled by `debug_union_type`.
+    fn debug_struct_type(&self, layout: TyAndLayout<'tcx>) -> Type<'gcc> {
+        // The struct is put in the cache before computing its fields in order to support
+        // recursive types like linked lists.
+        let typ = self.context.new_opaque_struct_type(None, self.debug_type_name(layout.ty));
+        self.debug_types.borrow_mut().insert(layout.ty, typ.as_type());
+
+        match self.debug_fields(layout) {
+            Some(fields) => typ.set_fields(None, &fields),
+            None => {
+                let bytes = self.context.new_field(None, self.opaque_bytes(layout), "__bytes");
+                typ.set_fields(None, &[bytes]);
+            }
+        }
+        typ.as_type()
+    }
+
+    /// Enums and unions are described as a struct with a single union field, with a member for
+    /// each variant of the enum or each field of the union.
+    ///
+    /// libgccjit can't express the variant parts used by cg_llvm, so the debugger shows every
+    /// variant and not only the active one.
+    fn debug_union_type(&self, layout: TyAndLayout<'tcx>) -> Type<'gcc> {
+        let typ = self.context.new_opaque_struct_type(None, self.debug_type_name(layout.ty));
+        self.debug_types.borrow_mut().insert(layout.ty, typ.as_type());
+
+        let mut members = vec![];
+        match (layout.ty.kind(), &layout.variants) {
+            (ty::Adt(def, _), _) if def.is_union() => {
+                for (index, field) in def.non_enum_variant().fields.iter().enumerate() {
+                    let field_type = self.debug_type(layout.field(self, index).ty);
+                    members.push(self.context.new_field(None, field_type, field.name.as_str()));
+                }
+            }
+            (ty::Adt(def, _), Variants::Single { index }) => {
+                if !def.variants().is_empty() {
+                    let name = def.variant(*index).name;
+                    let variant = self.debug_variant_type(layout, name.as_str());
+                    members.push(self.context.new_field(None, variant, name.as_str()));
+                }
+            }
+            (ty::Adt(def, _), Variants::Multiple { variants, .. }) => {
+                for index in variants.indices() {
+                    let name = def.variant(index).name;
+                    let variant_layout = layout.for_variant(self, index);
+                    let variant = self.debug_variant_type(variant_layout, name.as_str());
+                    members.push(self.context.new_field(None, variant, name.as_str()));
+                }
+            }
+            _ => unreachable!("{:?} is neither an enum nor a union", layout.ty),
+        }
+        // Makes sure that the size of the union covers the tag and any padding.
+        members.push(self.context.new_field(None, self.opaque_bytes(layout), "__bytes"));
+
+        let union_type = self.context.new_union_type(None, "__variants", &members);
+        typ.set_fields(None, &[self.context.new_field(None, union_type, "__variants")]);
+        typ.as_type()
+    }
+
+    fn debug_variant_type(&self, layout: TyAndLayout<'tcx>, variant_name: &str) -> Type<'gcc> {
+        let name = format!("{}::{}", self.debug_type_name(layout.ty), variant_name);
+        let fields = self.debug_fields(layout).unwrap_or_default();
+        self.context.new_struct_type(None, name, &fields).as_type()
+    }
+
+    /// Returns the fields of `layout` in memory order, with padding fields where needed, or
+    /// `None` if the fields can't be described exactly by a C struct (e.g. in packed structs).
+    fn debug_fields(&self, layout: TyAndLayout<'tcx>) -> Option<Vec<Field<'gcc>>> {
+        let mut fields = vec![];
+        let add_padding = |fields: &mut Vec<Field<'gcc>>, size: Size| {
+            let padding = self.type_array(self.u8_type, size.bytes());
+            let name = format!("__padding{}", fields.len());
+            fields.push(self.context.new_field(None, padding, name));
+        };
+
+        let mut offset = Size::ZERO;
+        let field_indices = match layout.abi {
+            // The fields of an uninhabited type may not fit in its layout.
+            Abi::Uninhabited => None,
+            _ => Some(layout.fields.index_by_increasing_offset()),
+        };
+        for index in field_indices.into_iter().flatten() {
+            let field_offset = layout.fields.offset(index);
+            let field = layout.field(self, index);
+            if field_offset < offset || !field_offset.is_aligned(field.align.abi) {
+                return None;
+            }
+            if field_offset > offset {
+                add_padding(&mut fields, field_offset - offset);
+            }
+
+            let name = match (layout.ty.kind(), &layout.variants) {
+                (ty::Adt(def, _), Variants::Single { index: variant_index }) => {
+                    def.variant(*variant_index).fields[FieldIdx::from_usize(index)].name.to_string()
+                }
+                _ => format!("__{}", index),
+            };
+            fields.push(self.context.new_field(None, self.debug_type(field.ty), name));
+            offset = field_offset + field.size;
+        }
+        // The struct has to be as large as the Rust type, as it is also used for stack slots.
+        if layout.is_sized() && layout.size > offset {
+            add_padding(&mut fields, layout.size - offset);
+        }
+        Some(fields)
+    }
+
+    /// A named struct of the right size for types which can't be described in more detail.
+    fn opaque(&self, layout: TyAndLayout<'tcx>) -> Type<'gcc> {
+        let bytes = self.context.new_field(None, self.opaque_bytes(layout), "__bytes");
+        self.context.new_struct_type(None, self.debug_type_name(layout.ty), &[bytes]).as_type()
+    }
+
+    fn opaque_bytes(&self, layout: TyAndLayout<'tcx>) -> Type<'gcc> {
+        let size = if layout.is_sized() { layout.size.bytes() } else { 0 };
+        self.type_array(self.u8_type, size)
+    }
+}
@@ -1,6 +1,6 @@
 use rustc_arena::TypedArena;
 use rustc_ast::CRATE_NODE_ID;
-use rustc_data_structures::fx::{FxIndexMap, FxIndexSet};
+use rustc_data_structures::fx::{FxHashMap, FxIndexMap, FxIndexSet};
 use rustc_data_structures::memmap::Mmap;
 use rustc_data_structures::temp_dir::MaybeTempDir;
 use rustc_errors::{DiagCtxt, ErrorGuaranteed, FatalError};
@@ -43,6 +43,7 @@
 use tempfile::Builder as TempFileBuilder;
 
 use itertools::Itertools;
+use object::{Object, ObjectSection, ObjectSymbol};
 use std::collections::BTreeSet;
 use std::ffi::{OsStr, OsString};
 use std::fs::{read, File, OpenOptions};
@@ -70,6 +71,13 @@ pub fn link_binary(
 ) -> Result<(), ErrorGuaranteed> {
     let _timer = sess.timer("link_binary");
     let output_metadata = sess.opts.output_types.contains_key(&OutputType::Metadata);
+
+    for print in &sess.opts.prints {
+        if print.kind == PrintKind::MonoItemsSize {
+            print_mono_items_size(sess, &print.out, codegen_results);
+        }
+    }
+
     let mut tempfiles_for_stdout_output: Vec<PathBuf> = Vec::new();
     for &crate_type in &codegen_results.crate_info.crate_types {
         // Ignore executable crates if we have -Z no-codegen, as they will error.
@@ -1550,6 +1558,62 @@ fn print_native_static_libs(
     }
 }
 
+/// Prints the size of the machine code of the instantiations of generic functions, see
+/// `rustc_monomorphize::mono_items_size_report`.
+fn print_mono_items_size(sess: &Session, out: &OutFileName, codegen_results: &CodegenResults) {
+    let mut symbol_sizes = FxHashMap::default();
+    for object in codegen_results.modules.iter().filter_map(|m| m.object.as_ref()) {
+        if let Err(error) = add_symbol_sizes(object, &mut symbol_sizes) {
+            sess.dcx().emit_warn(errors::MonoItemsSizeReadObject { path: object, error });
+        }
+    }
+
+    let report = rustc_monomorphize::mono_items_size_report(
+        &codegen_results.crate_info.generic_instantiations,
+        |instantiation| {
+            let name = &instantiation.symbol_name;
+            // Mach-O and 32-bit Windows prefix all symbols with an underscore.
+            let size = symbol_sizes.get(name).or_else(|| symbol_sizes.get(&format!("_{name}")));
+            size.copied().unwrap_or(0)
+        },
+    );
+    out.overwrite(&report, sess);
+}
+
+/// Adds the size of the functions defined in the object file at `path` to `symbol_sizes`. A
+/// function emitted in several codegen units (e.g. an `#[inline]` function) counts several times.
+fn add_symbol_sizes(path: &Path, symbol_sizes: &mut FxHashMap<String, u64>) -> Result<(), String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mmap = unsafe { Mmap::map(file) }.map_err(|e| e.to_string())?;
+    let object = object::File::parse(&*mmap).map_err(|e| e.to_string())?;
+
+    let mut symbols: Vec<_> = object
+        .symbols()
+        .filter(|symbol| symbol.kind() == object::SymbolKind::Text && symbol.is_definition())
+        .filter_map(|symbol| {
+            Some((symbol.section_index()?, symbol.address(), symbol.size(), symbol.name().ok()?))
+        })
+        .collect();
+    symbols.sort_by_key(|&(section, address, ..)| (section.0, address));
+
+    for (i, &(section, address, size, name)) in symbols.iter().enumerate() {
+        // Mach-O and COFF don't record the size of symbols, use the distance to the next symbol
+        // or to the end of the section instead.
+        let size = if size != 0 {
+            size
+        } else if let Some(&(next_section, next_address, ..)) = symbols.get(i + 1)
+            && next_section == section
+        {
+            next_address - address
+        } else {
+            let section = object.section_by_index(section).map_err(|e| e.to_string())?;
+            (section.address() + section.size()).saturating_sub(address)
+        };
+        *symbol_sizes.entry(name.to_owned()).or_default() += size;
+    }
+    Ok(())
+}
+
 fn get_object_file_path(sess: &Session, name: &str, self_contained: bool) -> PathBuf {
     let fs = sess.target_filesearch(PathKind::Native);
     let file_path = fs.get_lib_path().join(name);
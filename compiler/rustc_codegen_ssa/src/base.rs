@@ -33,7 +33,7 @@
 use rustc_middle::query::Providers;
 use rustc_middle::ty::layout::{HasTyCtxt, LayoutOf, TyAndLayout};
 use rustc_middle::ty::{self, Instance, Ty, TyCtxt};
-use rustc_session::config::{self, CrateType, EntryFnType, OptLevel, OutputType};
+use rustc_session::config::{self, CrateType, EntryFnType, OptLevel, OutputType, PrintKind};
 use rustc_session::Session;
 use rustc_span::symbol::sym;
 use rustc_span::Symbol;
@@ -591,6 +591,9 @@ pub fn codegen_crate<B: ExtraBackendMethods>(
     // Run the monomorphization collector and partition the collected items into
     // codegen units.
     let codegen_units = tcx.collect_and_partition_mono_items(()).1;
+    for cgu in codegen_units {
+        tcx.prof.register_codegen_unit(cgu.name().as_str());
+    }
 
     // Force all codegen_unit queries so they are already either red or green
     // when compile_codegen_unit accesses them. We are not able to re-execute
@@ -867,6 +870,7 @@ pub fn new(tcx: TyCtxt<'_>, target_cpu: String) -> CrateInfo {
             dependency_formats: tcx.dependency_formats(()).clone(),
             windows_subsystem,
             natvis_debugger_visualizers: Default::default(),
+            generic_instantiations: Default::default(),
         };
 
         info.native_libraries.reserve(n_crates);
@@ -967,6 +971,10 @@ pub fn new(tcx: TyCtxt<'_>, target_cpu: String) -> CrateInfo {
                 collect_debugger_visualizers_transitive(tcx, DebuggerVisualizerType::Natvis);
         }
 
+        if tcx.sess.opts.prints.iter().any(|print| print.kind == PrintKind::MonoItemsSize) {
+            info.generic_instantiations = rustc_monomorphize::generic_instantiations(tcx);
+        }
+
         info
     }
 }
@@ -543,6 +543,13 @@ pub struct ReadFileError {
     pub message: std::io::Error,
 }
 
+#[derive(Diagnostic)]
+#[diag(codegen_ssa_mono_items_size_read_object)]
+pub struct MonoItemsSizeReadObject<'a> {
+    pub path: &'a Path,
+    pub error: String,
+}
+
 #[derive(Diagnostic)]
 #[diag(codegen_ssa_unsupported_link_self_contained)]
 pub struct UnsupportedLinkSelfContained;
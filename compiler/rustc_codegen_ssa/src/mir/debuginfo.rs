@@ -224,9 +224,30 @@ fn adjusted_span_and_dbg_scope(
         Some((scope.adjust_dbg_scope_for_span(self.cx, span), scope.inlined_at, span))
     }
 
+    /// The name of the user variable which is the whole of `local`, if any.
+    fn whole_local_var_name(&self, local: mir::Local) -> Option<Symbol> {
+        let vars = &self.per_local_var_debug_info.as_ref()?[local];
+        let var = vars.iter().find(|var| var.projection.is_empty())?;
+        (var.name != kw::Empty).then_some(var.name)
+    }
+
+    /// Allocates the stack slot of `local`, see `BuilderMethods::var_alloca`.
+    pub fn alloca_local(
+        &self,
+        bx: &mut Bx,
+        local: mir::Local,
+        layout: TyAndLayout<'tcx>,
+    ) -> PlaceRef<'tcx, Bx::Value> {
+        match self.whole_local_var_name(local) {
+            Some(var_name) => PlaceRef::var_alloca(bx, layout, var_name),
+            None => PlaceRef::alloca(bx, layout),
+        }
+    }
+
     fn spill_operand_to_stack(
         operand: OperandRef<'tcx, Bx::Value>,
         name: Option<String>,
+        var_name: Option<Symbol>,
         bx: &mut Bx,
     ) -> PlaceRef<'tcx, Bx::Value> {
         // "Spill" the value onto the stack, for debuginfo,
@@ -234,7 +255,10 @@ fn spill_operand_to_stack(
         // to also load from the stack every single time.
         // FIXME(#68817) use `llvm.dbg.value` instead,
         // at least for the cases which LLVM handles correctly.
-        let spill_slot = PlaceRef::alloca(bx, operand.layout);
+        let spill_slot = match var_name {
+            Some(var_name) => PlaceRef::var_alloca(bx, operand.layout, var_name),
+            None => PlaceRef::alloca(bx, operand.layout),
+        };
         if let Some(name) = name {
             bx.set_var_name(spill_slot.val.llval, &(name + ".dbg.spill"));
         }
@@ -350,7 +374,7 @@ pub fn debug_introduce_local(&self, bx: &mut Bx, local: mir::Local) {
                     return;
                 }
 
-                Self::spill_operand_to_stack(*operand, name, bx)
+                Self::spill_operand_to_stack(*operand, name, self.whole_local_var_name(local), bx)
             }
 
             LocalRef::Place(place) => *place,
@@ -534,8 +558,12 @@ pub fn compute_per_local_var_debug_info(
 
                         let operand = self.eval_mir_constant_to_operand(bx, &c);
                         self.set_debug_loc(bx, var.source_info);
-                        let base =
-                            Self::spill_operand_to_stack(operand, Some(var.name.to_string()), bx);
+                        let base = Self::spill_operand_to_stack(
+                            operand,
+                            Some(var.name.to_string()),
+                            Some(var.name),
+                            bx,
+                        );
 
                         bx.dbg_var_addr(
                             dbg_var,
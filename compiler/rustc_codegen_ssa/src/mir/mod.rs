@@ -239,7 +239,7 @@ pub fn codegen_mir<'a, 'tcx, Bx: BuilderMethods<'a, 'tcx>>(
                 if layout.is_unsized() {
                     LocalRef::UnsizedPlace(PlaceRef::alloca_unsized_indirect(&mut start_bx, layout))
                 } else {
-                    LocalRef::Place(PlaceRef::alloca(&mut start_bx, layout))
+                    LocalRef::Place(fx.alloca_local(&mut start_bx, local, layout))
                 }
             } else {
                 debug!("alloc: {:?} -> operand", local);
@@ -418,7 +418,7 @@ fn arg_local_refs<'a, 'tcx, Bx: BuilderMethods<'a, 'tcx>>(
                     LocalRef::UnsizedPlace(tmp)
                 }
                 _ => {
-                    let tmp = PlaceRef::alloca(bx, arg.layout);
+                    let tmp = fx.alloca_local(bx, local, arg.layout);
                     bx.store_fn_arg(arg, &mut llarg_idx, tmp);
                     LocalRef::Place(tmp)
                 }
@@ -9,6 +9,7 @@
 use rustc_middle::mir::tcx::PlaceTy;
 use rustc_middle::ty::layout::{HasTyCtxt, LayoutOf, TyAndLayout};
 use rustc_middle::ty::{self, Ty};
+use rustc_span::Symbol;
 use rustc_target::abi::{Align, FieldsShape, Int, Pointer, TagEncoding};
 use rustc_target::abi::{VariantIdx, Variants};
 
@@ -85,6 +86,17 @@ pub fn alloca_aligned<Bx: BuilderMethods<'a, 'tcx, Value = V>>(
         Self::new_sized_aligned(tmp, layout, align)
     }
 
+    /// Allocates the stack slot of the user variable `name`, see `BuilderMethods::var_alloca`.
+    pub fn var_alloca<Bx: BuilderMethods<'a, 'tcx, Value = V>>(
+        bx: &mut Bx,
+        layout: TyAndLayout<'tcx>,
+        name: Symbol,
+    ) -> Self {
+        assert!(layout.is_sized(), "tried to statically allocate unsized place");
+        let tmp = bx.var_alloca(layout, name);
+        Self::new_sized(tmp, layout)
+    }
+
     /// Returns a place for an indirect reference to an unsized place.
     // FIXME(eddyb) pass something else for the name so no work is done
     // unless LLVM IR names are turned on (e.g. for `--emit=llvm-ir`).
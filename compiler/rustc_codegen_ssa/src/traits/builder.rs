@@ -19,7 +19,7 @@
 use rustc_middle::ty::layout::{HasParamEnv, TyAndLayout};
 use rustc_middle::ty::{Instance, Ty};
 use rustc_session::config::OptLevel;
-use rustc_span::Span;
+use rustc_span::{Span, Symbol};
 use rustc_target::abi::call::FnAbi;
 use rustc_target::abi::{Abi, Align, Scalar, Size, WrappingRange};
 use rustc_target::spec::HasTargetSpec;
@@ -145,6 +145,12 @@ fn to_immediate(&mut self, val: Self::Value, layout: TyAndLayout<'_>) -> Self::V
     fn to_immediate_scalar(&mut self, val: Self::Value, scalar: Scalar) -> Self::Value;
 
     fn alloca(&mut self, size: Size, align: Align) -> Self::Value;
+    /// Allocates the stack slot of a local which is entirely the user variable `name`, before
+    /// `dbg_var_addr` describes the variable. Backends which can't attach a name or a type to a
+    /// stack slot after creating it override this to describe the variable with the slot itself.
+    fn var_alloca(&mut self, layout: TyAndLayout<'tcx>, _name: Symbol) -> Self::Value {
+        self.alloca(layout.size, layout.align.abi)
+    }
     fn dynamic_alloca(&mut self, size: Self::Value, align: Align) -> Self::Value;
 
     fn load(&mut self, ty: Self::Type, ptr: Self::Value, align: Align) -> Self::Value;
@@ -79,6 +79,14 @@
 //! invocation) and allocate the corresponding strings together with a mapping
 //! for `DepNodeIndex as StringId`.
 //!
+//!
+//! ## Exports
+//!
+//! The `measureme` files can only be analyzed with the `measureme` tools. With
+//! `-Z self-profile-export`, the events are also recorded in memory and
+//! written as a Chrome trace or a JSON summary once the profiler is dropped,
+//! see the `export` module.
+//!
 //! [mm]: https://github.com/rust-lang/measureme/
 
 use crate::fx::FxHashMap;
@@ -95,6 +103,7 @@
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+use self::export::{EventKind, EventRecorder, RecordingGuard};
 pub use measureme::EventId;
 use measureme::{EventIdBuilder, Profiler, SerializableString, StringId};
 use parking_lot::RwLock;
@@ -156,6 +165,16 @@ pub enum TimePassesFormat {
     Json,
 }
 
+/// Which additional files to write for `-Z self-profile`, see `-Z self-profile-export`.
+#[derive(Clone, Copy, PartialEq, Hash, Debug)]
+pub enum SelfProfileExportFormat {
+    /// A trace in the Chrome trace event format, which can be opened by `chrome://tracing`,
+    /// Perfetto or Speedscope.
+    ChromeTrace,
+    /// A JSON summary of the time spent in each pass, query and codegen unit.
+    JsonSummary,
+}
+
 /// A reference to the SelfProfiler. It can be cloned and sent across thread
 /// boundaries at will.
 #[derive(Clone)]
@@ -248,9 +267,15 @@ pub fn verbose_generic_activity_with_arg<A>(
     #[inline(always)]
     pub fn generic_activity(&self, event_label: &'static str) -> TimingGuard<'_> {
         self.exec(EventFilter::GENERIC_ACTIVITIES, |profiler| {
-            let event_label = profiler.get_or_alloc_cached_string(event_label);
-            let event_id = EventId::from_label(event_label);
-            TimingGuard::start(profiler, profiler.generic_activity_event_kind, event_id)
+            let event_id = EventId::from_label(profiler.get_or_alloc_cached_string(event_label));
+            TimingGuard::start_recorded(
+                profiler,
+                profiler.generic_activity_event_kind,
+                event_id,
+                EventKind::GenericActivity,
+                event_label,
+                None,
+            )
         })
     }
 
@@ -276,14 +301,22 @@ pub fn generic_activity_with_arg<A>(
     {
         self.exec(EventFilter::GENERIC_ACTIVITIES, |profiler| {
             let builder = EventIdBuilder::new(&profiler.profiler);
-            let event_label = profiler.get_or_alloc_cached_string(event_label);
+            let recorded_arg = profiler.recorder.as_ref().map(|_| event_arg.borrow().into());
+            let label = profiler.get_or_alloc_cached_string(event_label);
             let event_id = if profiler.event_filter_mask.contains(EventFilter::FUNCTION_ARGS) {
                 let event_arg = profiler.get_or_alloc_cached_string(event_arg);
-                builder.from_label_and_arg(event_label, event_arg)
+                builder.from_label_and_arg(label, event_arg)
             } else {
-                builder.from_label(event_label)
+                builder.from_label(label)
             };
-            TimingGuard::start(profiler, profiler.generic_activity_event_kind, event_id)
+            TimingGuard::start_recorded(
+                profiler,
+                profiler.generic_activity_event_kind,
+                event_id,
+                EventKind::GenericActivity,
+                event_label,
+                recorded_arg,
+            )
         })
     }
 
@@ -319,14 +352,20 @@ pub fn generic_activity_with_arg_recorder<F>(
         // Ensure this event will only be recorded when self-profiling is turned on.
         self.exec(EventFilter::GENERIC_ACTIVITIES, |profiler| {
             let builder = EventIdBuilder::new(&profiler.profiler);
-            let event_label = profiler.get_or_alloc_cached_string(event_label);
+            let label = profiler.get_or_alloc_cached_string(event_label);
+            let mut recorded_arg = None;
 
             // Ensure the closure to create event arguments will only be called when argument
-            // recording is turned on.
-            let event_id = if profiler.event_filter_mask.contains(EventFilter::FUNCTION_ARGS) {
+            // recording is turned on, or when the arguments are needed for an export.
+            let record_args = profiler.event_filter_mask.contains(EventFilter::FUNCTION_ARGS);
+            let event_id = if record_args || profiler.recorder.is_some() {
                 // Set up the builder and call the user-provided closure to record potentially
                 // costly event arguments.
-                let mut recorder = EventArgRecorder { profiler, args: SmallVec::new() };
+                let mut recorder = EventArgRecorder {
+                    profiler,
+                    args: SmallVec::new(),
+                    text: profiler.recorder.as_ref().map(|_| String::new()),
+                };
                 f(&mut recorder);
 
                 // It is expected that the closure will record at least one argument. If that
@@ -339,11 +378,23 @@ pub fn generic_activity_with_arg_recorder<F>(
                     );
                 }
 
-                builder.from_label_and_args(event_label, &recorder.args)
+                recorded_arg = recorder.text.map(String::into_boxed_str);
+                if record_args {
+                    builder.from_label_and_args(label, &recorder.args)
+                } else {
+                    builder.from_label(label)
+                }
             } else {
-                builder.from_label(event_label)
+                builder.from_label(label)
             };
-            TimingGuard::start(profiler, profiler.generic_activity_event_kind, event_id)
+            TimingGuard::start_recorded(
+                profiler,
+                profiler.generic_activity_event_kind,
+                event_id,
+                EventKind::GenericActivity,
+                event_label,
+                recorded_arg,
+            )
         })
     }
 
@@ -382,17 +433,25 @@ pub fn generic_activity_with_args(
     ) -> TimingGuard<'_> {
         self.exec(EventFilter::GENERIC_ACTIVITIES, |profiler| {
             let builder = EventIdBuilder::new(&profiler.profiler);
-            let event_label = profiler.get_or_alloc_cached_string(event_label);
+            let label = profiler.get_or_alloc_cached_string(event_label);
             let event_id = if profiler.event_filter_mask.contains(EventFilter::FUNCTION_ARGS) {
                 let event_args: Vec<_> = event_args
                     .iter()
                     .map(|s| profiler.get_or_alloc_cached_string(&s[..]))
                     .collect();
-                builder.from_label_and_args(event_label, &event_args)
+                builder.from_label_and_args(label, &event_args)
             } else {
-                builder.from_label(event_label)
+                builder.from_label(label)
             };
-            TimingGuard::start(profiler, profiler.generic_activity_event_kind, event_id)
+            let recorded_arg = profiler.recorder.as_ref().map(|_| event_args.join(", ").into());
+            TimingGuard::start_recorded(
+                profiler,
+                profiler.generic_activity_event_kind,
+                event_id,
+                EventKind::GenericActivity,
+                event_label,
+                recorded_arg,
+            )
         })
     }
 
@@ -401,7 +460,7 @@ pub fn generic_activity_with_args(
     #[inline(always)]
     pub fn query_provider(&self) -> TimingGuard<'_> {
         self.exec(EventFilter::QUERY_PROVIDERS, |profiler| {
-            TimingGuard::start(profiler, profiler.query_event_kind, EventId::INVALID)
+            TimingGuard::start_query(profiler, profiler.query_event_kind, EventKind::Query)
         })
     }
 
@@ -413,6 +472,7 @@ pub fn query_cache_hit(&self, query_invocation_id: QueryInvocationId) {
         fn cold_call(profiler_ref: &SelfProfilerRef, query_invocation_id: QueryInvocationId) {
             profiler_ref.instant_query_event(
                 |profiler| profiler.query_cache_hit_event_kind,
+                EventKind::QueryCacheHit,
                 query_invocation_id,
             );
         }
@@ -428,7 +488,11 @@ fn cold_call(profiler_ref: &SelfProfilerRef, query_invocation_id: QueryInvocatio
     #[inline(always)]
     pub fn query_blocked(&self) -> TimingGuard<'_> {
         self.exec(EventFilter::QUERY_BLOCKED, |profiler| {
-            TimingGuard::start(profiler, profiler.query_blocked_event_kind, EventId::INVALID)
+            TimingGuard::start_query(
+                profiler,
+                profiler.query_blocked_event_kind,
+                EventKind::QueryBlocked,
+            )
         })
     }
 
@@ -438,10 +502,10 @@ pub fn query_blocked(&self) -> TimingGuard<'_> {
     #[inline(always)]
     pub fn incr_cache_loading(&self) -> TimingGuard<'_> {
         self.exec(EventFilter::INCR_CACHE_LOADS, |profiler| {
-            TimingGuard::start(
+            TimingGuard::start_query(
                 profiler,
                 profiler.incremental_load_result_event_kind,
-                EventId::INVALID,
+                EventKind::IncrementalLoadResult,
             )
         })
     }
@@ -451,10 +515,10 @@ pub fn incr_cache_loading(&self) -> TimingGuard<'_> {
     #[inline(always)]
     pub fn incr_result_hashing(&self) -> TimingGuard<'_> {
         self.exec(EventFilter::INCR_RESULT_HASHING, |profiler| {
-            TimingGuard::start(
+            TimingGuard::start_query(
                 profiler,
                 profiler.incremental_result_hashing_event_kind,
-                EventId::INVALID,
+                EventKind::IncrementalResultHashing,
             )
         })
     }
@@ -463,6 +527,7 @@ pub fn incr_result_hashing(&self) -> TimingGuard<'_> {
     fn instant_query_event(
         &self,
         event_kind: fn(&SelfProfiler) -> StringId,
+        recorded_kind: EventKind,
         query_invocation_id: QueryInvocationId,
     ) {
         let event_id = StringId::new_virtual(query_invocation_id.0);
@@ -473,6 +538,9 @@ fn instant_query_event(
             EventId::from_virtual(event_id),
             thread_id,
         );
+        if let Some(recorder) = &profiler.recorder {
+            recorder.record_instant_query_event(recorded_kind, query_invocation_id);
+        }
     }
 
     pub fn with_profiler(&self, f: impl FnOnce(&SelfProfiler)) {
@@ -502,6 +570,15 @@ pub fn llvm_recording_enabled(&self) -> bool {
     pub fn get_self_profiler(&self) -> Option<Arc<SelfProfiler>> {
         self.profiler.clone()
     }
+
+    /// Registers the name of a codegen unit, so that the exports of the
+    /// self-profiler can attribute the generic activities whose argument is that
+    /// name to the codegen unit.
+    pub fn register_codegen_unit(&self, cgu_name: &str) {
+        if let Some(recorder) = self.profiler.as_ref().and_then(|p| p.recorder.as_ref()) {
+            recorder.register_codegen_unit(cgu_name);
+        }
+    }
 }
 
 /// A helper for recording costly arguments to self-profiling events. Used with
@@ -515,6 +592,9 @@ pub struct EventArgRecorder<'p> {
     /// The most common case, when actually recording event arguments, is to have one argument. Then
     /// followed by recording two, in a couple places.
     args: SmallVec<[StringId; 2]>,
+
+    /// The comma separated arguments, if the event is also recorded for an export.
+    text: Option<String>,
 }
 
 impl EventArgRecorder<'_> {
@@ -526,6 +606,12 @@ pub fn record_arg<A>(&mut self, event_arg: A)
     where
         A: Borrow<str> + Into<String>,
     {
+        if let Some(text) = &mut self.text {
+            if !text.is_empty() {
+                text.push_str(", ");
+            }
+            text.push_str(event_arg.borrow());
+        }
         let event_arg = self.profiler.get_or_alloc_cached_string(event_arg);
         self.args.push(event_arg);
     }
@@ -544,6 +630,9 @@ pub struct SelfProfiler {
     query_blocked_event_kind: StringId,
     query_cache_hit_event_kind: StringId,
     artifact_size_event_kind: StringId,
+
+    // Only present if `-Z self-profile-export` is used.
+    recorder: Option<EventRecorder>,
 }
 
 impl SelfProfiler {
@@ -552,6 +641,7 @@ pub fn new(
         crate_name: Option<&str>,
         event_filters: Option<&[String]>,
         counter_name: &str,
+        export_formats: &[SelfProfileExportFormat],
     ) -> Result<SelfProfiler, Box<dyn Error + Send + Sync>> {
         fs::create_dir_all(output_directory)?;
 
@@ -565,6 +655,11 @@ pub fn new(
         let profiler =
             Profiler::with_counter(&path, measureme::counters::Counter::by_name(counter_name)?)?;
 
+        let recorder = (!export_formats.is_empty()).then(|| {
+            let file_stem = output_directory.join(format!("{crate_name}-{pid:07}"));
+            EventRecorder::new(export_formats.to_vec(), crate_name, file_stem)
+        });
+
         let query_event_kind = profiler.alloc_string("Query");
         let generic_activity_event_kind = profiler.alloc_string("GenericActivity");
         let incremental_load_result_event_kind = profiler.alloc_string("IncrementalLoadResult");
@@ -618,6 +713,7 @@ pub fn new(
             query_blocked_event_kind,
             query_cache_hit_event_kind,
             artifact_size_event_kind,
+            recorder,
         })
     }
 
@@ -673,13 +769,43 @@ pub fn query_key_recording_enabled(&self) -> bool {
         self.event_filter_mask.contains(EventFilter::QUERY_KEYS)
     }
 
+    /// Returns `true` if the query invocation ids have to be mapped to the name
+    /// of their query with `bulk_map_query_invocation_id_to_query_name` for an
+    /// export.
+    pub fn query_name_recording_enabled(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    pub fn bulk_map_query_invocation_id_to_query_name<I>(&self, from: I, query_name: &'static str)
+    where
+        I: Iterator<Item = QueryInvocationId>,
+    {
+        if let Some(recorder) = &self.recorder {
+            recorder.map_query_invocation_ids(from, query_name);
+        }
+    }
+
     pub fn event_id_builder(&self) -> EventIdBuilder<'_> {
         EventIdBuilder::new(&self.profiler)
     }
 }
 
+impl Drop for SelfProfiler {
+    fn drop(&mut self) {
+        if let Some(recorder) = &self.recorder {
+            recorder.write_exports();
+        }
+    }
+}
+
 #[must_use]
-pub struct TimingGuard<'a>(Option<measureme::TimingGuard<'a>>);
+pub struct TimingGuard<'a> {
+    guard: Option<measureme::TimingGuard<'a>>,
+    // Records the event for the exports of the profiler, if any. This is only
+    // done for the events started by `SelfProfilerRef`, events started with
+    // `TimingGuard::start` (e.g. LLVM passes) are not part of the exports.
+    recording: Option<RecordingGuard<'a>>,
+}
 
 impl<'a> TimingGuard<'a> {
     #[inline]
@@ -692,23 +818,52 @@ pub fn start(
         let raw_profiler = &profiler.profiler;
         let timing_guard =
             raw_profiler.start_recording_interval_event(event_kind, event_id, thread_id);
-        TimingGuard(Some(timing_guard))
+        TimingGuard { guard: Some(timing_guard), recording: None }
+    }
+
+    #[inline]
+    fn start_recorded(
+        profiler: &'a SelfProfiler,
+        event_kind: StringId,
+        event_id: EventId,
+        recorded_kind: EventKind,
+        label: &'static str,
+        arg: Option<Box<str>>,
+    ) -> TimingGuard<'a> {
+        let mut guard = TimingGuard::start(profiler, event_kind, event_id);
+        guard.recording = profiler.recorder.as_ref().map(|r| r.start(recorded_kind, label, arg));
+        guard
+    }
+
+    /// Starts an event whose id is only known once it is finished with
+    /// `finish_with_query_invocation_id`.
+    #[inline]
+    fn start_query(
+        profiler: &'a SelfProfiler,
+        event_kind: StringId,
+        recorded_kind: EventKind,
+    ) -> TimingGuard<'a> {
+        TimingGuard::start_recorded(profiler, event_kind, EventId::INVALID, recorded_kind, "", None)
     }
 
     #[inline]
     pub fn finish_with_query_invocation_id(self, query_invocation_id: QueryInvocationId) {
-        if let Some(guard) = self.0 {
+        let TimingGuard { guard, recording } = self;
+        if let Some(guard) = guard {
             outline(|| {
                 let event_id = StringId::new_virtual(query_invocation_id.0);
                 let event_id = EventId::from_virtual(event_id);
                 guard.finish_with_override_event_id(event_id);
             });
         }
+        if let Some(recording) = recording {
+            outline(|| recording.finish_with_query_invocation_id(query_invocation_id));
+        }
     }
 
     #[inline]
     pub fn none() -> TimingGuard<'a> {
-        TimingGuard(None)
+        TimingGuard { guard: None, recording: None }
     }
 
     #[inline(always)]
@@ -921,5 +1076,7 @@ pub fn get_resident_set_size() -> Option<usize> {
     }
 }
 
+mod export;
+
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,494 @@
+//! Exports of the self-profiling data that don't need the `measureme` tools to
+//! be consumed, enabled with `-Z self-profile-export`.
+//!
+//! `measureme` streams its events to disk in a compact format, so they can't be
+//! read back cheaply. Instead, when an export is requested, the events started
+//! through `SelfProfilerRef` are also recorded in memory, together with the
+//! resident set size at the start and end of generic activities. The exports
+//! are written when the `SelfProfiler` is dropped, once the query invocation ids
+//! have been mapped to the names of their queries.
+//!
+//! Two formats are supported:
+//!
+//! - `chrome-trace`: every event in the [Chrome trace event format][trace],
+//!   written to `{crate}-{pid}.trace.json`. Memory usage is shown as an `rss`
+//!   counter.
+//! - `json-summary`: the time spent in each generic activity ("pass"), query and
+//!   codegen unit, in the spirit of the `summarize` tool of `measureme`, written
+//!   to `{crate}-{pid}.summary.json`. The self time of an event excludes the
+//!   time spent in the events nested in it on the same thread.
+//!
+//! [trace]: https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU
+
+use super::{get_resident_set_size, get_thread_id, QueryInvocationId, SelfProfileExportFormat};
+use crate::fx::{FxHashMap, FxHashSet, FxIndexMap};
+
+use parking_lot::Mutex;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::time::Instant;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(super) enum EventKind {
+    GenericActivity,
+    Query,
+    QueryBlocked,
+    QueryCacheHit,
+    IncrementalLoadResult,
+    IncrementalResultHashing,
+}
+
+impl EventKind {
+    /// The same names as the event kinds of the `measureme` data.
+    fn name(self) -> &'static str {
+        match self {
+            EventKind::GenericActivity => "GenericActivity",
+            EventKind::Query => "Query",
+            EventKind::QueryBlocked => "QueryBlocked",
+            EventKind::QueryCacheHit => "QueryCacheHit",
+            EventKind::IncrementalLoadResult => "IncrementalLoadResult",
+            EventKind::IncrementalResultHashing => "IncrementalResultHashing",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct RecordedEvent {
+    kind: EventKind,
+    /// The label of generic activities. The other events are named after the
+    /// query of their `query_invocation_id`.
+    label: &'static str,
+    arg: Option<Box<str>>,
+    query_invocation_id: Option<u32>,
+    thread_id: u32,
+    /// In nanoseconds since the creation of the recorder. Both are equal for
+    /// instant events.
+    start: u64,
+    end: u64,
+    start_rss: Option<usize>,
+    end_rss: Option<usize>,
+}
+
+impl RecordedEvent {
+    fn is_instant(&self) -> bool {
+        self.kind == EventKind::QueryCacheHit
+    }
+
+    fn name<'a>(&'a self, query_names: &FxHashMap<u32, &'static str>) -> &'a str {
+        match self.kind {
+            EventKind::GenericActivity => self.label,
+            _ => self
+                .query_invocation_id
+                .and_then(|id| query_names.get(&id).copied())
+                .unwrap_or("<unknown>"),
+        }
+    }
+}
+
+pub(super) struct EventRecorder {
+    formats: Vec<SelfProfileExportFormat>,
+    crate_name: String,
+    /// The path of the exports, without their extension.
+    file_stem: PathBuf,
+    start_time: Instant,
+    events: Mutex<Vec<RecordedEvent>>,
+    query_names: Mutex<FxHashMap<u32, &'static str>>,
+    codegen_units: Mutex<FxHashSet<Box<str>>>,
+}
+
+impl EventRecorder {
+    pub(super) fn new(
+        formats: Vec<SelfProfileExportFormat>,
+        crate_name: &str,
+        file_stem: PathBuf,
+    ) -> EventRecorder {
+        EventRecorder {
+            formats,
+            crate_name: crate_name.to_owned(),
+            file_stem,
+            start_time: Instant::now(),
+            events: Default::default(),
+            query_names: Default::default(),
+            codegen_units: Default::default(),
+        }
+    }
+
+    fn now(&self) -> u64 {
+        self.start_time.elapsed().as_nanos() as u64
+    }
+
+    pub(super) fn start(
+        &self,
+        kind: EventKind,
+        label: &'static str,
+        arg: Option<Box<str>>,
+    ) -> RecordingGuard<'_> {
+        // Measuring the memory usage is too costly to be done for every query.
+        let start_rss =
+            if kind == EventKind::GenericActivity { get_resident_set_size() } else { None };
+        let event = RecordedEvent {
+            kind,
+            label,
+            arg,
+            query_invocation_id: None,
+            thread_id: get_thread_id(),
+            start: self.now(),
+            end: 0,
+            start_rss,
+            end_rss: None,
+        };
+        RecordingGuard { recorder: self, event: Some(event) }
+    }
+
+    pub(super) fn record_instant_query_event(
+        &self,
+        kind: EventKind,
+        query_invocation_id: QueryInvocationId,
+    ) {
+        let now = self.now();
+        self.events.lock().push(RecordedEvent {
+            kind,
+            label: "",
+            arg: None,
+            query_invocation_id: Some(query_invocation_id.0),
+            thread_id: get_thread_id(),
+            start: now,
+            end: now,
+            start_rss: None,
+            end_rss: None,
+        });
+    }
+
+    pub(super) fn map_query_invocation_ids(
+        &self,
+        from: impl Iterator<Item = QueryInvocationId>,
+        query_name: &'static str,
+    ) {
+        let mut query_names = self.query_names.lock();
+        query_names.extend(from.map(|id| (id.0, query_name)));
+    }
+
+    pub(super) fn register_codegen_unit(&self, cgu_name: &str) {
+        self.codegen_units.lock().insert(cgu_name.into());
+    }
+
+    pub(super) fn write_exports(&self) {
+        let events = std::mem::take(&mut *self.events.lock());
+        let query_names = self.query_names.lock();
+        let codegen_units = self.codegen_units.lock();
+
+        for &format in &self.formats {
+            let (extension, contents) = match format {
+                SelfProfileExportFormat::ChromeTrace => {
+                    ("trace.json", chrome_trace(&events, &query_names, &self.crate_name))
+                }
+                SelfProfileExportFormat::JsonSummary => {
+                    ("summary.json", json_summary(&events, &query_names, &codegen_units))
+                }
+            };
+            let mut path = self.file_stem.clone().into_os_string();
+            path.push(".");
+            path.push(extension);
+            let path = PathBuf::from(path);
+            // There is no diagnostic context anymore when the profiler is dropped.
+            if let Err(err) = fs::write(&path, contents) {
+                eprintln!("failed to write self-profile export `{}`: {err}", path.display());
+            }
+        }
+    }
+}
+
+pub(super) struct RecordingGuard<'a> {
+    recorder: &'a EventRecorder,
+    // Only `None` once dropped.
+    event: Option<RecordedEvent>,
+}
+
+impl RecordingGuard<'_> {
+    pub(super) fn finish_with_query_invocation_id(
+        mut self,
+        query_invocation_id: QueryInvocationId,
+    ) {
+        if let Some(event) = &mut self.event {
+            event.query_invocation_id = Some(query_invocation_id.0);
+        }
+    }
+}
+
+impl Drop for RecordingGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(mut event) = self.event.take() {
+            event.end = self.recorder.now();
+            if event.kind == EventKind::GenericActivity {
+                event.end_rss = get_resident_set_size();
+            }
+            self.recorder.events.lock().push(event);
+        }
+    }
+}
+
+/// Computes the self time of each event, in the order of `events`. Instant
+/// events have no duration and are ignored.
+fn self_times(events: &[RecordedEvent]) -> Vec<u64> {
+    let mut order: Vec<usize> = (0..events.len()).filter(|&i| !events[i].is_instant()).collect();
+    // Parents start before their children, or at the same time but end after them.
+    order.sort_by_key(|&i| {
+        let event = &events[i];
+        (event.thread_id, event.start, std::cmp::Reverse(event.end))
+    });
+
+    let mut self_times: Vec<u64> = events.iter().map(|e| e.end - e.start).collect();
+    let mut stack: Vec<usize> = Vec::new();
+    for i in order {
+        let event = &events[i];
+        while let Some(&parent) = stack.last() {
+            let parent = &events[parent];
+            if parent.thread_id == event.thread_id && parent.end > event.start {
+                break;
+            }
+            stack.pop();
+        }
+        if let Some(&parent) = stack.last() {
+            self_times[parent] = self_times[parent].saturating_sub(event.end - event.start);
+        }
+        stack.push(i);
+    }
+    self_times
+}
+
+#[derive(Default)]
+struct PassStats {
+    count: u64,
+    time: u64,
+    self_time: u64,
+    max_rss: Option<usize>,
+}
+
+#[derive(Default)]
+struct QueryStats {
+    invocations: u64,
+    time: u64,
+    self_time: u64,
+    cache_hits: u64,
+    blocked_time: u64,
+    incremental_load_time: u64,
+    incremental_hashing_time: u64,
+}
+
+#[derive(Default)]
+struct CodegenUnitStats<'a> {
+    self_time: u64,
+    max_rss: Option<usize>,
+    passes: FxIndexMap<&'a str, u64>,
+}
+
+fn json_summary(
+    events: &[RecordedEvent],
+    query_names: &FxHashMap<u32, &'static str>,
+    codegen_units: &FxHashSet<Box<str>>,
+) -> String {
+    let self_times = self_times(events);
+
+    let mut total_time = 0;
+    let mut max_rss = None;
+    let mut passes: FxIndexMap<&str, PassStats> = FxIndexMap::default();
+    let mut queries: FxIndexMap<&str, QueryStats> = FxIndexMap::default();
+    let mut cgus: FxIndexMap<&str, CodegenUnitStats<'_>> = FxIndexMap::default();
+
+    for (event, &self_time) in events.iter().zip(&self_times) {
+        let time = event.end - event.start;
+        total_time = total_time.max(event.end);
+        max_rss = max_rss.max(event.end_rss);
+
+        if event.kind == EventKind::GenericActivity {
+            let stats = passes.entry(event.label).or_default();
+            stats.count += 1;
+            stats.time += time;
+            stats.self_time += self_time;
+            stats.max_rss = stats.max_rss.max(event.end_rss);
+
+            if let Some(arg) = event.arg.as_deref().filter(|&arg| codegen_units.contains(arg)) {
+                let stats = cgus.entry(arg).or_default();
+                stats.self_time += self_time;
+                stats.max_rss = stats.max_rss.max(event.end_rss);
+                *stats.passes.entry(event.label).or_default() += time;
+            }
+            continue;
+        }
+
+        let stats = queries.entry(event.name(query_names)).or_default();
+        match event.kind {
+            EventKind::GenericActivity => unreachable!(),
+            EventKind::Query => {
+                stats.invocations += 1;
+                stats.time += time;
+                stats.self_time += self_time;
+            }
+            EventKind::QueryCacheHit => stats.cache_hits += 1,
+            EventKind::QueryBlocked => stats.blocked_time += time,
+            EventKind::IncrementalLoadResult => stats.incremental_load_time += time,
+            EventKind::IncrementalResultHashing => stats.incremental_hashing_time += time,
+        }
+    }
+
+    passes.sort_by(|_, a, _, b| b.self_time.cmp(&a.self_time));
+    queries.sort_by(|_, a, _, b| b.self_time.cmp(&a.self_time));
+    cgus.sort_by(|_, a, _, b| b.self_time.cmp(&a.self_time));
+
+    let mut out = String::new();
+    write!(out, r#"{{"total_time":{},"max_rss":"#, secs(total_time)).unwrap();
+    write_opt(&mut out, max_rss);
+
+    out.push_str(r#","passes":["#);
+    for (i, (pass, stats)) in passes.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str(r#"{"pass":"#);
+        write_str(&mut out, pass);
+        write!(
+            out,
+            r#","count":{},"time":{},"self_time":{},"max_rss":"#,
+            stats.count,
+            secs(stats.time),
+            secs(stats.self_time),
+        )
+        .unwrap();
+        write_opt(&mut out, stats.max_rss);
+        out.push('}');
+    }
+
+    out.push_str(r#"],"queries":["#);
+    for (i, (query, stats)) in queries.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str(r#"{"query":"#);
+        write_str(&mut out, query);
+        write!(
+            out,
+            r#","invocations":{},"time":{},"self_time":{},"cache_hits":{},"blocked_time":{},"#,
+            stats.invocations,
+            secs(stats.time),
+            secs(stats.self_time),
+            stats.cache_hits,
+            secs(stats.blocked_time),
+        )
+        .unwrap();
+        write!(
+            out,
+            r#""incremental_load_time":{},"incremental_hashing_time":{}}}"#,
+            secs(stats.incremental_load_time),
+            secs(stats.incremental_hashing_time),
+        )
+        .unwrap();
+    }
+
+    out.push_str(r#"],"codegen_units":["#);
+    for (i, (cgu, stats)) in cgus.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str(r#"{"codegen_unit":"#);
+        write_str(&mut out, cgu);
+        write!(out, r#","time":{},"max_rss":"#, secs(stats.self_time)).unwrap();
+        write_opt(&mut out, stats.max_rss);
+        out.push_str(r#","passes":["#);
+        for (j, (pass, &time)) in stats.passes.iter().enumerate() {
+            if j > 0 {
+                out.push(',');
+            }
+            out.push_str(r#"{"pass":"#);
+            write_str(&mut out, pass);
+            write!(out, r#","time":{}}}"#, secs(time)).unwrap();
+        }
+        out.push_str("]}");
+    }
+    out.push_str("]}\n");
+    out
+}
+
+fn chrome_trace(
+    events: &[RecordedEvent],
+    query_names: &FxHashMap<u32, &'static str>,
+    crate_name: &str,
+) -> String {
+    let pid = process::id();
+    let mut sorted: Vec<&RecordedEvent> = events.iter().collect();
+    sorted.sort_by_key(|event| event.start);
+
+    let mut out = String::new();
+    write!(out, r#"{{"traceEvents":[{{"name":"process_name","ph":"M","pid":{pid},"#).unwrap();
+    out.push_str(r#""args":{"name":"#);
+    write_str(&mut out, crate_name);
+    out.push_str("}}");
+
+    let write_rss = |out: &mut String, ts: u64, rss: Option<usize>| {
+        if let Some(rss) = rss {
+            write!(out, r#",{{"name":"rss","ph":"C","ts":{},"pid":{pid},"#, micros(ts)).unwrap();
+            write!(out, r#""args":{{"rss":{rss}}}}}"#).unwrap();
+        }
+    };
+
+    for event in sorted {
+        out.push_str(r#",{"name":"#);
+        write_str(&mut out, event.name(query_names));
+        write!(out, r#","cat":"{}","#, event.kind.name()).unwrap();
+        if event.is_instant() {
+            write!(out, r#""ph":"i","s":"t","ts":{},"#, micros(event.start)).unwrap();
+        } else {
+            let ts = micros(event.start);
+            let dur = micros(event.end - event.start);
+            write!(out, r#""ph":"X","ts":{ts},"dur":{dur},"#).unwrap();
+        }
+        write!(out, r#""pid":{pid},"tid":{}"#, event.thread_id).unwrap();
+        if let Some(arg) = &event.arg {
+            out.push_str(r#","args":{"arg":"#);
+            write_str(&mut out, arg);
+            out.push('}');
+        }
+        out.push('}');
+
+        write_rss(&mut out, event.start, event.start_rss);
+        write_rss(&mut out, event.end, event.end_rss);
+    }
+    out.push_str(r#"],"displayTimeUnit":"ms"}"#);
+    out.push('\n');
+    out
+}
+
+fn secs(nanos: u64) -> f64 {
+    nanos as f64 / 1e9
+}
+
+fn micros(nanos: u64) -> String {
+    format!("{}.{:03}", nanos / 1000, nanos % 1000)
+}
+
+fn write_opt(out: &mut String, value: Option<usize>) {
+    match value {
+        Some(value) => write!(out, "{value}").unwrap(),
+        None => out.push_str("null"),
+    }
+}
+
+fn write_str(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
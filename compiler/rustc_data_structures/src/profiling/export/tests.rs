@@ -0,0 +1,65 @@
+use super::*;
+
+fn event(kind: EventKind, label: &'static str, tid: u32, start: u64, end: u64) -> RecordedEvent {
+    RecordedEvent {
+        kind,
+        label,
+        arg: None,
+        query_invocation_id: None,
+        thread_id: tid,
+        start,
+        end,
+        start_rss: None,
+        end_rss: None,
+    }
+}
+
+#[test]
+fn nested_self_times() {
+    let events = [
+        event(EventKind::GenericActivity, "child", 1, 10, 30),
+        event(EventKind::GenericActivity, "parent", 1, 0, 100),
+        event(EventKind::GenericActivity, "grandchild", 1, 15, 20),
+        event(EventKind::GenericActivity, "other_thread", 2, 5, 50),
+        event(EventKind::GenericActivity, "sibling", 1, 40, 60),
+        event(EventKind::QueryCacheHit, "", 1, 45, 45),
+    ];
+    assert_eq!(self_times(&events), [15, 60, 5, 45, 20, 0]);
+}
+
+#[test]
+fn summary() {
+    let mut query = event(EventKind::Query, "", 1, 0, 40);
+    query.query_invocation_id = Some(7);
+    let mut cache_hit = event(EventKind::QueryCacheHit, "", 1, 50, 50);
+    cache_hit.query_invocation_id = Some(7);
+    let mut codegen = event(EventKind::GenericActivity, "codegen_module", 1, 100, 300);
+    codegen.arg = Some("krate.abc-cgu.0".into());
+    codegen.end_rss = Some(1024);
+    let events = [query, cache_hit, codegen];
+
+    let query_names: FxHashMap<_, _> = [(7, "typeck")].into_iter().collect();
+    let codegen_units: FxHashSet<_> = [Box::from("krate.abc-cgu.0")].into_iter().collect();
+
+    assert_eq!(
+        json_summary(&events, &query_names, &codegen_units),
+        concat!(
+            r#"{"total_time":0.0000003,"max_rss":1024,"#,
+            r#""passes":[{"pass":"codegen_module","count":1,"time":0.0000002,"#,
+            r#""self_time":0.0000002,"max_rss":1024}],"#,
+            r#""queries":[{"query":"typeck","invocations":1,"time":0.00000004,"#,
+            r#""self_time":0.00000004,"cache_hits":1,"blocked_time":0,"#,
+            r#""incremental_load_time":0,"incremental_hashing_time":0}],"#,
+            r#""codegen_units":[{"codegen_unit":"krate.abc-cgu.0","time":0.0000002,"#,
+            r#""max_rss":1024,"passes":[{"pass":"codegen_module","time":0.0000002}]}]}"#,
+            "\n",
+        )
+    );
+}
+
+#[test]
+fn escaped_strings() {
+    let mut out = String::new();
+    write_str(&mut out, "<impl \"Foo\" at src\\lib.rs>\n\u{1}");
+    assert_eq!(out, r#""<impl \"Foo\" at src\\lib.rs>\n\u0001""#);
+}
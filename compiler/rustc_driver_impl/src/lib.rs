@@ -703,9 +703,14 @@ fn print_crate_info(
     #[allow(unused_imports)]
     use {do_not_use_safe_print as safe_print, do_not_use_safe_print as safe_println};
 
-    // NativeStaticLibs and LinkArgs are special - printed during linking
+    // NativeStaticLibs, LinkArgs and MonoItemsSize are special - printed during linking
     // (empty iterator returns true)
-    if sess.opts.prints.iter().all(|p| p.kind == NativeStaticLibs || p.kind == LinkArgs) {
+    if sess
+        .opts
+        .prints
+        .iter()
+        .all(|p| p.kind == NativeStaticLibs || p.kind == LinkArgs || p.kind == MonoItemsSize)
+    {
         return Compilation::Continue;
     }
 
@@ -820,6 +825,7 @@ fn print_crate_info(
             // Any output here interferes with Cargo's parsing of other printed output
             NativeStaticLibs => {}
             LinkArgs => {}
+            MonoItemsSize => {}
             SplitDebuginfo => {
                 use rustc_target::spec::SplitDebuginfo::{Off, Packed, Unpacked};
 
@@ -199,6 +199,10 @@ fn emit_unused_externs(
     ) {
     }
 
+    /// Describes the lints of the session to the emitter.
+    /// Currently only used by the SARIF format, which includes the description of lints.
+    fn register_lints(&mut self, _lints: &[&'static rustc_lint_defs::Lint]) {}
+
     /// Checks if should show explanations about "rustc --explain"
     fn should_show_explain(&self) -> bool {
         true
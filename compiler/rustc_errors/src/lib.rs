@@ -89,6 +89,7 @@
 mod lock;
 pub mod markdown;
 pub mod registry;
+pub mod sarif;
 mod snippet;
 mod styled_buffer;
 #[cfg(test)]
@@ -1012,6 +1013,10 @@ pub fn emit_artifact_notification(&self, path: &Path, artifact_type: &str) {
         self.inner.borrow_mut().emitter.emit_artifact_notification(path, artifact_type);
     }
 
+    pub fn register_lints(&self, lints: &[&'static rustc_lint_defs::Lint]) {
+        self.inner.borrow_mut().emitter.register_lints(lints);
+    }
+
     pub fn emit_future_breakage_report(&self) {
         let mut inner = self.inner.borrow_mut();
         let diags = std::mem::take(&mut inner.future_breakage_diagnostics);
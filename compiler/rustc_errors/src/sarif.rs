@@ -0,0 +1,594 @@
+//! A SARIF emitter for errors.
+//!
+//! This emits all diagnostics of a compilation as a single [SARIF 2.1.0] log, the format that
+//! code-scanning tools use to exchange the results of static analyzers. Since a SARIF log is a
+//! single JSON document, results are buffered and the log is written when the emitter is dropped,
+//! i.e. when the `DiagCtxt` owning it goes away.
+//!
+//! Error codes and lints are described as SARIF rules. The description of an error code comes
+//! from the [`Registry`], the description of a lint from its definition, which the driver passes
+//! to `DiagCtxt::register_lints` once the lint store is built. Suggestions are described as
+//! `fixes` and the secondary spans and sub-diagnostics of a diagnostic as `relatedLocations`.
+//!
+//! [SARIF 2.1.0]: https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html
+
+use crate::emitter::Emitter;
+use crate::registry::Registry;
+use crate::translation::{to_fluent_args, Translate};
+use crate::{
+    diagnostic::IsLint, CodeSuggestion, DiagInner, ErrCode, FluentBundle, LazyFallbackBundle, Level,
+};
+use rustc_data_structures::fx::{FxHashMap, FxIndexMap};
+use rustc_data_structures::sync::{IntoDynSyncSend, Lrc};
+use rustc_error_messages::FluentArgs;
+use rustc_lint_defs::{Applicability, Lint};
+use rustc_span::source_map::SourceMap;
+use rustc_span::{FileName, Span};
+use serde::Serialize;
+use std::error::Report;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::path::Path;
+
+#[cfg(test)]
+mod tests;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+
+pub struct SarifEmitter {
+    dst: IntoDynSyncSend<Box<dyn Write + Send>>,
+    registry: Option<Registry>,
+    sm: Lrc<SourceMap>,
+    fluent_bundle: Option<Lrc<FluentBundle>>,
+    fallback_bundle: LazyFallbackBundle,
+    /// The name of the tool in the log, e.g. `rustc` or `rustdoc`.
+    tool_name: &'static str,
+    /// Whether a log without any result is written. Diagnostic contexts which only exist to
+    /// report errors in the command line should not produce a log when nothing went wrong, as it
+    /// would be followed by the log of the session.
+    emit_empty_log: bool,
+    lints: FxHashMap<String, &'static Lint>,
+    rules: FxIndexMap<String, ReportingDescriptor>,
+    results: Vec<SarifResult>,
+    notifications: Vec<Notification>,
+    has_errors: bool,
+}
+
+impl SarifEmitter {
+    pub fn new(
+        dst: Box<dyn Write + Send>,
+        sm: Lrc<SourceMap>,
+        fallback_bundle: LazyFallbackBundle,
+    ) -> SarifEmitter {
+        SarifEmitter {
+            dst: IntoDynSyncSend(dst),
+            registry: None,
+            sm,
+            fluent_bundle: None,
+            fallback_bundle,
+            tool_name: "rustc",
+            emit_empty_log: true,
+            lints: FxHashMap::default(),
+            rules: FxIndexMap::default(),
+            results: Vec::new(),
+            notifications: Vec::new(),
+            has_errors: false,
+        }
+    }
+
+    // The setters are written by hand as `derive_setters` moves out of `self`, which the `Drop`
+    // implementation doesn't allow.
+
+    pub fn registry(mut self, registry: Option<Registry>) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    pub fn fluent_bundle(mut self, fluent_bundle: Option<Lrc<FluentBundle>>) -> Self {
+        self.fluent_bundle = fluent_bundle;
+        self
+    }
+
+    pub fn tool_name(mut self, tool_name: &'static str) -> Self {
+        self.tool_name = tool_name;
+        self
+    }
+
+    pub fn emit_empty_log(mut self, emit_empty_log: bool) -> Self {
+        self.emit_empty_log = emit_empty_log;
+        self
+    }
+
+    fn write_log(&mut self) -> io::Result<()> {
+        let log = SarifLog {
+            schema: SARIF_SCHEMA,
+            version: SARIF_VERSION,
+            runs: vec![Run {
+                tool: Tool {
+                    driver: ToolComponent {
+                        name: self.tool_name,
+                        information_uri: "https://www.rust-lang.org/",
+                        rules: std::mem::take(&mut self.rules).into_values().collect(),
+                    },
+                },
+                invocations: vec![Invocation {
+                    execution_successful: !self.has_errors,
+                    tool_execution_notifications: std::mem::take(&mut self.notifications),
+                }],
+                results: std::mem::take(&mut self.results),
+                column_kind: "unicodeCodePoints",
+            }],
+        };
+        serde_json::to_writer(&mut *self.dst, &log)?;
+        self.dst.write_all(b"\n")?;
+        self.dst.flush()
+    }
+
+    fn result(&mut self, diag: DiagInner) -> SarifResult {
+        let args = to_fluent_args(diag.args.iter());
+
+        let rule_index = if let Some(code) = diag.code {
+            Some(self.error_code_rule(code))
+        } else if let Some(IsLint { name, .. }) = &diag.is_lint {
+            Some(self.lint_rule(name))
+        } else {
+            None
+        };
+        let rule_id = rule_index.map(|index| self.rules[index].id.clone());
+
+        let mut text = self.translate_messages(&diag.messages, &args).into_owned();
+        let mut locations = vec![];
+        let mut related_locations = vec![];
+        for span_label in diag.span.span_labels() {
+            let Some(physical_location) = self.physical_location(span_label.span) else {
+                continue;
+            };
+            let message = span_label.label.as_ref().map(|label| {
+                Message::new(self.translate_message(label, &args).map_err(Report::new).unwrap())
+            });
+            if span_label.is_primary {
+                locations.push(Location { id: None, physical_location, message });
+            } else {
+                let id = Some(related_locations.len());
+                related_locations.push(Location { id, physical_location, message });
+            }
+        }
+
+        // Sub-diagnostics with a span become related locations, the others are appended to the
+        // message of the result like they would be in the human readable output.
+        for child in &diag.children {
+            let message = format!(
+                "{}: {}",
+                child.level.to_str(),
+                self.translate_messages(&child.messages, &args)
+            );
+            let mut has_location = false;
+            for &span in child.span.primary_spans() {
+                if let Some(physical_location) = self.physical_location(span) {
+                    let id = Some(related_locations.len());
+                    let message = Some(Message::new(message.clone()));
+                    related_locations.push(Location { id, physical_location, message });
+                    has_location = true;
+                }
+            }
+            if !has_location {
+                text.push('\n');
+                text.push_str(&message);
+            }
+        }
+
+        let fixes =
+            diag.suggestions.iter().flatten().flat_map(|sugg| self.fixes(sugg, &args)).collect();
+
+        SarifResult {
+            rule_id,
+            rule_index,
+            level: sarif_level(diag.level),
+            message: Message::new(text),
+            locations,
+            related_locations,
+            fixes,
+        }
+    }
+
+    /// Returns the index of the rule describing the error code `code`.
+    fn error_code_rule(&mut self, code: ErrCode) -> usize {
+        let id = code.to_string();
+        if let Some(index) = self.rules.get_index_of(&id) {
+            return index;
+        }
+
+        let description = self
+            .registry
+            .as_ref()
+            .and_then(|registry| registry.try_find_description(code).ok())
+            .map(|description| description.trim());
+        let rule = ReportingDescriptor {
+            // The first paragraph of the long description is a one-sentence summary of the error.
+            short_description: description
+                .map(|description| Message::new(description.split("\n\n").next().unwrap())),
+            full_description: description.map(Message::with_markdown),
+            help_uri: Some(format!("https://doc.rust-lang.org/error_codes/{id}.html")),
+            default_configuration: None,
+            id: id.clone(),
+        };
+        self.rules.insert_full(id, rule).0
+    }
+
+    /// Returns the index of the rule describing the lint named `name`.
+    fn lint_rule(&mut self, name: &str) -> usize {
+        if let Some(index) = self.rules.get_index_of(name) {
+            return index;
+        }
+
+        let lint = self.lints.get(name).copied();
+        let rule = ReportingDescriptor {
+            id: name.to_owned(),
+            short_description: lint.map(|lint| Message::new(lint.desc)),
+            full_description: None,
+            help_uri: lint_help_uri(name, lint),
+            default_configuration: lint.map(|lint| ReportingConfiguration {
+                level: match lint.default_level {
+                    rustc_lint_defs::Level::Allow | rustc_lint_defs::Level::Expect(_) => "none",
+                    rustc_lint_defs::Level::Warn | rustc_lint_defs::Level::ForceWarn(_) => {
+                        "warning"
+                    }
+                    rustc_lint_defs::Level::Deny | rustc_lint_defs::Level::Forbid => "error",
+                },
+            }),
+        };
+        self.rules.insert_full(name.to_owned(), rule).0
+    }
+
+    /// Describes each substitution of `suggestion` as a fix.
+    fn fixes(&self, suggestion: &CodeSuggestion, args: &FluentArgs<'_>) -> Vec<Fix> {
+        let description = Message::new(
+            self.translate_message(&suggestion.msg, args).map_err(Report::new).unwrap(),
+        );
+        suggestion
+            .substitutions
+            .iter()
+            .filter_map(|substitution| {
+                let mut changes: FxIndexMap<String, Vec<Replacement>> = FxIndexMap::default();
+                for part in &substitution.parts {
+                    // Suggestions touching files we can't point to can't be applied by tools.
+                    let (uri, deleted_region) = self.region(part.span)?;
+                    changes.entry(uri).or_default().push(Replacement {
+                        deleted_region,
+                        inserted_content: ArtifactContent { text: part.snippet.clone() },
+                    });
+                }
+                let artifact_changes = changes
+                    .into_iter()
+                    .map(|(uri, replacements)| ArtifactChange {
+                        artifact_location: ArtifactLocation { uri },
+                        replacements,
+                    })
+                    .collect();
+                Some(Fix {
+                    description: description.clone(),
+                    artifact_changes,
+                    properties: FixProperties { applicability: suggestion.applicability },
+                })
+            })
+            .collect()
+    }
+
+    fn physical_location(&self, span: Span) -> Option<PhysicalLocation> {
+        let (uri, mut region) = self.region(span)?;
+        region.snippet = self.sm.span_to_snippet(span).ok().map(|text| ArtifactContent { text });
+        Some(PhysicalLocation { artifact_location: ArtifactLocation { uri }, region })
+    }
+
+    /// Returns the URI of the file containing `span` and the region of `span` in that file, or
+    /// `None` if `span` doesn't point to a file on disk.
+    fn region(&self, span: Span) -> Option<(String, Region)> {
+        if span.is_dummy() {
+            return None;
+        }
+        let start = self.sm.lookup_char_pos(span.lo());
+        let end = self.sm.lookup_char_pos(span.hi());
+        let FileName::Real(_) = start.file.name else {
+            return None;
+        };
+        let file_name = self.sm.filename_for_diagnostics(&start.file.name).to_string();
+        let region = Region {
+            start_line: start.line,
+            start_column: start.col.0 + 1,
+            end_line: end.line,
+            end_column: end.col.0 + 1,
+            snippet: None,
+        };
+        Some((artifact_uri(&file_name), region))
+    }
+}
+
+impl Translate for SarifEmitter {
+    fn fluent_bundle(&self) -> Option<&Lrc<FluentBundle>> {
+        self.fluent_bundle.as_ref()
+    }
+
+    fn fallback_fluent_bundle(&self) -> &FluentBundle {
+        &self.fallback_bundle
+    }
+}
+
+impl Emitter for SarifEmitter {
+    fn emit_diagnostic(&mut self, diag: DiagInner) {
+        // Failure notes like "aborting due to 2 previous errors" are about the compilation and
+        // not about the code.
+        if diag.level == Level::FailureNote {
+            let args = to_fluent_args(diag.args.iter());
+            let message = self.translate_messages(&diag.messages, &args).into_owned();
+            self.notifications.push(Notification { level: "note", message: Message::new(message) });
+            return;
+        }
+
+        if diag.is_error() {
+            self.has_errors = true;
+        }
+        let result = self.result(diag);
+        self.results.push(result);
+    }
+
+    fn register_lints(&mut self, lints: &[&'static Lint]) {
+        self.lints.extend(lints.iter().map(|&lint| (lint.name_lower(), lint)));
+    }
+
+    fn source_map(&self) -> Option<&Lrc<SourceMap>> {
+        Some(&self.sm)
+    }
+
+    fn should_show_explain(&self) -> bool {
+        // The explanations of error codes are part of the rules.
+        false
+    }
+}
+
+impl Drop for SarifEmitter {
+    fn drop(&mut self) {
+        if self.results.is_empty() && self.notifications.is_empty() && !self.emit_empty_log {
+            return;
+        }
+        if let Err(e) = self.write_log()
+            && !std::thread::panicking()
+        {
+            panic!("failed to print diagnostics: {e:?}");
+        }
+    }
+}
+
+fn sarif_level(level: Level) -> &'static str {
+    match level {
+        Level::Bug | Level::Fatal | Level::Error | Level::DelayedBug => "error",
+        Level::ForceWarning(_) | Level::Warning => "warning",
+        Level::Note | Level::OnceNote | Level::Help | Level::OnceHelp | Level::FailureNote => {
+            "note"
+        }
+        Level::Allow | Level::Expect(_) => "none",
+    }
+}
+
+/// Returns the documentation of the lint named `name`, for the lints documented on a known page.
+fn lint_help_uri(name: &str, lint: Option<&'static Lint>) -> Option<String> {
+    match name.split_once("::") {
+        None => {
+            let page = match lint?.default_level {
+                rustc_lint_defs::Level::Allow | rustc_lint_defs::Level::Expect(_) => {
+                    "allowed-by-default"
+                }
+                rustc_lint_defs::Level::Warn | rustc_lint_defs::Level::ForceWarn(_) => {
+                    "warn-by-default"
+                }
+                rustc_lint_defs::Level::Deny | rustc_lint_defs::Level::Forbid => "deny-by-default",
+            };
+            let anchor = name.replace('_', "-");
+            Some(format!("https://doc.rust-lang.org/rustc/lints/listing/{page}.html#{anchor}"))
+        }
+        Some(("clippy", name)) => {
+            Some(format!("https://rust-lang.github.io/rust-clippy/master/index.html#{name}"))
+        }
+        Some(("rustdoc", name)) => {
+            Some(format!("https://doc.rust-lang.org/rustdoc/lints.html#{name}"))
+        }
+        Some(_) => None,
+    }
+}
+
+/// Converts a path to the URI reference SARIF expects for artifact locations. Relative paths stay
+/// relative, so that consumers resolve them against the root of the repository.
+fn artifact_uri(path: &str) -> String {
+    let mut uri = String::with_capacity(path.len());
+    let normalized = path.replace('\\', "/");
+    if Path::new(path).is_absolute() {
+        uri.push_str("file://");
+        // Windows paths like `C:/foo` need a leading slash.
+        if !normalized.starts_with('/') {
+            uri.push('/');
+        }
+    }
+    for c in normalized.chars() {
+        match c {
+            ' ' | '"' | '%' | '#' | '?' | '[' | ']' | '<' | '>' | '`' | '{' | '}' | '|' | '^' => {
+                write!(uri, "%{:02X}", c as u32).unwrap()
+            }
+            _ => uri.push(c),
+        }
+    }
+    uri
+}
+
+// The following data types are provided just for serialisation. They follow the naming of the
+// SARIF specification, which calls a diagnostic a result and a lint or error code a rule.
+
+#[derive(Serialize)]
+struct SarifLog {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: Vec<Run>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Run {
+    tool: Tool,
+    invocations: Vec<Invocation>,
+    results: Vec<SarifResult>,
+    /// rustc counts columns in characters, SARIF in UTF-16 code units by default.
+    column_kind: &'static str,
+}
+
+#[derive(Serialize)]
+struct Tool {
+    driver: ToolComponent,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ToolComponent {
+    name: &'static str,
+    information_uri: &'static str,
+    rules: Vec<ReportingDescriptor>,
+}
+
+/// A rule, i.e. an error code or a lint.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReportingDescriptor {
+    id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    short_description: Option<Message>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    full_description: Option<Message>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    help_uri: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    default_configuration: Option<ReportingConfiguration>,
+}
+
+#[derive(Serialize)]
+struct ReportingConfiguration {
+    /// The level of a lint when no lint level attribute or flag applies to it.
+    level: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Invocation {
+    execution_successful: bool,
+    tool_execution_notifications: Vec<Notification>,
+}
+
+#[derive(Serialize)]
+struct Notification {
+    level: &'static str,
+    message: Message,
+}
+
+/// A diagnostic.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rule_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rule_index: Option<usize>,
+    /// "error", "warning", "note" or "none".
+    level: &'static str,
+    message: Message,
+    locations: Vec<Location>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    related_locations: Vec<Location>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    fixes: Vec<Fix>,
+}
+
+#[derive(Serialize, Clone)]
+struct Message {
+    text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    markdown: Option<String>,
+}
+
+impl Message {
+    fn new(text: impl Into<String>) -> Message {
+        Message { text: text.into(), markdown: None }
+    }
+
+    fn with_markdown(markdown: &str) -> Message {
+        Message { text: markdown.to_owned(), markdown: Some(markdown.to_owned()) }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Location {
+    /// Only set for related locations.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<usize>,
+    physical_location: PhysicalLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<Message>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+    artifact_location: ArtifactLocation,
+    region: Region,
+}
+
+#[derive(Serialize)]
+struct ArtifactLocation {
+    uri: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+    /// 1-based.
+    start_line: usize,
+    /// 1-based, character offset.
+    start_column: usize,
+    end_line: usize,
+    /// Exclusive.
+    end_column: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    snippet: Option<ArtifactContent>,
+}
+
+#[derive(Serialize)]
+struct ArtifactContent {
+    text: String,
+}
+
+/// A suggestion.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Fix {
+    description: Message,
+    artifact_changes: Vec<ArtifactChange>,
+    properties: FixProperties,
+}
+
+#[derive(Serialize)]
+struct FixProperties {
+    /// Whether the fix can be applied automatically, see [`Applicability`].
+    applicability: Applicability,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ArtifactChange {
+    artifact_location: ArtifactLocation,
+    replacements: Vec<Replacement>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Replacement {
+    deleted_region: Region,
+    inserted_content: ArtifactContent,
+}
@@ -0,0 +1,153 @@
+use super::*;
+
+use crate::{DiagCtxt, E0308};
+use rustc_span::source_map::FilePathMapping;
+use rustc_span::BytePos;
+
+use std::str;
+use std::sync::{Arc, Mutex};
+
+use serde_json::{json, Value};
+
+struct Shared<T> {
+    data: Arc<Mutex<T>>,
+}
+
+impl<T: Write> Write for Shared<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.data.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.data.lock().unwrap().flush()
+    }
+}
+
+/// Runs `f` with a `DiagCtxt` emitting SARIF for a single `test.rs` file containing `code`, and
+/// returns the log written once the `DiagCtxt` is dropped.
+fn sarif_log(code: &str, f: impl FnOnce(&DiagCtxt)) -> Value {
+    rustc_span::create_default_session_globals_then(|| {
+        let sm = Lrc::new(SourceMap::new(FilePathMapping::empty()));
+        sm.new_source_file(Path::new("test.rs").to_owned().into(), code.to_owned());
+        let fallback_bundle =
+            crate::fallback_fluent_bundle(vec![crate::DEFAULT_LOCALE_RESOURCE], false);
+
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let se = SarifEmitter::new(Box::new(Shared { data: output.clone() }), sm, fallback_bundle)
+            .registry(Some(Registry::new(&[(E0308, "Expected type did not match.\n\nMore.")])));
+
+        let dcx = DiagCtxt::new(Box::new(se));
+        f(&dcx);
+        drop(dcx);
+
+        let bytes = output.lock().unwrap();
+        serde_json::from_str(str::from_utf8(&bytes).unwrap()).unwrap()
+    })
+}
+
+fn span(lo: u32, hi: u32) -> Span {
+    Span::with_root_ctxt(BytePos(lo), BytePos(hi))
+}
+
+#[test]
+fn empty_log() {
+    let log = sarif_log("fn main() {}\n", |_| {});
+    assert_eq!(log["version"], "2.1.0");
+    assert_eq!(log["runs"][0]["tool"]["driver"]["name"], "rustc");
+    assert_eq!(log["runs"][0]["results"], json!([]));
+    assert_eq!(log["runs"][0]["invocations"][0]["executionSuccessful"], true);
+}
+
+#[test]
+fn result_with_rule_and_locations() {
+    let log = sarif_log("let x: u8 = 'a';\n", |dcx| {
+        dcx.struct_span_err(span(12, 15), "mismatched types")
+            .with_code(E0308)
+            .with_span_label(span(12, 15), "expected `u8`, found `char`")
+            .with_span_label(span(7, 9), "expected due to this")
+            .emit();
+    });
+    let run = &log["runs"][0];
+    assert_eq!(run["invocations"][0]["executionSuccessful"], false);
+    assert_eq!(
+        run["tool"]["driver"]["rules"][0],
+        json!({
+            "id": "E0308",
+            "shortDescription": { "text": "Expected type did not match." },
+            "fullDescription": {
+                "text": "Expected type did not match.\n\nMore.",
+                "markdown": "Expected type did not match.\n\nMore.",
+            },
+            "helpUri": "https://doc.rust-lang.org/error_codes/E0308.html",
+        })
+    );
+
+    let result = &run["results"][0];
+    assert_eq!(result["ruleId"], "E0308");
+    assert_eq!(result["ruleIndex"], 0);
+    assert_eq!(result["level"], "error");
+    assert_eq!(result["message"]["text"], "mismatched types");
+    assert_eq!(
+        result["locations"][0],
+        json!({
+            "physicalLocation": {
+                "artifactLocation": { "uri": "test.rs" },
+                "region": {
+                    "startLine": 1,
+                    "startColumn": 13,
+                    "endLine": 1,
+                    "endColumn": 16,
+                    "snippet": { "text": "'a'" },
+                },
+            },
+            "message": { "text": "expected `u8`, found `char`" },
+        })
+    );
+    assert_eq!(result["relatedLocations"][0]["id"], 0);
+    assert_eq!(result["relatedLocations"][0]["message"]["text"], "expected due to this");
+}
+
+#[test]
+fn suggestion_as_fix() {
+    let log = sarif_log("let x = 1;\n", |dcx| {
+        dcx.struct_span_warn(span(4, 5), "unused variable: `x`")
+            .with_span_suggestion(
+                span(4, 5),
+                "if this is intentional, prefix it with an underscore",
+                "_x",
+                Applicability::MachineApplicable,
+            )
+            .emit();
+    });
+    let result = &log["runs"][0]["results"][0];
+    assert_eq!(result["level"], "warning");
+    assert_eq!(
+        result["fixes"],
+        json!([{
+            "description": { "text": "if this is intentional, prefix it with an underscore" },
+            "artifactChanges": [{
+                "artifactLocation": { "uri": "test.rs" },
+                "replacements": [{
+                    "deletedRegion": {
+                        "startLine": 1,
+                        "startColumn": 5,
+                        "endLine": 1,
+                        "endColumn": 6,
+                    },
+                    "insertedContent": { "text": "_x" },
+                }],
+            }],
+            "properties": { "applicability": "MachineApplicable" },
+        }])
+    );
+}
+
+#[test]
+fn artifact_uris() {
+    assert_eq!(artifact_uri("src/main.rs"), "src/main.rs");
+    assert_eq!(artifact_uri("src/a b#c.rs"), "src/a%20b%23c.rs");
+    #[cfg(unix)]
+    assert_eq!(artifact_uri("/src/main.rs"), "file:///src/main.rs");
+    #[cfg(windows)]
+    assert_eq!(artifact_uri(r"C:\src\main.rs"), "file:///C:/src/main.rs");
+}
@@ -470,6 +470,10 @@ pub struct BuiltinAttribute {
         EncodeCrossCrate::Yes, custom_test_frameworks,
         "custom test frameworks are an unstable feature",
     ),
+    gated!(
+        test_timeout, Normal, template!(NameValueStr: "seconds"), ErrorFollowing,
+        EncodeCrossCrate::No, experimental!(test_timeout)
+    ),
     // RFC #1268
     gated!(
         marker, Normal, template!(Word), WarnFollowing, EncodeCrossCrate::No,
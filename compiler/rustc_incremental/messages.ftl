@@ -83,6 +83,9 @@ incremental_repeated_depnode_label = dep-node label `{$label}` is repeated
 incremental_session_gc_failed =
     failed to garbage collect incremental compilation session directory `{$path}`: {$err}
 
+incremental_shared_cache_export =
+    failed to publish incremental compilation session to the shared cache `{$path}`: {$err}
+
 incremental_unchecked_clean = found unchecked `#[rustc_clean]` attribute
 
 incremental_undefined_clean_dirty_assertions =
@@ -270,6 +270,13 @@ pub struct LoadDepGraph {
     pub err: std::io::Error,
 }
 
+#[derive(Diagnostic)]
+#[diag(incremental_shared_cache_export)]
+pub struct SharedCacheExport<'a> {
+    pub path: &'a Path,
+    pub err: std::io::Error,
+}
+
 #[derive(Diagnostic)]
 #[diag(incremental_write_dep_graph)]
 pub struct WriteDepGraph<'a> {
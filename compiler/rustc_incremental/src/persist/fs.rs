@@ -89,6 +89,14 @@
 //! so, it will also place a read lock on that the respective session directory
 //! so that it won't be deleted while the metadata hashes are loaded.
 //!
+//! ## Shared cache
+//!
+//! With `-Z incremental-shared-cache`, finalized session directories are also
+//! published to a content-addressed cache that can be shared between
+//! incremental compilation directories. A new session directory for which no
+//! finalized session directory exists locally starts out as a copy of the
+//! latest session in the shared cache. See the `shared` module for details.
+//!
 //! ## Preconditions
 //!
 //! This system relies on two features being available in the file system in
@@ -121,6 +129,7 @@
 
 use rand::{thread_rng, RngCore};
 
+mod shared;
 #[cfg(test)]
 mod tests;
 
@@ -256,11 +265,18 @@ pub(crate) fn prepare_session_directory(sess: &Session) -> Result<(), ErrorGuara
         let source_directory = find_source_directory(&crate_dir, &source_directories_already_tried);
 
         let Some(source_directory) = source_directory else {
-            // There's nowhere to copy from, we're done
-            debug!(
-                "no source directory found. Continuing with empty session \
-                    directory."
-            );
+            // There's nowhere to copy from locally, so fall back to the
+            // shared cache if there is one.
+            let imported = match &sess.opts.unstable_opts.incremental_shared_cache {
+                Some(shared_dir) => shared::import_session(sess, shared_dir, &session_dir),
+                None => false,
+            };
+            if !imported {
+                debug!(
+                    "no source directory found. Continuing with empty session \
+                        directory."
+                );
+            }
 
             sess.init_incr_comp_session(session_dir, directory_lock);
             return Ok(());
@@ -363,6 +379,14 @@ pub fn finalize_session_directory(sess: &Session, svh: Option<Svh>) {
         Ok(_) => {
             debug!("finalize_session_directory() - directory renamed successfully");
 
+            // Publish the session while we still hold the lock, so that it
+            // can't be garbage collected in the meantime.
+            if let Some(shared_dir) = &sess.opts.unstable_opts.incremental_shared_cache {
+                if let Err(err) = shared::export_session(sess, shared_dir, &new_path) {
+                    sess.dcx().emit_warn(errors::SharedCacheExport { path: shared_dir, err });
+                }
+            }
+
             // This unlocks the directory
             sess.finalize_incr_comp_session(new_path);
         }
@@ -611,7 +635,12 @@ fn string_to_timestamp(s: &str) -> Result<SystemTime, &'static str> {
 
 fn crate_path(sess: &Session) -> PathBuf {
     let incr_dir = sess.opts.incremental.as_ref().unwrap().clone();
+    incr_dir.join(crate_dir_name(sess))
+}
 
+/// Returns the name of the directory holding the sessions of the crate, of the form
+/// `{crate-name}-{stable-crate-id}`.
+fn crate_dir_name(sess: &Session) -> String {
     let crate_name = find_crate_name(sess, &[]);
     let crate_types = collect_crate_types(sess, &[]);
     let stable_crate_id = StableCrateId::new(
@@ -623,8 +652,7 @@ fn crate_path(sess: &Session) -> PathBuf {
 
     let stable_crate_id = base_n::encode(stable_crate_id.as_u64() as u128, INT_ENCODE_BASE);
 
-    let crate_name = format!("{crate_name}-{stable_crate_id}");
-    incr_dir.join(crate_name)
+    format!("{crate_name}-{stable_crate_id}")
 }
 
 fn is_old_enough_to_be_collected(timestamp: SystemTime) -> bool {
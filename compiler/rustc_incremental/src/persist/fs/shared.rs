@@ -0,0 +1,184 @@
+//! A cache of finalized session directories that can be shared between
+//! checkouts and machines, enabled with `-Z incremental-shared-cache=DIR`.
+//!
+//! The files of a session directory don't depend on the location of the
+//! incremental compilation directory. They only depend on the location of the
+//! sources through the file names in spans, which are affected by
+//! `--remap-path-prefix` and the working directory, both of which are part of
+//! the dependency tracking hash of the options. Entries of the shared cache are
+//! thus keyed by the name of the crate directory and that hash: compilations of
+//! the same crate with the same options from checkouts that are remapped to the
+//! same path share an entry. Reusing a session from a different configuration
+//! would not be unsound anyway, as everything loaded from it is validated as
+//! usual, but it would not result in many incremental hits.
+//!
+//! The shared cache has the following layout:
+//!
+//! - `objects/{hash}`: the files of the published sessions, named after the hash
+//!   of their contents, so that the files shared by several sessions (e.g. the
+//!   object files of unchanged codegen units) are only stored once.
+//! - `sessions/{crate-dir}-{options-hash}`: the manifest of the last session
+//!   published for a crate, with a `{hash} {file-name}` line per file of the
+//!   session directory.
+//!
+//! Objects are never modified once written and manifests are replaced
+//! atomically, so concurrent compilations only race on which session ends up in
+//! the manifest. Nothing is ever removed from the shared cache, cleaning it up
+//! is left to whoever provisions it.
+
+use super::{crate_dir_name, INT_ENCODE_BASE};
+use rustc_data_structures::base_n;
+use rustc_data_structures::stable_hasher::{Hash128, StableHasher};
+use rustc_fs_util::{link_or_copy, LinkOrCopy};
+use rustc_session::Session;
+
+use std::fmt::Write as _;
+use std::fs as std_fs;
+use std::hash::Hasher;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use rand::{thread_rng, RngCore};
+
+const OBJECTS_DIR: &str = "objects";
+const SESSIONS_DIR: &str = "sessions";
+
+/// Fills the new, empty session directory with the files of the last session
+/// published to the shared cache for this crate.
+///
+/// Returns `false` if there is no such session or if it couldn't be copied, in
+/// which case the session directory is left empty.
+pub(super) fn import_session(sess: &Session, shared_dir: &Path, session_dir: &Path) -> bool {
+    let manifest_path = manifest_path(sess, shared_dir);
+    let Ok(manifest) = std_fs::read_to_string(&manifest_path) else {
+        debug!("import_session() - no manifest at {}", manifest_path.display());
+        return false;
+    };
+
+    let mut files_linked = 0;
+    let mut files_copied = 0;
+
+    for line in manifest.lines() {
+        let copied = parse_manifest_line(line).and_then(|(hash, file_name)| {
+            let source_path = shared_dir.join(OBJECTS_DIR).join(hash);
+            debug!("copying into session dir: {}", source_path.display());
+            link_or_copy(source_path, session_dir.join(file_name)).ok()
+        });
+        match copied {
+            Some(LinkOrCopy::Link) => files_linked += 1,
+            Some(LinkOrCopy::Copy) => files_copied += 1,
+            None => {
+                debug!("import_session() - invalid or missing file for `{line}`");
+                // Don't leave an incomplete session behind.
+                if let Ok(entries) = session_dir.read_dir() {
+                    for entry in entries.flatten() {
+                        let _ = std_fs::remove_file(entry.path());
+                    }
+                }
+                return false;
+            }
+        }
+    }
+
+    if sess.opts.unstable_opts.incremental_info {
+        eprintln!(
+            "[incremental] shared cache: \
+                  {files_linked} files hard-linked"
+        );
+        eprintln!(
+            "[incremental] shared cache: \
+                 {files_copied} files copied"
+        );
+    }
+
+    true
+}
+
+/// Publishes the files of a finalized session directory to the shared cache
+/// and makes it the session to import for this crate.
+pub(super) fn export_session(
+    sess: &Session,
+    shared_dir: &Path,
+    session_dir: &Path,
+) -> io::Result<()> {
+    let objects_dir = shared_dir.join(OBJECTS_DIR);
+    std_fs::create_dir_all(&objects_dir)?;
+    std_fs::create_dir_all(shared_dir.join(SESSIONS_DIR))?;
+
+    let mut entries = session_dir.read_dir()?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    let mut manifest = String::new();
+    for entry in entries {
+        let file_name = entry.file_name();
+        let Some(file_name) = file_name.to_str() else {
+            return Err(io::Error::other(format!(
+                "non-Unicode file name in session directory: {}",
+                entry.path().display()
+            )));
+        };
+
+        let source_path = entry.path();
+        let hash = content_hash(&std_fs::read(&source_path)?);
+        let object_path = objects_dir.join(&hash);
+        if !object_path.exists() {
+            // The session directory is never modified once finalized, so the
+            // object can be a hard link to the file.
+            let temp_path = temp_path(&object_path);
+            link_or_copy(&source_path, &temp_path)?;
+            rename_or_remove(&temp_path, &object_path)?;
+        }
+        writeln!(manifest, "{hash} {file_name}").unwrap();
+    }
+
+    let manifest_path = manifest_path(sess, shared_dir);
+    let temp_path = temp_path(&manifest_path);
+    std_fs::write(&temp_path, manifest)?;
+    rename_or_remove(&temp_path, &manifest_path)?;
+
+    debug!("export_session() - published to {}", manifest_path.display());
+    Ok(())
+}
+
+/// Returns the path of the manifest of this crate in the shared cache:
+/// `{shared-dir}/sessions/{crate-dir}-{options-hash}`.
+fn manifest_path(sess: &Session, shared_dir: &Path) -> PathBuf {
+    let mut name = crate_dir_name(sess);
+    name.push('-');
+    base_n::push_str(sess.opts.incr_comp_dep_tracking_hash() as u128, INT_ENCODE_BASE, &mut name);
+    shared_dir.join(SESSIONS_DIR).join(name)
+}
+
+fn content_hash(contents: &[u8]) -> String {
+    let mut hasher = StableHasher::new();
+    hasher.write(contents);
+    let hash: Hash128 = hasher.finish();
+    base_n::encode(hash.as_u128(), INT_ENCODE_BASE)
+}
+
+/// Parses a `{hash} {file-name}` line of a manifest. Returns `None` if the
+/// line is malformed or if the file name is not a plain file name, so that a
+/// corrupted manifest can't make us write outside of the session directory.
+pub(super) fn parse_manifest_line(line: &str) -> Option<(&str, &str)> {
+    let (hash, file_name) = line.split_once(' ')?;
+    let is_valid_hash = !hash.is_empty() && hash.bytes().all(|b| b.is_ascii_alphanumeric());
+    let is_plain_file_name = Path::new(file_name).file_name().and_then(|name| name.to_str())
+        == Some(file_name)
+        && !file_name.contains(['/', '\\']);
+    (is_valid_hash && is_plain_file_name).then_some((hash, file_name))
+}
+
+/// Files are written to a temporary path next to their final path first and
+/// then renamed, so that other processes never see a partially written file.
+fn temp_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap().to_os_string();
+    let suffix = base_n::encode(thread_rng().next_u32() as u128, INT_ENCODE_BASE);
+    file_name.push(format!(".tmp-{suffix}"));
+    path.with_file_name(file_name)
+}
+
+fn rename_or_remove(temp_path: &Path, path: &Path) -> io::Result<()> {
+    std_fs::rename(temp_path, path).inspect_err(|_| {
+        let _ = std_fs::remove_file(temp_path);
+    })
+}
@@ -75,3 +75,17 @@ fn test_find_source_directory_in_iter() {
         None
     );
 }
+
+#[test]
+fn test_parse_manifest_line() {
+    assert_eq!(
+        shared::parse_manifest_line("1a2b3c dep-graph.bin"),
+        Some(("1a2b3c", "dep-graph.bin"))
+    );
+    assert_eq!(shared::parse_manifest_line("1a2b3c"), None);
+    assert_eq!(shared::parse_manifest_line(" dep-graph.bin"), None);
+    assert_eq!(shared::parse_manifest_line("../1a2b3c dep-graph.bin"), None);
+    assert_eq!(shared::parse_manifest_line("1a2b3c ../dep-graph.bin"), None);
+    assert_eq!(shared::parse_manifest_line("1a2b3c sub/dep-graph.bin"), None);
+    assert_eq!(shared::parse_manifest_line("1a2b3c .."), None);
+}
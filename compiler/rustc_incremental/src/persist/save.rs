@@ -170,7 +170,7 @@ pub(crate) fn build_dep_graph(
     file_format::write_file_header(&mut encoder, sess);
 
     // First encode the commandline arguments hash
-    sess.opts.dep_tracking_hash(false).encode(&mut encoder);
+    sess.opts.incr_comp_dep_tracking_hash().encode(&mut encoder);
 
     Some(DepGraph::new(
         &sess.prof,
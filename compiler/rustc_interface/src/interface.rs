@@ -446,6 +446,7 @@ pub fn run_compiler<R: Send>(config: Config, f: impl FnOnce(&Compiler) -> R + Se
                 register_lints(&sess, &mut lint_store);
                 sess.registered_lints = true;
             }
+            sess.dcx().register_lints(lint_store.get_lints());
             sess.lint_store = Some(Lrc::new(lint_store));
 
             let compiler = Compiler {
@@ -1,15 +1,15 @@
 #![allow(rustc::bad_opt_access)]
 use crate::interface::{initialize_checked_jobserver, parse_cfg};
-use rustc_data_structures::profiling::TimePassesFormat;
+use rustc_data_structures::profiling::{SelfProfileExportFormat, TimePassesFormat};
 use rustc_errors::{emitter::HumanReadableErrorType, registry, ColorConfig};
 use rustc_session::config::{
     build_configuration, build_session_options, rustc_optgroups, BranchProtection, CFGuard, Cfg,
     CollapseMacroDebuginfo, CoverageOptions, DebugInfo, DumpMonoStatsFormat, ErrorOutputType,
     ExternEntry, ExternLocation, Externs, FunctionReturn, InliningThreshold, Input,
-    InstrumentCoverage, InstrumentXRay, LinkSelfContained, LinkerPluginLto, LocationDetail, LtoCli,
-    NextSolverConfig, OomStrategy, Options, OutFileName, OutputType, OutputTypes, PAuthKey, PacRet,
-    Passes, Polonius, ProcMacroExecutionStrategy, Strip, SwitchWithOptPath, SymbolManglingVersion,
-    WasiExecModel,
+    InstrumentCoverage, InstrumentXRay, LinkSelfContained, LinkerPluginLto, LintConfigFile,
+    LocationDetail, LtoCli, NextSolverConfig, OomStrategy, Options, OutFileName, OutputType,
+    OutputTypes, PAuthKey, PacRet, Passes, Polonius, ProcMacroExecutionStrategy, Strip,
+    SwitchWithOptPath, SymbolManglingVersion, WasiExecModel,
 };
 use rustc_session::lint::Level;
 use rustc_session::search_paths::SearchPath;
@@ -302,6 +302,23 @@ fn test_lint_cap_hash_different() {
     assert_non_crate_hash_different(&v2, &v3);
 }
 
+#[test]
+fn test_lint_config_hash_different() {
+    let mut v1 = Options::default();
+    let mut v2 = Options::default();
+    let v3 = Options::default();
+
+    let path = PathBuf::from("lints.toml");
+    let src = "[lints.rust]\nunused = \"warn\"\n".to_owned();
+    v1.lint_config = Some(LintConfigFile { path: path.clone(), src });
+    let src = "[lints.rust]\nunused = \"deny\"\n".to_owned();
+    v2.lint_config = Some(LintConfigFile { path, src });
+
+    assert_non_crate_hash_different(&v1, &v2);
+    assert_non_crate_hash_different(&v1, &v3);
+    assert_non_crate_hash_different(&v2, &v3);
+}
+
 #[test]
 fn test_search_paths_tracking_hash_different_order() {
     let mut v1 = Options::default();
@@ -662,6 +679,27 @@ macro_rules! tracked {
     // tidy-alphabetical-end
 }
 
+#[test]
+fn test_incr_comp_hash_shared_cache_remap_path_prefix() {
+    let mut v1 = Options::default();
+    let mut v2 = Options::default();
+    let mut v3 = Options::default();
+
+    v1.remap_path_prefix = vec![("/first".into(), "/checkout".into())];
+    v2.remap_path_prefix = vec![("/second".into(), "/checkout".into())];
+    v3.remap_path_prefix = vec![("/first".into(), "/elsewhere".into())];
+
+    // Without a shared cache, the remapped paths are hashed as usual.
+    assert_ne!(v1.incr_comp_dep_tracking_hash(), v2.incr_comp_dep_tracking_hash());
+
+    // With one, only the paths they are remapped to are.
+    for opts in [&mut v1, &mut v2, &mut v3] {
+        opts.unstable_opts.incremental_shared_cache = Some("shared".into());
+    }
+    assert_eq!(v1.incr_comp_dep_tracking_hash(), v2.incr_comp_dep_tracking_hash());
+    assert_ne!(v1.incr_comp_dep_tracking_hash(), v3.incr_comp_dep_tracking_hash());
+}
+
 #[test]
 fn test_unstable_options_tracking_hash() {
     let reference = Options::default();
@@ -693,6 +731,7 @@ macro_rules! untracked {
     untracked!(hir_stats, true);
     untracked!(identify_regions, true);
     untracked!(incremental_info, true);
+    untracked!(incremental_shared_cache, Some(PathBuf::from("abc")));
     untracked!(incremental_verify_ich, true);
     untracked!(input_stats, true);
     untracked!(link_native_libraries, false);
@@ -718,6 +757,7 @@ macro_rules! untracked {
     untracked!(query_dep_graph, true);
     untracked!(self_profile, SwitchWithOptPath::Enabled(None));
     untracked!(self_profile_events, Some(vec![String::new()]));
+    untracked!(self_profile_export, vec![SelfProfileExportFormat::ChromeTrace]);
     untracked!(shell_argfiles, true);
     untracked!(span_debug, true);
     untracked!(span_free_formats, true);
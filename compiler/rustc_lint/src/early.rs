@@ -90,11 +90,22 @@ fn visit_param(&mut self, param: &'a ast::Param) {
     }
 
     fn visit_item(&mut self, it: &'a ast::Item) {
+        // The `--lint-config` overrides of an out-of-line module come before its attributes.
+        let lint_config_push = match &it.kind {
+            ast::ItemKind::Mod(_, ast::ModKind::Loaded(_, ast::Inline::No, spans)) => {
+                let builder = &mut self.context.builder;
+                Some(builder.push_module_lint_config_overrides(it.span, spans.inner_span))
+            }
+            _ => None,
+        };
         self.with_lint_attrs(it.id, &it.attrs, |cx| {
             lint_callback!(cx, check_item, it);
             ast_visit::walk_item(cx, it);
             lint_callback!(cx, check_item_post, it);
-        })
+        });
+        if let Some(push) = lint_config_push {
+            self.context.builder.pop(push);
+        }
     }
 
     fn visit_foreign_item(&mut self, it: &'a ast::ForeignItem) {
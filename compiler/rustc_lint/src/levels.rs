@@ -1,7 +1,7 @@
 use crate::errors::{CheckNameUnknownTool, RequestedLevel, UnsupportedGroup};
 use crate::lints::{
-    DeprecatedLintNameFromCommandLine, RemovedLintFromCommandLine, RenamedLintFromCommandLine,
-    UnknownLintFromCommandLine,
+    DeprecatedLintNameFromCommandLine, DeprecatedLintNameFromLintConfig,
+    RemovedLintFromCommandLine, RenamedLintFromCommandLine, UnknownLintFromCommandLine,
 };
 use crate::{
     builtin::MISSING_DOCS,
@@ -36,10 +36,12 @@
     },
     Level, Lint, LintExpectationId, LintId,
 };
+use rustc_session::lint_config::ConfiguredLint;
 use rustc_session::parse::feature_err;
 use rustc_session::Session;
 use rustc_span::symbol::{sym, Symbol};
-use rustc_span::{Span, DUMMY_SP};
+use rustc_span::{FileName, Span, DUMMY_SP};
+use std::path::Path;
 
 use crate::errors::{
     MalformedAttribute, MalformedAttributeSub, OverruledAttribute, OverruledAttributeSub,
@@ -172,6 +174,11 @@ fn shallow_lint_levels_on(tcx: TyCtxt<'_>, owner: hir::OwnerId) -> ShallowLintLe
 
     if owner == hir::CRATE_OWNER_ID {
         levels.add_command_line();
+    } else if tcx.sess.lint_config.is_some()
+        && let hir::OwnerNode::Item(item) = tcx.hir_owner_node(owner)
+        && let hir::ItemKind::Mod(module) = item.kind
+    {
+        levels.add_module_lint_config_overrides(item.span, module.spans.inner_span);
     }
 
     match attrs.map.range(..) {
@@ -526,6 +533,27 @@ pub(crate) fn push(
         BuilderPush { prev }
     }
 
+    /// Like `push`, for the `--lint-config` overrides of a module, which come before the
+    /// attributes of the module.
+    pub(crate) fn push_module_lint_config_overrides(
+        &mut self,
+        item_span: Span,
+        inner_span: Span,
+    ) -> BuilderPush {
+        let prev = self.provider.cur;
+        self.provider.cur =
+            self.provider.sets.list.push(LintSet { specs: FxIndexMap::default(), parent: prev });
+
+        self.add_module_lint_config_overrides(item_span, inner_span);
+
+        if self.provider.current_specs().is_empty() {
+            self.provider.sets.list.pop();
+            self.provider.cur = prev;
+        }
+
+        BuilderPush { prev }
+    }
+
     /// Called after `push` when the scope of a set of attributes are exited.
     pub(crate) fn pop(&mut self, push: BuilderPush) {
         self.provider.cur = push.prev;
@@ -557,7 +585,14 @@ fn insert(&mut self, id: LintId, lvl: LevelAndSource) {
         self.provider.insert(id, lvl)
     }
 
+    /// Adds the lint levels of the `--lint-config` file and of the `-A`, `-W`, `-D` and `-F`
+    /// flags, which take precedence, then the overrides of the file for the crate root.
     fn add_command_line(&mut self) {
+        let sess = self.sess;
+        if let Some(lint_config) = &sess.lint_config {
+            self.add_configured_lints(&lint_config.lints);
+        }
+
         for &(ref lint_name, level) in &self.sess.opts.lint_opts {
             // Checks the validity of lint names derived from the command line.
             let (tool_name, lint_name_only) = parse_lint_and_tool_name(lint_name);
@@ -627,6 +662,86 @@ fn add_command_line(&mut self) {
                 }
             }
         }
+
+        if let Some(path) = sess.io.input.opt_path() {
+            self.add_lint_config_overrides(path);
+        }
+    }
+
+    /// Adds the `--lint-config` overrides for an out-of-line module.
+    fn add_module_lint_config_overrides(&mut self, item_span: Span, inner_span: Span) {
+        // An inline module is in the same file as its parent, which already got the overrides.
+        if item_span.contains(inner_span) {
+            return;
+        }
+        if let FileName::Real(name) = self.sess.source_map().span_to_filename(inner_span)
+            && let Some(path) = name.local_path()
+        {
+            self.add_lint_config_overrides(path);
+        }
+    }
+
+    /// Adds the `--lint-config` overrides for the module in the file at `path`.
+    fn add_lint_config_overrides(&mut self, path: &Path) {
+        let sess = self.sess;
+        let Some(lint_config) = &sess.lint_config else { return };
+        for lint_override in lint_config.overrides_for(path) {
+            self.add_configured_lints(&lint_override.lints);
+        }
+    }
+
+    /// Adds lint levels of the `--lint-config` file. They are checked like the ones of
+    /// attributes, with spans pointing into the file.
+    fn add_configured_lints(&mut self, lints: &[ConfiguredLint]) {
+        for lint in lints {
+            let name = lint.name.as_str();
+            let span = lint.span;
+            let (tool_name, lint_name_only) = parse_lint_and_tool_name(name);
+            match self.store.check_lint_name(lint_name_only, tool_name, self.registered_tools) {
+                CheckLintNameResult::NoTool => {
+                    self.sess.dcx().emit_err(UnknownToolInScopedLint {
+                        span: Some(span),
+                        tool_name: tool_name.unwrap(),
+                        lint_name: name.to_owned(),
+                        is_nightly_build: self.sess.is_nightly_build().then_some(()),
+                    });
+                    continue;
+                }
+                _ if !self.lint_added_lints => {}
+                CheckLintNameResult::Renamed(ref replace) => {
+                    let suggestion = RenamedLintSuggestion::WithoutSpan { replace };
+                    let renamed = RenamedLint { name, suggestion };
+                    self.emit_span_lint(RENAMED_AND_REMOVED_LINTS, span.into(), renamed);
+                }
+                CheckLintNameResult::Removed(ref reason) => {
+                    let removed = RemovedLint { name, reason };
+                    self.emit_span_lint(RENAMED_AND_REMOVED_LINTS, span.into(), removed);
+                }
+                CheckLintNameResult::NoLint(suggestion) => {
+                    let suggestion = suggestion.map(|(replace, from_rustc)| {
+                        UnknownLintSuggestion::WithoutSpan { replace, from_rustc }
+                    });
+                    let unknown = UnknownLint { name: name.to_owned(), suggestion };
+                    self.emit_span_lint(UNKNOWN_LINTS, span.into(), unknown);
+                }
+                CheckLintNameResult::Tool(Err((Some(_), ref replace))) => {
+                    let deprecated = DeprecatedLintNameFromLintConfig { name, replace };
+                    self.emit_span_lint(RENAMED_AND_REMOVED_LINTS, span.into(), deprecated);
+                }
+                _ => {}
+            }
+
+            let Ok(ids) = self.store.find_lints(name) else {
+                // errors already handled above
+                continue;
+            };
+            let src = LintLevelSource::Node { name: Symbol::intern(name), span, reason: None };
+            for id in ids {
+                if self.check_gated_lint(id, span, false) {
+                    self.insert_spec(id, (lint.level, src));
+                }
+            }
+        }
     }
 
     /// Attempts to insert the `id` to `level_src` map entry. If unsuccessful
@@ -1015,6 +1015,14 @@ pub struct DeprecatedLintNameFromCommandLine<'a> {
     pub requested_level: RequestedLevel<'a>,
 }
 
+#[derive(LintDiagnostic)]
+#[diag(lint_deprecated_lint_name)]
+#[help]
+pub struct DeprecatedLintNameFromLintConfig<'a> {
+    pub name: &'a str,
+    pub replace: &'a str,
+}
+
 #[derive(LintDiagnostic)]
 #[diag(lint_renamed_lint)]
 pub struct RenamedLint<'a> {
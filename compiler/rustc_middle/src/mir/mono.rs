@@ -291,6 +291,20 @@ pub enum Visibility {
     Protected,
 }
 
+/// An instantiation of a generic function, reported by `--print=mono-items-size`
+/// along with the size of its machine code.
+#[derive(Debug, Encodable, Decodable)]
+pub struct GenericInstantiation {
+    /// The name of the crate defining the generic function.
+    pub crate_name: String,
+    /// The path of the generic function, e.g. `alloc::vec::Vec::<T, A>::push`.
+    pub item: String,
+    /// The generic arguments of the instantiation, without lifetimes.
+    pub args: Vec<String>,
+    /// Used to find the machine code of the instantiation in the object files.
+    pub symbol_name: String,
+}
+
 impl<'tcx> CodegenUnit<'tcx> {
     #[inline]
     pub fn new(name: Symbol) -> CodegenUnit<'tcx> {
@@ -25,6 +25,7 @@
 mod util;
 
 use collector::should_codegen_locally;
+pub use partitioning::{generic_instantiations, mono_items_size_report};
 
 rustc_fluent_macro::fluent_messages! { "../messages.ftl" }
 
@@ -106,12 +106,12 @@
 use rustc_middle::middle::codegen_fn_attrs::CodegenFnAttrFlags;
 use rustc_middle::middle::exported_symbols::{SymbolExportInfo, SymbolExportLevel};
 use rustc_middle::mir::mono::{
-    CodegenUnit, CodegenUnitNameBuilder, InstantiationMode, Linkage, MonoItem, MonoItemData,
-    Visibility,
+    CodegenUnit, CodegenUnitNameBuilder, GenericInstantiation, InstantiationMode, Linkage,
+    MonoItem, MonoItemData, Visibility,
 };
 use rustc_middle::query::Providers;
 use rustc_middle::ty::print::{characteristic_def_id_of_type, with_no_trimmed_paths};
-use rustc_middle::ty::{self, visit::TypeVisitableExt, InstanceDef, TyCtxt};
+use rustc_middle::ty::{self, visit::TypeVisitableExt, GenericArgKind, InstanceDef, TyCtxt};
 use rustc_session::config::{DumpMonoStatsFormat, SwitchWithOptPath};
 use rustc_session::CodegenUnits;
 use rustc_span::symbol::Symbol;
@@ -1293,6 +1293,111 @@ struct MonoItem {
     Ok(())
 }
 
+/// Lists the instantiations of generic functions in the codegen units, for
+/// `--print=mono-items-size`. Compiler-generated shims are included, e.g. the
+/// instantiations of `core::ptr::drop_in_place` are the drop glue of each type.
+pub fn generic_instantiations(tcx: TyCtxt<'_>) -> Vec<GenericInstantiation> {
+    let (_, codegen_units) = tcx.collect_and_partition_mono_items(());
+
+    let mut seen = FxHashSet::default();
+    let mut instantiations = Vec::new();
+    for cgu in codegen_units {
+        for mono_item in cgu.items().keys() {
+            let MonoItem::Fn(instance) = *mono_item else { continue };
+            if !seen.insert(instance) {
+                continue;
+            }
+
+            // The generic arguments of closures and coroutines also contain their signature and
+            // upvars, only keep the ones inherited from the item they are defined in.
+            let def_id = instance.def_id();
+            let args = if tcx.is_closure_like(def_id) {
+                let parent_count = tcx.generics_of(tcx.typeck_root_def_id(def_id)).count();
+                &instance.args[..parent_count]
+            } else {
+                &instance.args[..]
+            };
+            let args: Vec<_> = args
+                .iter()
+                .filter(|arg| !matches!(arg.unpack(), GenericArgKind::Lifetime(_)))
+                .map(|arg| with_no_trimmed_paths!(arg.to_string()))
+                .collect();
+            if args.is_empty() {
+                continue;
+            }
+
+            instantiations.push(GenericInstantiation {
+                crate_name: tcx.crate_name(def_id.krate).to_string(),
+                item: with_no_trimmed_paths!(tcx.def_path_str(def_id)),
+                args,
+                symbol_name: tcx.symbol_name(instance).name.to_owned(),
+            });
+        }
+    }
+    instantiations
+}
+
+/// Formats the `--print=mono-items-size` report, given the size of the machine
+/// code of each instantiation. The instantiations are grouped by generic function
+/// and by the crate defining it, from the largest to the smallest.
+pub fn mono_items_size_report(
+    instantiations: &[GenericInstantiation],
+    size_of: impl Fn(&GenericInstantiation) -> u64,
+) -> String {
+    #[derive(serde::Serialize)]
+    struct Crate<'a> {
+        #[serde(rename = "crate")]
+        name: &'a str,
+        size: u64,
+        instantiations: usize,
+        items: Vec<Item<'a>>,
+    }
+
+    #[derive(serde::Serialize)]
+    struct Item<'a> {
+        item: &'a str,
+        size: u64,
+        instantiations: Vec<Instantiation<'a>>,
+    }
+
+    #[derive(serde::Serialize)]
+    struct Instantiation<'a> {
+        args: &'a [String],
+        size: u64,
+    }
+
+    let mut items_per_crate: FxHashMap<&str, FxHashMap<&str, Vec<_>>> = Default::default();
+    for instantiation in instantiations {
+        items_per_crate
+            .entry(&instantiation.crate_name)
+            .or_default()
+            .entry(&instantiation.item)
+            .or_default()
+            .push(Instantiation { args: &instantiation.args, size: size_of(instantiation) });
+    }
+
+    let mut crates: Vec<_> = items_per_crate
+        .into_iter()
+        .map(|(name, items)| {
+            let mut items: Vec<_> = items
+                .into_iter()
+                .map(|(item, mut instantiations)| {
+                    instantiations.sort_by(|a, b| b.size.cmp(&a.size).then(a.args.cmp(b.args)));
+                    let size = instantiations.iter().map(|i| i.size).sum();
+                    Item { item, size, instantiations }
+                })
+                .collect();
+            items.sort_by(|a, b| b.size.cmp(&a.size).then(a.item.cmp(b.item)));
+            let size = items.iter().map(|item| item.size).sum();
+            let instantiations = items.iter().map(|item| item.instantiations.len()).sum();
+            Crate { name, size, instantiations, items }
+        })
+        .collect();
+    crates.sort_by(|a, b| b.size.cmp(&a.size).then(a.name.cmp(b.name)));
+
+    serde_json::to_string(&crates).unwrap()
+}
+
 pub fn provide(providers: &mut Providers) {
     providers.collect_and_partition_mono_items = collect_and_partition_mono_items;
 
@@ -186,6 +186,15 @@ pub(crate) fn alloc_self_profile_query_strings_for_query_cache<'tcx, C>(
     tcx.prof.with_profiler(|profiler| {
         let event_id_builder = profiler.event_id_builder();
 
+        if profiler.query_name_recording_enabled() {
+            let mut query_invocation_ids = Vec::new();
+            query_cache.iter(&mut |_, _, i| query_invocation_ids.push(i.into()));
+            profiler.bulk_map_query_invocation_id_to_query_name(
+                query_invocation_ids.into_iter(),
+                query_name,
+            );
+        }
+
         // Walk the entire query cache and allocate the appropriate
         // string representations. Each cache entry is uniquely
         // identified by its dep_node_index.
@@ -61,6 +61,8 @@ session_invalid_float_literal_width = invalid width `{$width}` for float literal
 session_invalid_int_literal_width = invalid width `{$width}` for integer literal
     .help = valid widths are 8, 16, 32, 64 and 128
 
+session_invalid_lint_config = invalid lint configuration: {$message}
+
 session_invalid_literal_suffix = suffixes on {$kind} literals are invalid
     .label = invalid suffix `{$suffix}`
 
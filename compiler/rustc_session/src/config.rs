@@ -561,6 +561,8 @@ pub enum ErrorOutputType {
         /// human output.
         json_rendered: HumanReadableErrorType,
     },
+    /// A single SARIF 2.1.0 log, consumed by code-scanning tools.
+    Sarif,
 }
 
 impl Default for ErrorOutputType {
@@ -759,6 +761,7 @@ pub enum PrintKind {
     LinkArgs,
     SplitDebuginfo,
     DeploymentTarget,
+    MonoItemsSize,
 }
 
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
@@ -1081,6 +1084,7 @@ fn default() -> Options {
             debuginfo_compression: DebugInfoCompression::None,
             lint_opts: Vec::new(),
             lint_cap: None,
+            lint_config: None,
             describe_lints: false,
             output_types: OutputTypes(BTreeMap::new()),
             search_paths: vec![],
@@ -1151,6 +1155,24 @@ pub fn share_generics(&self) -> bool {
     pub fn get_symbol_mangling_version(&self) -> SymbolManglingVersion {
         self.cg.symbol_mangling_version.unwrap_or(SymbolManglingVersion::Legacy)
     }
+
+    /// The hash of the options that is stored in, and checked against, the
+    /// incremental compilation cache.
+    ///
+    /// With `-Z incremental-shared-cache`, only the targets of
+    /// `--remap-path-prefix` are hashed, so that checkouts of the same crate in
+    /// different directories can share sessions as long as they are remapped to
+    /// the same path.
+    pub fn incr_comp_dep_tracking_hash(&self) -> u64 {
+        if self.unstable_opts.incremental_shared_cache.is_none() {
+            return self.dep_tracking_hash(false);
+        }
+        let mut opts = self.clone();
+        for (from, _) in &mut opts.remap_path_prefix {
+            *from = PathBuf::new();
+        }
+        opts.dep_tracking_hash(false)
+    }
 }
 
 impl UnstableOptions {
@@ -1362,7 +1384,7 @@ pub(crate) fn flagmulti_s(a: S, b: S, c: S) -> R {
         stable(longer(a, b), move |opts| opts.optflagmulti(a, b, c))
     }
 
-    fn opt(a: S, b: S, c: S, d: S) -> R {
+    pub(crate) fn opt(a: S, b: S, c: S, d: S) -> R {
         unstable(longer(a, b), move |opts| opts.optopt(a, b, c, d))
     }
     pub(crate) fn multi(a: S, b: S, c: S, d: S) -> R {
@@ -1511,6 +1533,7 @@ pub fn rustc_optgroups() -> Vec<RustcOptGroup> {
             "FROM=TO",
         ),
         opt::multi("", "env-set", "Inject an environment variable", "VAR=VALUE"),
+        opt::opt("", "lint-config", "Read lint levels from a TOML file", "PATH"),
     ]);
     opts
 }
@@ -1547,6 +1570,29 @@ pub fn get_cmd_lint_options(
     (lint_opts, describe_lints, lint_cap)
 }
 
+/// The file passed to `--lint-config`, see [`crate::lint_config`].
+#[derive(Clone, Hash, Debug)]
+pub struct LintConfigFile {
+    pub path: PathBuf,
+    pub src: String,
+}
+
+/// Reads the `--lint-config` file. It is parsed once the session exists, so that the
+/// errors can point into it.
+pub fn read_lint_config(
+    early_dcx: &EarlyDiagCtxt,
+    matches: &getopts::Matches,
+) -> Option<LintConfigFile> {
+    let path = PathBuf::from(matches.opt_str("lint-config")?);
+    match fs::read_to_string(&path) {
+        Ok(src) => Some(LintConfigFile { path, src }),
+        Err(e) => early_dcx.early_fatal(format!(
+            "failed to read the lint configuration file `{}`: {e}",
+            path.display()
+        )),
+    }
+}
+
 /// Parses the `--color` flag.
 pub fn parse_color(early_dcx: &EarlyDiagCtxt, matches: &getopts::Matches) -> ColorConfig {
     match matches.opt_str("color").as_deref() {
@@ -1659,6 +1705,7 @@ pub fn parse_error_format(
             }
             Some("json") => ErrorOutputType::Json { pretty: false, json_rendered },
             Some("pretty-json") => ErrorOutputType::Json { pretty: true, json_rendered },
+            Some("sarif") => ErrorOutputType::Sarif,
             Some("short") => ErrorOutputType::HumanReadable(HumanReadableErrorType::Short(color)),
 
             Some(arg) => {
@@ -1731,6 +1778,9 @@ fn check_error_format_stability(
         {
             early_dcx.early_fatal("`--error-format=human-annotate-rs` is unstable");
         }
+        if let ErrorOutputType::Sarif = error_format {
+            early_dcx.early_fatal("`--error-format=sarif` is unstable");
+        }
     }
 }
 
@@ -1839,6 +1889,7 @@ fn collect_print_requests(
         ("deployment-target", PrintKind::DeploymentTarget),
         ("file-names", PrintKind::FileNames),
         ("link-args", PrintKind::LinkArgs),
+        ("mono-items-size", PrintKind::MonoItemsSize),
         ("native-static-libs", PrintKind::NativeStaticLibs),
         ("relocation-models", PrintKind::RelocationModels),
         ("split-debuginfo", PrintKind::SplitDebuginfo),
@@ -1883,6 +1934,16 @@ fn collect_print_requests(
                     );
                 }
             }
+            Some((_, PrintKind::MonoItemsSize)) => {
+                if unstable_opts.unstable_options {
+                    PrintKind::MonoItemsSize
+                } else {
+                    early_dcx.early_fatal(
+                        "the `-Z unstable-options` flag must also be passed to \
+                         enable the mono-items-size print option",
+                    );
+                }
+            }
             Some(&(_, print_kind)) => print_kind,
             None => {
                 let prints =
@@ -2329,6 +2390,7 @@ pub fn build_session_options(early_dcx: &mut EarlyDiagCtxt, matches: &getopts::M
 
     let mut unstable_opts = UnstableOptions::build(early_dcx, matches);
     let (lint_opts, describe_lints, lint_cap) = get_cmd_lint_options(early_dcx, matches);
+    let lint_config = read_lint_config(early_dcx, matches);
 
     check_error_format_stability(early_dcx, &unstable_opts, error_format);
 
@@ -2514,6 +2576,14 @@ pub fn build_session_options(early_dcx: &mut EarlyDiagCtxt, matches: &getopts::M
             .early_warn("using -Z remark-dir without enabling remarks using e.g. -C remark=all");
     }
 
+    if !unstable_opts.self_profile_export.is_empty()
+        && unstable_opts.self_profile == SwitchWithOptPath::Disabled
+    {
+        early_dcx.early_warn(
+            "using -Z self-profile-export without enabling the self-profiler using -Z self-profile",
+        );
+    }
+
     let externs = parse_externs(early_dcx, matches, &unstable_opts);
 
     let crate_name = matches.opt_str("crate-name");
@@ -2577,6 +2647,7 @@ pub fn build_session_options(early_dcx: &mut EarlyDiagCtxt, matches: &getopts::M
         debuginfo_compression,
         lint_opts,
         lint_cap,
+        lint_config,
         describe_lints,
         output_types,
         search_paths,
@@ -2878,9 +2949,9 @@ pub(crate) mod dep_tracking {
     use super::{
         BranchProtection, CFGuard, CFProtection, CollapseMacroDebuginfo, CoverageOptions,
         CrateType, DebugInfo, DebugInfoCompression, ErrorOutputType, FunctionReturn,
-        InliningThreshold, InstrumentCoverage, InstrumentXRay, LinkerPluginLto, LocationDetail,
-        LtoCli, NextSolverConfig, OomStrategy, OptLevel, OutFileName, OutputType, OutputTypes,
-        Polonius, RemapPathScopeComponents, ResolveDocLinks, SourceFileHashAlgorithm,
+        InliningThreshold, InstrumentCoverage, InstrumentXRay, LinkerPluginLto, LintConfigFile,
+        LocationDetail, LtoCli, NextSolverConfig, OomStrategy, OptLevel, OutFileName, OutputType,
+        OutputTypes, Polonius, RemapPathScopeComponents, ResolveDocLinks, SourceFileHashAlgorithm,
         SplitDwarfKind, SwitchWithOptPath, SymbolManglingVersion, WasiExecModel,
     };
     use crate::lint;
@@ -2990,6 +3061,7 @@ fn hash(
         InliningThreshold,
         FunctionReturn,
         WasmCAbi,
+        LintConfigFile,
     );
 
     impl<T1, T2> DepTrackingHash for (T1, T2)
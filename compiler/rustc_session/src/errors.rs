@@ -472,3 +472,11 @@ pub(crate) struct IncompatibleLinkerFlavor {
 pub(crate) struct FailedToCreateProfiler {
     pub(crate) err: String,
 }
+
+#[derive(Diagnostic)]
+#[diag(session_invalid_lint_config)]
+pub(crate) struct InvalidLintConfig {
+    #[primary_span]
+    pub(crate) span: Span,
+    pub(crate) message: String,
+}
@@ -0,0 +1,270 @@
+//! Lint levels read from the TOML file passed to `--lint-config`.
+//!
+//! ```toml
+//! [lints.rust]
+//! missing_docs = "warn"
+//! unsafe_code = { level = "forbid", priority = 1 }
+//!
+//! [lints.clippy]
+//! pedantic = { level = "warn", priority = -1 }
+//!
+//! [[overrides]]
+//! paths = ["tests"]
+//! lints.rust.missing_docs = "allow"
+//! ```
+//!
+//! The `[lints]` table has the format of the one of Cargo manifests: one table per lint tool,
+//! `rust` being rustc itself, mapping lint names to a level or to a table with a level and a
+//! priority. Lints are applied by increasing priority, so that a lint group can be overridden
+//! for some of its lints. These levels apply to the whole crate and come before the `-A`, `-W`,
+//! `-D` and `-F` flags, which take precedence.
+//!
+//! Each `[[overrides]]` entry applies its `lints` to the modules, including the crate root, whose
+//! file is under one of its `paths`. The paths are relative to the directory of the configuration
+//! file. An override takes precedence over the command line, like an inner attribute at the top
+//! of the module would.
+//!
+//! The checks that the lints exist happen in `rustc_lint::levels`, which knows about them.
+
+use crate::config::LintConfigFile;
+use crate::errors::InvalidLintConfig;
+use crate::lint::Level;
+use crate::parse::ParseSess;
+use rustc_span::{BytePos, FileName, Span};
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+
+mod toml;
+
+use toml::{Spanned, Table, Value};
+
+/// A lint level set by the configuration file.
+#[derive(Debug)]
+pub struct ConfiguredLint {
+    /// The name of the lint, prefixed with its tool for tool lints, e.g. `clippy::pedantic`.
+    pub name: String,
+    pub level: Level,
+    /// The span of the name of the lint in the configuration file.
+    pub span: Span,
+}
+
+/// An `[[overrides]]` entry of the configuration file.
+#[derive(Debug)]
+pub struct LintConfigOverride {
+    /// The absolute paths the override applies to.
+    paths: Vec<PathBuf>,
+    pub lints: Vec<ConfiguredLint>,
+}
+
+/// The lint levels of the `--lint-config` file, see the module documentation.
+#[derive(Debug, Default)]
+pub struct LintConfig {
+    /// The lint levels for the whole crate, sorted by priority.
+    pub lints: Vec<ConfiguredLint>,
+    pub overrides: Vec<LintConfigOverride>,
+    working_dir: PathBuf,
+}
+
+impl LintConfig {
+    /// Parses the configuration file, which is added to the source map so that the lint
+    /// levels can point into it. Errors are reported and the invalid entries skipped.
+    pub fn load(file: &LintConfigFile, working_dir: &Path, psess: &ParseSess) -> LintConfig {
+        let source_map = psess.source_map();
+        let name = FileName::Real(source_map.path_mapping().to_real_filename(&file.path));
+        let source_file = source_map.new_source_file(name, file.src.clone());
+
+        let loader = Loader {
+            psess,
+            start_pos: source_file.start_pos,
+            base_dir: normalize(working_dir, file.path.parent().unwrap_or(Path::new(""))),
+        };
+        let mut config = match toml::parse(&file.src) {
+            Ok(table) => loader.config(&table),
+            Err(err) => {
+                loader.error(err.message, &err.span);
+                LintConfig::default()
+            }
+        };
+        config.working_dir = working_dir.to_owned();
+        config
+    }
+
+    /// Returns the overrides applying to the module in the file at `path`.
+    pub fn overrides_for<'a>(
+        &'a self,
+        path: &Path,
+    ) -> impl Iterator<Item = &'a LintConfigOverride> + 'a {
+        let path = normalize(&self.working_dir, path);
+        self.overrides.iter().filter(move |o| o.paths.iter().any(|prefix| path.starts_with(prefix)))
+    }
+}
+
+/// Makes `path` absolute and removes its `.` and `..` components, without accessing the file
+/// system: the source files of the crate don't need to exist on disk.
+fn normalize(working_dir: &Path, path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in working_dir.join(path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+struct Loader<'a> {
+    psess: &'a ParseSess,
+    start_pos: BytePos,
+    base_dir: PathBuf,
+}
+
+impl Loader<'_> {
+    fn span(&self, range: &Range<usize>) -> Span {
+        let lo = self.start_pos + BytePos(range.start as u32);
+        let hi = self.start_pos + BytePos(range.end as u32);
+        Span::with_root_ctxt(lo, hi)
+    }
+
+    fn error(&self, message: impl Into<String>, range: &Range<usize>) {
+        let span = self.span(range);
+        self.psess.dcx.emit_err(InvalidLintConfig { span, message: message.into() });
+    }
+
+    fn expect_table<'v>(&self, value: &'v Spanned<Value>) -> Option<&'v Table> {
+        match &value.value {
+            Value::Table(table) => Some(table),
+            v => {
+                self.error(format!("expected a table, found {}", v.type_name()), &value.span);
+                None
+            }
+        }
+    }
+
+    fn config(&self, table: &Table) -> LintConfig {
+        let mut config = LintConfig::default();
+        for (key, value) in &table.entries {
+            match key.value.as_str() {
+                "lints" => config.lints = self.lints(value),
+                "overrides" => match &value.value {
+                    Value::Array(overrides) => {
+                        config.overrides.extend(overrides.iter().filter_map(|o| self.override_(o)))
+                    }
+                    v => {
+                        let message =
+                            format!("expected an array of tables, found {}", v.type_name());
+                        self.error(message, &value.span);
+                    }
+                },
+                _ => self.error(format!("unknown key `{}`", key.value), &key.span),
+            }
+        }
+        config
+    }
+
+    fn override_(&self, value: &Spanned<Value>) -> Option<LintConfigOverride> {
+        let table = self.expect_table(value)?;
+        let mut paths = None;
+        let mut lints = Vec::new();
+        for (key, value) in &table.entries {
+            match key.value.as_str() {
+                "paths" => paths = Some(self.paths(value)),
+                "lints" => lints = self.lints(value),
+                _ => self.error(format!("unknown key `{}`", key.value), &key.span),
+            }
+        }
+        let Some(paths) = paths else {
+            self.error("missing `paths` in override", &value.span);
+            return None;
+        };
+        Some(LintConfigOverride { paths, lints })
+    }
+
+    fn paths(&self, value: &Spanned<Value>) -> Vec<PathBuf> {
+        let Value::Array(paths) = &value.value else {
+            let message = format!("expected an array of paths, found {}", value.value.type_name());
+            self.error(message, &value.span);
+            return Vec::new();
+        };
+        paths
+            .iter()
+            .filter_map(|path| match &path.value {
+                Value::String(p) => Some(normalize(&self.base_dir, Path::new(p))),
+                v => {
+                    self.error(format!("expected a path, found {}", v.type_name()), &path.span);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Reads a `[lints]` table, whose keys are lint tools.
+    fn lints(&self, value: &Spanned<Value>) -> Vec<ConfiguredLint> {
+        let Some(tools) = self.expect_table(value) else { return Vec::new() };
+        let mut lints = Vec::new();
+        for (tool, value) in &tools.entries {
+            let Some(table) = self.expect_table(value) else { continue };
+            for (key, value) in &table.entries {
+                let Some((level, priority)) = self.level_and_priority(value) else { continue };
+                let name = key.value.replace('-', "_");
+                let name =
+                    if tool.value == "rust" { name } else { format!("{}::{name}", tool.value) };
+                lints.push((priority, ConfiguredLint { name, level, span: self.span(&key.span) }));
+            }
+        }
+        // Keep the order of the file for lints with the same priority.
+        lints.sort_by_key(|&(priority, _)| priority);
+        lints.into_iter().map(|(_, lint)| lint).collect()
+    }
+
+    /// Reads a lint level, either `"warn"` or `{ level = "warn", priority = 1 }`.
+    fn level_and_priority(&self, value: &Spanned<Value>) -> Option<(Level, i64)> {
+        let (level, priority) = match &value.value {
+            Value::String(_) => (value, 0),
+            Value::Table(table) => {
+                let mut level = None;
+                let mut priority = 0;
+                for (key, value) in &table.entries {
+                    match (key.value.as_str(), &value.value) {
+                        ("level", _) => level = Some(value),
+                        ("priority", &Value::Integer(p)) => priority = p,
+                        ("priority", v) => {
+                            let message = format!("expected an integer, found {}", v.type_name());
+                            self.error(message, &value.span);
+                        }
+                        _ => self.error(format!("unknown key `{}`", key.value), &key.span),
+                    }
+                }
+                let Some(level) = level else {
+                    self.error("missing `level`", &value.span);
+                    return None;
+                };
+                (level, priority)
+            }
+            v => {
+                let message = format!("expected a lint level or a table, found {}", v.type_name());
+                self.error(message, &value.span);
+                return None;
+            }
+        };
+        let Value::String(s) = &level.value else {
+            self.error(
+                format!("expected a lint level, found {}", level.value.type_name()),
+                &level.span,
+            );
+            return None;
+        };
+        match Level::from_str(s) {
+            Some(level) => Some((level, priority)),
+            None => {
+                let message = format!(
+                    "invalid lint level `{s}`, expected `allow`, `warn`, `deny` or `forbid`"
+                );
+                self.error(message, &level.span);
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,403 @@
+//! A parser for the subset of TOML used by `--lint-config` files.
+//!
+//! Only strings (without the multi-line forms), integers, booleans, arrays and tables are
+//! supported, which is all a lint configuration needs. Unlike a general purpose TOML
+//! library, the parser keeps the byte range of every key and value so that diagnostics
+//! can point into the file.
+
+use std::ops::Range;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Spanned<T> {
+    pub value: T,
+    pub span: Range<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Value {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+    Array(Vec<Spanned<Value>>),
+    Table(Table),
+}
+
+impl Value {
+    pub(crate) fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "a string",
+            Value::Integer(_) => "an integer",
+            Value::Boolean(_) => "a boolean",
+            Value::Array(_) => "an array",
+            Value::Table(_) => "a table",
+        }
+    }
+}
+
+/// The entries of a table, in the order they appear in the file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Table {
+    pub entries: Vec<(Spanned<String>, Spanned<Value>)>,
+    /// Whether the table was defined by a `[header]`, which can only happen once.
+    defined: bool,
+}
+
+impl Table {
+    fn get_mut(&mut self, key: &str) -> Option<&mut Spanned<Value>> {
+        self.entries.iter_mut().find(|(k, _)| k.value == key).map(|(_, value)| value)
+    }
+
+    /// Returns the table at `keys`, creating the missing ones. Arrays of tables resolve to
+    /// their last element, like in a `[a.b]` header following a `[[a]]` header.
+    fn table_at(&mut self, keys: &[Spanned<String>]) -> Result<&mut Table, ParseError> {
+        let Some((key, rest)) = keys.split_first() else { return Ok(self) };
+        if self.get_mut(&key.value).is_none() {
+            let value = Spanned { value: Value::Table(Table::default()), span: key.span.clone() };
+            self.entries.push((key.clone(), value));
+        }
+        let not_a_table = |type_name| {
+            let message = format!("`{}` is {type_name}, not a table", key.value);
+            Err(ParseError { message, span: key.span.clone() })
+        };
+        let table = match &mut self.get_mut(&key.value).unwrap().value {
+            Value::Table(table) => table,
+            Value::Array(array) => match array.last_mut() {
+                Some(Spanned { value: Value::Table(table), .. }) => table,
+                _ => return not_a_table("an array"),
+            },
+            value => return not_a_table(value.type_name()),
+        };
+        table.table_at(rest)
+    }
+
+    /// Inserts `value` at the dotted key `keys`.
+    fn insert(
+        &mut self,
+        keys: &[Spanned<String>],
+        value: Spanned<Value>,
+    ) -> Result<(), ParseError> {
+        let (key, parents) = keys.split_last().unwrap();
+        let table = self.table_at(parents)?;
+        if table.get_mut(&key.value).is_some() {
+            let message = format!("duplicate key `{}`", key.value);
+            return Err(ParseError { message, span: key.span.clone() });
+        }
+        table.entries.push((key.clone(), value));
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct ParseError {
+    pub message: String,
+    pub span: Range<usize>,
+}
+
+pub(crate) fn parse(src: &str) -> Result<Table, ParseError> {
+    Parser { src, pos: 0 }.parse_document()
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, prefix: &str) -> bool {
+        let found = self.src[self.pos..].starts_with(prefix);
+        if found {
+            self.pos += prefix.len();
+        }
+        found
+    }
+
+    fn error<T>(&self, message: impl Into<String>, start: usize) -> Result<T, ParseError> {
+        Err(ParseError { message: message.into(), span: start..self.pos })
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), ParseError> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            self.error(format!("expected `{expected}`"), self.pos)
+        }
+    }
+
+    /// Skips spaces and tabs.
+    fn skip_whitespace(&mut self) {
+        while let Some(' ' | '\t') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    /// Skips whitespace, comments and newlines.
+    fn skip_blank_lines(&mut self) {
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some('#') {
+                self.skip_comment();
+            }
+            if !self.eat("\n") && !self.eat("\r\n") {
+                return;
+            }
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        let len = self.src[self.pos..].find('\n').unwrap_or(self.src.len() - self.pos);
+        self.pos += len;
+        // Leave the `\r` of a `\r\n` line ending to the caller.
+        if self.src[..self.pos].ends_with('\r') {
+            self.pos -= 1;
+        }
+    }
+
+    /// Expects the end of a line, after an optional comment.
+    fn expect_line_end(&mut self) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        if self.peek() == Some('#') {
+            self.skip_comment();
+        }
+        if self.pos == self.src.len() || self.eat("\n") || self.eat("\r\n") {
+            Ok(())
+        } else {
+            self.error("expected a newline", self.pos)
+        }
+    }
+
+    fn parse_document(mut self) -> Result<Table, ParseError> {
+        let mut root = Table::default();
+        let mut current = Vec::new();
+        loop {
+            self.skip_blank_lines();
+            let start = self.pos;
+            if self.pos == self.src.len() {
+                return Ok(root);
+            } else if self.eat("[[") {
+                let keys = self.parse_key()?;
+                self.expect("]]")?;
+                let (key, parents) = keys.split_last().unwrap();
+                let parent = root.table_at(parents)?;
+                let table =
+                    Spanned { value: Value::Table(Table::default()), span: start..self.pos };
+                match parent.get_mut(&key.value) {
+                    None => {
+                        let span = table.span.clone();
+                        parent.entries.push((
+                            key.clone(),
+                            Spanned { value: Value::Array(vec![table]), span },
+                        ));
+                    }
+                    Some(Spanned { value: Value::Array(array), .. }) => array.push(table),
+                    Some(Spanned { value, .. }) => {
+                        let message =
+                            format!("`{}` is {}, not an array", key.value, value.type_name());
+                        return Err(ParseError { message, span: key.span.clone() });
+                    }
+                }
+                current = keys;
+            } else if self.eat("[") {
+                let keys = self.parse_key()?;
+                self.expect("]")?;
+                let table = root.table_at(&keys)?;
+                if table.defined {
+                    return self.error("duplicate table", start);
+                }
+                table.defined = true;
+                current = keys;
+            } else {
+                let keys = self.parse_key()?;
+                self.expect("=")?;
+                self.skip_whitespace();
+                let value = self.parse_value()?;
+                root.table_at(&current)?.insert(&keys, value)?;
+            }
+            self.expect_line_end()?;
+        }
+    }
+
+    /// Parses a possibly dotted key, e.g. `lints.rust`.
+    fn parse_key(&mut self) -> Result<Vec<Spanned<String>>, ParseError> {
+        let mut keys = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let start = self.pos;
+            let value = match self.peek() {
+                Some('"') => self.parse_basic_string()?,
+                Some('\'') => self.parse_literal_string()?,
+                _ => {
+                    let len = self.src[self.pos..]
+                        .find(|c: char| !c.is_ascii_alphanumeric() && c != '_' && c != '-')
+                        .unwrap_or(self.src.len() - self.pos);
+                    if len == 0 {
+                        return self.error("expected a key", start);
+                    }
+                    self.pos += len;
+                    self.src[start..self.pos].to_owned()
+                }
+            };
+            keys.push(Spanned { value, span: start..self.pos });
+            self.skip_whitespace();
+            if !self.eat(".") {
+                return Ok(keys);
+            }
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Spanned<Value>, ParseError> {
+        let start = self.pos;
+        let value = match self.peek() {
+            Some('"') if self.src[self.pos..].starts_with("\"\"\"") => {
+                self.pos += 3;
+                return self.error("multi-line strings are not supported", start);
+            }
+            Some('\'') if self.src[self.pos..].starts_with("'''") => {
+                self.pos += 3;
+                return self.error("multi-line strings are not supported", start);
+            }
+            Some('"') => Value::String(self.parse_basic_string()?),
+            Some('\'') => Value::String(self.parse_literal_string()?),
+            Some('[') => Value::Array(self.parse_array()?),
+            Some('{') => Value::Table(self.parse_inline_table()?),
+            Some(_) => {
+                let len = self.src[self.pos..]
+                    .find(|c: char| c.is_whitespace() || matches!(c, ',' | ']' | '}' | '#'))
+                    .unwrap_or(self.src.len() - self.pos);
+                self.pos += len;
+                match &self.src[start..self.pos] {
+                    "" => return self.error("expected a value", start),
+                    "true" => Value::Boolean(true),
+                    "false" => Value::Boolean(false),
+                    token => match token.replace('_', "").parse() {
+                        Ok(integer) if !token.starts_with('_') && !token.ends_with('_') => {
+                            Value::Integer(integer)
+                        }
+                        _ => {
+                            return self.error(
+                                format!(
+                                    "invalid value `{token}`, only strings, integers, booleans, \
+                                     arrays and tables are supported"
+                                ),
+                                start,
+                            );
+                        }
+                    },
+                }
+            }
+            None => return self.error("expected a value", start),
+        };
+        Ok(Spanned { value, span: start..self.pos })
+    }
+
+    fn parse_array(&mut self) -> Result<Vec<Spanned<Value>>, ParseError> {
+        self.expect("[")?;
+        let mut values = Vec::new();
+        loop {
+            self.skip_blank_lines();
+            if self.eat("]") {
+                return Ok(values);
+            }
+            values.push(self.parse_value()?);
+            self.skip_blank_lines();
+            if !self.eat(",") {
+                self.skip_blank_lines();
+                self.expect("]")?;
+                return Ok(values);
+            }
+        }
+    }
+
+    fn parse_inline_table(&mut self) -> Result<Table, ParseError> {
+        self.expect("{")?;
+        let mut table = Table::default();
+        self.skip_whitespace();
+        if self.eat("}") {
+            return Ok(table);
+        }
+        loop {
+            let keys = self.parse_key()?;
+            self.expect("=")?;
+            self.skip_whitespace();
+            let value = self.parse_value()?;
+            table.insert(&keys, value)?;
+            self.skip_whitespace();
+            if !self.eat(",") {
+                self.expect("}")?;
+                return Ok(table);
+            }
+        }
+    }
+
+    fn parse_literal_string(&mut self) -> Result<String, ParseError> {
+        let start = self.pos;
+        self.expect("'")?;
+        let len = self.src[self.pos..].find(['\'', '\n']).unwrap_or(self.src.len() - self.pos);
+        let value = self.src[self.pos..self.pos + len].to_owned();
+        self.pos += len;
+        if !self.eat("'") {
+            return self.error("unterminated string", start);
+        }
+        Ok(value)
+    }
+
+    fn parse_basic_string(&mut self) -> Result<String, ParseError> {
+        let start = self.pos;
+        self.expect("\"")?;
+        let mut value = String::new();
+        loop {
+            let escape_start = self.pos;
+            match self.peek() {
+                None | Some('\n') => return self.error("unterminated string", start),
+                Some('"') => {
+                    self.pos += 1;
+                    return Ok(value);
+                }
+                Some('\\') => {
+                    self.pos += 1;
+                    let escaped = match self.peek() {
+                        Some('b') => '\u{8}',
+                        Some('t') => '\t',
+                        Some('n') => '\n',
+                        Some('f') => '\u{c}',
+                        Some('r') => '\r',
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some(c @ ('u' | 'U')) => {
+                            let len = if c == 'u' { 4 } else { 8 };
+                            let digits = self.src.get(self.pos + 1..self.pos + 1 + len);
+                            let escaped = digits
+                                .filter(|digits| digits.bytes().all(|b| b.is_ascii_hexdigit()))
+                                .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+                                .and_then(char::from_u32);
+                            let Some(escaped) = escaped else {
+                                self.pos += 1;
+                                return self.error("invalid unicode escape", escape_start);
+                            };
+                            self.pos += len;
+                            escaped
+                        }
+                        _ => {
+                            // Point at the escaped character too, unless the line ends there.
+                            self.pos +=
+                                self.peek().filter(|&c| c != '\n').map_or(0, char::len_utf8);
+                            return self.error("invalid escape", escape_start);
+                        }
+                    };
+                    self.pos += 1;
+                    value.push(escaped);
+                }
+                Some(c) => {
+                    self.pos += c.len_utf8();
+                    value.push(c);
+                }
+            }
+        }
+    }
+}
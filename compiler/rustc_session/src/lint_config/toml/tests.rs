@@ -0,0 +1,188 @@
+use super::*;
+
+fn parse_ok(src: &str) -> Table {
+    match parse(src) {
+        Ok(table) => table,
+        Err(err) => panic!("failed to parse {src:?}: {err:?}"),
+    }
+}
+
+/// Returns the error for `src`, along with the text its span points to.
+fn parse_err(src: &str) -> (String, &str) {
+    match parse(src) {
+        Ok(table) => panic!("parsed {src:?} to {table:?}"),
+        Err(err) => (err.message, &src[err.span]),
+    }
+}
+
+/// Returns the value at the dotted key `path`, going through the last element of arrays.
+fn get<'a>(table: &'a Table, path: &str) -> &'a Spanned<Value> {
+    let (parents, key) = match path.rsplit_once('.') {
+        Some((parents, key)) => (Some(parents), key),
+        None => (None, path),
+    };
+    let table = match parents.map(|parents| &get(table, parents).value) {
+        None => table,
+        Some(Value::Table(table)) => table,
+        Some(Value::Array(array)) => match array.last() {
+            Some(Spanned { value: Value::Table(table), .. }) => table,
+            value => panic!("`{path}` is not in a table: {value:?}"),
+        },
+        Some(value) => panic!("`{path}` is not in a table: {value:?}"),
+    };
+    match table.entries.iter().find(|(k, _)| k.value == key) {
+        Some((_, value)) => value,
+        None => panic!("no `{path}` in {table:?}"),
+    }
+}
+
+fn string(value: &str) -> Value {
+    Value::String(value.to_owned())
+}
+
+fn keys(table: &Table) -> Vec<&str> {
+    table.entries.iter().map(|(key, _)| key.value.as_str()).collect()
+}
+
+#[test]
+fn dotted_keys() {
+    let src = "\
+a.b.c = 1
+a . 'quoted.key' . \"d\" = 2
+[lints]
+rust.unused = \"warn\"
+rust.dead-code = { level = \"deny\", priority.value = 3 }
+";
+    let table = parse_ok(src);
+    assert_eq!(keys(&table), ["a", "lints"]);
+    assert_eq!(get(&table, "a.b.c").value, Value::Integer(1));
+    let Value::Table(a) = &get(&table, "a").value else { panic!() };
+    assert_eq!(keys(a), ["b", "quoted.key"]);
+    let Value::Table(quoted) = &a.entries[1].1.value else { panic!() };
+    assert_eq!(quoted.entries[0].1.value, Value::Integer(2));
+    assert_eq!(get(&table, "lints.rust.unused").value, string("warn"));
+    assert_eq!(get(&table, "lints.rust.dead-code.level").value, string("deny"));
+    assert_eq!(get(&table, "lints.rust.dead-code.priority.value").value, Value::Integer(3));
+}
+
+#[test]
+fn arrays_of_tables() {
+    let src = "\
+[[overrides]]
+paths = [\"tests\"]
+[overrides.lints.rust]
+missing_docs = \"allow\"
+
+[[overrides]]
+paths = [
+    \"benches\",
+    \"examples\", # A trailing comma is allowed.
+]
+lints.rust.unused = \"allow\"
+";
+    let table = parse_ok(src);
+    let Value::Array(overrides) = &get(&table, "overrides").value else { panic!() };
+    assert_eq!(overrides.len(), 2);
+    let Value::Table(first) = &overrides[0].value else { panic!() };
+    assert_eq!(keys(first), ["paths", "lints"]);
+    assert_eq!(get(first, "lints.rust.missing_docs").value, string("allow"));
+
+    // `[overrides.lints.rust]` went to the first element, dotted keys to the last one.
+    assert_eq!(get(&table, "overrides.lints.rust.unused").value, string("allow"));
+    let Value::Array(paths) = &get(&table, "overrides.paths").value else { panic!() };
+    let paths: Vec<_> = paths.iter().map(|path| &path.value).collect();
+    assert_eq!(paths, [&string("benches"), &string("examples")]);
+}
+
+#[test]
+fn duplicates() {
+    assert_eq!(parse_err("a = 1\na = 2"), ("duplicate key `a`".to_owned(), "a"));
+    assert_eq!(parse_err("a.b = 1\na.b = 2"), ("duplicate key `b`".to_owned(), "b"));
+    assert_eq!(parse_err("x = { k = 1, k = 2 }"), ("duplicate key `k`".to_owned(), "k"));
+    assert_eq!(parse_err("[a]\nb = 1\n[a]"), ("duplicate table".to_owned(), "[a]"));
+    assert_eq!(parse_err("[a.b]\n[a.b]"), ("duplicate table".to_owned(), "[a.b]"));
+
+    // A table is only defined by its own header, not by the headers of its subtables.
+    parse_ok("[a.b]\n[a]\n[a.c]");
+}
+
+#[test]
+fn type_conflicts() {
+    assert_eq!(parse_err("a = 1\n[a.b]"), ("`a` is an integer, not a table".to_owned(), "a"));
+    assert_eq!(parse_err("a = 'x'\na.b = 1"), ("`a` is a string, not a table".to_owned(), "a"));
+    assert_eq!(parse_err("[a]\n[[a]]"), ("`a` is a table, not an array".to_owned(), "a"));
+    assert_eq!(parse_err("a = [1]\n[a.b]"), ("`a` is an array, not a table".to_owned(), "a"));
+}
+
+#[test]
+fn strings() {
+    let table = parse_ok(r#"a = "tab\tquote\"backslash\\ \u00e9\U0001F980" "#);
+    assert_eq!(get(&table, "a").value, string("tab\tquote\"backslash\\ \u{e9}\u{1f980}"));
+    let table = parse_ok(r#"a = 'C:\no\escapes'"#);
+    assert_eq!(get(&table, "a").value, string(r"C:\no\escapes"));
+
+    assert_eq!(parse_err(r#"a = "\x41""#), ("invalid escape".to_owned(), r"\x"));
+    assert_eq!(parse_err(r#"a = "\u12""#), ("invalid unicode escape".to_owned(), r"\u"));
+    assert_eq!(parse_err(r#"a = "\u12G4""#), ("invalid unicode escape".to_owned(), r"\u"));
+    assert_eq!(parse_err(r#"a = "\uD800""#), ("invalid unicode escape".to_owned(), r"\u"));
+    assert_eq!(parse_err(r#"a = "\U00110000""#), ("invalid unicode escape".to_owned(), r"\U"));
+    assert_eq!(parse_err("a = \"abc\nb = 1"), ("unterminated string".to_owned(), "\"abc"));
+    assert_eq!(parse_err("a = 'abc"), ("unterminated string".to_owned(), "'abc"));
+    assert_eq!(
+        parse_err("a = \"\"\"\nabc\"\"\""),
+        ("multi-line strings are not supported".to_owned(), "\"\"\"")
+    );
+}
+
+#[test]
+fn line_endings_and_comments() {
+    let src =
+        "# A comment.\r\n\r\n[lints.rust] # After a header.\r\nunused = \"warn\"#No space.\r\n";
+    let table = parse_ok(src);
+    assert_eq!(get(&table, "lints.rust.unused").value, string("warn"));
+
+    // No newline at the end of the file, after a comment or not.
+    assert_eq!(get(&parse_ok("a = 1 # comment"), "a").value, Value::Integer(1));
+    assert_eq!(get(&parse_ok("a = 1"), "a").value, Value::Integer(1));
+
+    assert_eq!(parse_err("a = 1\rb = 2"), ("expected a newline".to_owned(), ""));
+    assert_eq!(parse_err("a = 1 b = 2"), ("expected a newline".to_owned(), ""));
+}
+
+#[test]
+fn integers() {
+    let table = parse_ok("a = 1_000\nb = -5\nc = +7\nd = 0\ne = true\nf = false");
+    assert_eq!(get(&table, "a").value, Value::Integer(1000));
+    assert_eq!(get(&table, "b").value, Value::Integer(-5));
+    assert_eq!(get(&table, "c").value, Value::Integer(7));
+    assert_eq!(get(&table, "d").value, Value::Integer(0));
+    assert_eq!(get(&table, "e").value, Value::Boolean(true));
+    assert_eq!(get(&table, "f").value, Value::Boolean(false));
+
+    for invalid in ["_1", "1_", "1.5", "0x10", "9223372036854775808", "yes"] {
+        let src = format!("a = {invalid}");
+        let (message, span) = parse_err(&src);
+        assert!(message.starts_with(&format!("invalid value `{invalid}`")), "{message}");
+        assert_eq!(span, invalid);
+    }
+}
+
+#[test]
+fn spans() {
+    let src = "[lints.rust]\nunused = { level = \"warn\", priority = -1 }\n";
+    let table = parse_ok(src);
+    let Value::Table(lints) = &table.entries[0].1.value else { panic!() };
+    let Value::Table(rust) = &lints.entries[0].1.value else { panic!() };
+    let (key, value) = &rust.entries[0];
+    assert_eq!(&src[key.span.clone()], "unused");
+    assert_eq!(&src[value.span.clone()], "{ level = \"warn\", priority = -1 }");
+    assert_eq!(&src[get(&table, "lints.rust.unused.level").span.clone()], "\"warn\"");
+    assert_eq!(&src[get(&table, "lints.rust.unused.priority").span.clone()], "-1");
+
+    assert_eq!(parse_err("a ="), ("expected a value".to_owned(), ""));
+    assert_eq!(parse_err("a = [1, 2"), ("expected `]`".to_owned(), ""));
+    assert_eq!(parse_err("a = { b = 1"), ("expected `}`".to_owned(), ""));
+    assert_eq!(parse_err("[a"), ("expected `]`".to_owned(), ""));
+    assert_eq!(parse_err("= 1"), ("expected a key".to_owned(), ""));
+    assert_eq!(parse_err("a 1"), ("expected `=`".to_owned(), ""));
+}
@@ -3,7 +3,7 @@
 use crate::utils::NativeLib;
 use crate::{lint, EarlyDiagCtxt};
 use rustc_data_structures::fx::FxIndexMap;
-use rustc_data_structures::profiling::TimePassesFormat;
+use rustc_data_structures::profiling::{SelfProfileExportFormat, TimePassesFormat};
 use rustc_data_structures::stable_hasher::Hash64;
 use rustc_errors::ColorConfig;
 use rustc_errors::{LanguageIdentifier, TerminalUrl};
@@ -141,6 +141,9 @@ pub struct Options {
         debuginfo_compression: DebugInfoCompression [TRACKED],
         lint_opts: Vec<(String, lint::Level)> [TRACKED_NO_CRATE_HASH],
         lint_cap: Option<lint::Level> [TRACKED_NO_CRATE_HASH],
+        /// The `--lint-config` file. Its contents are tracked so that editing it
+        /// invalidates the lint levels of the incremental cache.
+        lint_config: Option<LintConfigFile> [TRACKED_NO_CRATE_HASH],
         describe_lints: bool [UNTRACKED],
         output_types: OutputTypes [TRACKED],
         search_paths: Vec<SearchPath> [UNTRACKED],
@@ -376,6 +379,8 @@ mod desc {
     pub const parse_opt_number: &str = parse_number;
     pub const parse_threads: &str = parse_number;
     pub const parse_time_passes_format: &str = "`text` (default) or `json`";
+    pub const parse_self_profile_export: &str =
+        "a comma-separated list of export formats: `chrome-trace`, `json-summary`";
     pub const parse_passes: &str = "a space-separated list of passes, or `all`";
     pub const parse_panic_strategy: &str = "either `unwind` or `abort`";
     pub const parse_opt_panic_strategy: &str = parse_panic_strategy;
@@ -884,6 +889,24 @@ pub(crate) fn parse_unpretty(slot: &mut Option<String>, v: Option<&str>) -> bool
         }
     }
 
+    pub(crate) fn parse_self_profile_export(
+        slot: &mut Vec<SelfProfileExportFormat>,
+        v: Option<&str>,
+    ) -> bool {
+        let Some(v) = v else { return false };
+        for format in v.split(',') {
+            let format = match format {
+                "chrome-trace" => SelfProfileExportFormat::ChromeTrace,
+                "json-summary" => SelfProfileExportFormat::JsonSummary,
+                _ => return false,
+            };
+            if !slot.contains(&format) {
+                slot.push(format);
+            }
+        }
+        true
+    }
+
     pub(crate) fn parse_time_passes_format(slot: &mut TimePassesFormat, v: Option<&str>) -> bool {
         match v {
             None => true,
@@ -1723,6 +1746,10 @@ pub(crate) fn parse_wasm_c_abi(slot: &mut WasmCAbi, v: Option<&str>) -> bool {
     incremental_info: bool = (false, parse_bool, [UNTRACKED],
         "print high-level information about incremental reuse (or the lack thereof) \
         (default: no)"),
+    incremental_shared_cache: Option<PathBuf> = (None, parse_opt_pathbuf, [UNTRACKED],
+        "publish finalized incremental compilation sessions to this directory and start from \
+        them when the incremental directory is empty, e.g. to share them between CI runners \
+        (requires `-C incremental`)"),
     incremental_verify_ich: bool = (false, parse_bool, [UNTRACKED],
         "verify extended properties for incr. comp. (default: no):
         - hashes of green query instances
@@ -1943,6 +1970,11 @@ pub(crate) fn parse_wasm_c_abi(slot: &mut WasmCAbi, v: Option<&str>) -> bool {
         for example: `-Z self-profile-events=default,query-keys`
         all options: none, all, default, generic-activity, query-provider, query-cache-hit
                      query-blocked, incr-cache-load, incr-result-hashing, query-keys, function-args, args, llvm, artifact-sizes"),
+    self_profile_export: Vec<SelfProfileExportFormat> = (Vec::new(),
+        parse_self_profile_export, [UNTRACKED],
+        "also write the events recorded by the self profiler in the given formats, which don't \
+        require the `measureme` tools: `chrome-trace` (Chrome trace event JSON) or `json-summary` \
+        (time spent in each pass, query and codegen unit, as JSON)"),
     share_generics: Option<bool> = (None, parse_opt_bool, [TRACKED],
         "make the current crate share its generic instantiations"),
     shell_argfiles: bool = (false, parse_bool, [UNTRACKED],
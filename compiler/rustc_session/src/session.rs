@@ -6,6 +6,7 @@
 };
 use crate::config::{ErrorOutputType, Input};
 use crate::errors;
+use crate::lint_config::LintConfig;
 use crate::parse::{add_feature_diagnostics, ParseSess};
 use crate::search_paths::{PathKind, SearchPath};
 use crate::{filesearch, lint};
@@ -21,6 +22,7 @@
 use rustc_errors::emitter::{stderr_destination, DynEmitter, HumanEmitter, HumanReadableErrorType};
 use rustc_errors::json::JsonEmitter;
 use rustc_errors::registry::Registry;
+use rustc_errors::sarif::SarifEmitter;
 use rustc_errors::{
     codes::*, fallback_fluent_bundle, Diag, DiagCtxt, DiagMessage, Diagnostic, ErrorGuaranteed,
     FatalAbort, FluentBundle, LazyFallbackBundle, TerminalUrl,
@@ -216,6 +218,9 @@ pub struct Session {
     /// This is mainly useful for other tools that reads that debuginfo to figure out
     /// how to call the compiler with the same arguments.
     pub expanded_args: Vec<String>,
+
+    /// The lint levels of the `--lint-config` file, applied by `rustc_lint`.
+    pub lint_config: Option<LintConfig>,
 }
 
 #[derive(PartialEq, Eq, PartialOrd, Ord)]
@@ -978,6 +983,16 @@ fn default_emitter(
             .track_diagnostics(track_diagnostics)
             .terminal_url(terminal_url),
         ),
+        config::ErrorOutputType::Sarif => Box::new(
+            SarifEmitter::new(
+                Box::new(io::BufWriter::new(io::stderr())),
+                source_map,
+                fallback_bundle,
+            )
+            .registry(Some(registry))
+            .fluent_bundle(bundle)
+            .tool_name(if sopts.actually_rustdoc { "rustdoc" } else { "rustc" }),
+        ),
     }
 }
 
@@ -1045,6 +1060,7 @@ pub fn build_session(
             sopts.crate_name.as_deref(),
             sopts.unstable_opts.self_profile_events.as_deref(),
             &sopts.unstable_opts.self_profile_counter,
+            &sopts.unstable_opts.self_profile_export,
         );
         match profiler {
             Ok(profiler) => Some(Arc::new(profiler)),
@@ -1090,6 +1106,11 @@ pub fn build_session(
 
     let asm_arch = if target.allow_asm { InlineAsmArch::from_str(&target.arch).ok() } else { None };
 
+    let lint_config = sopts
+        .lint_config
+        .as_ref()
+        .map(|file| LintConfig::load(file, sopts.working_dir.local_path_if_available(), &psess));
+
     let sess = Session {
         target,
         host,
@@ -1116,6 +1137,7 @@ pub fn build_session(
         cfg_version,
         using_internal_features,
         expanded_args,
+        lint_config,
     };
 
     validate_commandline_args_with_session_available(&sess);
@@ -1428,6 +1450,14 @@ fn mk_emitter(output: ErrorOutputType) -> Box<DynEmitter> {
             pretty,
             json_rendered,
         )),
+        config::ErrorOutputType::Sarif => Box::new(
+            SarifEmitter::new(
+                Box::new(io::BufWriter::new(io::stderr())),
+                Lrc::new(SourceMap::new(FilePathMapping::empty())),
+                fallback_bundle,
+            )
+            .emit_empty_log(false),
+        ),
     };
     emitter
 }
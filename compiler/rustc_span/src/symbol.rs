@@ -1831,6 +1831,7 @@
         test_case,
         test_removed_feature,
         test_runner,
+        test_timeout,
         test_unstable_lint,
         thread,
         thread_local,
@@ -229,6 +229,45 @@ pub struct DirBuilder {
     recursive: bool,
 }
 
+/// A handle to an open directory.
+///
+/// Paths passed to the methods of `Dir` are resolved relative to the open
+/// directory rather than to the current working directory. On platforms that
+/// support it (such as Linux and the BSDs) the handle holds a directory file
+/// descriptor and every operation goes through the `*at` family of system
+/// calls (`openat`, `unlinkat`, `renameat`, ...). Renaming or replacing one of
+/// the directory's ancestors after it was opened therefore cannot redirect
+/// later operations to a different part of the filesystem, which makes it
+/// possible to manipulate a directory tree without time-of-check to
+/// time-of-use races.
+///
+/// Absolute paths are resolved as usual, ignoring the directory handle.
+///
+/// # Platform-specific behavior
+///
+/// On platforms without `openat`-style system calls, including Windows, this
+/// type only remembers the absolute path of the directory and every operation
+/// re-resolves it. There the race protection described above does not apply.
+///
+/// # Examples
+///
+/// ```no_run
+/// #![feature(dirfd)]
+/// use std::fs::Dir;
+/// use std::io::Read;
+///
+/// fn main() -> std::io::Result<()> {
+///     let dir = Dir::open("/etc")?;
+///     let mut contents = String::new();
+///     dir.open_file("hostname")?.read_to_string(&mut contents)?;
+///     Ok(())
+/// }
+/// ```
+#[unstable(feature = "dirfd", issue = "none")]
+pub struct Dir {
+    inner: fs_imp::DirHandle,
+}
+
 /// Read the entire contents of a file into a bytes vector.
 ///
 /// This is a convenience function for using [`File::open`] and [`read_to_end`]
@@ -532,6 +571,186 @@ pub fn sync_data(&self) -> io::Result<()> {
         self.inner.datasync()
     }
 
+    /// Acquires an exclusive advisory lock on the file, blocking until it can
+    /// be acquired.
+    ///
+    /// At most one handle to a file can hold an exclusive lock at a time, and
+    /// no other handle can hold a shared lock on it at the same time. Locks are
+    /// advisory: they do not prevent other code from reading or writing the
+    /// file unless that code also takes a lock.
+    ///
+    /// If this handle already holds a lock, the behavior of calling this
+    /// method again is unspecified and platform dependent, including the
+    /// possibility of a deadlock. The lock is released when [`unlock`] is
+    /// called or when the file (and every handle duplicated from it with
+    /// [`try_clone`]) is closed.
+    ///
+    /// # Platform-specific behavior
+    ///
+    /// This function currently corresponds to the `flock` function on Unix
+    /// with the `LOCK_EX` flag, and the `LockFileEx` function on Windows with
+    /// the `LOCKFILE_EXCLUSIVE_LOCK` flag. Note that, this
+    /// [may change in the future][changes].
+    ///
+    /// On other platforms, and on Unix platforms without `flock`, this
+    /// returns an error of kind [`io::ErrorKind::Unsupported`].
+    ///
+    /// [changes]: io#platform-specific-behavior
+    /// [`unlock`]: File::unlock
+    /// [`try_clone`]: File::try_clone
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(file_lock)]
+    /// use std::fs::File;
+    ///
+    /// fn main() -> std::io::Result<()> {
+    ///     let f = File::create("foo.lock")?;
+    ///     f.lock()?;
+    ///     Ok(())
+    /// }
+    /// ```
+    #[unstable(feature = "file_lock", issue = "none")]
+    pub fn lock(&self) -> io::Result<()> {
+        self.inner.lock()
+    }
+
+    /// Acquires a shared advisory lock on the file, blocking until it can be
+    /// acquired.
+    ///
+    /// Any number of handles can hold a shared lock on a file at the same
+    /// time, but no handle can hold an exclusive lock while a shared lock is
+    /// held. See [`lock`] for the remaining caveats, which apply here too.
+    ///
+    /// # Platform-specific behavior
+    ///
+    /// This function currently corresponds to the `flock` function on Unix
+    /// with the `LOCK_SH` flag, and the `LockFileEx` function on Windows.
+    /// Note that, this [may change in the future][changes].
+    ///
+    /// [changes]: io#platform-specific-behavior
+    /// [`lock`]: File::lock
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(file_lock)]
+    /// use std::fs::File;
+    ///
+    /// fn main() -> std::io::Result<()> {
+    ///     let f = File::open("foo.lock")?;
+    ///     f.lock_shared()?;
+    ///     Ok(())
+    /// }
+    /// ```
+    #[unstable(feature = "file_lock", issue = "none")]
+    pub fn lock_shared(&self) -> io::Result<()> {
+        self.inner.lock_shared()
+    }
+
+    /// Tries to acquire an exclusive advisory lock on the file without
+    /// blocking.
+    ///
+    /// Returns `Ok(false)` if a different handle holds a lock on the file,
+    /// and `Ok(true)` if the lock was acquired. See [`lock`] for the meaning
+    /// of an exclusive lock.
+    ///
+    /// # Platform-specific behavior
+    ///
+    /// This function currently corresponds to the `flock` function on Unix
+    /// with the `LOCK_EX` and `LOCK_NB` flags, and the `LockFileEx` function
+    /// on Windows with the `LOCKFILE_EXCLUSIVE_LOCK` and
+    /// `LOCKFILE_FAIL_IMMEDIATELY` flags. Note that, this
+    /// [may change in the future][changes].
+    ///
+    /// [changes]: io#platform-specific-behavior
+    /// [`lock`]: File::lock
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(file_lock)]
+    /// use std::fs::File;
+    ///
+    /// fn main() -> std::io::Result<()> {
+    ///     let f = File::create("foo.lock")?;
+    ///     if !f.try_lock()? {
+    ///         eprintln!("another process holds the lock");
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    #[unstable(feature = "file_lock", issue = "none")]
+    pub fn try_lock(&self) -> io::Result<bool> {
+        self.inner.try_lock()
+    }
+
+    /// Tries to acquire a shared advisory lock on the file without blocking.
+    ///
+    /// Returns `Ok(false)` if a different handle holds an exclusive lock on
+    /// the file, and `Ok(true)` if the lock was acquired. See [`lock_shared`]
+    /// for the meaning of a shared lock.
+    ///
+    /// # Platform-specific behavior
+    ///
+    /// This function currently corresponds to the `flock` function on Unix
+    /// with the `LOCK_SH` and `LOCK_NB` flags, and the `LockFileEx` function
+    /// on Windows with the `LOCKFILE_FAIL_IMMEDIATELY` flag. Note that, this
+    /// [may change in the future][changes].
+    ///
+    /// [changes]: io#platform-specific-behavior
+    /// [`lock_shared`]: File::lock_shared
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(file_lock)]
+    /// use std::fs::File;
+    ///
+    /// fn main() -> std::io::Result<()> {
+    ///     let f = File::open("foo.lock")?;
+    ///     if !f.try_lock_shared()? {
+    ///         eprintln!("another process holds an exclusive lock");
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    #[unstable(feature = "file_lock", issue = "none")]
+    pub fn try_lock_shared(&self) -> io::Result<bool> {
+        self.inner.try_lock_shared()
+    }
+
+    /// Releases any lock held on the file by this handle.
+    ///
+    /// # Platform-specific behavior
+    ///
+    /// This function currently corresponds to the `flock` function on Unix
+    /// with the `LOCK_UN` flag, and the `UnlockFile` function on Windows.
+    /// Note that, this [may change in the future][changes].
+    ///
+    /// On Windows, calling this without holding a lock returns an error.
+    ///
+    /// [changes]: io#platform-specific-behavior
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(file_lock)]
+    /// use std::fs::File;
+    ///
+    /// fn main() -> std::io::Result<()> {
+    ///     let f = File::create("foo.lock")?;
+    ///     f.lock()?;
+    ///     f.unlock()?;
+    ///     Ok(())
+    /// }
+    /// ```
+    #[unstable(feature = "file_lock", issue = "none")]
+    pub fn unlock(&self) -> io::Result<()> {
+        self.inner.unlock()
+    }
+
     /// Truncates or extends the underlying file, updating the size of
     /// this file to become `size`.
     ///
@@ -2633,6 +2852,213 @@ fn as_inner_mut(&mut self) -> &mut fs_imp::DirBuilder {
     }
 }
 
+impl Dir {
+    /// Opens the directory at `path`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `path` does not exist or is not
+    /// a directory, or if the user lacks permission to open it.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(dirfd)]
+    /// use std::fs::Dir;
+    ///
+    /// fn main() -> std::io::Result<()> {
+    ///     let dir = Dir::open("foo")?;
+    ///     Ok(())
+    /// }
+    /// ```
+    #[unstable(feature = "dirfd", issue = "none")]
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Dir> {
+        fs_imp::DirHandle::open(path.as_ref()).map(|inner| Dir { inner })
+    }
+
+    /// Opens the file at `path`, relative to this directory, in read-only
+    /// mode.
+    ///
+    /// This is the `Dir` equivalent of [`File::open`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(dirfd)]
+    /// use std::fs::Dir;
+    ///
+    /// fn main() -> std::io::Result<()> {
+    ///     let dir = Dir::open("foo")?;
+    ///     let file = dir.open_file("bar.txt")?;
+    ///     Ok(())
+    /// }
+    /// ```
+    #[unstable(feature = "dirfd", issue = "none")]
+    pub fn open_file<P: AsRef<Path>>(&self, path: P) -> io::Result<File> {
+        self.open_file_with(path, OpenOptions::new().read(true))
+    }
+
+    /// Opens the file at `path`, relative to this directory, with the options
+    /// specified by `opts`.
+    ///
+    /// This is the `Dir` equivalent of [`OpenOptions::open`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(dirfd)]
+    /// use std::fs::{Dir, OpenOptions};
+    /// use std::io::Write;
+    ///
+    /// fn main() -> std::io::Result<()> {
+    ///     let dir = Dir::open("foo")?;
+    ///     let mut opts = OpenOptions::new();
+    ///     opts.write(true).create(true);
+    ///     let mut file = dir.open_file_with("bar.txt", &opts)?;
+    ///     file.write_all(b"hello")?;
+    ///     Ok(())
+    /// }
+    /// ```
+    #[unstable(feature = "dirfd", issue = "none")]
+    pub fn open_file_with<P: AsRef<Path>>(&self, path: P, opts: &OpenOptions) -> io::Result<File> {
+        self.inner.open_file(path.as_ref(), &opts.0).map(|inner| File { inner })
+    }
+
+    /// Opens the directory at `path`, relative to this directory.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(dirfd)]
+    /// use std::fs::Dir;
+    ///
+    /// fn main() -> std::io::Result<()> {
+    ///     let dir = Dir::open("foo")?;
+    ///     let subdir = dir.open_dir("bar")?;
+    ///     Ok(())
+    /// }
+    /// ```
+    #[unstable(feature = "dirfd", issue = "none")]
+    pub fn open_dir<P: AsRef<Path>>(&self, path: P) -> io::Result<Dir> {
+        self.inner.open_dir(path.as_ref()).map(|inner| Dir { inner })
+    }
+
+    /// Queries the metadata of the entry at `path`, relative to this
+    /// directory, following symbolic links.
+    ///
+    /// This is the `Dir` equivalent of [`metadata`].
+    #[unstable(feature = "dirfd", issue = "none")]
+    pub fn metadata<P: AsRef<Path>>(&self, path: P) -> io::Result<Metadata> {
+        self.inner.stat(path.as_ref()).map(Metadata)
+    }
+
+    /// Queries the metadata of the entry at `path`, relative to this
+    /// directory, without following symbolic links.
+    ///
+    /// This is the `Dir` equivalent of [`symlink_metadata`].
+    #[unstable(feature = "dirfd", issue = "none")]
+    pub fn symlink_metadata<P: AsRef<Path>>(&self, path: P) -> io::Result<Metadata> {
+        self.inner.lstat(path.as_ref()).map(Metadata)
+    }
+
+    /// Creates a new, empty directory at `path`, relative to this directory.
+    ///
+    /// This is the `Dir` equivalent of [`create_dir`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(dirfd)]
+    /// use std::fs::Dir;
+    ///
+    /// fn main() -> std::io::Result<()> {
+    ///     let dir = Dir::open("foo")?;
+    ///     dir.create_dir("bar")?;
+    ///     Ok(())
+    /// }
+    /// ```
+    #[unstable(feature = "dirfd", issue = "none")]
+    pub fn create_dir<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.inner.mkdir(path.as_ref())
+    }
+
+    /// Removes the file at `path`, relative to this directory.
+    ///
+    /// This is the `Dir` equivalent of [`remove_file`].
+    #[unstable(feature = "dirfd", issue = "none")]
+    pub fn remove_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.inner.unlink(path.as_ref())
+    }
+
+    /// Removes the empty directory at `path`, relative to this directory.
+    ///
+    /// This is the `Dir` equivalent of [`remove_dir`].
+    #[unstable(feature = "dirfd", issue = "none")]
+    pub fn remove_dir<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.inner.rmdir(path.as_ref())
+    }
+
+    /// Renames the entry at `from`, relative to this directory, to `to`,
+    /// relative to `to_dir`.
+    ///
+    /// This is the `Dir` equivalent of [`rename`]. `to_dir` may be `self` to
+    /// rename an entry within the same directory.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(dirfd)]
+    /// use std::fs::Dir;
+    ///
+    /// fn main() -> std::io::Result<()> {
+    ///     let dir = Dir::open("foo")?;
+    ///     dir.rename("a.txt", &dir, "b.txt")?;
+    ///     Ok(())
+    /// }
+    /// ```
+    #[unstable(feature = "dirfd", issue = "none")]
+    pub fn rename<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        from: P,
+        to_dir: &Dir,
+        to: Q,
+    ) -> io::Result<()> {
+        self.inner.rename(from.as_ref(), &to_dir.inner, to.as_ref())
+    }
+
+    /// Returns an iterator over the entries within this directory.
+    ///
+    /// Each call starts a fresh iteration from the beginning of the
+    /// directory. See [`read_dir`] for the guarantees (or lack thereof) on the
+    /// order and stability of the returned entries.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(dirfd)]
+    /// use std::fs::Dir;
+    ///
+    /// fn main() -> std::io::Result<()> {
+    ///     let dir = Dir::open("foo")?;
+    ///     for entry in dir.entries()? {
+    ///         println!("{:?}", entry?.file_name());
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    #[unstable(feature = "dirfd", issue = "none")]
+    pub fn entries(&self) -> io::Result<ReadDir> {
+        self.inner.readdir().map(ReadDir)
+    }
+}
+
+#[unstable(feature = "dirfd", issue = "none")]
+impl fmt::Debug for Dir {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
 /// Returns `Ok(true)` if the path points at an existing entity.
 ///
 /// This function will traverse symbolic links to query information about the
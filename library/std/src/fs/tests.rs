@@ -1,7 +1,7 @@
 use crate::io::prelude::*;
 
 use crate::env;
-use crate::fs::{self, File, FileTimes, OpenOptions};
+use crate::fs::{self, Dir, File, FileTimes, OpenOptions};
 use crate::io::{BorrowedBuf, ErrorKind, SeekFrom};
 use crate::mem::MaybeUninit;
 use crate::path::Path;
@@ -1830,3 +1830,111 @@ fn test_hidden_file_truncation() {
     let metadata = file.metadata().unwrap();
     assert_eq!(metadata.len(), 0);
 }
+
+#[test]
+fn dir_relative_ops() {
+    let tmpdir = tmpdir();
+    check!(fs::create_dir(tmpdir.join("d")));
+    let dir = check!(Dir::open(tmpdir.join("d")));
+
+    let mut opts = OpenOptions::new();
+    opts.write(true).create_new(true);
+    check!(check!(dir.open_file_with("file", &opts)).write_all(b"hello"));
+    let mut contents = String::new();
+    check!(check!(dir.open_file("file")).read_to_string(&mut contents));
+    assert_eq!(contents, "hello");
+    assert_eq!(check!(dir.metadata("file")).len(), 5);
+    assert!(check!(fs::metadata(tmpdir.join("d/file"))).is_file());
+
+    check!(dir.create_dir("sub"));
+    let sub = check!(dir.open_dir("sub"));
+    assert!(check!(sub.symlink_metadata(".")).is_dir());
+    check!(dir.rename("file", &sub, "moved"));
+    assert!(!tmpdir.join("d/file").exists());
+    assert!(tmpdir.join("d/sub/moved").exists());
+
+    let names = |dir: &Dir| {
+        let mut names: Vec<_> = check!(dir.entries()).map(|e| check!(e).file_name()).collect();
+        names.sort();
+        names
+    };
+    assert_eq!(names(&dir), ["sub"]);
+    // A second iteration starts from the beginning again.
+    assert_eq!(names(&dir), ["sub"]);
+    assert_eq!(names(&sub), ["moved"]);
+
+    #[cfg(windows)]
+    error!(dir.remove_dir("sub"), 145); // ERROR_DIR_NOT_EMPTY
+    #[cfg(unix)]
+    error!(dir.remove_dir("sub"), "Directory not empty");
+    check!(sub.remove_file("moved"));
+    check!(dir.remove_dir("sub"));
+    assert!(names(&dir).is_empty());
+}
+
+#[test]
+#[cfg_attr(any(not(unix), miri), ignore)] // no directory fd to hold on to
+fn dir_survives_rename_of_parent() {
+    let tmpdir = tmpdir();
+    check!(fs::create_dir_all(tmpdir.join("a/b")));
+    let dir = check!(Dir::open(tmpdir.join("a")));
+    check!(fs::rename(tmpdir.join("a"), tmpdir.join("c")));
+    check!(fs::create_dir(tmpdir.join("a")));
+
+    // Operations keep targeting the directory that was opened, not the new `a`.
+    check!(dir.create_dir("b/x"));
+    assert!(tmpdir.join("c/b/x").is_dir());
+    assert!(!tmpdir.join("a/b").exists());
+}
+
+#[test]
+#[cfg(any(
+    windows,
+    target_os = "freebsd",
+    target_os = "linux",
+    target_os = "netbsd",
+    target_os = "macos",
+))]
+#[cfg_attr(miri, ignore)] // no file locking in Miri
+fn file_lock_multiple_handles() {
+    let tmpdir = tmpdir();
+    let filename = &tmpdir.join("file_lock_multiple_handles.txt");
+    let f1 = check!(File::create(filename));
+    let f2 = check!(OpenOptions::new().write(true).open(filename));
+
+    check!(f1.lock());
+    assert!(!check!(f2.try_lock()));
+    assert!(!check!(f2.try_lock_shared()));
+    check!(f1.unlock());
+    assert!(check!(f2.try_lock()));
+    check!(f2.unlock());
+
+    check!(f1.lock_shared());
+    assert!(check!(f2.try_lock_shared()));
+    check!(f2.unlock());
+    assert!(!check!(f2.try_lock()));
+    check!(f1.unlock());
+    assert!(check!(f2.try_lock()));
+    check!(f2.unlock());
+}
+
+#[test]
+#[cfg(any(
+    windows,
+    target_os = "freebsd",
+    target_os = "linux",
+    target_os = "netbsd",
+    target_os = "macos",
+))]
+#[cfg_attr(miri, ignore)] // no file locking in Miri
+fn file_lock_released_on_drop() {
+    let tmpdir = tmpdir();
+    let filename = &tmpdir.join("file_lock_released_on_drop.txt");
+    let f1 = check!(File::create(filename));
+    let f2 = check!(OpenOptions::new().write(true).open(filename));
+
+    check!(f1.lock());
+    assert!(!check!(f2.try_lock()));
+    drop(f1);
+    assert!(check!(f2.try_lock()));
+}
@@ -11,6 +11,7 @@
 #[cfg(not(doc))]
 use crate::sys::fd::FileDesc;
 use crate::sys_common::{AsInner, AsInnerMut, FromInner, IntoInner};
+use crate::time::Duration;
 
 #[cfg(doc)]
 struct FileDesc;
@@ -51,6 +52,64 @@ pub struct PidFd {
     inner: FileDesc,
 }
 
+impl PidFd {
+    /// Opens a pidfd referring to an existing process.
+    ///
+    /// Unlike [`create_pidfd`], this works for any process the caller can see,
+    /// not only for children spawned by [`Command`]. The usual pid caveats
+    /// apply at the moment of the call: if `pid` has already exited and been
+    /// reaped, the pidfd may refer to an unrelated process that reused the pid.
+    /// Once opened, however, the pidfd keeps referring to the same process.
+    ///
+    /// This requires Linux 5.3 or later.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(linux_pidfd)]
+    /// use std::os::linux::process::PidFd;
+    ///
+    /// let pidfd = PidFd::open(std::process::id())?;
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    ///
+    /// Refer to the man page of [`pidfd_open(2)`] for further details.
+    ///
+    /// [`create_pidfd`]: CommandExt::create_pidfd
+    /// [`Command`]: process::Command
+    /// [`pidfd_open(2)`]: https://man7.org/linux/man-pages/man2/pidfd_open.2.html
+    pub fn open(pid: u32) -> Result<PidFd> {
+        FileDesc::pidfd_open(pid).map(Self::from_inner)
+    }
+
+    /// Sends a signal to the process referred to by this pidfd.
+    ///
+    /// Signals sent this way cannot be delivered to the wrong process because
+    /// of pid reuse. If the process has already exited, an error is returned.
+    ///
+    /// This requires Linux 5.1 or later.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(linux_pidfd)]
+    /// use std::os::linux::process::PidFd;
+    ///
+    /// # let pid = std::process::id();
+    /// let pidfd = PidFd::open(pid)?;
+    /// // SIGTERM
+    /// pidfd.send_signal(15)?;
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    ///
+    /// Refer to the man page of [`pidfd_send_signal(2)`] for further details.
+    ///
+    /// [`pidfd_send_signal(2)`]: https://man7.org/linux/man-pages/man2/pidfd_send_signal.2.html
+    pub fn send_signal(&self, signal: i32) -> Result<()> {
+        self.inner.pidfd_send_signal(signal)
+    }
+}
+
 impl AsInner<FileDesc> for PidFd {
     #[inline]
     fn as_inner(&self) -> &FileDesc {
@@ -136,6 +195,38 @@ pub trait ChildExt: Sealed {
     /// [`create_pidfd`]: CommandExt::create_pidfd
     /// [`Child`]: process::Child
     fn take_pidfd(&mut self) -> Result<PidFd>;
+
+    /// Waits for the child to exit, but only for a limited time.
+    ///
+    /// Returns `Ok(Some(status))` if the child exited within `timeout`, and
+    /// `Ok(None)` if it was still running when the timeout elapsed. As with
+    /// [`Child::wait`], the stdin handle of the child, if any, is closed
+    /// before waiting.
+    ///
+    /// The wait is performed by polling a pidfd, so no extra thread is needed.
+    /// The child's own pidfd is used if one was created with
+    /// [`create_pidfd`]; otherwise a temporary one is opened, which requires
+    /// Linux 5.3 or later.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(linux_pidfd)]
+    /// use std::os::linux::process::ChildExt;
+    /// use std::process::Command;
+    /// use std::time::Duration;
+    ///
+    /// let mut child = Command::new("sleep").arg("10").spawn()?;
+    /// if child.wait_timeout(Duration::from_secs(1))?.is_none() {
+    ///     child.kill()?;
+    ///     child.wait()?;
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    ///
+    /// [`Child::wait`]: process::Child::wait
+    /// [`create_pidfd`]: CommandExt::create_pidfd
+    fn wait_timeout(&mut self, timeout: Duration) -> Result<Option<process::ExitStatus>>;
 }
 
 /// Os-specific extensions for [`Command`]
@@ -63,6 +63,29 @@ pub trait FileExt {
     /// Data is copied to fill each buffer in order, with the final buffer
     /// written to possibly being only partially filled. This method must behave
     /// equivalently to a single call to read with concatenated buffers.
+    ///
+    /// On most Unix platforms this is a single `preadv` system call.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(unix_file_vectored_at)]
+    /// use std::fs::File;
+    /// use std::io::{self, IoSliceMut};
+    /// use std::os::unix::prelude::FileExt;
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let file = File::open("foo.txt")?;
+    ///     let mut header = [0u8; 4];
+    ///     let mut body = [0u8; 12];
+    ///
+    ///     // Read up to 16 bytes from offset 10, filling `header` first.
+    ///     let mut bufs = [IoSliceMut::new(&mut header), IoSliceMut::new(&mut body)];
+    ///     let n = file.read_vectored_at(&mut bufs, 10)?;
+    ///     println!("read {n} bytes");
+    ///     Ok(())
+    /// }
+    /// ```
     #[unstable(feature = "unix_file_vectored_at", issue = "89517")]
     fn read_vectored_at(&self, bufs: &mut [io::IoSliceMut<'_>], offset: u64) -> io::Result<usize> {
         io::default_read_vectored(|b| self.read_at(b, offset), bufs)
@@ -199,6 +222,32 @@ fn read_exact_at(&self, mut buf: &mut [u8], mut offset: u64) -> io::Result<()> {
     /// Data is copied from each buffer in order, with the final buffer read
     /// from possibly being only partially consumed. This method must behave as
     /// a call to `write_at` with the buffers concatenated would.
+    ///
+    /// On most Unix platforms this is a single `pwritev` system call. Like
+    /// [`write_at`], it may return after writing only part of the data; see
+    /// [`write_all_vectored_at`] to write everything.
+    ///
+    /// [`write_at`]: FileExt::write_at
+    /// [`write_all_vectored_at`]: FileExt::write_all_vectored_at
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(unix_file_vectored_at)]
+    /// use std::fs::File;
+    /// use std::io::{self, IoSlice};
+    /// use std::os::unix::prelude::FileExt;
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let file = File::create("foo.txt")?;
+    ///
+    ///     // Write "sushi" at offset 10 from two separate buffers.
+    ///     let bufs = [IoSlice::new(b"su"), IoSlice::new(b"shi")];
+    ///     let n = file.write_vectored_at(&bufs, 10)?;
+    ///     println!("wrote {n} bytes");
+    ///     Ok(())
+    /// }
+    /// ```
     #[unstable(feature = "unix_file_vectored_at", issue = "89517")]
     fn write_vectored_at(&self, bufs: &[io::IoSlice<'_>], offset: u64) -> io::Result<usize> {
         io::default_write_vectored(|b| self.write_at(b, offset), bufs)
@@ -257,6 +306,74 @@ fn write_all_at(&self, mut buf: &[u8], mut offset: u64) -> io::Result<()> {
         }
         Ok(())
     }
+
+    /// Attempts to write multiple buffers entirely, starting from a given offset.
+    ///
+    /// The offset is relative to the start of the file and thus independent
+    /// from the current cursor.
+    ///
+    /// The current file cursor is not affected by this function.
+    ///
+    /// This method will continuously call [`write_vectored_at`] until there is
+    /// no more data to be written or an error of non-[`io::ErrorKind::Interrupted`]
+    /// kind is returned. It is the vectored counterpart of [`write_all_at`]:
+    /// the buffers are written as if they had been concatenated, usually with
+    /// far fewer system calls than writing them one by one.
+    ///
+    /// The slice of buffers is modified to track how much has been written, so
+    /// its contents are unspecified once this method returns.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the first error of
+    /// non-[`io::ErrorKind::Interrupted`] kind that [`write_vectored_at`] returns.
+    ///
+    /// [`write_vectored_at`]: FileExt::write_vectored_at
+    /// [`write_all_at`]: FileExt::write_all_at
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(unix_file_vectored_at)]
+    /// use std::fs::File;
+    /// use std::io::{self, IoSlice};
+    /// use std::os::unix::prelude::FileExt;
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let file = File::create("foo.txt")?;
+    ///     let header = [0u8; 16];
+    ///     let page = [0xffu8; 4096];
+    ///
+    ///     // Write both buffers back to back, starting at offset 4096.
+    ///     let mut bufs = [IoSlice::new(&header), IoSlice::new(&page)];
+    ///     file.write_all_vectored_at(&mut bufs, 4096)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    #[unstable(feature = "unix_file_vectored_at", issue = "89517")]
+    fn write_all_vectored_at(
+        &self,
+        mut bufs: &mut [io::IoSlice<'_>],
+        mut offset: u64,
+    ) -> io::Result<()> {
+        // Guarantee that bufs is empty if it contains no data,
+        // to avoid calling write_vectored_at if there is no data to be written.
+        io::IoSlice::advance_slices(&mut bufs, 0);
+        while !bufs.is_empty() {
+            match self.write_vectored_at(bufs, offset) {
+                Ok(0) => {
+                    return Err(io::Error::WRITE_ALL_EOF);
+                }
+                Ok(n) => {
+                    io::IoSlice::advance_slices(&mut bufs, n);
+                    offset += n as u64;
+                }
+                Err(ref e) if e.is_interrupted() => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
 }
 
 #[stable(feature = "file_offset", since = "1.15.0")]
@@ -55,3 +55,24 @@ fn write_vectored_at() {
     let content = fs::read(&filename).unwrap();
     assert_eq!(&content, expected);
 }
+
+#[test]
+fn write_all_vectored_at() {
+    let msg = b"pwritev is not working!";
+    let dir = crate::sys_common::io::test::tmpdir();
+
+    let filename = dir.join("pwritev_all.txt");
+    {
+        let mut file = fs::File::create(&filename).unwrap();
+        file.write_all(msg).unwrap();
+    }
+    {
+        let file = fs::File::options().write(true).open(&filename).unwrap();
+        let mut iovec =
+            [io::IoSlice::new(b""), io::IoSlice::new(b"    "), io::IoSlice::new(b"great  ")];
+        file.write_all_vectored_at(&mut iovec, 11).unwrap();
+    }
+
+    let content = fs::read(&filename).unwrap();
+    assert_eq!(&content, b"pwritev is     great  !");
+}
@@ -95,6 +95,13 @@ mod platform {
 pub mod net;
 pub mod process;
 pub mod raw;
+#[cfg(not(any(
+    target_os = "espidf",
+    target_os = "horizon",
+    target_os = "vita",
+    target_os = "vxworks"
+)))]
+pub mod signal;
 pub mod thread;
 
 /// A prelude for conveniently writing platform-specific code.
@@ -194,6 +194,97 @@ fn arg0<S>(&mut self, arg: S) -> &mut process::Command
     /// ```
     #[stable(feature = "process_set_process_group", since = "1.64.0")]
     fn process_group(&mut self, pgroup: i32) -> &mut process::Command;
+
+    /// Runs the child process in a new session. Equivalent to a `setsid`
+    /// call in the child process, but may be more efficient.
+    ///
+    /// The child becomes the leader of a new session and of a new process
+    /// group, and has no controlling terminal. This cannot be combined with
+    /// [`process_group`], since a session leader cannot change its process
+    /// group: spawning will fail.
+    ///
+    /// [`process_group`]: CommandExt::process_group
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(process_setsid)]
+    /// use std::process::Command;
+    /// use std::os::unix::process::CommandExt;
+    ///
+    /// // Detach the daemon from our terminal.
+    /// Command::new("my-daemon").setsid(true).spawn()?;
+    /// #
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
+    /// ```
+    #[unstable(feature = "process_setsid", issue = "none")]
+    fn setsid(&mut self, setsid: bool) -> &mut process::Command;
+
+    /// Sets a resource limit of the child process. Equivalent to a
+    /// `setrlimit` call in the child process.
+    ///
+    /// `resource` is one of the platform's `RLIMIT_*` constants, and `soft`
+    /// and `hard` are the soft and hard limits to apply; use the platform's
+    /// `RLIM_INFINITY` for no limit. Limits that are too large for the
+    /// platform's `rlim_t` are also treated as `RLIM_INFINITY`. Setting the
+    /// same resource again replaces the earlier limits.
+    ///
+    /// Limits are applied before the child's user and group IDs are changed
+    /// with [`uid`] and [`gid`], so a privileged parent may raise hard limits
+    /// for an unprivileged child.
+    ///
+    /// [`uid`]: CommandExt::uid
+    /// [`gid`]: CommandExt::gid
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(process_setrlimit)]
+    /// use std::process::Command;
+    /// use std::os::unix::process::CommandExt;
+    ///
+    /// const RLIMIT_CORE: i32 = 4; // on Linux
+    ///
+    /// // Don't let the child dump core.
+    /// Command::new("untrusted").setrlimit(RLIMIT_CORE, 0, 0).spawn()?;
+    /// #
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
+    /// ```
+    #[unstable(feature = "process_setrlimit", issue = "none")]
+    fn setrlimit(&mut self, resource: i32, soft: u64, hard: u64) -> &mut process::Command;
+
+    /// Makes `tty` the controlling terminal of the child process.
+    ///
+    /// This implies [`setsid`], since only a session leader without a
+    /// controlling terminal can acquire one. The terminal is acquired with
+    /// `ioctl(tty, TIOCSCTTY)` in the child; `tty` is typically the follower
+    /// side of a pseudoterminal, which callers will usually also pass as the
+    /// child's standard streams.
+    ///
+    /// The command keeps `tty` open, so that it can be spawned repeatedly.
+    ///
+    /// [`setsid`]: CommandExt::setsid
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(process_controlling_tty)]
+    /// use std::fs::File;
+    /// use std::process::{Command, Stdio};
+    /// use std::os::unix::process::CommandExt;
+    ///
+    /// let tty = File::options().read(true).write(true).open("/dev/pts/3")?;
+    /// Command::new("sh")
+    ///     .stdin(Stdio::from(tty.try_clone()?))
+    ///     .stdout(Stdio::from(tty.try_clone()?))
+    ///     .stderr(Stdio::from(tty.try_clone()?))
+    ///     .controlling_tty(tty)
+    ///     .spawn()?;
+    /// #
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
+    /// ```
+    #[unstable(feature = "process_controlling_tty", issue = "none")]
+    fn controlling_tty<T: Into<OwnedFd>>(&mut self, tty: T) -> &mut process::Command;
 }
 
 #[stable(feature = "rust1", since = "1.0.0")]
@@ -239,6 +330,21 @@ fn process_group(&mut self, pgroup: i32) -> &mut process::Command {
         self.as_inner_mut().pgroup(pgroup);
         self
     }
+
+    fn setsid(&mut self, setsid: bool) -> &mut process::Command {
+        self.as_inner_mut().setsid(setsid);
+        self
+    }
+
+    fn setrlimit(&mut self, resource: i32, soft: u64, hard: u64) -> &mut process::Command {
+        self.as_inner_mut().setrlimit(resource, soft, hard);
+        self
+    }
+
+    fn controlling_tty<T: Into<OwnedFd>>(&mut self, tty: T) -> &mut process::Command {
+        self.as_inner_mut().controlling_tty(FromInner::from_inner(tty.into()));
+        self
+    }
 }
 
 /// Unix-specific extensions to [`process::ExitStatus`] and
@@ -0,0 +1,182 @@
+//! Unix-specific signal handling.
+//!
+//! Signal handlers run asynchronously and may only do a very limited set of
+//! things, which makes installing one by hand difficult to get right. This
+//! module instead lets a program register interest in a set of signals and
+//! then receive them as ordinary values through a [`Signals`] handle, at a time
+//! of its own choosing.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! #![feature(unix_signals)]
+//! use std::os::unix::signal::Signals;
+//!
+//! const SIGHUP: i32 = 1;
+//! const SIGTERM: i32 = 15;
+//!
+//! fn main() -> std::io::Result<()> {
+//!     let signals = Signals::new([SIGHUP, SIGTERM])?;
+//!     for signal in &signals {
+//!         match signal? {
+//!             SIGHUP => println!("reloading configuration"),
+//!             _ => break,
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+#![unstable(feature = "unix_signals", issue = "none")]
+
+use crate::fmt;
+use crate::io;
+use crate::os::unix::io::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd};
+
+// Tests for this module
+#[cfg(test)]
+mod tests;
+
+/// A handle that receives a set of signals delivered to the process.
+///
+/// The first time a signal is registered with any `Signals`, std installs a
+/// process-wide handler for it. That handler records each delivery by writing
+/// to a pipe owned by every `Signals` interested in the signal, and then calls
+/// any handler that was installed before it, so existing handlers keep working.
+/// Signals are retrieved from the pipe with [`wait`], [`try_wait`] or by
+/// iterating over `&Signals`. The pipe's read end is also available through
+/// [`AsFd`], so that a `Signals` can be added to an event loop: it becomes
+/// readable whenever a signal is pending.
+///
+/// As with the signals themselves, deliveries of the same signal that happen
+/// in quick succession may be coalesced into one.
+///
+/// # Restrictions
+///
+/// `SIGKILL` and `SIGSTOP` cannot be caught, and the synchronous fault signals
+/// `SIGSEGV`, `SIGBUS`, `SIGILL` and `SIGFPE` cannot be registered either:
+/// std uses `SIGSEGV` and `SIGBUS` to report stack overflows, and returning
+/// from a handler for any of them resumes the faulting instruction.
+///
+/// Once the handler for a signal has been installed, it stays installed even
+/// after every `Signals` interested in it is dropped. The signal is then
+/// simply discarded instead of performing its default action, such as
+/// terminating the process.
+///
+/// [`wait`]: Signals::wait
+/// [`try_wait`]: Signals::try_wait
+pub struct Signals {
+    read: OwnedFd,
+    write: OwnedFd,
+}
+
+impl Signals {
+    /// Creates a handle that receives the given signals.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if one of the
+    /// signals is not a valid signal number or cannot be registered, see the
+    /// [type-level documentation](Signals#restrictions).
+    pub fn new<I: IntoIterator<Item = i32>>(signals: I) -> io::Result<Signals> {
+        let (read, write) = crate::sys::signal::signal_pipe()?;
+        let this = Signals { read, write };
+        for signal in signals {
+            this.add_signal(signal)?;
+        }
+        Ok(this)
+    }
+
+    /// Starts receiving another signal.
+    ///
+    /// Adding a signal that is already registered has no effect.
+    pub fn add_signal(&self, signal: i32) -> io::Result<()> {
+        crate::sys::signal::register(signal, self.write.as_fd())
+    }
+
+    /// Blocks until a signal is delivered, and returns it.
+    ///
+    /// Signals that were delivered before this call are returned immediately,
+    /// in the order they arrived.
+    pub fn wait(&self) -> io::Result<i32> {
+        loop {
+            if let Some(signal) = self.try_wait()? {
+                return Ok(signal);
+            }
+            crate::sys::signal::wait_readable(self.read.as_fd())?;
+        }
+    }
+
+    /// Returns the next pending signal, if any, without blocking.
+    pub fn try_wait(&self) -> io::Result<Option<i32>> {
+        crate::sys::signal::try_read(self.read.as_fd())
+    }
+
+    /// Returns an iterator that blocks waiting for signals.
+    ///
+    /// The iterator never returns [`None`]; each item is the result of a call
+    /// to [`wait`].
+    ///
+    /// [`wait`]: Signals::wait
+    pub fn iter(&self) -> Iter<'_> {
+        Iter { signals: self }
+    }
+}
+
+impl Drop for Signals {
+    fn drop(&mut self) {
+        // Make sure no handler writes to the pipe once it has been closed.
+        crate::sys::signal::unregister(self.write.as_fd());
+    }
+}
+
+impl AsFd for Signals {
+    /// Returns the read end of the pipe signals are delivered through.
+    ///
+    /// Do not read from it directly other than to wait for readiness, or
+    /// signals will be lost.
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.read.as_fd()
+    }
+}
+
+impl AsRawFd for Signals {
+    #[inline]
+    fn as_raw_fd(&self) -> RawFd {
+        self.read.as_raw_fd()
+    }
+}
+
+impl fmt::Debug for Signals {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Signals").field("fd", &self.read.as_raw_fd()).finish_non_exhaustive()
+    }
+}
+
+/// An iterator that blocks waiting for signals.
+///
+/// This `struct` is created by [`Signals::iter`]. See its documentation for
+/// more.
+#[derive(Debug)]
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct Iter<'a> {
+    signals: &'a Signals,
+}
+
+impl Iterator for Iter<'_> {
+    type Item = io::Result<i32>;
+
+    fn next(&mut self) -> Option<io::Result<i32>> {
+        Some(self.signals.wait())
+    }
+}
+
+impl<'a> IntoIterator for &'a Signals {
+    type Item = io::Result<i32>;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
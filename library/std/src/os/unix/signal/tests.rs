@@ -0,0 +1,36 @@
+use super::*;
+
+#[test]
+#[cfg_attr(miri, ignore)] // Miri does not support signal handlers
+fn raise_and_receive() {
+    let signals = Signals::new([libc::SIGUSR1]).unwrap();
+    assert_eq!(signals.try_wait().unwrap(), None);
+
+    unsafe { libc::raise(libc::SIGUSR1) };
+    assert_eq!(signals.wait().unwrap(), libc::SIGUSR1);
+    assert_eq!(signals.try_wait().unwrap(), None);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)] // Miri does not support signal handlers
+fn multiple_subscribers() {
+    let a = Signals::new([libc::SIGUSR2]).unwrap();
+    let b = Signals::new([libc::SIGUSR2]).unwrap();
+
+    unsafe { libc::raise(libc::SIGUSR2) };
+    assert_eq!(a.wait().unwrap(), libc::SIGUSR2);
+    assert_eq!(b.wait().unwrap(), libc::SIGUSR2);
+
+    drop(a);
+    unsafe { libc::raise(libc::SIGUSR2) };
+    assert_eq!(b.wait().unwrap(), libc::SIGUSR2);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)] // Miri does not support non-blocking pipes
+fn forbidden_signals() {
+    for signal in [libc::SIGKILL, libc::SIGSTOP, libc::SIGSEGV, libc::SIGBUS, 0, -1, 1000] {
+        let err = Signals::new([signal]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -125,6 +125,11 @@
 //! - [`Condvar`]: Condition Variable, providing the ability to block
 //!   a thread while waiting for an event to occur.
 //!
+//! - [`mpmc`]: Multi-producer, multi-consumer queues, used for
+//!   message-based communication where receivers can be cloned too.
+//!   Operations on several channels can be waited on at once with
+//!   [`mpmc::Select`].
+//!
 //! - [`mpsc`]: Multi-producer, single-consumer queues, used for
 //!   message-based communication. Can provide a lightweight
 //!   inter-thread synchronisation mechanism, at the cost of some
@@ -146,6 +151,8 @@
 //! [`Arc`]: crate::sync::Arc
 //! [`Barrier`]: crate::sync::Barrier
 //! [`Condvar`]: crate::sync::Condvar
+//! [`mpmc`]: crate::sync::mpmc
+//! [`mpmc::Select`]: crate::sync::mpmc::Select
 //! [`mpsc`]: crate::sync::mpsc
 //! [`Mutex`]: crate::sync::Mutex
 //! [`Once`]: crate::sync::Once
@@ -187,12 +194,13 @@
 #[unstable(feature = "reentrant_lock", issue = "121440")]
 pub use self::reentrant_lock::{ReentrantLock, ReentrantLockGuard};
 
+#[unstable(feature = "mpmc_channel", issue = "none")]
+pub mod mpmc;
 pub mod mpsc;
 
 mod barrier;
 mod condvar;
 mod lazy_lock;
-mod mpmc;
 mod mutex;
 pub(crate) mod once;
 mod once_lock;
@@ -1,8 +1,61 @@
-//! Multi-producer multi-consumer channels.
-
-// This module is not currently exposed publicly, but is used
-// as the implementation for the channels in `sync::mpsc`. The
-// implementation comes from the crossbeam-channel crate:
+//! Multi-producer, multi-consumer FIFO queue communication primitives.
+//!
+//! This module provides message-based communication over channels, concretely
+//! defined as two types:
+//!
+//! * [`Sender`]
+//! * [`Receiver`]
+//!
+//! Unlike the channels in [`mpsc`], both halves of an `mpmc` channel can be
+//! cloned: every message is received by exactly one of the receivers that are
+//! alive at the time. A channel is disconnected once all senders or all
+//! receivers have been dropped.
+//!
+//! Channels come in two flavors, created by [`channel`] and
+//! [`sync_channel`], with the same buffering semantics as their [`mpsc`]
+//! counterparts.
+//!
+//! Operations on several channels can be waited on at once with [`Select`],
+//! which blocks until one of the registered sends or receives can proceed.
+//!
+//! [`mpsc`]: crate::sync::mpsc
+//!
+//! # Examples
+//!
+//! ```
+//! #![feature(mpmc_channel)]
+//!
+//! use std::sync::mpmc::channel;
+//! use std::thread;
+//!
+//! let (tx, rx) = channel();
+//!
+//! let handles: Vec<_> = (0..4)
+//!     .map(|_| {
+//!         let rx = rx.clone();
+//!         thread::spawn(move || {
+//!             let mut sum = 0;
+//!             while let Ok(n) = rx.recv() {
+//!                 sum += n;
+//!             }
+//!             sum
+//!         })
+//!     })
+//!     .collect();
+//! drop(rx);
+//!
+//! for i in 1..=100 {
+//!     tx.send(i).unwrap();
+//! }
+//! drop(tx);
+//!
+//! let total: i32 = handles.into_iter().map(|h| h.join().unwrap()).sum();
+//! assert_eq!(total, 5050);
+//! ```
+
+// This module is also used as the implementation for the channels
+// in `sync::mpsc`. The implementation comes from the
+// crossbeam-channel crate:
 //
 // Copyright (c) 2019 The Crossbeam Project Developers
 //
@@ -30,6 +83,9 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+#[cfg(all(test, not(target_os = "emscripten")))]
+mod tests;
+
 mod array;
 mod context;
 mod counter;
@@ -43,11 +99,18 @@
 use crate::fmt;
 use crate::panic::{RefUnwindSafe, UnwindSafe};
 use crate::time::{Duration, Instant};
+#[unstable(feature = "mpmc_channel", issue = "none")]
 pub use error::*;
+#[unstable(feature = "mpmc_channel", issue = "none")]
+pub use select::{Select, SelectedOperation};
+
+use context::Context;
+use select::{Operation, SelectHandle, Token};
 
 /// Creates a channel of unbounded capacity.
 ///
 /// This channel has a growable buffer that can hold any number of messages at a time.
+#[unstable(feature = "mpmc_channel", issue = "none")]
 pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
     let (s, r) = counter::new(list::Channel::new());
     let s = Sender { flavor: SenderFlavor::List(s) };
@@ -61,6 +124,7 @@ pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
 ///
 /// A special case is zero-capacity channel, which cannot hold any messages. Instead, send and
 /// receive operations must appear at the same time in order to pair up and pass the message over.
+#[unstable(feature = "mpmc_channel", issue = "none")]
 pub fn sync_channel<T>(cap: usize) -> (Sender<T>, Receiver<T>) {
     if cap == 0 {
         let (s, r) = counter::new(zero::Channel::new());
@@ -76,6 +140,7 @@ pub fn sync_channel<T>(cap: usize) -> (Sender<T>, Receiver<T>) {
 }
 
 /// The sending side of a channel.
+#[unstable(feature = "mpmc_channel", issue = "none")]
 pub struct Sender<T> {
     flavor: SenderFlavor<T>,
 }
@@ -92,10 +157,14 @@ enum SenderFlavor<T> {
     Zero(counter::Sender<zero::Channel<T>>),
 }
 
+#[unstable(feature = "mpmc_channel", issue = "none")]
 unsafe impl<T: Send> Send for Sender<T> {}
+#[unstable(feature = "mpmc_channel", issue = "none")]
 unsafe impl<T: Send> Sync for Sender<T> {}
 
+#[unstable(feature = "mpmc_channel", issue = "none")]
 impl<T> UnwindSafe for Sender<T> {}
+#[unstable(feature = "mpmc_channel", issue = "none")]
 impl<T> RefUnwindSafe for Sender<T> {}
 
 impl<T> Sender<T> {
@@ -106,6 +175,7 @@ impl<T> Sender<T> {
     ///
     /// If called on a zero-capacity channel, this method will send the message only if there
     /// happens to be a receive operation on the other side of the channel at the same time.
+    #[unstable(feature = "mpmc_channel", issue = "none")]
     pub fn try_send(&self, msg: T) -> Result<(), TrySendError<T>> {
         match &self.flavor {
             SenderFlavor::Array(chan) => chan.try_send(msg),
@@ -122,6 +192,7 @@ pub fn try_send(&self, msg: T) -> Result<(), TrySendError<T>> {
     ///
     /// If called on a zero-capacity channel, this method will wait for a receive operation to
     /// appear on the other side of the channel.
+    #[unstable(feature = "mpmc_channel", issue = "none")]
     pub fn send(&self, msg: T) -> Result<(), SendError<T>> {
         match &self.flavor {
             SenderFlavor::Array(chan) => chan.send(msg, None),
@@ -135,10 +206,6 @@ pub fn send(&self, msg: T) -> Result<(), SendError<T>> {
     }
 }
 
-// The methods below are not used by `sync::mpsc`, but
-// are useful and we'll likely want to expose them
-// eventually
-#[allow(unused)]
 impl<T> Sender<T> {
     /// Waits for a message to be sent into the channel, but only for a limited time.
     ///
@@ -148,6 +215,7 @@ impl<T> Sender<T> {
     ///
     /// If called on a zero-capacity channel, this method will wait for a receive operation to
     /// appear on the other side of the channel.
+    #[unstable(feature = "mpmc_channel", issue = "none")]
     pub fn send_timeout(&self, msg: T, timeout: Duration) -> Result<(), SendTimeoutError<T>> {
         match Instant::now().checked_add(timeout) {
             Some(deadline) => self.send_deadline(msg, deadline),
@@ -164,6 +232,7 @@ pub fn send_timeout(&self, msg: T, timeout: Duration) -> Result<(), SendTimeoutE
     ///
     /// If called on a zero-capacity channel, this method will wait for a receive operation to
     /// appear on the other side of the channel.
+    #[unstable(feature = "mpmc_channel", issue = "none")]
     pub fn send_deadline(&self, msg: T, deadline: Instant) -> Result<(), SendTimeoutError<T>> {
         match &self.flavor {
             SenderFlavor::Array(chan) => chan.send(msg, Some(deadline)),
@@ -175,6 +244,7 @@ pub fn send_deadline(&self, msg: T, deadline: Instant) -> Result<(), SendTimeout
     /// Returns `true` if the channel is empty.
     ///
     /// Note: Zero-capacity channels are always empty.
+    #[unstable(feature = "mpmc_channel", issue = "none")]
     pub fn is_empty(&self) -> bool {
         match &self.flavor {
             SenderFlavor::Array(chan) => chan.is_empty(),
@@ -186,6 +256,7 @@ pub fn is_empty(&self) -> bool {
     /// Returns `true` if the channel is full.
     ///
     /// Note: Zero-capacity channels are always full.
+    #[unstable(feature = "mpmc_channel", issue = "none")]
     pub fn is_full(&self) -> bool {
         match &self.flavor {
             SenderFlavor::Array(chan) => chan.is_full(),
@@ -195,6 +266,7 @@ pub fn is_full(&self) -> bool {
     }
 
     /// Returns the number of messages in the channel.
+    #[unstable(feature = "mpmc_channel", issue = "none")]
     pub fn len(&self) -> usize {
         match &self.flavor {
             SenderFlavor::Array(chan) => chan.len(),
@@ -204,6 +276,7 @@ pub fn len(&self) -> usize {
     }
 
     /// If the channel is bounded, returns its capacity.
+    #[unstable(feature = "mpmc_channel", issue = "none")]
     pub fn capacity(&self) -> Option<usize> {
         match &self.flavor {
             SenderFlavor::Array(chan) => chan.capacity(),
@@ -213,6 +286,7 @@ pub fn capacity(&self) -> Option<usize> {
     }
 
     /// Returns `true` if senders belong to the same channel.
+    #[unstable(feature = "mpmc_channel", issue = "none")]
     pub fn same_channel(&self, other: &Sender<T>) -> bool {
         match (&self.flavor, &other.flavor) {
             (SenderFlavor::Array(ref a), SenderFlavor::Array(ref b)) => a == b,
@@ -223,6 +297,7 @@ pub fn same_channel(&self, other: &Sender<T>) -> bool {
     }
 }
 
+#[unstable(feature = "mpmc_channel", issue = "none")]
 impl<T> Drop for Sender<T> {
     fn drop(&mut self) {
         unsafe {
@@ -235,6 +310,7 @@ fn drop(&mut self) {
     }
 }
 
+#[unstable(feature = "mpmc_channel", issue = "none")]
 impl<T> Clone for Sender<T> {
     fn clone(&self) -> Self {
         let flavor = match &self.flavor {
@@ -247,6 +323,7 @@ fn clone(&self) -> Self {
     }
 }
 
+#[unstable(feature = "mpmc_channel", issue = "none")]
 impl<T> fmt::Debug for Sender<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.pad("Sender { .. }")
@@ -254,6 +331,7 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 }
 
 /// The receiving side of a channel.
+#[unstable(feature = "mpmc_channel", issue = "none")]
 pub struct Receiver<T> {
     flavor: ReceiverFlavor<T>,
 }
@@ -270,10 +348,14 @@ enum ReceiverFlavor<T> {
     Zero(counter::Receiver<zero::Channel<T>>),
 }
 
+#[unstable(feature = "mpmc_channel", issue = "none")]
 unsafe impl<T: Send> Send for Receiver<T> {}
+#[unstable(feature = "mpmc_channel", issue = "none")]
 unsafe impl<T: Send> Sync for Receiver<T> {}
 
+#[unstable(feature = "mpmc_channel", issue = "none")]
 impl<T> UnwindSafe for Receiver<T> {}
+#[unstable(feature = "mpmc_channel", issue = "none")]
 impl<T> RefUnwindSafe for Receiver<T> {}
 
 impl<T> Receiver<T> {
@@ -284,6 +366,7 @@ impl<T> Receiver<T> {
     ///
     /// If called on a zero-capacity channel, this method will receive a message only if there
     /// happens to be a send operation on the other side of the channel at the same time.
+    #[unstable(feature = "mpmc_channel", issue = "none")]
     pub fn try_recv(&self) -> Result<T, TryRecvError> {
         match &self.flavor {
             ReceiverFlavor::Array(chan) => chan.try_recv(),
@@ -301,6 +384,7 @@ pub fn try_recv(&self) -> Result<T, TryRecvError> {
     ///
     /// If called on a zero-capacity channel, this method will wait for a send operation to appear
     /// on the other side of the channel.
+    #[unstable(feature = "mpmc_channel", issue = "none")]
     pub fn recv(&self) -> Result<T, RecvError> {
         match &self.flavor {
             ReceiverFlavor::Array(chan) => chan.recv(None),
@@ -318,6 +402,7 @@ pub fn recv(&self) -> Result<T, RecvError> {
     ///
     /// If called on a zero-capacity channel, this method will wait for a send operation to appear
     /// on the other side of the channel.
+    #[unstable(feature = "mpmc_channel", issue = "none")]
     pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
         match Instant::now().checked_add(timeout) {
             Some(deadline) => self.recv_deadline(deadline),
@@ -334,6 +419,7 @@ pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
     ///
     /// If called on a zero-capacity channel, this method will wait for a send operation to appear
     /// on the other side of the channel.
+    #[unstable(feature = "mpmc_channel", issue = "none")]
     pub fn recv_deadline(&self, deadline: Instant) -> Result<T, RecvTimeoutError> {
         match &self.flavor {
             ReceiverFlavor::Array(chan) => chan.recv(Some(deadline)),
@@ -343,14 +429,11 @@ pub fn recv_deadline(&self, deadline: Instant) -> Result<T, RecvTimeoutError> {
     }
 }
 
-// The methods below are not used by `sync::mpsc`, but
-// are useful and we'll likely want to expose them
-// eventually
-#[allow(unused)]
 impl<T> Receiver<T> {
     /// Returns `true` if the channel is empty.
     ///
     /// Note: Zero-capacity channels are always empty.
+    #[unstable(feature = "mpmc_channel", issue = "none")]
     pub fn is_empty(&self) -> bool {
         match &self.flavor {
             ReceiverFlavor::Array(chan) => chan.is_empty(),
@@ -362,6 +445,7 @@ pub fn is_empty(&self) -> bool {
     /// Returns `true` if the channel is full.
     ///
     /// Note: Zero-capacity channels are always full.
+    #[unstable(feature = "mpmc_channel", issue = "none")]
     pub fn is_full(&self) -> bool {
         match &self.flavor {
             ReceiverFlavor::Array(chan) => chan.is_full(),
@@ -371,6 +455,7 @@ pub fn is_full(&self) -> bool {
     }
 
     /// Returns the number of messages in the channel.
+    #[unstable(feature = "mpmc_channel", issue = "none")]
     pub fn len(&self) -> usize {
         match &self.flavor {
             ReceiverFlavor::Array(chan) => chan.len(),
@@ -380,6 +465,7 @@ pub fn len(&self) -> usize {
     }
 
     /// If the channel is bounded, returns its capacity.
+    #[unstable(feature = "mpmc_channel", issue = "none")]
     pub fn capacity(&self) -> Option<usize> {
         match &self.flavor {
             ReceiverFlavor::Array(chan) => chan.capacity(),
@@ -389,6 +475,7 @@ pub fn capacity(&self) -> Option<usize> {
     }
 
     /// Returns `true` if receivers belong to the same channel.
+    #[unstable(feature = "mpmc_channel", issue = "none")]
     pub fn same_channel(&self, other: &Receiver<T>) -> bool {
         match (&self.flavor, &other.flavor) {
             (ReceiverFlavor::Array(a), ReceiverFlavor::Array(b)) => a == b,
@@ -399,6 +486,7 @@ pub fn same_channel(&self, other: &Receiver<T>) -> bool {
     }
 }
 
+#[unstable(feature = "mpmc_channel", issue = "none")]
 impl<T> Drop for Receiver<T> {
     fn drop(&mut self) {
         unsafe {
@@ -411,6 +499,7 @@ fn drop(&mut self) {
     }
 }
 
+#[unstable(feature = "mpmc_channel", issue = "none")]
 impl<T> Clone for Receiver<T> {
     fn clone(&self) -> Self {
         let flavor = match &self.flavor {
@@ -423,8 +512,109 @@ fn clone(&self) -> Self {
     }
 }
 
+#[unstable(feature = "mpmc_channel", issue = "none")]
 impl<T> fmt::Debug for Receiver<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.pad("Receiver { .. }")
     }
 }
+
+impl<T> Sender<T> {
+    /// Completes a send operation selected by `Select`.
+    ///
+    /// # Safety
+    ///
+    /// The token must have been filled in by a successful `try_select` or `accept` on this
+    /// sender.
+    unsafe fn write(&self, token: &mut Token, msg: T) -> Result<(), T> {
+        match &self.flavor {
+            SenderFlavor::Array(chan) => chan.write(token, msg),
+            SenderFlavor::List(chan) => chan.write(token, msg),
+            SenderFlavor::Zero(chan) => chan.write(token, msg),
+        }
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Completes a receive operation selected by `Select`.
+    ///
+    /// # Safety
+    ///
+    /// The token must have been filled in by a successful `try_select` or `accept` on this
+    /// receiver.
+    unsafe fn read(&self, token: &mut Token) -> Result<T, ()> {
+        match &self.flavor {
+            ReceiverFlavor::Array(chan) => chan.read(token),
+            ReceiverFlavor::List(chan) => chan.read(token),
+            ReceiverFlavor::Zero(chan) => chan.read(token),
+        }
+    }
+}
+
+impl<T> SelectHandle for Sender<T> {
+    fn try_select(&self, token: &mut Token) -> bool {
+        match &self.flavor {
+            SenderFlavor::Array(chan) => chan.sender().try_select(token),
+            SenderFlavor::List(chan) => chan.sender().try_select(token),
+            SenderFlavor::Zero(chan) => chan.sender().try_select(token),
+        }
+    }
+
+    fn register(&self, oper: Operation, cx: &Context) -> bool {
+        match &self.flavor {
+            SenderFlavor::Array(chan) => chan.sender().register(oper, cx),
+            SenderFlavor::List(chan) => chan.sender().register(oper, cx),
+            SenderFlavor::Zero(chan) => chan.sender().register(oper, cx),
+        }
+    }
+
+    fn unregister(&self, oper: Operation) {
+        match &self.flavor {
+            SenderFlavor::Array(chan) => chan.sender().unregister(oper),
+            SenderFlavor::List(chan) => chan.sender().unregister(oper),
+            SenderFlavor::Zero(chan) => chan.sender().unregister(oper),
+        }
+    }
+
+    fn accept(&self, token: &mut Token, cx: &Context) -> bool {
+        match &self.flavor {
+            SenderFlavor::Array(chan) => chan.sender().accept(token, cx),
+            SenderFlavor::List(chan) => chan.sender().accept(token, cx),
+            SenderFlavor::Zero(chan) => chan.sender().accept(token, cx),
+        }
+    }
+}
+
+impl<T> SelectHandle for Receiver<T> {
+    fn try_select(&self, token: &mut Token) -> bool {
+        match &self.flavor {
+            ReceiverFlavor::Array(chan) => chan.receiver().try_select(token),
+            ReceiverFlavor::List(chan) => chan.receiver().try_select(token),
+            ReceiverFlavor::Zero(chan) => chan.receiver().try_select(token),
+        }
+    }
+
+    fn register(&self, oper: Operation, cx: &Context) -> bool {
+        match &self.flavor {
+            ReceiverFlavor::Array(chan) => chan.receiver().register(oper, cx),
+            ReceiverFlavor::List(chan) => chan.receiver().register(oper, cx),
+            ReceiverFlavor::Zero(chan) => chan.receiver().register(oper, cx),
+        }
+    }
+
+    fn unregister(&self, oper: Operation) {
+        match &self.flavor {
+            ReceiverFlavor::Array(chan) => chan.receiver().unregister(oper),
+            ReceiverFlavor::List(chan) => chan.receiver().unregister(oper),
+            ReceiverFlavor::Zero(chan) => chan.receiver().unregister(oper),
+        }
+    }
+
+    fn accept(&self, token: &mut Token, cx: &Context) -> bool {
+        match &self.flavor {
+            ReceiverFlavor::Array(chan) => chan.receiver().accept(token, cx),
+            ReceiverFlavor::List(chan) => chan.receiver().accept(token, cx),
+            ReceiverFlavor::Zero(chan) => chan.receiver().accept(token, cx),
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use super::*;
+use crate::thread;
+use crate::time::Duration;
+
+#[test]
+fn cloned_receivers_share_messages() {
+    let (tx, rx) = channel::<usize>();
+    let rx2 = rx.clone();
+
+    let handles: Vec<_> = [rx, rx2]
+        .into_iter()
+        .map(|rx| {
+            thread::spawn(move || {
+                let mut received = Vec::new();
+                while let Ok(n) = rx.recv() {
+                    received.push(n);
+                }
+                received
+            })
+        })
+        .collect();
+
+    for i in 0..1000 {
+        tx.send(i).unwrap();
+    }
+    drop(tx);
+
+    let mut all: Vec<usize> = handles.into_iter().flat_map(|h| h.join().unwrap()).collect();
+    all.sort();
+    assert_eq!(all, (0..1000).collect::<Vec<_>>());
+}
+
+#[test]
+fn receivers_disconnect_sender() {
+    let (tx, rx) = sync_channel::<i32>(1);
+    let rx2 = rx.clone();
+    drop(rx);
+    tx.send(1).unwrap();
+    drop(rx2);
+    assert_eq!(tx.send(2), Err(SendError(2)));
+}
+
+#[test]
+fn select_recv() {
+    let (tx1, rx1) = channel::<i32>();
+    let (tx2, rx2) = sync_channel::<i32>(1);
+
+    tx2.send(2).unwrap();
+
+    let mut sel = Select::new();
+    let oper1 = sel.recv(&rx1);
+    let oper2 = sel.recv(&rx2);
+
+    let oper = sel.select();
+    assert_eq!(oper.index(), oper2);
+    assert_eq!(oper.recv(&rx2), Ok(2));
+
+    assert!(sel.try_select().is_err());
+
+    tx1.send(1).unwrap();
+    let oper = sel.try_select().unwrap();
+    assert_eq!(oper.index(), oper1);
+    assert_eq!(oper.recv(&rx1), Ok(1));
+}
+
+#[test]
+fn select_send() {
+    let (tx1, rx1) = sync_channel::<i32>(1);
+    let (tx2, _rx2) = sync_channel::<i32>(1);
+
+    tx2.send(0).unwrap();
+
+    let mut sel = Select::new();
+    let oper1 = sel.send(&tx1);
+    sel.send(&tx2);
+
+    let oper = sel.select();
+    assert_eq!(oper.index(), oper1);
+    oper.send(&tx1, 1).unwrap();
+    assert_eq!(rx1.recv(), Ok(1));
+}
+
+#[test]
+fn select_timeout() {
+    let (_tx, rx) = channel::<i32>();
+
+    let mut sel = Select::new();
+    sel.recv(&rx);
+    assert_eq!(sel.select_timeout(Duration::from_millis(10)).unwrap_err(), SelectTimeoutError);
+}
+
+#[test]
+fn select_disconnected() {
+    let (tx, rx) = channel::<i32>();
+    drop(tx);
+
+    let mut sel = Select::new();
+    sel.recv(&rx);
+    let oper = sel.select();
+    assert_eq!(oper.recv(&rx), Err(RecvError));
+}
+
+#[test]
+fn select_remove() {
+    let (tx1, rx1) = channel::<i32>();
+    let (tx2, rx2) = channel::<i32>();
+    drop(tx1);
+
+    let mut sel = Select::new();
+    let oper1 = sel.recv(&rx1);
+    let oper2 = sel.recv(&rx2);
+    sel.remove(oper1);
+
+    tx2.send(2).unwrap();
+    let oper = sel.select();
+    assert_eq!(oper.index(), oper2);
+    assert_eq!(oper.recv(&rx2), Ok(2));
+}
+
+#[test]
+fn select_zero_capacity() {
+    let (tx, rx) = sync_channel::<i32>(0);
+
+    let t = thread::spawn(move || {
+        for i in 0..10 {
+            tx.send(i).unwrap();
+        }
+    });
+
+    for i in 0..10 {
+        let mut sel = Select::new();
+        sel.recv(&rx);
+        let oper = sel.select();
+        assert_eq!(oper.recv(&rx), Ok(i));
+    }
+
+    t.join().unwrap();
+}
+
+#[test]
+fn select_zero_capacity_both_sides() {
+    let (tx, rx) = sync_channel::<i32>(0);
+
+    let t = thread::spawn(move || {
+        let mut sel = Select::new();
+        sel.send(&tx);
+        sel.select().send(&tx, 7).unwrap();
+    });
+
+    let mut sel = Select::new();
+    sel.recv(&rx);
+    assert_eq!(sel.select().recv(&rx), Ok(7));
+
+    t.join().unwrap();
+}
+
+#[test]
+#[should_panic = "dropped `SelectedOperation` without completing the operation"]
+fn select_drop_incomplete() {
+    let (tx, rx) = channel::<i32>();
+    tx.send(1).unwrap();
+
+    let mut sel = Select::new();
+    sel.recv(&rx);
+    let _oper = sel.select();
+}
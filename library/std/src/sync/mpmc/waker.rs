@@ -94,6 +94,20 @@ pub(crate) fn try_select(&mut self) -> Option<Entry> {
         }
     }
 
+    /// Returns `true` if there is an entry which can be selected by the current thread.
+    #[inline]
+    pub(crate) fn can_select(&self) -> bool {
+        if self.selectors.is_empty() {
+            false
+        } else {
+            let thread_id = current_thread_id();
+
+            self.selectors.iter().any(|entry| {
+                entry.cx.thread_id() != thread_id && entry.cx.selected() == Selected::Waiting
+            })
+        }
+    }
+
     /// Notifies all operations waiting to be ready.
     #[inline]
     pub(crate) fn notify(&mut self) {
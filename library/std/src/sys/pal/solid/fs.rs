@@ -12,7 +12,7 @@
     sys::unsupported,
 };
 
-pub use crate::sys_common::fs::try_exists;
+pub use crate::sys_common::fs::{try_exists, DirHandle};
 
 /// A file descriptor.
 #[derive(Clone, Copy)]
@@ -356,6 +356,26 @@ pub fn truncate(&self, _size: u64) -> io::Result<()> {
         unsupported()
     }
 
+    pub fn lock(&self) -> io::Result<()> {
+        unsupported()
+    }
+
+    pub fn lock_shared(&self) -> io::Result<()> {
+        unsupported()
+    }
+
+    pub fn try_lock(&self) -> io::Result<bool> {
+        unsupported()
+    }
+
+    pub fn try_lock_shared(&self) -> io::Result<bool> {
+        unsupported()
+    }
+
+    pub fn unlock(&self) -> io::Result<()> {
+        unsupported()
+    }
+
     pub fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
         unsafe {
             let mut out_num_bytes = MaybeUninit::uninit();
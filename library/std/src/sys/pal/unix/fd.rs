@@ -537,6 +537,30 @@ pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
     pub fn duplicate(&self) -> io::Result<FileDesc> {
         Ok(Self(self.0.try_clone()?))
     }
+
+    /// Opens a pidfd referring to the process with the given pid.
+    #[cfg(target_os = "linux")]
+    pub fn pidfd_open(pid: u32) -> io::Result<FileDesc> {
+        // pidfd_open sets CLOEXEC by default
+        let fd = cvt(unsafe { libc::syscall(libc::SYS_pidfd_open, pid as libc::pid_t, 0) })?;
+        // Safety: the syscall succeeded, so `fd` is a freshly opened, otherwise unowned descriptor.
+        Ok(unsafe { FileDesc::from_raw_fd(fd as RawFd) })
+    }
+
+    /// Sends `signal` to the process referred to by this pidfd.
+    #[cfg(target_os = "linux")]
+    pub fn pidfd_send_signal(&self, signal: libc::c_int) -> io::Result<()> {
+        cvt(unsafe {
+            libc::syscall(
+                libc::SYS_pidfd_send_signal,
+                self.as_raw_fd(),
+                signal,
+                crate::ptr::null::<()>(),
+                0,
+            )
+        })
+        .map(drop)
+    }
 }
 
 impl<'a> Read for &'a FileDesc {
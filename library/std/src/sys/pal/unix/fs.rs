@@ -1312,6 +1312,193 @@ pub fn truncate(&self, size: u64) -> io::Result<()> {
         cvt_r(|| unsafe { ftruncate64(self.as_raw_fd(), size) }).map(drop)
     }
 
+    #[cfg(any(
+        target_os = "freebsd",
+        target_os = "fuchsia",
+        target_os = "linux",
+        target_os = "android",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "tvos",
+        target_os = "watchos",
+        target_os = "visionos",
+    ))]
+    pub fn lock(&self) -> io::Result<()> {
+        cvt_r(|| unsafe { libc::flock(self.as_raw_fd(), libc::LOCK_EX) })?;
+        Ok(())
+    }
+
+    #[cfg(not(any(
+        target_os = "freebsd",
+        target_os = "fuchsia",
+        target_os = "linux",
+        target_os = "android",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "tvos",
+        target_os = "watchos",
+        target_os = "visionos",
+    )))]
+    pub fn lock(&self) -> io::Result<()> {
+        Err(io::const_io_error!(io::ErrorKind::Unsupported, "lock() not supported"))
+    }
+
+    #[cfg(any(
+        target_os = "freebsd",
+        target_os = "fuchsia",
+        target_os = "linux",
+        target_os = "android",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "tvos",
+        target_os = "watchos",
+        target_os = "visionos",
+    ))]
+    pub fn lock_shared(&self) -> io::Result<()> {
+        cvt_r(|| unsafe { libc::flock(self.as_raw_fd(), libc::LOCK_SH) })?;
+        Ok(())
+    }
+
+    #[cfg(not(any(
+        target_os = "freebsd",
+        target_os = "fuchsia",
+        target_os = "linux",
+        target_os = "android",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "tvos",
+        target_os = "watchos",
+        target_os = "visionos",
+    )))]
+    pub fn lock_shared(&self) -> io::Result<()> {
+        Err(io::const_io_error!(io::ErrorKind::Unsupported, "lock_shared() not supported"))
+    }
+
+    #[cfg(any(
+        target_os = "freebsd",
+        target_os = "fuchsia",
+        target_os = "linux",
+        target_os = "android",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "tvos",
+        target_os = "watchos",
+        target_os = "visionos",
+    ))]
+    pub fn try_lock(&self) -> io::Result<bool> {
+        let result = cvt(unsafe { libc::flock(self.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) });
+        if let Err(ref err) = result {
+            if err.kind() == io::ErrorKind::WouldBlock {
+                return Ok(false);
+            }
+        }
+        result?;
+        Ok(true)
+    }
+
+    #[cfg(not(any(
+        target_os = "freebsd",
+        target_os = "fuchsia",
+        target_os = "linux",
+        target_os = "android",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "tvos",
+        target_os = "watchos",
+        target_os = "visionos",
+    )))]
+    pub fn try_lock(&self) -> io::Result<bool> {
+        Err(io::const_io_error!(io::ErrorKind::Unsupported, "try_lock() not supported"))
+    }
+
+    #[cfg(any(
+        target_os = "freebsd",
+        target_os = "fuchsia",
+        target_os = "linux",
+        target_os = "android",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "tvos",
+        target_os = "watchos",
+        target_os = "visionos",
+    ))]
+    pub fn try_lock_shared(&self) -> io::Result<bool> {
+        let result = cvt(unsafe { libc::flock(self.as_raw_fd(), libc::LOCK_SH | libc::LOCK_NB) });
+        if let Err(ref err) = result {
+            if err.kind() == io::ErrorKind::WouldBlock {
+                return Ok(false);
+            }
+        }
+        result?;
+        Ok(true)
+    }
+
+    #[cfg(not(any(
+        target_os = "freebsd",
+        target_os = "fuchsia",
+        target_os = "linux",
+        target_os = "android",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "tvos",
+        target_os = "watchos",
+        target_os = "visionos",
+    )))]
+    pub fn try_lock_shared(&self) -> io::Result<bool> {
+        Err(io::const_io_error!(io::ErrorKind::Unsupported, "try_lock_shared() not supported"))
+    }
+
+    #[cfg(any(
+        target_os = "freebsd",
+        target_os = "fuchsia",
+        target_os = "linux",
+        target_os = "android",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "tvos",
+        target_os = "watchos",
+        target_os = "visionos",
+    ))]
+    pub fn unlock(&self) -> io::Result<()> {
+        cvt(unsafe { libc::flock(self.as_raw_fd(), libc::LOCK_UN) })?;
+        Ok(())
+    }
+
+    #[cfg(not(any(
+        target_os = "freebsd",
+        target_os = "fuchsia",
+        target_os = "linux",
+        target_os = "android",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "tvos",
+        target_os = "watchos",
+        target_os = "visionos",
+    )))]
+    pub fn unlock(&self) -> io::Result<()> {
+        Err(io::const_io_error!(io::ErrorKind::Unsupported, "unlock() not supported"))
+    }
+
     pub fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
         self.0.read(buf)
     }
@@ -2086,6 +2273,193 @@ pub fn chroot(dir: &Path) -> io::Result<()> {
     run_path_with_cstr(dir, &|dir| cvt(unsafe { libc::chroot(dir.as_ptr()) }).map(|_| ()))
 }
 
+pub use dir_impl::DirHandle;
+
+// Fallback for targets without a usable openat() family of syscalls
+#[cfg(any(
+    target_os = "redox",
+    target_os = "espidf",
+    target_os = "horizon",
+    target_os = "vita",
+    target_os = "nto",
+    target_os = "l4re",
+    target_os = "vxworks",
+    miri
+))]
+mod dir_impl {
+    pub use crate::sys_common::fs::DirHandle;
+}
+
+// Directory handle backed by an O_DIRECTORY fd, resolving every path with the *at() syscalls
+#[cfg(not(any(
+    target_os = "redox",
+    target_os = "espidf",
+    target_os = "horizon",
+    target_os = "vita",
+    target_os = "nto",
+    target_os = "l4re",
+    target_os = "vxworks",
+    miri
+)))]
+mod dir_impl {
+    use super::{Dir, File, FileAttr, InnerReadDir, OpenOptions, ReadDir};
+    use crate::ffi::CStr;
+    use crate::fmt;
+    use crate::io;
+    use crate::mem;
+    use crate::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd};
+    use crate::os::unix::prelude::{OwnedFd, RawFd};
+    use crate::path::{Path, PathBuf};
+    use crate::sys::common::small_c_string::run_path_with_cstr;
+    use crate::sys::fd::FileDesc;
+    use crate::sys::{cvt, cvt_r};
+
+    use libc::c_int;
+
+    #[cfg(any(
+        all(target_os = "linux", not(target_env = "musl")),
+        target_os = "emscripten",
+        target_os = "android",
+        target_os = "hurd"
+    ))]
+    use super::{fstatat64, stat64};
+    #[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+    use libc::openat;
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    use libc::openat64 as openat;
+    #[cfg(not(any(
+        all(target_os = "linux", not(target_env = "musl")),
+        target_os = "emscripten",
+        target_os = "android",
+        target_os = "hurd"
+    )))]
+    use libc::{fstatat as fstatat64, stat as stat64};
+
+    pub struct DirHandle {
+        fd: OwnedFd,
+        // only used for `Debug` and for `DirEntry::path`, never to resolve anything
+        root: PathBuf,
+    }
+
+    fn openat_dir(dir_fd: RawFd, p: &CStr) -> io::Result<OwnedFd> {
+        let fd = cvt_r(|| unsafe {
+            openat(dir_fd, p.as_ptr(), libc::O_CLOEXEC | libc::O_RDONLY | libc::O_DIRECTORY)
+        })?;
+        Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+    }
+
+    impl DirHandle {
+        pub fn open(path: &Path) -> io::Result<DirHandle> {
+            let fd = run_path_with_cstr(path, &|p| openat_dir(libc::AT_FDCWD, p))?;
+            Ok(DirHandle { fd, root: path.to_path_buf() })
+        }
+
+        pub fn open_file(&self, path: &Path, opts: &OpenOptions) -> io::Result<File> {
+            run_path_with_cstr(path, &|path| {
+                let flags = libc::O_CLOEXEC
+                    | opts.get_access_mode()?
+                    | opts.get_creation_mode()?
+                    | (opts.custom_flags as c_int & !libc::O_ACCMODE);
+                // See `File::open_c` for why the mode is passed as `c_int`.
+                let fd = cvt_r(|| unsafe {
+                    openat(self.fd.as_raw_fd(), path.as_ptr(), flags, opts.mode as c_int)
+                })?;
+                Ok(File(unsafe { FileDesc::from_raw_fd(fd) }))
+            })
+        }
+
+        pub fn open_dir(&self, path: &Path) -> io::Result<DirHandle> {
+            let fd = run_path_with_cstr(path, &|p| openat_dir(self.fd.as_raw_fd(), p))?;
+            Ok(DirHandle { fd, root: self.root.join(path) })
+        }
+
+        pub fn stat(&self, path: &Path) -> io::Result<FileAttr> {
+            self.fstatat(path, 0)
+        }
+
+        pub fn lstat(&self, path: &Path) -> io::Result<FileAttr> {
+            self.fstatat(path, libc::AT_SYMLINK_NOFOLLOW)
+        }
+
+        fn fstatat(&self, path: &Path, flags: c_int) -> io::Result<FileAttr> {
+            let fd = self.fd.as_raw_fd();
+            run_path_with_cstr(path, &|p| {
+                cfg_has_statx! {
+                    if let Some(ret) = unsafe { super::try_statx(
+                        fd,
+                        p.as_ptr(),
+                        flags | libc::AT_STATX_SYNC_AS_STAT,
+                        libc::STATX_ALL,
+                    ) } {
+                        return ret;
+                    }
+                }
+
+                let mut stat: stat64 = unsafe { mem::zeroed() };
+                cvt(unsafe { fstatat64(fd, p.as_ptr(), &mut stat, flags) })?;
+                Ok(FileAttr::from_stat64(stat))
+            })
+        }
+
+        pub fn mkdir(&self, path: &Path) -> io::Result<()> {
+            run_path_with_cstr(path, &|p| {
+                cvt(unsafe { libc::mkdirat(self.fd.as_raw_fd(), p.as_ptr(), 0o777) }).map(|_| ())
+            })
+        }
+
+        pub fn unlink(&self, path: &Path) -> io::Result<()> {
+            run_path_with_cstr(path, &|p| {
+                cvt(unsafe { libc::unlinkat(self.fd.as_raw_fd(), p.as_ptr(), 0) }).map(|_| ())
+            })
+        }
+
+        pub fn rmdir(&self, path: &Path) -> io::Result<()> {
+            run_path_with_cstr(path, &|p| {
+                cvt(unsafe { libc::unlinkat(self.fd.as_raw_fd(), p.as_ptr(), libc::AT_REMOVEDIR) })
+                    .map(|_| ())
+            })
+        }
+
+        pub fn rename(&self, from: &Path, to_dir: &DirHandle, to: &Path) -> io::Result<()> {
+            run_path_with_cstr(from, &|from| {
+                run_path_with_cstr(to, &|to| {
+                    cvt(unsafe {
+                        libc::renameat(
+                            self.fd.as_raw_fd(),
+                            from.as_ptr(),
+                            to_dir.fd.as_raw_fd(),
+                            to.as_ptr(),
+                        )
+                    })
+                    .map(|_| ())
+                })
+            })
+        }
+
+        pub fn readdir(&self) -> io::Result<ReadDir> {
+            // Reopen "." instead of duplicating the fd: a dup would share the file offset, so a
+            // second iteration would start where the first one stopped.
+            let fd = openat_dir(self.fd.as_raw_fd(), c".")?;
+            let ptr = unsafe { libc::fdopendir(fd.as_raw_fd()) };
+            if ptr.is_null() {
+                return Err(io::Error::last_os_error());
+            }
+            // file descriptor is automatically closed by libc::closedir() now, so give up ownership
+            let _ = fd.into_raw_fd();
+            Ok(ReadDir::new(InnerReadDir { dirp: Dir(ptr), root: self.root.clone() }))
+        }
+    }
+
+    impl fmt::Debug for DirHandle {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Dir")
+                .field("fd", &self.fd.as_raw_fd())
+                .field("path", &self.root)
+                .finish()
+        }
+    }
+}
+
 pub use remove_dir_impl::remove_dir_all;
 
 // Fallback for REDOX, ESP-ID, Horizon, Vita and Miri
@@ -28,6 +28,13 @@
 pub mod pipe;
 pub mod process;
 pub mod rand;
+#[cfg(not(any(
+    target_os = "espidf",
+    target_os = "horizon",
+    target_os = "vita",
+    target_os = "vxworks"
+)))]
+pub mod signal;
 pub mod stack_overflow;
 pub mod stdio;
 pub mod thread;
@@ -108,6 +108,9 @@ pub struct Command {
     #[cfg(target_os = "linux")]
     create_pidfd: bool,
     pgroup: Option<pid_t>,
+    setsid: bool,
+    rlimits: Vec<(c_int, u64, u64)>,
+    controlling_tty: Option<FileDesc>,
 }
 
 // Create a new type for argv, so that we can make it `Send` and `Sync`
@@ -199,6 +202,9 @@ pub fn new(program: &OsStr) -> Command {
             stdout: None,
             stderr: None,
             pgroup: None,
+            setsid: false,
+            rlimits: Vec::new(),
+            controlling_tty: None,
         }
     }
 
@@ -224,6 +230,9 @@ pub fn new(program: &OsStr) -> Command {
             stderr: None,
             create_pidfd: false,
             pgroup: None,
+            setsid: false,
+            rlimits: Vec::new(),
+            controlling_tty: None,
         }
     }
 
@@ -262,6 +271,17 @@ pub fn groups(&mut self, groups: &[gid_t]) {
     pub fn pgroup(&mut self, pgroup: pid_t) {
         self.pgroup = Some(pgroup);
     }
+    pub fn setsid(&mut self, setsid: bool) {
+        self.setsid = setsid;
+    }
+    pub fn setrlimit(&mut self, resource: c_int, soft: u64, hard: u64) {
+        // A later call for the same resource overrides an earlier one.
+        self.rlimits.retain(|&(r, _, _)| r != resource);
+        self.rlimits.push((resource, soft, hard));
+    }
+    pub fn controlling_tty(&mut self, tty: FileDesc) {
+        self.controlling_tty = Some(tty);
+    }
 
     #[cfg(target_os = "linux")]
     pub fn create_pidfd(&mut self, val: bool) {
@@ -334,6 +354,19 @@ pub fn get_groups(&self) -> Option<&[gid_t]> {
     pub fn get_pgroup(&self) -> Option<pid_t> {
         self.pgroup
     }
+    #[allow(dead_code)]
+    pub fn get_setsid(&self) -> bool {
+        // Acquiring a controlling terminal requires a new session.
+        self.setsid || self.controlling_tty.is_some()
+    }
+    #[allow(dead_code)]
+    pub fn get_rlimits(&self) -> &[(c_int, u64, u64)] {
+        &self.rlimits
+    }
+    #[allow(dead_code)]
+    pub fn get_controlling_tty(&self) -> Option<c_int> {
+        self.controlling_tty.as_ref().map(|fd| fd.as_raw_fd())
+    }
 
     pub fn get_closures(&mut self) -> &mut Vec<Box<dyn FnMut() -> io::Result<()> + Send + Sync>> {
         &mut self.closures
@@ -575,6 +608,15 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             if self.pgroup.is_some() {
                 debug_command.field("pgroup", &self.pgroup);
             }
+            if self.setsid {
+                debug_command.field("setsid", &self.setsid);
+            }
+            if !self.rlimits.is_empty() {
+                debug_command.field("rlimits", &self.rlimits);
+            }
+            if self.controlling_tty.is_some() {
+                debug_command.field("controlling_tty", &self.controlling_tty);
+            }
 
             #[cfg(target_os = "linux")]
             {
@@ -6,10 +6,14 @@
 use crate::sys::cvt;
 use crate::sys::process::process_common::*;
 
+#[cfg(target_os = "linux")]
+use crate::cmp;
 #[cfg(target_os = "linux")]
 use crate::os::linux::process::PidFd;
 #[cfg(target_os = "linux")]
 use crate::os::unix::io::AsRawFd;
+#[cfg(target_os = "linux")]
+use crate::time::{Duration, Instant};
 
 #[cfg(target_os = "vxworks")]
 use libc::RTP_ID as pid_t;
@@ -306,6 +310,55 @@ unsafe fn do_exec(
             cvt_r(|| libc::dup2(fd, libc::STDERR_FILENO))?;
         }
 
+        if self.get_setsid() {
+            cvt(libc::setsid())?;
+        }
+        if let Some(tty) = self.get_controlling_tty() {
+            #[cfg(any(
+                target_os = "android",
+                target_os = "dragonfly",
+                target_os = "freebsd",
+                target_os = "hurd",
+                target_os = "illumos",
+                target_os = "ios",
+                target_os = "linux",
+                target_os = "macos",
+                target_os = "netbsd",
+                target_os = "openbsd",
+                target_os = "solaris",
+            ))]
+            cvt(libc::ioctl(tty, libc::TIOCSCTTY as _, 0))?;
+            #[cfg(not(any(
+                target_os = "android",
+                target_os = "dragonfly",
+                target_os = "freebsd",
+                target_os = "hurd",
+                target_os = "illumos",
+                target_os = "ios",
+                target_os = "linux",
+                target_os = "macos",
+                target_os = "netbsd",
+                target_os = "openbsd",
+                target_os = "solaris",
+            )))]
+            {
+                let _ = tty;
+                return Err(io::const_io_error!(
+                    ErrorKind::Unsupported,
+                    "setting a controlling terminal is not supported on this platform",
+                ));
+            }
+        }
+
+        // Limits are applied before dropping privileges below, since raising a
+        // hard limit may require them.
+        for &(resource, soft, hard) in self.get_rlimits() {
+            // A limit that is too large for `rlim_t` is no limit at all.
+            let to_rlim = |value: u64| value.try_into().unwrap_or(libc::RLIM_INFINITY);
+            let limit = libc::rlimit { rlim_cur: to_rlim(soft), rlim_max: to_rlim(hard) };
+            cvt(libc::setrlimit(resource as _, &limit))?;
+        }
+
         #[cfg(not(target_os = "l4re"))]
         {
             if let Some(_g) = self.get_groups() {
@@ -463,10 +516,26 @@ fn posix_spawn(
             || !self.get_closures().is_empty()
             || self.get_groups().is_some()
             || self.get_create_pidfd()
+            || !self.get_rlimits().is_empty()
+            || self.get_controlling_tty().is_some()
         {
             return Ok(None);
         }
 
+        // POSIX_SPAWN_SETSID is a glibc 2.26+ extension, so only use it there.
+        // The value is part of glibc's ABI.
+        #[cfg(all(target_os = "linux", target_env = "gnu"))]
+        const POSIX_SPAWN_SETSID: c_int = 0x80;
+        if self.get_setsid() {
+            #[cfg(all(target_os = "linux", target_env = "gnu"))]
+            match sys::os::glibc_version() {
+                Some(version) if version >= (2, 26) => {}
+                _ => return Ok(None),
+            }
+            #[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+            return Ok(None);
+        }
+
         // Only glibc 2.24+ posix_spawn() supports returning ENOENT directly.
         #[cfg(all(target_os = "linux", target_env = "gnu"))]
         {
@@ -614,6 +683,11 @@ fn drop(&mut self) {
                 cvt_nz(libc::posix_spawnattr_setpgroup(attrs.0.as_mut_ptr(), pgroup))?;
             }
 
+            #[cfg(all(target_os = "linux", target_env = "gnu"))]
+            if self.get_setsid() {
+                flags |= POSIX_SPAWN_SETSID;
+            }
+
             // Inherit the signal mask from this process rather than resetting it (i.e. do not call
             // posix_spawnattr_setsigmask).
 
@@ -819,17 +893,9 @@ pub fn kill(&mut self) -> io::Result<()> {
         }
         #[cfg(target_os = "linux")]
         if let Some(pid_fd) = self.pidfd.as_ref() {
+            use crate::sys_common::AsInner;
             // pidfd_send_signal predates pidfd_open. so if we were able to get an fd then sending signals will work too
-            return cvt(unsafe {
-                libc::syscall(
-                    libc::SYS_pidfd_send_signal,
-                    pid_fd.as_raw_fd(),
-                    libc::SIGKILL,
-                    crate::ptr::null::<()>(),
-                    0,
-                )
-            })
-            .map(drop);
+            return pid_fd.as_inner().pidfd_send_signal(libc::SIGKILL);
         }
         cvt(unsafe { libc::kill(self.pid, libc::SIGKILL) }).map(drop)
     }
@@ -888,6 +954,53 @@ pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
             Ok(Some(ExitStatus::new(status)))
         }
     }
+
+    #[cfg(target_os = "linux")]
+    pub fn wait_timeout(&mut self, timeout: Duration) -> io::Result<Option<ExitStatus>> {
+        use crate::sys_common::FromInner;
+
+        if let Some(status) = self.try_wait()? {
+            return Ok(Some(status));
+        }
+
+        // If the pidfd was never created or has been taken, open a temporary one. The pid cannot
+        // have been recycled yet, since the child has not been reaped.
+        let temp_pidfd;
+        let pid_fd = match self.pidfd.as_ref() {
+            Some(pid_fd) => pid_fd,
+            None => {
+                temp_pidfd = PidFd::from_inner(sys::fd::FileDesc::pidfd_open(self.pid as u32)?);
+                &temp_pidfd
+            }
+        };
+
+        // A pidfd becomes readable once the process has terminated.
+        let mut pollfd = libc::pollfd { fd: pid_fd.as_raw_fd(), events: libc::POLLIN, revents: 0 };
+        let start = Instant::now();
+        loop {
+            let elapsed = start.elapsed();
+            if elapsed >= timeout {
+                break;
+            }
+
+            // Round up, so that a sub-millisecond remainder doesn't turn into a busy loop.
+            let remaining = (timeout - elapsed).as_nanos().div_ceil(1_000_000);
+            let remaining = cmp::min(remaining, c_int::MAX as u128) as c_int;
+
+            match unsafe { libc::poll(&mut pollfd, 1, remaining) } {
+                -1 => {
+                    let err = io::Error::last_os_error();
+                    if !err.is_interrupted() {
+                        return Err(err);
+                    }
+                }
+                0 => {}
+                _ => break,
+            }
+        }
+
+        self.try_wait()
+    }
 }
 
 /// Unix exit statuses
@@ -1125,6 +1238,15 @@ fn take_pidfd(&mut self) -> io::Result<PidFd> {
             .take()
             .ok_or_else(|| Error::new(ErrorKind::Uncategorized, "No pidfd was created."))
     }
+
+    fn wait_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> io::Result<Option<crate::process::ExitStatus>> {
+        use crate::sys_common::FromInner;
+        drop(self.stdin.take());
+        Ok(self.handle.wait_timeout(timeout)?.map(crate::process::ExitStatus::from_inner))
+    }
 }
 
 #[cfg(test)]
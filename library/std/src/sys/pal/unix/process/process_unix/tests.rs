@@ -114,3 +114,48 @@ fn test_command_pidfd() {
         .pidfd()
         .expect_err("pidfd should not have been created");
 }
+
+#[test]
+#[cfg(target_os = "linux")] // pidfds are a linux-specific concept
+fn test_pidfd_open_and_wait_timeout() {
+    use crate::assert_matches::assert_matches;
+    use crate::os::linux::process::{ChildExt, PidFd};
+    use crate::process::Command;
+    use crate::time::Duration;
+
+    // Skip on kernels without pidfd_open.
+    let Ok(_) = PidFd::open(crate::process::id()) else { return };
+
+    let mut child = Command::new("sleep").arg("1000").spawn().unwrap();
+    assert_matches!(child.wait_timeout(Duration::from_millis(10)), Ok(None));
+
+    let pidfd = PidFd::open(child.id()).unwrap();
+    pidfd.send_signal(libc::SIGTERM).unwrap();
+
+    let status = child.wait_timeout(Duration::from_secs(60)).unwrap().expect("child did not exit");
+    assert_eq!(status.signal(), Some(libc::SIGTERM));
+    assert_eq!(child.wait().unwrap().signal(), Some(libc::SIGTERM));
+}
+
+#[test]
+#[cfg_attr(any(target_os = "vxworks", target_os = "emscripten"), ignore)]
+fn test_command_setsid() {
+    let mut child = Command::new("sleep").arg("1000").setsid(true).spawn().unwrap();
+    let sid = unsafe { libc::getsid(child.id() as libc::pid_t) };
+    child.kill().unwrap();
+    child.wait().unwrap();
+    assert_eq!(sid, child.id() as libc::pid_t);
+}
+
+#[test]
+#[cfg_attr(any(target_os = "vxworks", target_os = "emscripten"), ignore)]
+fn test_command_setrlimit() {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg("ulimit -n")
+        .setrlimit(libc::RLIMIT_NOFILE as i32, 42, 42)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "42");
+}
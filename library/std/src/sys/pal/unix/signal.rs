@@ -0,0 +1,292 @@
+//! Self-pipe based signal delivery for `std::os::unix::signal`.
+//!
+//! The first time a signal is registered, a process-wide handler is installed
+//! for it. The handler writes the signal number as a single byte to the write
+//! end of every pipe subscribed to that signal, then chains to whatever handler
+//! was installed before it. Subscriptions live in a fixed-size table of atomics
+//! so the handler never has to take a lock or allocate.
+//!
+//! The synchronous fault signals are refused: `SIGSEGV` and `SIGBUS` belong to
+//! the stack overflow handler in `stack_overflow.rs`, and catching `SIGILL` or
+//! `SIGFPE` and returning would just re-execute the faulting instruction.
+
+use crate::io;
+use crate::mem;
+use crate::os::unix::io::{AsRawFd, BorrowedFd, OwnedFd, RawFd};
+use crate::ptr;
+use crate::sync::atomic::{AtomicBool, AtomicI32, AtomicU32, Ordering};
+use crate::sync::Mutex;
+use crate::sys::cvt;
+use crate::sys::os::{errno, set_errno};
+use crate::sys::pipe::anon_pipe;
+use crate::sys_common::IntoInner;
+
+use libc::{c_int, c_void, sigaction, sighandler_t, siginfo_t};
+
+/// One more than the highest signal number we can subscribe to.
+///
+/// A `sigset_t` has a bit for every signal of the platform, e.g. 64 on most
+/// Linux architectures but 128 on MIPS, and often leaves room for more. Signals
+/// are sent through the pipes as a single byte, which bounds this as well.
+const NSIG: usize = {
+    let sigset_bits = mem::size_of::<libc::sigset_t>() * 8;
+    if sigset_bits < u8::MAX as usize { sigset_bits + 1 } else { u8::MAX as usize + 1 }
+};
+
+/// Maximum number of pipes that can be subscribed to a single signal.
+const MAX_SUBSCRIBERS: usize = 16;
+
+const NO_FD: AtomicI32 = AtomicI32::new(-1);
+const NO_FDS: [AtomicI32; MAX_SUBSCRIBERS] = [NO_FD; MAX_SUBSCRIBERS];
+
+/// Write ends of the pipes subscribed to each signal, or -1 for a free slot.
+static SUBSCRIBERS: [[AtomicI32; MAX_SUBSCRIBERS]; NSIG] = [NO_FDS; NSIG];
+
+/// Number of handler invocations currently reading `SUBSCRIBERS`.
+///
+/// `unregister` waits for this to drop to zero before returning, so that a
+/// handler never writes to a pipe that has been closed and possibly reused.
+/// Where std has futexes, the last handler to finish wakes the waiters.
+static HANDLERS_RUNNING: AtomicU32 = AtomicU32::new(0);
+
+const NOT_INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// Whether our handler has been installed for each signal.
+static INSTALLED: [AtomicBool; NSIG] = [NOT_INSTALLED; NSIG];
+
+const NO_ACTION: mem::MaybeUninit<sigaction> = mem::MaybeUninit::uninit();
+
+/// The actions that were in place before our handler was installed.
+///
+/// An entry is written once, under `INSTALL_LOCK`, before the corresponding
+/// `INSTALLED` flag is set and before our handler is installed for that
+/// signal. It is never modified afterwards.
+static mut PREVIOUS: [mem::MaybeUninit<sigaction>; NSIG] = [NO_ACTION; NSIG];
+
+static INSTALL_LOCK: Mutex<()> = Mutex::new(());
+
+/// Creates the pipe a `Signals` reads from, with both ends non-blocking.
+///
+/// The write end must never block, since it is written from a signal handler.
+/// The read end is made non-blocking so that it can be drained without
+/// waiting; blocking reads go through `poll` instead.
+pub fn signal_pipe() -> io::Result<(OwnedFd, OwnedFd)> {
+    let (read, write) = anon_pipe()?;
+    let read = read.into_inner();
+    let write = write.into_inner();
+    read.set_nonblocking(true)?;
+    write.set_nonblocking(true)?;
+    Ok((read.into_inner(), write.into_inner()))
+}
+
+fn check_signal(signal: c_int) -> io::Result<usize> {
+    match signal {
+        libc::SIGKILL | libc::SIGSTOP => Err(io::const_io_error!(
+            io::ErrorKind::InvalidInput,
+            "SIGKILL and SIGSTOP cannot be caught",
+        )),
+        libc::SIGSEGV | libc::SIGBUS | libc::SIGILL | libc::SIGFPE => Err(io::const_io_error!(
+            io::ErrorKind::InvalidInput,
+            "synchronous fault signals cannot be observed",
+        )),
+        _ if signal > 0 && (signal as usize) < NSIG => Ok(signal as usize),
+        _ => Err(io::const_io_error!(io::ErrorKind::InvalidInput, "invalid signal number")),
+    }
+}
+
+/// Subscribes the write end of a signal pipe to `signal`.
+///
+/// Registering the same pipe for a signal more than once has no effect.
+pub fn register(signal: c_int, pipe: BorrowedFd<'_>) -> io::Result<()> {
+    let index = check_signal(signal)?;
+    let fd = pipe.as_raw_fd();
+
+    let slots = &SUBSCRIBERS[index];
+    if !slots.iter().any(|slot| slot.load(Ordering::Relaxed) == fd) {
+        let claimed = slots
+            .iter()
+            .any(|slot| slot.compare_exchange(-1, fd, Ordering::AcqRel, Ordering::Relaxed).is_ok());
+        if !claimed {
+            return Err(io::const_io_error!(
+                io::ErrorKind::Uncategorized,
+                "too many subscribers for this signal",
+            ));
+        }
+    }
+
+    if let Err(e) = install(signal, index) {
+        clear(index, fd);
+        wait_for_handlers();
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Removes every subscription of the write end of a signal pipe.
+///
+/// When this returns, no signal handler will write to `pipe` anymore, so it
+/// is safe to close it.
+pub fn unregister(pipe: BorrowedFd<'_>) {
+    let fd = pipe.as_raw_fd();
+    for index in 1..NSIG {
+        clear(index, fd);
+    }
+    wait_for_handlers();
+}
+
+fn clear(index: usize, fd: RawFd) {
+    for slot in &SUBSCRIBERS[index] {
+        let _ = slot.compare_exchange(fd, -1, Ordering::SeqCst, Ordering::Relaxed);
+    }
+}
+
+/// Waits for running handlers to finish, since one that loaded a subscriber
+/// just before it was cleared may still be about to write to it.
+fn wait_for_handlers() {
+    loop {
+        let running = HANDLERS_RUNNING.load(Ordering::SeqCst);
+        if running == 0 {
+            return;
+        }
+        wait_for_handlers_change(running);
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        all(target_os = "emscripten", target_feature = "atomics"),
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "dragonfly",
+        target_os = "fuchsia",
+    ))] {
+        fn wait_for_handlers_change(running: u32) {
+            crate::sys::futex::futex_wait(&HANDLERS_RUNNING, running, None);
+        }
+
+        /// Wakes the threads in `unregister`. Waking is a plain system call, so
+        /// this is async-signal-safe.
+        fn handlers_finished() {
+            crate::sys::futex::futex_wake_all(&HANDLERS_RUNNING);
+        }
+    } else {
+        // Without futexes, give the CPU to the handlers until they are done,
+        // which is rarely more than a few `write`s away.
+        fn wait_for_handlers_change(_running: u32) {
+            crate::thread::yield_now();
+        }
+
+        fn handlers_finished() {}
+    }
+}
+
+fn install(signal: c_int, index: usize) -> io::Result<()> {
+    if INSTALLED[index].load(Ordering::Acquire) {
+        return Ok(());
+    }
+
+    let _guard = INSTALL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    if INSTALLED[index].load(Ordering::Acquire) {
+        return Ok(());
+    }
+
+    unsafe {
+        let previous = ptr::addr_of_mut!(PREVIOUS[index]);
+        cvt(sigaction(signal, ptr::null(), (*previous).as_mut_ptr()))?;
+        INSTALLED[index].store(true, Ordering::Release);
+
+        let mut action: sigaction = mem::zeroed();
+        action.sa_sigaction = handler as sighandler_t;
+        // SA_ONSTACK lets the handler run on the alternate signal stack that
+        // threads spawned by std set up for the stack overflow handler.
+        action.sa_flags = libc::SA_SIGINFO | libc::SA_RESTART | libc::SA_ONSTACK;
+        libc::sigemptyset(&mut action.sa_mask);
+        if let Err(e) = cvt(sigaction(signal, &action, ptr::null_mut())) {
+            INSTALLED[index].store(false, Ordering::Release);
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+unsafe extern "C" fn handler(signum: c_int, info: *mut siginfo_t, context: *mut c_void) {
+    // `write` may clobber errno, which the interrupted code could be about to
+    // inspect.
+    let saved_errno = errno();
+
+    let index = signum as usize;
+    if index < NSIG {
+        HANDLERS_RUNNING.fetch_add(1, Ordering::SeqCst);
+        let byte = signum as u8;
+        for slot in &SUBSCRIBERS[index] {
+            let fd = slot.load(Ordering::SeqCst);
+            if fd >= 0 {
+                // If the pipe is full, the reader already has a wakeup pending
+                // and this delivery is coalesced, just like the signal itself.
+                libc::write(fd, ptr::addr_of!(byte).cast::<c_void>(), 1);
+            }
+        }
+        if HANDLERS_RUNNING.fetch_sub(1, Ordering::SeqCst) == 1 {
+            handlers_finished();
+        }
+
+        if INSTALLED[index].load(Ordering::Acquire) {
+            chain(signum, info, context, &*ptr::addr_of!(PREVIOUS[index]).cast::<sigaction>());
+        }
+    }
+
+    set_errno(saved_errno);
+}
+
+/// Calls the handler that was installed before ours, if there was one.
+unsafe fn chain(signum: c_int, info: *mut siginfo_t, context: *mut c_void, prev: &sigaction) {
+    let f = prev.sa_sigaction;
+    if f == libc::SIG_DFL || f == libc::SIG_IGN {
+        return;
+    }
+    if prev.sa_flags & libc::SA_SIGINFO != 0 {
+        let f: extern "C" fn(c_int, *mut siginfo_t, *mut c_void) = mem::transmute(f);
+        f(signum, info, context);
+    } else {
+        let f: extern "C" fn(c_int) = mem::transmute(f);
+        f(signum);
+    }
+}
+
+/// Waits until the read end of a signal pipe is readable.
+pub fn wait_readable(pipe: BorrowedFd<'_>) -> io::Result<()> {
+    let mut pollfd = libc::pollfd { fd: pipe.as_raw_fd(), events: libc::POLLIN, revents: 0 };
+    loop {
+        match unsafe { libc::poll(&mut pollfd, 1, -1) } {
+            -1 => {
+                let err = io::Error::last_os_error();
+                if !err.is_interrupted() {
+                    return Err(err);
+                }
+            }
+            _ => return Ok(()),
+        }
+    }
+}
+
+/// Reads the next delivered signal from a signal pipe without blocking.
+pub fn try_read(pipe: BorrowedFd<'_>) -> io::Result<Option<c_int>> {
+    let mut byte = 0u8;
+    loop {
+        let ret = unsafe { libc::read(pipe.as_raw_fd(), ptr::addr_of_mut!(byte).cast(), 1) };
+        match ret {
+            1 => return Ok(Some(byte as c_int)),
+            -1 => {
+                let err = io::Error::last_os_error();
+                match err.kind() {
+                    io::ErrorKind::WouldBlock => return Ok(None),
+                    io::ErrorKind::Interrupted => {}
+                    _ => return Err(err),
+                }
+            }
+            _ => return Ok(None),
+        }
+    }
+}
@@ -6,6 +6,8 @@
 use crate::sys::time::SystemTime;
 use crate::sys::unsupported;
 
+pub use crate::sys_common::fs::DirHandle;
+
 pub struct File(!);
 
 pub struct FileAttr(!);
@@ -202,6 +204,26 @@ pub fn truncate(&self, _size: u64) -> io::Result<()> {
         self.0
     }
 
+    pub fn lock(&self) -> io::Result<()> {
+        self.0
+    }
+
+    pub fn lock_shared(&self) -> io::Result<()> {
+        self.0
+    }
+
+    pub fn try_lock(&self) -> io::Result<bool> {
+        self.0
+    }
+
+    pub fn try_lock_shared(&self) -> io::Result<bool> {
+        self.0
+    }
+
+    pub fn unlock(&self) -> io::Result<()> {
+        self.0
+    }
+
     pub fn read(&self, _buf: &mut [u8]) -> io::Result<usize> {
         self.0
     }
@@ -21,6 +21,8 @@
 use super::{api, to_u16s, IoResult};
 use crate::sys::path::maybe_verbatim;
 
+pub use crate::sys_common::fs::DirHandle;
+
 pub struct File {
     handle: Handle,
 }
@@ -354,6 +356,63 @@ pub fn truncate(&self, size: u64) -> io::Result<()> {
         api::set_file_information_by_handle(self.handle.as_raw_handle(), &info).io_result()
     }
 
+    fn lock_ex(&self, flags: c::LOCK_FILE_FLAGS) -> io::Result<()> {
+        // If the file was opened with `FILE_FLAG_OVERLAPPED`, `LockFileEx` returns
+        // `ERROR_IO_PENDING` instead of waiting for the lock, and signals the event
+        // once it is acquired. Without an event of our own it would signal the file
+        // handle, which any other I/O on the file does as well.
+        let event = Handle::new_event(true, false)?;
+        let mut overlapped: c::OVERLAPPED = unsafe { mem::zeroed() };
+        overlapped.hEvent = event.as_raw_handle();
+        // Lock the whole file, whatever its current or future size.
+        let res = cvt(unsafe {
+            c::LockFileEx(
+                self.handle.as_raw_handle(),
+                flags,
+                0,
+                u32::MAX,
+                u32::MAX,
+                &mut overlapped,
+            )
+        });
+        match res {
+            Ok(_) => Ok(()),
+            Err(e) if e.raw_os_error() == Some(c::ERROR_IO_PENDING as i32) => {
+                self.handle.overlapped_result(&mut overlapped, true).map(drop)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn try_lock_ex(&self, flags: c::LOCK_FILE_FLAGS) -> io::Result<bool> {
+        match self.lock_ex(flags | c::LOCKFILE_FAIL_IMMEDIATELY) {
+            Ok(()) => Ok(true),
+            Err(e) if e.raw_os_error() == Some(c::ERROR_LOCK_VIOLATION as i32) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn lock(&self) -> io::Result<()> {
+        self.lock_ex(c::LOCKFILE_EXCLUSIVE_LOCK)
+    }
+
+    pub fn lock_shared(&self) -> io::Result<()> {
+        self.lock_ex(0)
+    }
+
+    pub fn try_lock(&self) -> io::Result<bool> {
+        self.try_lock_ex(c::LOCKFILE_EXCLUSIVE_LOCK)
+    }
+
+    pub fn try_lock_shared(&self) -> io::Result<bool> {
+        self.try_lock_ex(0)
+    }
+
+    pub fn unlock(&self) -> io::Result<()> {
+        cvt(unsafe { c::UnlockFile(self.handle.as_raw_handle(), 0, 0, u32::MAX, u32::MAX) })?;
+        Ok(())
+    }
+
     #[cfg(not(target_vendor = "uwp"))]
     pub fn file_attr(&self) -> io::Result<FileAttr> {
         unsafe {
@@ -1,8 +1,10 @@
 #![allow(dead_code)] // not used on all platforms
 
+use crate::fmt;
 use crate::fs;
 use crate::io::{self, Error, ErrorKind};
-use crate::path::Path;
+use crate::path::{self, Path, PathBuf};
+use crate::sys::fs as fs_imp;
 
 pub(crate) const NOT_FILE_ERROR: Error = io::const_io_error!(
     ErrorKind::InvalidInput,
@@ -49,3 +51,64 @@ pub fn try_exists(path: &Path) -> io::Result<bool> {
         Err(error) => Err(error),
     }
 }
+
+/// Path-based fallback for directory handles on platforms without
+/// `openat`-style system calls.
+///
+/// Every operation re-resolves the directory's absolute path, so this offers
+/// none of the race protection of a real directory file descriptor.
+pub struct DirHandle {
+    root: PathBuf,
+}
+
+impl DirHandle {
+    pub fn open(path: &Path) -> io::Result<DirHandle> {
+        let root = path::absolute(path)?;
+        if !fs_imp::stat(&root)?.file_type().is_dir() {
+            return Err(io::const_io_error!(ErrorKind::NotADirectory, "not a directory"));
+        }
+        Ok(DirHandle { root })
+    }
+
+    pub fn open_file(&self, path: &Path, opts: &fs_imp::OpenOptions) -> io::Result<fs_imp::File> {
+        fs_imp::File::open(&self.root.join(path), opts)
+    }
+
+    pub fn open_dir(&self, path: &Path) -> io::Result<DirHandle> {
+        DirHandle::open(&self.root.join(path))
+    }
+
+    pub fn stat(&self, path: &Path) -> io::Result<fs_imp::FileAttr> {
+        fs_imp::stat(&self.root.join(path))
+    }
+
+    pub fn lstat(&self, path: &Path) -> io::Result<fs_imp::FileAttr> {
+        fs_imp::lstat(&self.root.join(path))
+    }
+
+    pub fn mkdir(&self, path: &Path) -> io::Result<()> {
+        fs_imp::DirBuilder::new().mkdir(&self.root.join(path))
+    }
+
+    pub fn unlink(&self, path: &Path) -> io::Result<()> {
+        fs_imp::unlink(&self.root.join(path))
+    }
+
+    pub fn rmdir(&self, path: &Path) -> io::Result<()> {
+        fs_imp::rmdir(&self.root.join(path))
+    }
+
+    pub fn rename(&self, from: &Path, to_dir: &DirHandle, to: &Path) -> io::Result<()> {
+        fs_imp::rename(&self.root.join(from), &to_dir.root.join(to))
+    }
+
+    pub fn readdir(&self) -> io::Result<fs_imp::ReadDir> {
+        fs_imp::readdir(&self.root)
+    }
+}
+
+impl fmt::Debug for DirHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dir").field("path", &self.root).finish()
+    }
+}
@@ -2,8 +2,9 @@
 
 use std::env;
 use std::path::PathBuf;
+use std::time::Duration;
 
-use super::options::{ColorConfig, Options, OutputFormat, RunIgnored};
+use super::options::{ColorConfig, Options, OutputFormat, Partition, PartitionKind, RunIgnored};
 use super::time::TestTimeOptions;
 use std::io::{self, IsTerminal};
 
@@ -25,7 +26,22 @@ pub struct TestOpts {
     pub shuffle_seed: Option<u64>,
     pub test_threads: Option<usize>,
     pub skip: Vec<String>,
+    /// Only run the tests belonging to this shard of the suite.
+    pub partition: Option<Partition>,
     pub time_options: Option<TestTimeOptions>,
+    /// Kill tests that run for longer than this, unless they set their own
+    /// limit with `#[test_timeout]`.
+    pub test_timeout: Option<Duration>,
+    /// Run failed tests again up to this many times. A test that passes on
+    /// a later attempt is reported as flaky instead of failed.
+    pub retries: usize,
+    /// Save the benchmark results under this name.
+    pub save_baseline: Option<String>,
+    /// Compare the benchmark results to the ones saved under this name.
+    pub baseline: Option<String>,
+    /// Fail if a benchmark is significantly slower than in the baseline, by
+    /// more than this many percent.
+    pub regression_threshold: Option<f64>,
     /// Stop at first failing test.
     /// May run a few more tests due to threading, but will
     /// abort as soon as possible.
@@ -102,8 +118,9 @@ fn optgroups() -> getopts::Options {
             pretty = Print verbose output;
             terse  = Display one character per test;
             json   = Output a json document;
-            junit  = Output a JUnit document",
-            "pretty|terse|json|junit",
+            junit  = Output a JUnit document;
+            tap    = Output TAP version 14",
+            "pretty|terse|json|junit|tap",
         )
         .optflag("", "show-output", "Show captured stdout of successful tests")
         .optopt(
@@ -142,12 +159,56 @@ fn optgroups() -> getopts::Options {
             `CRITICAL_TIME` here means the limit that should not be exceeded by test.
             ",
         )
+        .optopt(
+            "",
+            "test-timeout",
+            "Kill tests that are still running after SECS seconds and report
+            them as timed out. Such tests run in a separate process.
+            `#[test_timeout = \"SECS\"]` overrides this for a single test",
+            "SECS",
+        )
+        .optopt(
+            "",
+            "retries",
+            "Run each failing test again up to N times. Tests that pass
+            on a later attempt are reported as flaky. Dynamically
+            registered tests, such as doctests, can't be retried",
+            "N",
+        )
+        .optopt(
+            "",
+            "save-baseline",
+            "Save the benchmark results under NAME, to compare later runs to them",
+            "NAME",
+        )
+        .optopt(
+            "",
+            "baseline",
+            "Compare the benchmark results to the ones saved under NAME",
+            "NAME",
+        )
+        .optopt(
+            "",
+            "regression-threshold",
+            "Fail if a benchmark is significantly slower than in the
+            baseline, by more than PCT percent",
+            "PCT",
+        )
         .optflag("", "shuffle", "Run tests in random order")
         .optopt(
             "",
             "shuffle-seed",
             "Run tests in random order; seed the random number generator with SEED",
             "SEED",
+        )
+        .optopt(
+            "",
+            "partition",
+            "Run only one shard of the tests remaining after filtering:
+            hash:K/N  = Run the tests whose name hashes to shard K of N;
+            count:K/N = Sort the tests by name and run every Nth one,
+                        starting with the Kth",
+            "hash|count:K/N",
         );
     opts
 }
@@ -171,6 +232,21 @@ fn usage(binary: &str, options: &getopts::Options) {
 tests in the same order again. Note that --shuffle and --shuffle-seed do not
 affect whether the tests are run in parallel.
 
+To split the tests across several machines, run the same filters with
+--partition=hash:K/N or --partition=count:K/N for every K from 1 to N. Each test
+runs in exactly one of the N shards.
+
+To catch performance regressions, save the benchmark results with
+--save-baseline=NAME, and compare a later run to them with --baseline=NAME.
+With --regression-threshold=PCT, benchmarks that got significantly slower by
+more than PCT percent fail the run. Baselines are saved next to the test binary,
+or in RUST_TEST_BASELINE_DIR if it is set.
+
+With --retries=N, a failing test is run again up to N more times. A test that
+passes on one of these attempts counts as flaky: it doesn't fail the run, but
+it is listed separately in the summary. Only statically registered tests can be
+retried: dynamic ones, such as doctests, run once and their failure says so.
+
 All tests have their standard output and standard error captured by default.
 This can be overridden with the --nocapture flag or setting RUST_TEST_NOCAPTURE
 environment variable to a value other than "0". Logging is not captured by default.
@@ -259,8 +335,14 @@ fn parse_opts_impl(matches: getopts::Matches) -> OptRes {
     let force_run_in_process = unstable_optflag!(matches, allow_unstable, "force-run-in-process");
     let exclude_should_panic = unstable_optflag!(matches, allow_unstable, "exclude-should-panic");
     let time_options = get_time_options(&matches, allow_unstable)?;
+    let test_timeout = get_test_timeout(&matches, allow_unstable)?;
+    let retries = get_retries(&matches, allow_unstable)?;
+    let save_baseline = unstable_optopt!(matches, allow_unstable, "save-baseline");
+    let baseline = unstable_optopt!(matches, allow_unstable, "baseline");
+    let regression_threshold = get_regression_threshold(&matches, allow_unstable)?;
     let shuffle = get_shuffle(&matches, allow_unstable)?;
     let shuffle_seed = get_shuffle_seed(&matches, allow_unstable)?;
+    let partition = get_partition(&matches, allow_unstable)?;
 
     let include_ignored = matches.opt_present("include-ignored");
     let quiet = matches.opt_present("quiet");
@@ -298,7 +380,13 @@ fn parse_opts_impl(matches: getopts::Matches) -> OptRes {
         shuffle_seed,
         test_threads,
         skip,
+        partition,
         time_options,
+        test_timeout,
+        retries,
+        save_baseline,
+        baseline,
+        regression_threshold,
         options,
         fail_fast: false,
     };
@@ -336,6 +424,61 @@ fn get_time_options(
     Ok(options)
 }
 
+fn get_test_timeout(
+    matches: &getopts::Matches,
+    allow_unstable: bool,
+) -> OptPartRes<Option<Duration>> {
+    let test_timeout = match unstable_optopt!(matches, allow_unstable, "test-timeout") {
+        Some(secs) => match secs.parse::<u64>() {
+            Ok(0) => return Err("argument for --test-timeout must not be 0".to_string()),
+            Ok(secs) => Some(Duration::from_secs(secs)),
+            Err(e) => {
+                return Err(format!(
+                    "argument for --test-timeout must be a number of seconds > 0 \
+                     (error: {e})"
+                ));
+            }
+        },
+        None => None,
+    };
+
+    Ok(test_timeout)
+}
+
+fn get_retries(matches: &getopts::Matches, allow_unstable: bool) -> OptPartRes<usize> {
+    let retries = match unstable_optopt!(matches, allow_unstable, "retries") {
+        Some(n) => match n.parse::<usize>() {
+            Ok(n) => n,
+            Err(e) => {
+                return Err(format!(
+                    "argument for --retries must be a non-negative number (error: {e})"
+                ));
+            }
+        },
+        None => 0,
+    };
+
+    Ok(retries)
+}
+
+fn get_regression_threshold(
+    matches: &getopts::Matches,
+    allow_unstable: bool,
+) -> OptPartRes<Option<f64>> {
+    let Some(pct) = unstable_optopt!(matches, allow_unstable, "regression-threshold") else {
+        return Ok(None);
+    };
+    if !matches.opt_present("baseline") {
+        return Err("--regression-threshold requires --baseline".to_string());
+    }
+    match pct.parse::<f64>() {
+        Ok(pct) if pct >= 0.0 => Ok(Some(pct)),
+        _ => Err(format!(
+            "argument for --regression-threshold must be a non-negative percentage (was {pct})"
+        )),
+    }
+}
+
 fn get_shuffle(matches: &getopts::Matches, allow_unstable: bool) -> OptPartRes<bool> {
     let mut shuffle = unstable_optflag!(matches, allow_unstable, "shuffle");
     if !shuffle && allow_unstable {
@@ -375,6 +518,36 @@ fn get_shuffle_seed(matches: &getopts::Matches, allow_unstable: bool) -> OptPart
     Ok(shuffle_seed)
 }
 
+fn get_partition(
+    matches: &getopts::Matches,
+    allow_unstable: bool,
+) -> OptPartRes<Option<Partition>> {
+    let Some(partition) = unstable_optopt!(matches, allow_unstable, "partition") else {
+        return Ok(None);
+    };
+    let invalid = || {
+        format!(
+            "argument for --partition must be hash:K/N or count:K/N with 1 <= K <= N (was {partition})"
+        )
+    };
+
+    let (kind, shard) = partition.split_once(':').ok_or_else(invalid)?;
+    let kind = match kind {
+        "hash" => PartitionKind::Hash,
+        "count" => PartitionKind::Count,
+        _ => return Err(invalid()),
+    };
+    let (index, count) = shard.split_once('/').ok_or_else(invalid)?;
+    let (Ok(index), Ok(count)) = (index.parse::<usize>(), count.parse::<usize>()) else {
+        return Err(invalid());
+    };
+    if index == 0 || index > count {
+        return Err(invalid());
+    }
+
+    Ok(Some(Partition { kind, index, count }))
+}
+
 fn get_test_threads(matches: &getopts::Matches) -> OptPartRes<Option<usize>> {
     let test_threads = match matches.opt_str("test-threads") {
         Some(n_str) => match n_str.parse::<usize>() {
@@ -414,9 +587,15 @@ fn get_format(
             }
             OutputFormat::Junit
         }
+        Some("tap") => {
+            if !allow_unstable {
+                return Err("The \"tap\" format is only accepted on the nightly compiler with -Z unstable-options".into());
+            }
+            OutputFormat::Tap
+        }
         Some(v) => {
             return Err(format!(
-                "argument for --format must be pretty, terse, json or junit (was \
+                "argument for --format must be pretty, terse, json, junit or tap (was \
                  {v})"
             ));
         }
@@ -6,13 +6,20 @@
 use std::time::Instant;
 
 use super::{
-    bench::fmt_bench_samples,
+    bench::{fmt_bench_samples, BenchSamples},
     cli::TestOpts,
     event::{CompletedTest, TestEvent},
     filter_tests,
-    formatters::{JsonFormatter, JunitFormatter, OutputFormatter, PrettyFormatter, TerseFormatter},
-    helpers::{concurrency::get_concurrency, metrics::MetricMap},
-    options::{Options, OutputFormat},
+    formatters::{
+        JsonFormatter, JunitFormatter, OutputFormatter, PrettyFormatter, TapFormatter,
+        TerseFormatter,
+    },
+    helpers::{
+        baseline::{Baseline, Comparison},
+        concurrency::get_concurrency,
+        metrics::MetricMap,
+    },
+    options::{Options, OutputFormat, Partition},
     run_tests, term,
     test_result::TestResult,
     time::{TestExecTime, TestSuiteExecTime},
@@ -79,6 +86,7 @@ pub struct ConsoleTestState {
     pub total: usize,
     pub passed: usize,
     pub failed: usize,
+    pub flaky: usize,
     pub ignored: usize,
     pub filtered_out: usize,
     pub measured: usize,
@@ -88,6 +96,15 @@ pub struct ConsoleTestState {
     pub not_failures: Vec<(TestDesc, Vec<u8>)>,
     pub ignores: Vec<(TestDesc, Vec<u8>)>,
     pub time_failures: Vec<(TestDesc, Vec<u8>)>,
+    pub flakes: Vec<(TestDesc, Vec<u8>)>,
+    /// How many times a failing test is run again, see `--retries`.
+    pub retries: usize,
+    pub partition: Option<Partition>,
+    /// Results of an earlier run to compare the benchmarks to.
+    pub baseline: Option<Baseline>,
+    pub regression_threshold: Option<f64>,
+    /// Results of this run's benchmarks.
+    pub bench_results: Baseline,
     pub options: Options,
 }
 
@@ -103,6 +120,7 @@ pub fn new(opts: &TestOpts) -> io::Result<ConsoleTestState> {
             total: 0,
             passed: 0,
             failed: 0,
+            flaky: 0,
             ignored: 0,
             filtered_out: 0,
             measured: 0,
@@ -112,10 +130,35 @@ pub fn new(opts: &TestOpts) -> io::Result<ConsoleTestState> {
             not_failures: Vec::new(),
             ignores: Vec::new(),
             time_failures: Vec::new(),
+            flakes: Vec::new(),
+            retries: opts.retries,
+            partition: opts.partition,
+            baseline: opts.baseline.as_deref().map(Baseline::load).transpose()?,
+            regression_threshold: opts.regression_threshold,
+            bench_results: Baseline::new(),
             options: opts.options,
         })
     }
 
+    /// Compares a benchmark to its result in the baseline, if there is one.
+    pub fn compare_to_baseline(&self, test: &TestDesc, bs: &BenchSamples) -> Option<Comparison> {
+        let old = self.baseline.as_ref()?.get(test.name.as_slice())?;
+        Some(Comparison::new(old, &bs.ns_iter_summ))
+    }
+
+    /// Explains why a benchmark fails the run, if it regressed by more than the threshold.
+    pub fn regression_message(&self, test: &TestDesc, bs: &BenchSamples) -> Option<String> {
+        let threshold = self.regression_threshold?;
+        let comparison = self.compare_to_baseline(test, bs)?;
+        comparison.is_regression(threshold).then(|| {
+            format!(
+                "benchmark is {:.2}% slower than in the baseline, more than the threshold of \
+                 {threshold}%",
+                comparison.change_pct
+            )
+        })
+    }
+
     pub fn write_log<F, S>(&mut self, msg: F) -> io::Result<()>
     where
         S: AsRef<str>,
@@ -154,6 +197,8 @@ pub fn write_log_result(
                     }
                     TestResult::TrBench(ref bs) => fmt_bench_samples(bs),
                     TestResult::TrTimedFail => "failed (time limit exceeded)".to_owned(),
+                    TestResult::TrTimedOut => "failed (timed out)".to_owned(),
+                    TestResult::TrFlaky(_) => "ok (flaky)".to_owned(),
                 },
                 name,
             )
@@ -165,7 +210,7 @@ pub fn write_log_result(
     }
 
     fn current_test_count(&self) -> usize {
-        self.passed + self.failed + self.ignored + self.measured
+        self.passed + self.failed + self.flaky + self.ignored + self.measured
     }
 }
 
@@ -177,7 +222,7 @@ pub fn list_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Res
     };
 
     let mut out: Box<dyn OutputFormatter> = match opts.format {
-        OutputFormat::Pretty | OutputFormat::Junit => {
+        OutputFormat::Pretty | OutputFormat::Junit | OutputFormat::Tap => {
             Box::new(PrettyFormatter::new(output, false, 0, false, None))
         }
         OutputFormat::Terse => Box::new(TerseFormatter::new(output, false, 0, false)),
@@ -230,7 +275,15 @@ fn handle_test_result(st: &mut ConsoleTestState, completed_test: CompletedTest)
                 bs.ns_iter_summ.median,
                 bs.ns_iter_summ.max - bs.ns_iter_summ.min,
             );
-            st.measured += 1
+            st.bench_results.insert(test.name.as_slice(), &bs.ns_iter_summ);
+            if let Some(msg) = st.regression_message(&test, &bs) {
+                st.failed += 1;
+                let mut stdout = stdout;
+                stdout.extend_from_slice(format!("note: {msg}").as_bytes());
+                st.failures.push((test, stdout));
+            } else {
+                st.measured += 1;
+            }
         }
         TestResult::TrFailed => {
             st.failed += 1;
@@ -246,6 +299,21 @@ fn handle_test_result(st: &mut ConsoleTestState, completed_test: CompletedTest)
             st.failed += 1;
             st.time_failures.push((test, stdout));
         }
+        TestResult::TrTimedOut => {
+            st.failed += 1;
+            let mut stdout = stdout;
+            stdout.extend_from_slice(b"note: test timed out and was killed");
+            st.failures.push((test, stdout));
+        }
+        TestResult::TrFlaky(failures) => {
+            st.flaky += 1;
+            let mut stdout = stdout;
+            let attempts = if failures == 1 { "attempt" } else { "attempts" };
+            stdout.extend_from_slice(
+                format!("note: test passed after {failures} failed {attempts}").as_bytes(),
+            );
+            st.flakes.push((test, stdout));
+        }
     }
 }
 
@@ -310,6 +378,7 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
         }
         OutputFormat::Json => Box::new(JsonFormatter::new(output)),
         OutputFormat::Junit => Box::new(JunitFormatter::new(output)),
+        OutputFormat::Tap => Box::new(TapFormatter::new(output)),
     };
     let mut st = ConsoleTestState::new(opts)?;
 
@@ -324,6 +393,14 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
 
     assert!(opts.fail_fast || st.current_test_count() == st.total);
 
+    // When no benchmark was measured, e.g. because the benchmarks only ran as tests, or were all
+    // filtered out, keep the earlier baseline instead of replacing it with an empty one.
+    if let Some(ref name) = opts.save_baseline {
+        if !st.bench_results.is_empty() {
+            st.bench_results.save(name)?;
+        }
+    }
+
     out.write_run_finish(&st)
 }
 
@@ -146,6 +146,24 @@ fn write_result(
                 Some(r#""reason": "time limit exceeded""#),
             ),
 
+            TestResult::TrTimedOut => self.write_event(
+                "test",
+                desc.name.as_slice(),
+                "failed",
+                exec_time,
+                stdout,
+                Some(r#""reason": "timed out""#),
+            ),
+
+            TestResult::TrFlaky(failures) => self.write_event(
+                "test",
+                desc.name.as_slice(),
+                "ok",
+                exec_time,
+                stdout,
+                Some(&*format!(r#""flaky": true, "failed_attempts": {failures}"#)),
+            ),
+
             TestResult::TrFailedMsg(ref m) => self.write_event(
                 "test",
                 desc.name.as_slice(),
@@ -175,13 +193,20 @@ fn write_result(
                 } else {
                     format!(r#", "mib_per_second": {}"#, bs.mb_s)
                 };
+                let baseline = match state.compare_to_baseline(desc, bs) {
+                    Some(comparison) => format!(
+                        r#", "change_percent": {}, "significant": {}"#,
+                        comparison.change_pct, comparison.significant
+                    ),
+                    None => String::new(),
+                };
                 let name = EscapedString(desc.name.as_slice());
 
                 self.writeln_message(&format!(
                     "{{ \"type\": \"bench\", \
                      \"name\": \"{name}\", \
                      \"median\": {median}, \
-                     \"deviation\": {deviation}{mbps} }}\n",
+                     \"deviation\": {deviation}{mbps}{baseline} }}\n",
                 ))
             }
         }
@@ -207,10 +232,20 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
         } else {
             String::from("")
         };
+        let flaky_json = if state.retries > 0 {
+            format!(r#", "flaky": {}"#, state.flaky)
+        } else {
+            String::from("")
+        };
+        let partition_json = if let Some(partition) = state.partition {
+            format!(r#", "partition": "{partition}""#)
+        } else {
+            String::from("")
+        };
         let newline = "\n";
 
         self.writeln_message(&format!(
-            r#"{{ "type": "suite", "event": "{event}", "passed": {passed}, "failed": {failed}, "ignored": {ignored}, "measured": {measured}, "filtered_out": {filtered_out}{exec_time_json} }}{newline}"#
+            r#"{{ "type": "suite", "event": "{event}", "passed": {passed}, "failed": {failed}{flaky_json}, "ignored": {ignored}, "measured": {measured}, "filtered_out": {filtered_out}{exec_time_json}{partition_json} }}{newline}"#
         ))?;
 
         Ok(state.failed == 0)
@@ -219,7 +254,7 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
 
 /// A formatting utility used to print strings with characters in need of escaping.
 /// Base code taken form `libserialize::json::escape_str`
-struct EscapedString<S: AsRef<str>>(S);
+pub(crate) struct EscapedString<S: AsRef<str>>(pub S);
 
 impl<S: AsRef<str>> std::fmt::Display for EscapedString<S> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> ::std::fmt::Result {
@@ -19,6 +19,11 @@ pub fn new(out: OutputLocation<T>) -> Self {
         Self { out, results: Vec::new() }
     }
 
+    #[cfg(test)]
+    pub fn output_location(&self) -> &OutputLocation<T> {
+        &self.out
+    }
+
     fn write_message(&mut self, s: &str) -> io::Result<()> {
         assert!(!s.contains('\n'));
 
@@ -88,15 +93,26 @@ fn write_result(
     fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
         self.write_message("<testsuites>")?;
 
+        let flaky = if state.retries > 0 {
+            format!("flaky=\"{}\" ", state.flaky)
+        } else {
+            String::new()
+        };
         self.write_message(&format!(
             "<testsuite name=\"test\" package=\"test\" id=\"0\" \
              errors=\"0\" \
              failures=\"{}\" \
+             {flaky}\
              tests=\"{}\" \
              skipped=\"{}\" \
              >",
             state.failed, state.total, state.ignored
         ))?;
+        if let Some(partition) = state.partition {
+            self.write_message("<properties>")?;
+            self.write_message(&format!("<property name=\"partition\" value=\"{partition}\"/>"))?;
+            self.write_message("</properties>")?;
+        }
         for (desc, result, duration, stdout) in std::mem::take(&mut self.results) {
             let (class_name, test_name) = parse_class_name(&desc);
             match result {
@@ -147,14 +163,68 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
                     self.write_message("</testcase>")?;
                 }
 
-                TestResult::TrBench(ref b) => {
+                TestResult::TrTimedOut => {
+                    self.write_message(&format!(
+                        "<testcase classname=\"{}\" \
+                         name=\"{}\" time=\"{}\">",
+                        class_name,
+                        test_name,
+                        duration.as_secs_f64()
+                    ))?;
+                    self.write_message(
+                        "<failure message=\"test timed out and was killed\" type=\"timeout\"/>",
+                    )?;
+                    if !stdout.is_empty() {
+                        self.write_message("<system-out>")?;
+                        self.write_message(&str_to_cdata(&String::from_utf8_lossy(&stdout)))?;
+                        self.write_message("</system-out>")?;
+                    }
+                    self.write_message("</testcase>")?;
+                }
+
+                TestResult::TrFlaky(failures) => {
                     self.write_message(&format!(
-                        "<testcase classname=\"benchmark::{}\" \
-                         name=\"{}\" time=\"{}\" />",
-                        class_name, test_name, b.ns_iter_summ.sum
+                        "<testcase classname=\"{}\" \
+                         name=\"{}\" time=\"{}\">",
+                        class_name,
+                        test_name,
+                        duration.as_secs_f64()
                     ))?;
+                    for attempt in 1..=failures {
+                        self.write_message(&format!(
+                            "<flakyFailure message=\"attempt {attempt} failed\" type=\"flaky\"/>"
+                        ))?;
+                    }
+                    if !stdout.is_empty() {
+                        self.write_message("<system-out>")?;
+                        self.write_message(&str_to_cdata(&String::from_utf8_lossy(&stdout)))?;
+                        self.write_message("</system-out>")?;
+                    }
+                    self.write_message("</testcase>")?;
                 }
 
+                TestResult::TrBench(ref b) => match state.regression_message(&desc, b) {
+                    None => {
+                        self.write_message(&format!(
+                            "<testcase classname=\"benchmark::{}\" \
+                             name=\"{}\" time=\"{}\" />",
+                            class_name, test_name, b.ns_iter_summ.sum
+                        ))?;
+                    }
+                    // The regression fails the run, so it is reported as a failure.
+                    Some(msg) => {
+                        self.write_message(&format!(
+                            "<testcase classname=\"benchmark::{}\" \
+                             name=\"{}\" time=\"{}\">",
+                            class_name, test_name, b.ns_iter_summ.sum
+                        ))?;
+                        self.write_message(&format!(
+                            "<failure message=\"{msg}\" type=\"regression\"/>"
+                        ))?;
+                        self.write_message("</testcase>")?;
+                    }
+                },
+
                 TestResult::TrOk => {
                     self.write_message(&format!(
                         "<testcase classname=\"{}\" \
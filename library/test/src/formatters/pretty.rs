@@ -41,6 +41,10 @@ pub fn write_ok(&mut self) -> io::Result<()> {
         self.write_short_result("ok", term::color::GREEN)
     }
 
+    pub fn write_flaky(&mut self) -> io::Result<()> {
+        self.write_short_result("ok (flaky)", term::color::YELLOW)
+    }
+
     pub fn write_failed(&mut self) -> io::Result<()> {
         self.write_short_result("FAILED", term::color::RED)
     }
@@ -57,6 +61,10 @@ pub fn write_time_failed(&mut self) -> io::Result<()> {
         self.write_short_result("FAILED (time limit exceeded)", term::color::RED)
     }
 
+    pub fn write_timed_out(&mut self) -> io::Result<()> {
+        self.write_short_result("TIMED OUT", term::color::RED)
+    }
+
     pub fn write_bench(&mut self) -> io::Result<()> {
         self.write_pretty("bench", term::color::CYAN)
     }
@@ -168,6 +176,10 @@ pub fn write_time_failures(&mut self, state: &ConsoleTestState) -> io::Result<()
         self.write_results(&state.time_failures, "failures (time limit exceeded)")
     }
 
+    pub fn write_flakes(&mut self, state: &ConsoleTestState) -> io::Result<()> {
+        self.write_results(&state.flakes, "flaky")
+    }
+
     fn write_test_name(&mut self, desc: &TestDesc) -> io::Result<()> {
         let name = desc.padded_name(self.max_name_len, desc.name.padding());
         if let Some(test_mode) = desc.test_mode() {
@@ -236,7 +248,7 @@ fn write_result(
         result: &TestResult,
         exec_time: Option<&time::TestExecTime>,
         _: &[u8],
-        _: &ConsoleTestState,
+        state: &ConsoleTestState,
     ) -> io::Result<()> {
         if self.is_multithreaded {
             self.write_test_name(desc)?;
@@ -249,8 +261,13 @@ fn write_result(
             TestResult::TrBench(ref bs) => {
                 self.write_bench()?;
                 self.write_plain(format!(": {}", fmt_bench_samples(bs)))?;
+                if let Some(comparison) = state.compare_to_baseline(desc, bs) {
+                    self.write_plain(format!(" ({comparison})"))?;
+                }
             }
             TestResult::TrTimedFail => self.write_time_failed()?,
+            TestResult::TrTimedOut => self.write_timed_out()?,
+            TestResult::TrFlaky(_) => self.write_flaky()?,
         }
 
         self.write_time(desc, exec_time)?;
@@ -269,6 +286,9 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
         if state.options.display_output {
             self.write_successes(state)?;
         }
+        if !state.flakes.is_empty() {
+            self.write_flakes(state)?;
+        }
         let success = state.failed == 0;
         if !success {
             if !state.failures.is_empty() {
@@ -289,8 +309,10 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
             self.write_pretty("FAILED", term::color::RED)?;
         }
 
+        let flaky =
+            if state.flaky > 0 { format!(" {} flaky;", state.flaky) } else { String::new() };
         let s = format!(
-            ". {} passed; {} failed; {} ignored; {} measured; {} filtered out",
+            ". {} passed;{flaky} {} failed; {} ignored; {} measured; {} filtered out",
             state.passed, state.failed, state.ignored, state.measured, state.filtered_out
         );
 
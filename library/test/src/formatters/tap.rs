@@ -0,0 +1,203 @@
+use std::{io, io::prelude::Write};
+
+use super::{json::EscapedString, OutputFormatter};
+use crate::{
+    bench::fmt_bench_samples,
+    console::{ConsoleTestDiscoveryState, ConsoleTestState, OutputLocation},
+    test_result::TestResult,
+    time,
+    types::TestDesc,
+};
+
+/// Output in the format of the Test Anything Protocol, version 14.
+///
+/// See <https://testanything.org/tap-version-14-specification.html>.
+pub(crate) struct TapFormatter<T> {
+    out: OutputLocation<T>,
+    /// Number of the last test point, as TAP numbers them from 1.
+    test_number: usize,
+}
+
+impl<T: Write> TapFormatter<T> {
+    pub fn new(out: OutputLocation<T>) -> Self {
+        Self { out, test_number: 0 }
+    }
+
+    fn writeln(&mut self, s: &str) -> io::Result<()> {
+        // Write each line at once, so that a line is never split by output from another thread.
+        self.out.write_all(format!("{s}\n").as_bytes())
+    }
+
+    fn write_test_point(
+        &mut self,
+        ok: bool,
+        desc: &TestDesc,
+        directive: Option<&str>,
+    ) -> io::Result<()> {
+        self.test_number += 1;
+        let status = if ok { "ok" } else { "not ok" };
+        let number = self.test_number;
+        let name = escape_description(desc.name.as_slice());
+        match directive {
+            Some(directive) => self.writeln(&format!("{status} {number} - {name} # {directive}")),
+            None => self.writeln(&format!("{status} {number} - {name}")),
+        }
+    }
+
+    /// Writes the YAML diagnostics that belong to the last test point. Nothing is written if
+    /// there are none.
+    fn write_diagnostics(&mut self, fields: &[(&str, String)]) -> io::Result<()> {
+        if fields.is_empty() {
+            return Ok(());
+        }
+        self.writeln("  ---")?;
+        for (key, value) in fields {
+            self.writeln(&format!("  {key}: {value}"))?;
+        }
+        self.writeln("  ...")
+    }
+}
+
+impl<T: Write> OutputFormatter for TapFormatter<T> {
+    fn write_discovery_start(&mut self) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::NotFound, "Not yet implemented!"))
+    }
+
+    fn write_test_discovered(&mut self, _desc: &TestDesc, _test_type: &str) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::NotFound, "Not yet implemented!"))
+    }
+
+    fn write_discovery_finish(&mut self, _state: &ConsoleTestDiscoveryState) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::NotFound, "Not yet implemented!"))
+    }
+
+    fn write_run_start(&mut self, test_count: usize, shuffle_seed: Option<u64>) -> io::Result<()> {
+        self.writeln("TAP version 14")?;
+        self.writeln(&format!("1..{test_count}"))?;
+        if let Some(shuffle_seed) = shuffle_seed {
+            self.writeln(&format!("# shuffle seed: {shuffle_seed}"))?;
+        }
+        Ok(())
+    }
+
+    fn write_test_start(&mut self, _desc: &TestDesc) -> io::Result<()> {
+        // Test points are only written once the result is known.
+        Ok(())
+    }
+
+    fn write_timeout(&mut self, desc: &TestDesc) -> io::Result<()> {
+        self.writeln(&format!(
+            "# test {} has been running for over {} seconds",
+            desc.name,
+            time::TEST_WARN_TIMEOUT_S
+        ))
+    }
+
+    fn write_result(
+        &mut self,
+        desc: &TestDesc,
+        result: &TestResult,
+        exec_time: Option<&time::TestExecTime>,
+        stdout: &[u8],
+        state: &ConsoleTestState,
+    ) -> io::Result<()> {
+        let mut fields = Vec::new();
+        let (ok, message) = match *result {
+            TestResult::TrOk => (true, None),
+            TestResult::TrFlaky(failures) => {
+                fields.push(("failed_attempts", failures.to_string()));
+                (true, None)
+            }
+            TestResult::TrIgnored => {
+                let directive = match desc.ignore_message {
+                    Some(msg) => format!("SKIP {}", escape_description(msg)),
+                    None => "SKIP".to_string(),
+                };
+                return self.write_test_point(true, desc, Some(&directive));
+            }
+            TestResult::TrBench(ref bs) => {
+                fields.push(("median_ns", (bs.ns_iter_summ.median as usize).to_string()));
+                fields.push((
+                    "deviation_ns",
+                    ((bs.ns_iter_summ.max - bs.ns_iter_summ.min) as usize).to_string(),
+                ));
+                if let Some(comparison) = state.compare_to_baseline(desc, bs) {
+                    fields.push(("change_percent", comparison.change_pct.to_string()));
+                    fields.push(("significant", comparison.significant.to_string()));
+                }
+                let summary = yaml_string(fmt_bench_samples(bs).trim_start());
+                fields.insert(0, ("summary", summary));
+                let regression = state.regression_message(desc, bs);
+                (regression.is_none(), regression)
+            }
+            TestResult::TrFailed => (false, None),
+            TestResult::TrFailedMsg(ref msg) => (false, Some(msg.clone())),
+            TestResult::TrTimedFail => (false, Some("time limit exceeded".to_string())),
+            TestResult::TrTimedOut => (false, Some("test timed out and was killed".to_string())),
+        };
+
+        if let Some(message) = message {
+            fields.insert(0, ("message", yaml_string(&message)));
+        }
+        if !ok {
+            fields.push(("severity", "fail".to_string()));
+        }
+        if let Some(exec_time) = exec_time {
+            fields.push(("duration_ms", (exec_time.0.as_secs_f64() * 1000.0).to_string()));
+        }
+        if !stdout.is_empty() && (!ok || state.options.display_output) {
+            fields.push(("stdout", yaml_string(&String::from_utf8_lossy(stdout))));
+        }
+
+        self.write_test_point(ok, desc, None)?;
+        self.write_diagnostics(&fields)
+    }
+
+    fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
+        let flaky =
+            if state.flaky > 0 { format!(" {} flaky;", state.flaky) } else { String::new() };
+        self.writeln(&format!(
+            "# {} passed;{flaky} {} failed; {} ignored; {} measured; {} filtered out",
+            state.passed, state.failed, state.ignored, state.measured, state.filtered_out
+        ))?;
+        if let Some(ref exec_time) = state.exec_time {
+            self.writeln(&format!("# finished in {exec_time}"))?;
+        }
+
+        Ok(state.failed == 0)
+    }
+}
+
+// `#` starts a directive, so it has to be escaped in descriptions, and so does the escape
+// character itself.
+fn escape_description(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('#', "\\#").replace('\n', " ")
+}
+
+// Formats a string as a YAML scalar, indented to be the value of a diagnostic field.
+//
+// Multi-line strings, such as captured output, are written as literal blocks so that they stay
+// readable. Anything a literal block can't hold is written as a double-quoted string instead,
+// which uses the same escapes as JSON.
+fn yaml_string(s: &str) -> String {
+    let printable = s.chars().all(|c| c == '\n' || c == '\t' || !c.is_control());
+    if !s.contains('\n') || !printable || s.starts_with([' ', '\t', '\n']) {
+        return format!("\"{}\"", EscapedString(s));
+    }
+
+    // Keep the trailing newlines as they are.
+    let chomping = match s.trim_end_matches('\n').len() {
+        len if len == s.len() => "-",
+        len if len + 1 == s.len() => "",
+        _ => "+",
+    };
+    let mut block = format!("|{chomping}");
+    for line in s.lines() {
+        block.push('\n');
+        if !line.is_empty() {
+            block.push_str("    ");
+            block.push_str(line);
+        }
+    }
+    block
+}
@@ -50,8 +50,29 @@ pub fn write_ok(&mut self) -> io::Result<()> {
     }
 
     pub fn write_failed(&mut self, name: &str) -> io::Result<()> {
-        // Put failed tests on their own line and include the test name, so that it's faster
-        // to see which test failed without having to wait for them all to run.
+        self.write_failure(name, "FAILED")
+    }
+
+    pub fn write_timed_out(&mut self, name: &str) -> io::Result<()> {
+        self.write_failure(name, "TIMED OUT")
+    }
+
+    pub fn write_flaky(&mut self, name: &str) -> io::Result<()> {
+        self.write_noteworthy(name, "FLAKY", term::color::YELLOW)
+    }
+
+    fn write_failure(&mut self, name: &str, result: &str) -> io::Result<()> {
+        self.write_noteworthy(name, result, term::color::RED)
+    }
+
+    fn write_noteworthy(
+        &mut self,
+        name: &str,
+        result: &str,
+        color: term::color::Color,
+    ) -> io::Result<()> {
+        // Put failed (and flaky) tests on their own line and include the test name, so that it's
+        // faster to see which test failed without having to wait for them all to run.
 
         // normally, we write the progress unconditionally, even if the previous line was cut short.
         // but if this is the very first column, no short results will have been printed and we'll end up with *only* the progress on the line.
@@ -61,7 +82,7 @@ pub fn write_failed(&mut self, name: &str) -> io::Result<()> {
         }
         self.test_count += 1;
         self.write_plain(format!("{name} --- "))?;
-        self.write_pretty("FAILED", term::color::RED)?;
+        self.write_pretty(result, color)?;
         self.write_plain("\n")
     }
 
@@ -229,20 +250,26 @@ fn write_result(
         result: &TestResult,
         _: Option<&time::TestExecTime>,
         _: &[u8],
-        _: &ConsoleTestState,
+        state: &ConsoleTestState,
     ) -> io::Result<()> {
         match *result {
             TestResult::TrOk => self.write_ok(),
             TestResult::TrFailed | TestResult::TrFailedMsg(_) | TestResult::TrTimedFail => {
                 self.write_failed(desc.name.as_slice())
             }
+            TestResult::TrTimedOut => self.write_timed_out(desc.name.as_slice()),
+            TestResult::TrFlaky(_) => self.write_flaky(desc.name.as_slice()),
             TestResult::TrIgnored => self.write_ignored(),
             TestResult::TrBench(ref bs) => {
                 if self.is_multithreaded {
                     self.write_test_name(desc)?;
                 }
                 self.write_bench()?;
-                self.write_plain(format!(": {}\n", fmt_bench_samples(bs)))
+                self.write_plain(format!(": {}", fmt_bench_samples(bs)))?;
+                if let Some(comparison) = state.compare_to_baseline(desc, bs) {
+                    self.write_plain(format!(" ({comparison})"))?;
+                }
+                self.write_plain("\n")
             }
         }
     }
@@ -273,8 +300,10 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
             self.write_pretty("FAILED", term::color::RED)?;
         }
 
+        let flaky =
+            if state.flaky > 0 { format!(" {} flaky;", state.flaky) } else { String::new() };
         let s = format!(
-            ". {} passed; {} failed; {} ignored; {} measured; {} filtered out",
+            ". {} passed;{flaky} {} failed; {} ignored; {} measured; {} filtered out",
             state.passed, state.failed, state.ignored, state.measured, state.filtered_out
         );
 
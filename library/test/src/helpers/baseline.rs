@@ -0,0 +1,164 @@
+//! Benchmark baselines, to compare one benchmark run against an earlier one.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::bench::SAMPLES;
+use crate::stats::Summary;
+
+const HEADER: &str = "# libtest benchmark baseline v1";
+
+/// The parts of a benchmark's `Summary` that are needed to compare it to a later run.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BaselineEntry {
+    pub median: f64,
+    pub mean: f64,
+    pub var: f64,
+}
+
+impl From<&Summary> for BaselineEntry {
+    fn from(summary: &Summary) -> BaselineEntry {
+        BaselineEntry { median: summary.median, mean: summary.mean, var: summary.var }
+    }
+}
+
+/// The results of all benchmarks of one run, by benchmark name.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Baseline(BTreeMap<String, BaselineEntry>);
+
+impl Baseline {
+    pub fn new() -> Baseline {
+        Baseline(BTreeMap::new())
+    }
+
+    pub fn insert(&mut self, name: &str, summary: &Summary) {
+        self.0.insert(name.to_owned(), BaselineEntry::from(summary));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&BaselineEntry> {
+        self.0.get(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Reads the baseline called `name` that was saved by an earlier run of this test binary.
+    pub fn load(name: &str) -> io::Result<Baseline> {
+        let path = baseline_path(name)?;
+        let contents = fs::read_to_string(&path).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("failed to read benchmark baseline `{name}` from {}: {e}", path.display()),
+            )
+        })?;
+        Baseline::parse(&contents).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid benchmark baseline `{name}` in {}: {e}", path.display()),
+            )
+        })
+    }
+
+    /// Saves this baseline as `name`, replacing any earlier baseline with the same name.
+    pub fn save(&self, name: &str) -> io::Result<()> {
+        fs::write(baseline_path(name)?, self.to_string())
+    }
+
+    pub fn parse(contents: &str) -> Result<Baseline, String> {
+        let mut lines = contents.lines();
+        if lines.next() != Some(HEADER) {
+            return Err("unknown file format".to_string());
+        }
+        let mut baseline = Baseline::new();
+        for line in lines {
+            // The name goes last, as it is the only field that can contain tabs.
+            let mut fields = line.splitn(4, '\t');
+            let mut number = || -> Result<f64, String> {
+                let field = fields.next().ok_or_else(|| format!("truncated line `{line}`"))?;
+                field.parse().map_err(|e| format!("invalid number `{field}`: {e}"))
+            };
+            let entry = BaselineEntry { median: number()?, mean: number()?, var: number()? };
+            let name = fields.next().ok_or_else(|| format!("truncated line `{line}`"))?;
+            baseline.0.insert(name.to_owned(), entry);
+        }
+        Ok(baseline)
+    }
+}
+
+impl fmt::Display for Baseline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{HEADER}")?;
+        for (name, BaselineEntry { median, mean, var }) in &self.0 {
+            writeln!(f, "{median}\t{mean}\t{var}\t{name}")?;
+        }
+        Ok(())
+    }
+}
+
+// Baselines are kept next to the test binary unless `RUST_TEST_BASELINE_DIR` says otherwise.
+// The file name includes the binary's name, so that several test binaries can share a
+// directory and a baseline name.
+pub(crate) fn baseline_path(name: &str) -> io::Result<PathBuf> {
+    let exe = env::current_exe()?;
+    let dir = match env::var_os("RUST_TEST_BASELINE_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => exe.parent().map(PathBuf::from).unwrap_or_default(),
+    };
+    let exe_name = exe.file_stem().unwrap_or_default().to_string_lossy();
+    Ok(dir.join(format!("{exe_name}.{name}.baseline")))
+}
+
+/// How a benchmark's result compares to its result in a baseline.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Comparison {
+    /// Change of the median time per iteration, in percent of the baseline.
+    pub change_pct: f64,
+    /// Whether the change in the mean is statistically significant, rather than noise.
+    pub significant: bool,
+}
+
+impl Comparison {
+    pub fn new(old: &BaselineEntry, new: &Summary) -> Comparison {
+        let change_pct =
+            if old.median == 0.0 { 0.0 } else { (new.median - old.median) / old.median * 100.0 };
+        Comparison { change_pct, significant: welch_t_test(old, new) }
+    }
+
+    /// Whether this is a significant slowdown by more than `threshold_pct` percent.
+    pub fn is_regression(&self, threshold_pct: f64) -> bool {
+        self.significant && self.change_pct > threshold_pct
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verdict = if !self.significant || self.change_pct == 0.0 {
+            "within noise"
+        } else if self.change_pct > 0.0 {
+            "regressed"
+        } else {
+            "improved"
+        };
+        write!(f, "{:+.2}%, {verdict}", self.change_pct)
+    }
+}
+
+// Welch's t-test of the means of the old and the new samples, at a significance level of 5%.
+//
+// Every benchmark result is a summary of `SAMPLES` samples, so the test has at least
+// `SAMPLES - 1` degrees of freedom, for which the two-sided critical value is about 2.01.
+// Using that value for every comparison is slightly conservative for larger degrees of
+// freedom, which is fine for telling real changes from noise.
+fn welch_t_test(old: &BaselineEntry, new: &Summary) -> bool {
+    const CRITICAL_VALUE: f64 = 2.01;
+
+    let n = SAMPLES as f64;
+    let std_err = (old.var / n + new.var / n).sqrt();
+    let diff = (new.mean - old.mean).abs();
+    if std_err == 0.0 { diff > 0.0 } else { diff / std_err > CRITICAL_VALUE }
+}
@@ -1,6 +1,8 @@
 //! Module with common helpers not directly related to tests
 //! but used in `libtest`.
 
+pub mod baseline;
 pub mod concurrency;
 pub mod metrics;
+pub mod partition;
 pub mod shuffle;
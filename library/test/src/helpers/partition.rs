@@ -0,0 +1,37 @@
+use crate::options::{Partition, PartitionKind};
+use crate::types::TestDescAndFn;
+
+/// Keeps only the tests that belong to the selected shard.
+///
+/// Every test is assigned to exactly one shard, so running all shards of the
+/// same test binary with the same filters runs every test exactly once.
+pub fn partition_tests(partition: Partition, tests: Vec<TestDescAndFn>) -> Vec<TestDescAndFn> {
+    let Partition { kind, index, count } = partition;
+    let shard = index - 1;
+    match kind {
+        PartitionKind::Hash => tests
+            .into_iter()
+            .filter(|test| name_hash(test.desc.name.as_slice()) % count as u64 == shard as u64)
+            .collect(),
+        PartitionKind::Count => {
+            // The tests are usually passed in name order already, but don't rely on it: the
+            // shards must not depend on the order in which the tests were registered.
+            let mut order: Vec<usize> = (0..tests.len()).collect();
+            order.sort_by_key(|&test| tests[test].desc.name.as_slice());
+            let mut keep = vec![false; tests.len()];
+            for (position, &test) in order.iter().enumerate() {
+                keep[test] = position % count == shard;
+            }
+            tests.into_iter().zip(keep).filter(|(_, keep)| *keep).map(|(test, _)| test).collect()
+        }
+    }
+}
+
+// 64-bit FNV-1a. Unlike `DefaultHasher`, its output is fixed, so different machines (and
+// different toolchains) agree on which shard a test belongs to.
+fn name_hash(name: &str) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    name.bytes().fold(OFFSET_BASIS, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(PRIME))
+}
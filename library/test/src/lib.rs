@@ -42,7 +42,7 @@ pub mod test {
         cli::{parse_opts, TestOpts},
         filter_tests,
         helpers::metrics::{Metric, MetricMap},
-        options::{Options, RunIgnored, RunStrategy, ShouldPanic},
+        options::{Options, Partition, PartitionKind, RunIgnored, RunStrategy, ShouldPanic},
         run_test, test_main, test_main_static,
         test_result::{TestResult, TrFailed, TrFailedMsg, TrIgnored, TrOk},
         time::{TestExecTime, TestTimeOptions},
@@ -84,7 +84,9 @@ pub mod test {
 
 use core::any::Any;
 use event::{CompletedTest, TestEvent};
+use helpers::baseline::Baseline;
 use helpers::concurrency::get_concurrency;
+use helpers::partition::partition_tests;
 use helpers::shuffle::{get_shuffle_seed, shuffle_tests};
 use options::RunStrategy;
 use test_result::*;
@@ -99,6 +101,13 @@ pub mod test {
 // The default console test runner. It accepts the command line
 // arguments and a vector of test_descs.
 pub fn test_main(args: &[String], tests: Vec<TestDescAndFn>, options: Option<Options>) {
+    // If we're being run in SpawnedSecondary mode, run the test here. This
+    // will then exit the process.
+    if let Ok(name) = env::var(SECONDARY_TEST_INVOKER_VAR) {
+        env::remove_var(SECONDARY_TEST_INVOKER_VAR);
+        run_secondary_test(&name, tests);
+    }
+
     let mut opts = match cli::parse_opts(args) {
         Some(Ok(o)) => o,
         Some(Err(msg)) => {
@@ -110,6 +119,14 @@ pub fn test_main(args: &[String], tests: Vec<TestDescAndFn>, options: Option<Opt
     if let Some(options) = options {
         opts.options = options;
     }
+    // A baseline that can't be read is a mistake on the command line, so report it like the
+    // other usage errors instead of failing the run with an I/O error.
+    if let Some(name) = opts.baseline.as_deref().filter(|_| !opts.list) {
+        if let Err(e) = Baseline::load(name) {
+            eprintln!("error: {e}");
+            process::exit(ERROR_EXIT_CODE);
+        }
+    }
     if opts.list {
         if let Err(e) = console::list_tests_console(&opts, tests) {
             eprintln!("error: io error when listing tests: {e:?}");
@@ -173,40 +190,37 @@ pub fn test_main_static(tests: &[&TestDescAndFn]) {
 /// This is the entry point for the main function generated by `rustc --test`
 /// when panic=abort.
 pub fn test_main_static_abort(tests: &[&TestDescAndFn]) {
-    // If we're being run in SpawnedSecondary mode, run the test here. run_test
-    // will then exit the process.
-    if let Ok(name) = env::var(SECONDARY_TEST_INVOKER_VAR) {
-        env::remove_var(SECONDARY_TEST_INVOKER_VAR);
+    let args = env::args().collect::<Vec<_>>();
+    let owned_tests: Vec<_> = tests.iter().map(make_owned_test).collect();
+    test_main(&args, owned_tests, Some(Options::new().panic_abort(true)))
+}
 
-        // Convert benchmarks to tests if we're not benchmarking.
-        let mut tests = tests.iter().map(make_owned_test).collect::<Vec<_>>();
-        if env::var(SECONDARY_TEST_BENCH_BENCHMARKS_VAR).is_ok() {
-            env::remove_var(SECONDARY_TEST_BENCH_BENCHMARKS_VAR);
-        } else {
-            tests = convert_benchmarks_to_tests(tests);
-        };
+/// Runs the test called `name` in a process spawned by `spawn_test_subprocess`,
+/// and exits with its result.
+fn run_secondary_test(name: &str, mut tests: Vec<TestDescAndFn>) -> ! {
+    // Convert benchmarks to tests if we're not benchmarking.
+    if env::var(SECONDARY_TEST_BENCH_BENCHMARKS_VAR).is_ok() {
+        env::remove_var(SECONDARY_TEST_BENCH_BENCHMARKS_VAR);
+    } else {
+        tests = convert_benchmarks_to_tests(tests);
+    };
 
-        let test = tests
-            .into_iter()
-            .find(|test| test.desc.name.as_slice() == name)
-            .unwrap_or_else(|| panic!("couldn't find a test with the provided name '{name}'"));
-        let TestDescAndFn { desc, testfn } = test;
-        match testfn.into_runnable() {
-            Runnable::Test(runnable_test) => {
-                if runnable_test.is_dynamic() {
-                    panic!("only static tests are supported");
-                }
-                run_test_in_spawned_subprocess(desc, runnable_test);
-            }
-            Runnable::Bench(_) => {
-                panic!("benchmarks should not be executed into child processes")
+    let test = tests
+        .into_iter()
+        .find(|test| test.desc.name.as_slice() == name)
+        .unwrap_or_else(|| panic!("couldn't find a test with the provided name '{name}'"));
+    let TestDescAndFn { desc, testfn } = test;
+    match testfn.into_runnable() {
+        Runnable::Test(runnable_test) => {
+            if runnable_test.is_dynamic() {
+                panic!("only static tests are supported");
             }
+            run_test_in_spawned_subprocess(desc, runnable_test);
+        }
+        Runnable::Bench(_) => {
+            panic!("benchmarks should not be executed into child processes")
         }
     }
-
-    let args = env::args().collect::<Vec<_>>();
-    let owned_tests: Vec<_> = tests.iter().map(make_owned_test).collect();
-    test_main(&args, owned_tests, Some(Options::new().panic_abort(true)))
 }
 
 /// Clones static values for putting into a dynamic vector, which test_main()
@@ -296,6 +310,13 @@ struct TimeoutEntry {
         timeout: Instant,
     }
 
+    struct Retry {
+        testfn: TestFn,
+        failures: usize,
+    }
+
+    type RetryMap = HashMap<TestId, Retry, BuildHasherDefault<DefaultHasher>>;
+
     let tests_len = tests.len();
 
     let mut filtered = FilteredTests { tests: Vec::new(), benches: Vec::new(), next_id: 0 };
@@ -334,6 +355,15 @@ struct TimeoutEntry {
     if let Some(shuffle_seed) = shuffle_seed {
         shuffle_tests(shuffle_seed, &mut remaining);
     }
+    // Keep a copy of every test that can run again if it fails.
+    let mut retries: RetryMap = HashMap::default();
+    if opts.retries > 0 {
+        for (id, test) in &remaining {
+            if let Some(testfn) = test.testfn.try_clone() {
+                retries.insert(*id, Retry { testfn, failures: 0 });
+            }
+        }
+    }
     // Store the tests in a VecDeque so we can efficiently remove the first element to run the
     // tests in the order they were passed (unless shuffled).
     let mut remaining = VecDeque::from(remaining);
@@ -374,19 +404,64 @@ fn calc_timeout(timeout_queue: &VecDeque<TimeoutEntry>) -> Option<Duration> {
         })
     }
 
+    // Returns the test to run again if it failed and has attempts left. Otherwise, the result
+    // is final, and a test that only passed after failing is marked as flaky.
+    fn retry_test(
+        opts: &TestOpts,
+        retries: &mut RetryMap,
+        completed_test: &mut CompletedTest,
+    ) -> Option<TestDescAndFn> {
+        let Some(retry) = retries.get_mut(&completed_test.id) else {
+            // Dynamic tests, e.g. doctests, are consumed by running them.
+            let failed = matches!(
+                completed_test.result,
+                TrFailed | TrFailedMsg(_) | TrTimedFail | TrTimedOut
+            );
+            if opts.retries > 0 && failed {
+                completed_test.stdout.extend_from_slice(
+                    b"note: test was not retried: --retries only supports statically registered \
+                      tests, not dynamic ones such as doctests\n",
+                );
+            }
+            return None;
+        };
+        match completed_test.result {
+            TrFailed | TrFailedMsg(_) | TrTimedFail | TrTimedOut
+                if retry.failures < opts.retries =>
+            {
+                retry.failures += 1;
+                let desc = completed_test.desc.clone();
+                Some(TestDescAndFn { desc, testfn: retry.testfn.try_clone()? })
+            }
+            TrOk if retry.failures > 0 => {
+                completed_test.result = TrFlaky(retry.failures);
+                None
+            }
+            _ => None,
+        }
+    }
+
     if concurrency == 1 {
         while !remaining.is_empty() {
-            let (id, test) = remaining.pop_front().unwrap();
+            let (id, mut test) = remaining.pop_front().unwrap();
             let event = TestEvent::TeWait(test.desc.clone());
             notify_about_test_event(event)?;
-            let join_handle = run_test(opts, !opts.run_tests, id, test, run_strategy, tx.clone());
-            // Wait for the test to complete.
-            let mut completed_test = rx.recv().unwrap();
-            RunningTest { join_handle }.join(&mut completed_test);
+            let completed_test = loop {
+                let join_handle =
+                    run_test(opts, !opts.run_tests, id, test, run_strategy, tx.clone());
+                // Wait for the test to complete.
+                let mut completed_test = rx.recv().unwrap();
+                RunningTest { join_handle }.join(&mut completed_test);
+
+                match retry_test(opts, &mut retries, &mut completed_test) {
+                    Some(retry) => test = retry,
+                    None => break completed_test,
+                }
+            };
 
             let fail_fast = match completed_test.result {
-                TrIgnored | TrOk | TrBench(_) => false,
-                TrFailed | TrFailedMsg(_) | TrTimedFail => opts.fail_fast,
+                TrIgnored | TrOk | TrBench(_) | TrFlaky(_) => false,
+                TrFailed | TrFailedMsg(_) | TrTimedFail | TrTimedOut => opts.fail_fast,
             };
 
             let event = TestEvent::TeResult(completed_test);
@@ -397,14 +472,23 @@ fn calc_timeout(timeout_queue: &VecDeque<TimeoutEntry>) -> Option<Duration> {
             }
         }
     } else {
-        while pending > 0 || !remaining.is_empty() {
-            while pending < concurrency && !remaining.is_empty() {
-                let (id, test) = remaining.pop_front().unwrap();
+        // Failed tests waiting for another attempt. They take precedence over the tests that
+        // haven't started yet, and have already been announced.
+        let mut retried: VecDeque<(TestId, TestDescAndFn)> = VecDeque::new();
+        while pending > 0 || !retried.is_empty() || !remaining.is_empty() {
+            while pending < concurrency && !(retried.is_empty() && remaining.is_empty()) {
+                let (id, test) = match retried.pop_front() {
+                    Some(retry) => retry,
+                    None => {
+                        let (id, test) = remaining.pop_front().unwrap();
+                        let event = TestEvent::TeWait(test.desc.clone());
+                        notify_about_test_event(event)?; //here no pad
+                        (id, test)
+                    }
+                };
                 let timeout = time::get_default_test_timeout();
                 let desc = test.desc.clone();
 
-                let event = TestEvent::TeWait(desc.clone());
-                notify_about_test_event(event)?; //here no pad
                 let join_handle =
                     run_test(opts, !opts.run_tests, id, test, run_strategy, tx.clone());
                 running_tests.insert(id, RunningTest { join_handle });
@@ -440,9 +524,17 @@ fn calc_timeout(timeout_queue: &VecDeque<TimeoutEntry>) -> Option<Duration> {
             let running_test = running_tests.remove(&completed_test.id).unwrap();
             running_test.join(&mut completed_test);
 
+            if let Some(retry) = retry_test(opts, &mut retries, &mut completed_test) {
+                // Don't let the first attempt's deadline apply to the next one.
+                timeout_queue.retain(|entry| entry.id != completed_test.id);
+                retried.push_back((completed_test.id, retry));
+                pending -= 1;
+                continue;
+            }
+
             let fail_fast = match completed_test.result {
-                TrIgnored | TrOk | TrBench(_) => false,
-                TrFailed | TrFailedMsg(_) | TrTimedFail => opts.fail_fast,
+                TrIgnored | TrOk | TrBench(_) | TrFlaky(_) => false,
+                TrFailed | TrFailedMsg(_) | TrTimedFail | TrTimedOut => opts.fail_fast,
             };
 
             let event = TestEvent::TeResult(completed_test);
@@ -512,6 +604,11 @@ pub fn filter_tests(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> Vec<TestDescA
         RunIgnored::No => {}
     }
 
+    // Only keep this machine's share of what is left
+    if let Some(partition) = opts.partition {
+        filtered = partition_tests(partition, filtered);
+    }
+
     filtered
 }
 
@@ -560,6 +657,17 @@ pub fn run_test(
                 };
             }
 
+            // A test with a hard time limit runs in its own process, so that it can be killed
+            // once it runs out of time. Dynamic tests can't be started in a new process, and
+            // simply run without the limit.
+            let timeout = desc.timeout().or(opts.test_timeout);
+            let strategy = match timeout {
+                Some(_) if !runnable_test.is_dynamic() && supports_processes() => {
+                    RunStrategy::SpawnPrimary
+                }
+                _ => strategy,
+            };
+
             let name = desc.name.clone();
             let nocapture = opts.nocapture;
             let time_options = opts.time_options;
@@ -583,6 +691,7 @@ pub fn run_test(
                     monitor_ch,
                     time_options,
                     bench_benchmarks,
+                    timeout,
                 ),
             };
 
@@ -681,6 +790,7 @@ fn spawn_test_subprocess(
     monitor_ch: Sender<CompletedTest>,
     time_opts: Option<time::TestTimeOptions>,
     bench_benchmarks: bool,
+    timeout: Option<Duration>,
 ) {
     let (result, test_output, exec_time) = (|| {
         let args = env::args().collect::<Vec<_>>();
@@ -694,10 +804,13 @@ fn spawn_test_subprocess(
         if nocapture {
             command.stdout(process::Stdio::inherit());
             command.stderr(process::Stdio::inherit());
+        } else {
+            command.stdout(process::Stdio::piped());
+            command.stderr(process::Stdio::piped());
         }
 
         let start = report_time.then(Instant::now);
-        let output = match command.output() {
+        let (status, stdout, stderr) = match output_with_timeout(&mut command, timeout) {
             Ok(out) => out,
             Err(e) => {
                 let err = format!("Failed to spawn {} as child for test: {:?}", args[0], e);
@@ -709,12 +822,14 @@ fn spawn_test_subprocess(
             TestExecTime(duration)
         });
 
-        let std::process::Output { stdout, stderr, status } = output;
         let mut test_output = stdout;
         formatters::write_stderr_delimiter(&mut test_output, &desc.name);
         test_output.extend_from_slice(&stderr);
 
-        let result = get_result_from_exit_code(&desc, status, &time_opts, &exec_time);
+        let result = match status {
+            Some(status) => get_result_from_exit_code(&desc, status, &time_opts, &exec_time),
+            None => TrTimedOut,
+        };
         (result, test_output, exec_time)
     })();
 
@@ -722,13 +837,81 @@ fn spawn_test_subprocess(
     monitor_ch.send(message).unwrap();
 }
 
+/// Like `Command::output`, but kills the child once `timeout` has passed, in
+/// which case no exit status is returned.
+fn output_with_timeout(
+    command: &mut Command,
+    timeout: Option<Duration>,
+) -> io::Result<(Option<process::ExitStatus>, Vec<u8>, Vec<u8>)> {
+    let Some(timeout) = timeout else {
+        let process::Output { status, stdout, stderr } = command.output()?;
+        return Ok((Some(status), stdout, stderr));
+    };
+
+    // Drain the pipes while waiting, so that the child can't block on a full one.
+    fn read_to_end(mut pipe: impl io::Read + Send + 'static) -> thread::JoinHandle<Vec<u8>> {
+        thread::spawn(move || {
+            let mut output = Vec::new();
+            let _ = pipe.read_to_end(&mut output);
+            output
+        })
+    }
+
+    // Give the test a process group of its own, so that the processes it spawned are killed
+    // along with it. They could otherwise keep running, and keep its output pipes open.
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(command, 0);
+
+    let mut child = command.spawn()?;
+    let stdout = child.stdout.take().map(read_to_end);
+    let stderr = child.stderr.take().map(read_to_end);
+
+    // There is no portable way to wait for a child with a timeout, so poll it. Start
+    // with short sleeps, so that quick tests don't have to wait for long.
+    let deadline = Instant::now() + timeout;
+    let mut poll_interval = Duration::from_millis(1);
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            kill_process_group(&mut child);
+            child.wait()?;
+            break None;
+        }
+        thread::sleep(poll_interval.min(deadline - now));
+        poll_interval = (poll_interval * 2).min(Duration::from_millis(100));
+    };
+
+    let join = |reader: Option<thread::JoinHandle<Vec<u8>>>| {
+        reader.map(|reader| reader.join().unwrap_or_default()).unwrap_or_default()
+    };
+    Ok((status, join(stdout), join(stderr)))
+}
+
+/// Kills a child spawned by `output_with_timeout` and, on Unix, the rest of its process
+/// group. The child may have exited just now, so errors are ignored.
+fn kill_process_group(child: &mut process::Child) {
+    #[cfg(unix)]
+    // SAFETY: The child has not been waited for, so its pid still refers to it and to the
+    // process group it leads.
+    unsafe {
+        libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+    }
+    #[cfg(not(unix))]
+    let _ = child.kill();
+}
+
+/// Whether tests can be run in a child process on this platform.
+fn supports_processes() -> bool {
+    !cfg!(target_family = "wasm") && !cfg!(target_os = "zkvm")
+}
+
 fn run_test_in_spawned_subprocess(desc: TestDesc, runnable_test: RunnableTest) -> ! {
     let builtin_panic_hook = panic::take_hook();
-    let record_result = Arc::new(move |panic_info: Option<&'_ PanicInfo<'_>>| {
-        let test_result = match panic_info {
-            Some(info) => calc_result(&desc, Err(info.payload()), &None, &None),
-            None => calc_result(&desc, Ok(()), &None, &None),
-        };
+    let record_result = Arc::new(move |task_result: Result<(), &(dyn Any + Send)>| {
+        let test_result = calc_result(&desc, task_result, &None, &None);
 
         // We don't support serializing TrFailedMsg, so just
         // print the message out to stderr.
@@ -736,10 +919,6 @@ fn run_test_in_spawned_subprocess(desc: TestDesc, runnable_test: RunnableTest) -
             eprintln!("{msg}");
         }
 
-        if let Some(info) = panic_info {
-            builtin_panic_hook(info);
-        }
-
         if let TrOk = test_result {
             process::exit(test_result::TR_OK);
         } else {
@@ -747,10 +926,22 @@ fn run_test_in_spawned_subprocess(desc: TestDesc, runnable_test: RunnableTest) -
         }
     });
     let record_result2 = record_result.clone();
-    panic::set_hook(Box::new(move |info| record_result2(Some(info))));
-    if let Err(message) = runnable_test.run() {
-        panic!("{}", message);
+    panic::set_hook(Box::new(move |info| {
+        builtin_panic_hook(info);
+        // With panic=unwind, the panic may still be caught, either by the test
+        // itself or by the `catch_unwind` below.
+        if !info.can_unwind() {
+            record_result2(Err(info.payload()));
+        }
+    }));
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        if let Err(message) = runnable_test.run() {
+            panic!("{}", message);
+        }
+    }));
+    match result {
+        Ok(()) => record_result(Ok(())),
+        Err(e) => record_result(Err(e.as_ref())),
     }
-    record_result(None);
-    unreachable!("panic=abort callback should have exited the process")
+    unreachable!("recording the result should have exited the process")
 }
@@ -1,5 +1,7 @@
 //! Enums denoting options for test execution.
 
+use std::fmt;
+
 /// Number of times to run a benchmarked function
 #[derive(Clone, PartialEq, Eq)]
 pub enum BenchMode {
@@ -36,6 +38,8 @@ pub enum OutputFormat {
     Json,
     /// JUnit output
     Junit,
+    /// TAP version 14 output
+    Tap,
 }
 
 /// Whether ignored test should be run or not
@@ -47,6 +51,35 @@ pub enum RunIgnored {
     Only,
 }
 
+/// How the tests are assigned to shards by `--partition`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PartitionKind {
+    /// Assign each test by a stable hash of its name
+    Hash,
+    /// Deal the tests out in name order, so the shards differ in size by at most one
+    Count,
+}
+
+/// A single shard of the test suite, selected with `--partition=KIND:K/N`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Partition {
+    pub kind: PartitionKind,
+    /// The 1-based index of the shard to run
+    pub index: usize,
+    /// The total number of shards
+    pub count: usize,
+}
+
+impl fmt::Display for Partition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            PartitionKind::Hash => "hash",
+            PartitionKind::Count => "count",
+        };
+        write!(f, "{kind}:{}/{}", self.index, self.count)
+    }
+}
+
 #[derive(Clone, Copy)]
 pub enum RunStrategy {
     /// Runs the test in the current process, and sends the result back over the
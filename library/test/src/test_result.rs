@@ -29,6 +29,9 @@ pub enum TestResult {
     TrIgnored,
     TrBench(BenchSamples),
     TrTimedFail,
+    TrTimedOut,
+    /// Passed after failing this many times.
+    TrFlaky(usize),
 }
 
 /// Creates a `TestResult` depending on the raw result of test execution
@@ -3,9 +3,13 @@
 use crate::{
     console::OutputLocation,
     formatters::PrettyFormatter,
+    helpers::baseline::{baseline_path, Baseline, Comparison},
+    stats::Summary,
     test::{
         parse_opts,
         MetricMap,
+        Partition,
+        PartitionKind,
         // FIXME (introduced by #65251)
         // ShouldPanic, StaticTestName, TestDesc, TestDescAndFn, TestOpts, TestTimeOptions,
         // TestType, TrFailedMsg, TrIgnored, TrOk,
@@ -32,7 +36,13 @@ fn new() -> TestOpts {
             shuffle_seed: None,
             test_threads: None,
             skip: vec![],
+            partition: None,
             time_options: None,
+            test_timeout: None,
+            retries: 0,
+            save_baseline: None,
+            baseline: None,
+            regression_threshold: None,
             options: Options::new(),
             fail_fast: false,
         }
@@ -54,6 +64,8 @@ fn one_ignored_one_unignored_test() -> Vec<TestDescAndFn> {
                 should_panic: ShouldPanic::No,
                 compile_fail: false,
                 no_run: false,
+                #[cfg(not(bootstrap))]
+                timeout: None,
                 test_type: TestType::Unknown,
             },
             testfn: DynTestFn(Box::new(move || Ok(()))),
@@ -71,6 +83,8 @@ fn one_ignored_one_unignored_test() -> Vec<TestDescAndFn> {
                 should_panic: ShouldPanic::No,
                 compile_fail: false,
                 no_run: false,
+                #[cfg(not(bootstrap))]
+                timeout: None,
                 test_type: TestType::Unknown,
             },
             testfn: DynTestFn(Box::new(move || Ok(()))),
@@ -96,6 +110,8 @@ fn f() -> Result<(), String> {
             should_panic: ShouldPanic::No,
             compile_fail: false,
             no_run: false,
+            #[cfg(not(bootstrap))]
+            timeout: None,
             test_type: TestType::Unknown,
         },
         testfn: DynTestFn(Box::new(f)),
@@ -124,6 +140,8 @@ fn f() -> Result<(), String> {
             should_panic: ShouldPanic::No,
             compile_fail: false,
             no_run: false,
+            #[cfg(not(bootstrap))]
+            timeout: None,
             test_type: TestType::Unknown,
         },
         testfn: DynTestFn(Box::new(f)),
@@ -155,6 +173,8 @@ fn f() -> Result<(), String> {
             should_panic: ShouldPanic::Yes,
             compile_fail: false,
             no_run: false,
+            #[cfg(not(bootstrap))]
+            timeout: None,
             test_type: TestType::Unknown,
         },
         testfn: DynTestFn(Box::new(f)),
@@ -186,6 +206,8 @@ fn f() -> Result<(), String> {
             should_panic: ShouldPanic::YesWithMessage("error message"),
             compile_fail: false,
             no_run: false,
+            #[cfg(not(bootstrap))]
+            timeout: None,
             test_type: TestType::Unknown,
         },
         testfn: DynTestFn(Box::new(f)),
@@ -222,6 +244,8 @@ fn f() -> Result<(), String> {
             should_panic: ShouldPanic::YesWithMessage(expected),
             compile_fail: false,
             no_run: false,
+            #[cfg(not(bootstrap))]
+            timeout: None,
             test_type: TestType::Unknown,
         },
         testfn: DynTestFn(Box::new(f)),
@@ -262,6 +286,8 @@ fn f() -> Result<(), String> {
             should_panic: ShouldPanic::YesWithMessage(expected),
             compile_fail: false,
             no_run: false,
+            #[cfg(not(bootstrap))]
+            timeout: None,
             test_type: TestType::Unknown,
         },
         testfn: DynTestFn(Box::new(f)),
@@ -296,6 +322,8 @@ fn f() -> Result<(), String> {
                 should_panic,
                 compile_fail: false,
                 no_run: false,
+                #[cfg(not(bootstrap))]
+                timeout: None,
                 test_type: TestType::Unknown,
             },
             testfn: DynTestFn(Box::new(f)),
@@ -329,6 +357,8 @@ fn f() -> Result<(), String> {
             should_panic: ShouldPanic::No,
             compile_fail: false,
             no_run: false,
+            #[cfg(not(bootstrap))]
+            timeout: None,
             test_type: TestType::Unknown,
         },
         testfn: DynTestFn(Box::new(f)),
@@ -371,6 +401,8 @@ fn f() -> Result<(), String> {
             should_panic: ShouldPanic::No,
             compile_fail: false,
             no_run: false,
+            #[cfg(not(bootstrap))]
+            timeout: None,
             test_type,
         },
         testfn: DynTestFn(Box::new(f)),
@@ -415,6 +447,8 @@ fn typed_test_desc(test_type: TestType) -> TestDesc {
         should_panic: ShouldPanic::No,
         compile_fail: false,
         no_run: false,
+        #[cfg(not(bootstrap))]
+        timeout: None,
         test_type,
     }
 }
@@ -478,6 +512,83 @@ fn parse_include_ignored_flag() {
     assert_eq!(opts.run_ignored, RunIgnored::Yes);
 }
 
+#[test]
+fn parse_partition_option() {
+    let args = |partition: &str| {
+        vec![
+            "progname".to_string(),
+            "-Zunstable-options".to_string(),
+            format!("--partition={partition}"),
+        ]
+    };
+
+    let opts = parse_opts(&args("count:2/3")).unwrap().unwrap();
+    assert_eq!(opts.partition, Some(Partition { kind: PartitionKind::Count, index: 2, count: 3 }));
+    let opts = parse_opts(&args("hash:1/1")).unwrap().unwrap();
+    assert_eq!(opts.partition, Some(Partition { kind: PartitionKind::Hash, index: 1, count: 1 }));
+
+    for invalid in ["hash:0/2", "hash:3/2", "hash:1", "hash:a/2", "random:1/2", "1/2"] {
+        assert!(parse_opts(&args(invalid)).unwrap().is_err());
+    }
+}
+
+#[test]
+fn parse_test_timeout_option() {
+    let args = |secs: &str| {
+        vec![
+            "progname".to_string(),
+            "-Zunstable-options".to_string(),
+            format!("--test-timeout={secs}"),
+        ]
+    };
+
+    let opts = parse_opts(&args("30")).unwrap().unwrap();
+    assert_eq!(opts.test_timeout, Some(Duration::from_secs(30)));
+
+    for invalid in ["0", "-1", "soon"] {
+        assert!(parse_opts(&args(invalid)).unwrap().is_err());
+    }
+}
+
+#[test]
+fn parse_retries_option() {
+    let args = |n: &str| {
+        vec!["progname".to_string(), "-Zunstable-options".to_string(), format!("--retries={n}")]
+    };
+
+    let opts = parse_opts(&args("3")).unwrap().unwrap();
+    assert_eq!(opts.retries, 3);
+
+    for invalid in ["-1", "often"] {
+        assert!(parse_opts(&args(invalid)).unwrap().is_err());
+    }
+}
+
+#[test]
+fn parse_baseline_options() {
+    let args = |extra: &[&str]| {
+        let mut args = vec!["progname".to_string(), "-Zunstable-options".to_string()];
+        args.extend(extra.iter().map(|arg| arg.to_string()));
+        args
+    };
+
+    let opts = parse_opts(&args(&["--save-baseline=new", "--baseline=old"])).unwrap().unwrap();
+    assert_eq!(opts.save_baseline.as_deref(), Some("new"));
+    assert_eq!(opts.baseline.as_deref(), Some("old"));
+    assert_eq!(opts.regression_threshold, None);
+
+    let opts =
+        parse_opts(&args(&["--baseline=old", "--regression-threshold=2.5"])).unwrap().unwrap();
+    assert_eq!(opts.regression_threshold, Some(2.5));
+
+    // The threshold needs a baseline to compare to.
+    assert!(parse_opts(&args(&["--regression-threshold=5"])).unwrap().is_err());
+    for invalid in ["-1", "fast"] {
+        let threshold = format!("--regression-threshold={invalid}");
+        assert!(parse_opts(&args(&["--baseline=old", &threshold])).unwrap().is_err());
+    }
+}
+
 #[test]
 pub fn filter_for_ignored_option() {
     // When we run ignored tests the test filter should filter out all the
@@ -532,6 +643,8 @@ pub fn exclude_should_panic_option() {
             should_panic: ShouldPanic::Yes,
             compile_fail: false,
             no_run: false,
+            #[cfg(not(bootstrap))]
+            timeout: None,
             test_type: TestType::Unknown,
         },
         testfn: DynTestFn(Box::new(move || Ok(()))),
@@ -561,6 +674,8 @@ fn tests() -> Vec<TestDescAndFn> {
                     should_panic: ShouldPanic::No,
                     compile_fail: false,
                     no_run: false,
+                    #[cfg(not(bootstrap))]
+                    timeout: None,
                     test_type: TestType::Unknown,
                 },
                 testfn: DynTestFn(Box::new(move || Ok(()))),
@@ -657,6 +772,8 @@ fn testfn() -> Result<(), String> {
                 should_panic: ShouldPanic::No,
                 compile_fail: false,
                 no_run: false,
+                #[cfg(not(bootstrap))]
+                timeout: None,
                 test_type: TestType::Unknown,
             },
             testfn: DynTestFn(Box::new(testfn)),
@@ -731,6 +848,62 @@ pub fn order_depends_on_more_than_seed() {
     assert!(left.iter().zip(right).any(|(a, b)| a.0 != b.0));
 }
 
+fn partition_names(kind: PartitionKind, count: usize) -> Vec<Vec<String>> {
+    (1..=count)
+        .map(|index| {
+            let opts =
+                TestOpts { partition: Some(Partition { kind, index, count }), ..TestOpts::new() };
+            filter_tests(&opts, sample_tests())
+                .iter()
+                .map(|test| test.desc.name.to_string())
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+pub fn partitions_cover_all_tests_once() {
+    let mut all: Vec<String> =
+        sample_tests().iter().map(|test| test.desc.name.to_string()).collect();
+    all.sort();
+
+    for kind in [PartitionKind::Hash, PartitionKind::Count] {
+        for count in 1..=5 {
+            let mut union: Vec<String> = partition_names(kind, count).concat();
+            union.sort();
+            assert_eq!(union, all);
+        }
+    }
+}
+
+#[test]
+pub fn partitions_are_stable() {
+    for kind in [PartitionKind::Hash, PartitionKind::Count] {
+        assert_eq!(partition_names(kind, 3), partition_names(kind, 3));
+    }
+
+    // The shards don't depend on the order in which the tests are registered.
+    let partition = Partition { kind: PartitionKind::Count, index: 2, count: 3 };
+    let opts = TestOpts { partition: Some(partition), ..TestOpts::new() };
+    let mut reversed = sample_tests();
+    reversed.reverse();
+    let mut left: Vec<String> =
+        filter_tests(&opts, sample_tests()).iter().map(|test| test.desc.name.to_string()).collect();
+    let mut right: Vec<String> =
+        filter_tests(&opts, reversed).iter().map(|test| test.desc.name.to_string()).collect();
+    left.sort();
+    right.sort();
+    assert_eq!(left, right);
+}
+
+#[test]
+pub fn count_partitions_are_balanced() {
+    let shards = partition_names(PartitionKind::Count, 5);
+    let min = shards.iter().map(Vec::len).min().unwrap();
+    let max = shards.iter().map(Vec::len).max().unwrap();
+    assert!(max - min <= 1);
+}
+
 #[test]
 pub fn test_metricmap_compare() {
     let mut m1 = MetricMap::new();
@@ -791,6 +964,8 @@ fn f(_: &mut Bencher) -> Result<(), String> {
         should_panic: ShouldPanic::No,
         compile_fail: false,
         no_run: false,
+        #[cfg(not(bootstrap))]
+        timeout: None,
         test_type: TestType::Unknown,
     };
 
@@ -819,6 +994,8 @@ fn f(b: &mut Bencher) -> Result<(), String> {
         should_panic: ShouldPanic::No,
         compile_fail: false,
         no_run: false,
+        #[cfg(not(bootstrap))]
+        timeout: None,
         test_type: TestType::Unknown,
     };
 
@@ -840,6 +1017,8 @@ fn should_sort_failures_before_printing_them() {
         should_panic: ShouldPanic::No,
         compile_fail: false,
         no_run: false,
+        #[cfg(not(bootstrap))]
+        timeout: None,
         test_type: TestType::Unknown,
     };
 
@@ -855,6 +1034,8 @@ fn should_sort_failures_before_printing_them() {
         should_panic: ShouldPanic::No,
         compile_fail: false,
         no_run: false,
+        #[cfg(not(bootstrap))]
+        timeout: None,
         test_type: TestType::Unknown,
     };
 
@@ -865,6 +1046,7 @@ fn should_sort_failures_before_printing_them() {
         total: 0,
         passed: 0,
         failed: 0,
+        flaky: 0,
         ignored: 0,
         filtered_out: 0,
         measured: 0,
@@ -875,6 +1057,12 @@ fn should_sort_failures_before_printing_them() {
         not_failures: Vec::new(),
         ignores: Vec::new(),
         time_failures: Vec::new(),
+        flakes: Vec::new(),
+        retries: 0,
+        partition: None,
+        baseline: None,
+        regression_threshold: None,
+        bench_results: Baseline::new(),
     };
 
     out.write_failures(&st).unwrap();
@@ -907,6 +1095,8 @@ fn f(_: &mut Bencher) -> Result<(), String> {
             should_panic: ShouldPanic::No,
             compile_fail: false,
             no_run: false,
+            #[cfg(not(bootstrap))]
+            timeout: None,
             test_type: TestType::Unknown,
         },
         testfn: DynBenchFn(Box::new(f)),
@@ -922,3 +1112,240 @@ fn f(_: &mut Bencher) -> Result<(), String> {
     let result = rx.recv().unwrap().result;
     assert_eq!(result, TrFailed);
 }
+
+#[test]
+#[cfg(not(target_os = "emscripten"))]
+fn test_retries_report_flaky_tests() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static FLAKY_RUNS: AtomicUsize = AtomicUsize::new(0);
+    static FAILING_RUNS: AtomicUsize = AtomicUsize::new(0);
+
+    fn flaky() -> Result<(), String> {
+        match FLAKY_RUNS.fetch_add(1, Ordering::SeqCst) {
+            0 => Err("first attempt".into()),
+            _ => Ok(()),
+        }
+    }
+    fn failing() -> Result<(), String> {
+        FAILING_RUNS.fetch_add(1, Ordering::SeqCst);
+        Err("every attempt".into())
+    }
+    fn passing() -> Result<(), String> {
+        Ok(())
+    }
+
+    let test = |name, testfn| TestDescAndFn {
+        desc: TestDesc {
+            name: StaticTestName(name),
+            ignore: false,
+            ignore_message: None,
+            source_file: "",
+            start_line: 0,
+            start_col: 0,
+            end_line: 0,
+            end_col: 0,
+            should_panic: ShouldPanic::No,
+            compile_fail: false,
+            no_run: false,
+            #[cfg(not(bootstrap))]
+            timeout: None,
+            test_type: TestType::Unknown,
+        },
+        testfn: StaticTestFn(testfn),
+    };
+    let tests = vec![test("flaky", flaky), test("failing", failing), test("passing", passing)];
+
+    let (tx, rx) = channel();
+    let notify = move |event: TestEvent| {
+        if let TestEvent::TeResult(result) = event {
+            tx.send(result).unwrap();
+        }
+        Ok(())
+    };
+    let opts = TestOpts { run_tests: true, retries: 2, ..TestOpts::new() };
+    run_tests(&opts, tests, notify).unwrap();
+
+    let mut results: Vec<_> =
+        rx.iter().map(|test| (test.desc.name.to_string(), test.result)).collect();
+    results.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        results,
+        [
+            ("failing".to_string(), TrFailed),
+            ("flaky".to_string(), TrFlaky(1)),
+            ("passing".to_string(), TrOk),
+        ]
+    );
+    assert_eq!(FLAKY_RUNS.load(Ordering::SeqCst), 2);
+    assert_eq!(FAILING_RUNS.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+#[cfg(not(target_os = "emscripten"))]
+fn test_retries_skip_dynamic_tests() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let runs = Arc::new(AtomicUsize::new(0));
+    let desc = TestDescAndFn {
+        desc: TestDesc {
+            name: StaticTestName("dynamic"),
+            ignore: false,
+            ignore_message: None,
+            source_file: "",
+            start_line: 0,
+            start_col: 0,
+            end_line: 0,
+            end_col: 0,
+            should_panic: ShouldPanic::No,
+            compile_fail: false,
+            no_run: false,
+            #[cfg(not(bootstrap))]
+            timeout: None,
+            test_type: TestType::Unknown,
+        },
+        testfn: DynTestFn(Box::new({
+            let runs = runs.clone();
+            move || {
+                runs.fetch_add(1, Ordering::SeqCst);
+                Err("every attempt".into())
+            }
+        })),
+    };
+
+    let (tx, rx) = channel();
+    let notify = move |event: TestEvent| {
+        if let TestEvent::TeResult(result) = event {
+            tx.send(result).unwrap();
+        }
+        Ok(())
+    };
+    let opts = TestOpts { run_tests: true, retries: 2, ..TestOpts::new() };
+    run_tests(&opts, vec![desc], notify).unwrap();
+
+    let result = rx.recv().unwrap();
+    assert_eq!(result.result, TrFailed);
+    assert!(String::from_utf8_lossy(&result.stdout).contains("note: test was not retried"));
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn baseline_roundtrip() {
+    let mut baseline = Baseline::new();
+    baseline.insert("bench_one", &Summary::new(&[1.0, 2.0, 4.0]));
+    baseline.insert("bench\twith tab", &Summary::new(&[0.1, 0.2, 0.3]));
+    assert_eq!(Baseline::parse(&baseline.to_string()), Ok(baseline));
+
+    assert!(Baseline::parse("").is_err());
+    assert!(Baseline::parse("# libtest benchmark baseline v1\n1.0\t2.0\n").is_err());
+}
+
+#[test]
+fn baseline_not_saved_without_benchmarks() {
+    let name = "not-saved-without-benchmarks";
+    let mut baseline = Baseline::new();
+    baseline.insert("bench", &Summary::new(&[1.0, 2.0, 4.0]));
+    baseline.save(name).unwrap();
+
+    // Only tests run, so there is nothing to replace the earlier baseline with.
+    let mut opts = TestOpts::new();
+    opts.run_tests = true;
+    opts.save_baseline = Some(name.to_string());
+    let tests = one_ignored_one_unignored_test();
+    assert!(console::run_tests_console(&opts, tests).unwrap());
+
+    let saved = Baseline::load(name);
+    std::fs::remove_file(baseline_path(name).unwrap()).unwrap();
+    assert_eq!(saved.unwrap(), baseline);
+}
+
+#[test]
+fn baseline_comparison() {
+    let samples =
+        |center: f64| -> Vec<f64> { (0..50).map(|i| center + (i % 5) as f64 - 2.0).collect() };
+    let mut baseline = Baseline::new();
+    baseline.insert("bench", &Summary::new(&samples(100.0)));
+    let old = baseline.get("bench").unwrap();
+
+    // Within the noise of the samples.
+    let same = Comparison::new(old, &Summary::new(&samples(100.1)));
+    assert!(!same.significant);
+    assert!(!same.is_regression(0.0));
+
+    let slower = Comparison::new(old, &Summary::new(&samples(110.0)));
+    assert!(slower.significant);
+    assert!((slower.change_pct - 10.0).abs() < 1e-9);
+    assert!(slower.is_regression(5.0));
+    assert!(!slower.is_regression(20.0));
+    assert_eq!(slower.to_string(), "+10.00%, regressed");
+
+    let faster = Comparison::new(old, &Summary::new(&samples(90.0)));
+    assert!(faster.significant);
+    assert!(!faster.is_regression(0.0));
+    assert_eq!(faster.to_string(), "-10.00%, improved");
+}
+
+#[test]
+fn junit_reports_regressions_as_failures() {
+    use crate::bench::BenchSamples;
+    use crate::formatters::{JunitFormatter, OutputFormatter};
+
+    let samples =
+        |center: f64| -> Vec<f64> { (0..50).map(|i| center + (i % 5) as f64 - 2.0).collect() };
+    let mut baseline = Baseline::new();
+    baseline.insert("bench", &Summary::new(&samples(100.0)));
+
+    let desc = TestDesc {
+        name: StaticTestName("bench"),
+        ignore: false,
+        ignore_message: None,
+        source_file: "",
+        start_line: 0,
+        start_col: 0,
+        end_line: 0,
+        end_col: 0,
+        should_panic: ShouldPanic::No,
+        compile_fail: false,
+        no_run: false,
+        #[cfg(not(bootstrap))]
+        timeout: None,
+        test_type: TestType::Unknown,
+    };
+    let st = console::ConsoleTestState {
+        log_out: None,
+        total: 1,
+        passed: 0,
+        failed: 1,
+        flaky: 0,
+        ignored: 0,
+        filtered_out: 0,
+        measured: 0,
+        exec_time: None,
+        metrics: MetricMap::new(),
+        failures: Vec::new(),
+        options: Options::new(),
+        not_failures: Vec::new(),
+        ignores: Vec::new(),
+        time_failures: Vec::new(),
+        flakes: Vec::new(),
+        retries: 0,
+        partition: None,
+        baseline: Some(baseline),
+        regression_threshold: Some(5.0),
+        bench_results: Baseline::new(),
+    };
+
+    let mut out = JunitFormatter::new(OutputLocation::Raw(Vec::new()));
+    let bs = BenchSamples { ns_iter_summ: Summary::new(&samples(110.0)), mb_s: 0 };
+    out.write_result(&desc, &TrBench(bs), None, &[], &st).unwrap();
+    assert!(!out.write_run_finish(&st).unwrap());
+    let s = match out.output_location() {
+        &OutputLocation::Raw(ref m) => String::from_utf8_lossy(&m[..]).into_owned(),
+        &OutputLocation::Pretty(_) => unreachable!(),
+    };
+
+    assert!(s.contains(r#"failures="1""#));
+    let msg = "benchmark is 10.00% slower than in the baseline, more than the threshold of 5%";
+    assert!(s.contains(&format!(r#"<failure message="{msg}" type="regression"/>"#)));
+}
@@ -3,6 +3,7 @@
 use std::borrow::Cow;
 use std::fmt;
 use std::sync::mpsc::Sender;
+use std::time::Duration;
 
 use super::__rust_begin_short_backtrace;
 use super::bench::Bencher;
@@ -113,6 +114,17 @@ pub(crate) fn into_runnable(self) -> Runnable {
             DynBenchAsTestFn(f) => Runnable::Test(RunnableTest::DynamicBenchAsTest(f)),
         }
     }
+
+    /// Copies a statically registered test function. Dynamic test functions
+    /// are consumed by running them, so they can't be run a second time.
+    pub(crate) fn try_clone(&self) -> Option<TestFn> {
+        match *self {
+            StaticTestFn(f) => Some(StaticTestFn(f)),
+            StaticBenchFn(f) => Some(StaticBenchFn(f)),
+            StaticBenchAsTestFn(f) => Some(StaticBenchAsTestFn(f)),
+            DynTestFn(..) | DynBenchFn(..) | DynBenchAsTestFn(..) => None,
+        }
+    }
 }
 
 impl fmt::Debug for TestFn {
@@ -207,6 +219,10 @@ pub struct TestDesc {
     pub should_panic: options::ShouldPanic,
     pub compile_fail: bool,
     pub no_run: bool,
+    // Kill the test if it runs for longer than this many seconds (`#[test_timeout]`).
+    // FIXME: remove the `cfg` once the bootstrap compiler's `#[test]` sets this field.
+    #[cfg(not(bootstrap))]
+    pub timeout: Option<u32>,
     pub test_type: TestType,
 }
 
@@ -224,6 +240,14 @@ pub fn padded_name(&self, column_count: usize, align: NamePadding) -> String {
         }
     }
 
+    /// The hard time limit set on this test with `#[test_timeout]`, if any.
+    pub fn timeout(&self) -> Option<Duration> {
+        #[cfg(not(bootstrap))]
+        return self.timeout.map(|secs| Duration::from_secs(secs.into()));
+        #[cfg(bootstrap)]
+        None
+    }
+
     /// Returns None for ignored test or tests that are just run, otherwise returns a description of the type of test.
     /// Descriptions include "should panic", "compile fail" and "compile".
     pub fn test_mode(&self) -> Option<&'static str> {
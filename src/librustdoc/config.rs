@@ -8,9 +8,9 @@
 use rustc_session::config::{
     self, parse_crate_types_from_list, parse_externs, parse_target_triple, CrateType,
 };
-use rustc_session::config::{get_cmd_lint_options, nightly_options};
+use rustc_session::config::{get_cmd_lint_options, nightly_options, read_lint_config};
 use rustc_session::config::{
-    CodegenOptions, ErrorOutputType, Externs, JsonUnusedExterns, UnstableOptions,
+    CodegenOptions, ErrorOutputType, Externs, JsonUnusedExterns, LintConfigFile, UnstableOptions,
 };
 use rustc_session::getopts;
 use rustc_session::lint::Level;
@@ -104,6 +104,8 @@ pub(crate) struct Options {
     pub(crate) describe_lints: bool,
     /// What level to cap lints at.
     pub(crate) lint_cap: Option<Level>,
+    /// The `--lint-config` file.
+    pub(crate) lint_config: Option<LintConfigFile>,
 
     // Options specific to running doctests
     /// Whether we should run doctests instead of generating docs.
@@ -196,6 +198,7 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             .field("lint_opts", &self.lint_opts)
             .field("describe_lints", &self.describe_lints)
             .field("lint_cap", &self.lint_cap)
+            .field("lint_config", &self.lint_config.as_ref().map(|file| &file.path))
             .field("should_test", &self.should_test)
             .field("test_args", &self.test_args)
             .field("test_run_directory", &self.test_run_directory)
@@ -449,6 +452,7 @@ fn println_condition(condition: Condition) {
         }
 
         let (lint_opts, describe_lints, lint_cap) = get_cmd_lint_options(early_dcx, matches);
+        let lint_config = read_lint_config(early_dcx, matches);
 
         let input = PathBuf::from(if describe_lints {
             "" // dummy, this won't be used
@@ -741,6 +745,7 @@ fn println_condition(condition: Condition) {
             lint_opts,
             describe_lints,
             lint_cap,
+            lint_config,
             should_test,
             test_args,
             show_coverage,
@@ -474,6 +474,9 @@ fn opts() -> Vec<RustcOptGroup> {
                 "LEVEL",
             )
         }),
+        unstable("lint-config", |o| {
+            o.optopt("", "lint-config", "Read lint levels from a TOML file", "PATH")
+        }),
         unstable("index-page", |o| {
             o.optopt("", "index-page", "Markdown file to be used as index page", "PATH")
         }),
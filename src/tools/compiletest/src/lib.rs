@@ -548,9 +548,22 @@ pub fn test_opts(config: &Config) -> test::TestOpts {
         shuffle_seed: None,
         test_threads: None,
         skip: config.skip.clone(),
+        // Not in the stage0 libtest yet.
+        #[cfg(not(bootstrap))]
+        partition: None,
         list: false,
         options: test::Options::new(),
         time_options: None,
+        #[cfg(not(bootstrap))]
+        test_timeout: None,
+        #[cfg(not(bootstrap))]
+        retries: 0,
+        #[cfg(not(bootstrap))]
+        save_baseline: None,
+        #[cfg(not(bootstrap))]
+        baseline: None,
+        #[cfg(not(bootstrap))]
+        regression_threshold: None,
         force_run_in_process: false,
         fail_fast: std::env::var_os("RUSTC_TEST_FAIL_FAST").is_some(),
     }
@@ -108,6 +108,12 @@ pub enum BlockReason {
     Futex { addr: u64 },
     /// Blocked on an InitOnce.
     InitOnce(InitOnceId),
+    /// Blocked on a `read` from a file descriptor that has no data available yet.
+    Read,
+    /// Blocked on `epoll_wait` until an event is ready.
+    Epoll,
+    /// Blocked on `accept` until a connection arrives.
+    Accept,
 }
 
 /// The state of a thread.
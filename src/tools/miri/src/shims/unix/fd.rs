@@ -2,15 +2,36 @@
 //! standard file descriptors (stdin/stdout/stderr).
 
 use std::any::Any;
+use std::cell::RefCell;
 use std::collections::BTreeMap;
 use std::io::{self, ErrorKind, IsTerminal, Read, SeekFrom, Write};
+use std::net::SocketAddr;
+use std::rc::Rc;
 
 use rustc_middle::ty::TyCtxt;
 use rustc_target::abi::Size;
 
+use crate::concurrency::VClock;
+use crate::shims::unix::linux::epoll::{BlockedEpollWait, EvalContextExt as _};
+use crate::shims::unix::net::{BlockedAccept, Network};
 use crate::shims::unix::*;
 use crate::*;
 
+/// Whether operations on an FD can make progress, as reported by `epoll`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Readiness {
+    /// A `read` would not block.
+    pub readable: bool,
+    /// A `write` would not block.
+    pub writable: bool,
+    /// The peer has stopped writing, so reads will hit EOF once the data is drained.
+    pub read_hangup: bool,
+    /// The peer is closed in both directions.
+    pub hangup: bool,
+    /// An error is pending, like the read end of a pipe being closed.
+    pub error: bool,
+}
+
 /// Represents an open file descriptor.
 pub trait FileDescriptor: std::fmt::Debug + Any {
     fn name(&self) -> &'static str;
@@ -48,6 +69,18 @@ fn close<'tcx>(
         throw_unsup_format!("cannot close {}", self.name());
     }
 
+    /// Whether this FD was opened for reading. `read` fails with `EBADF` on FDs that weren't,
+    /// like the write end of a pipe.
+    fn is_readable(&self) -> bool {
+        true
+    }
+
+    /// Whether this FD was opened for writing. `write` fails with `EBADF` on FDs that weren't,
+    /// like the read end of a pipe.
+    fn is_writable(&self) -> bool {
+        true
+    }
+
     /// Return a new file descriptor *that refers to the same underlying object*.
     fn dup(&mut self) -> io::Result<Box<dyn FileDescriptor>>;
 
@@ -56,6 +89,40 @@ fn is_tty(&self, _communicate_allowed: bool) -> bool {
         // so we use a default impl here.
         false
     }
+
+    /// Whether a `read` that fails with `WouldBlock` should block the calling thread until the
+    /// read can make progress. Only FDs whose state is entirely modeled by Miri may return
+    /// `true`: blocked reads are retried after every `write` and `close`, and nothing else would
+    /// ever wake the thread up.
+    fn is_blocking(&self) -> bool {
+        false
+    }
+
+    /// Switches between blocking and non-blocking mode, for `ioctl(FIONBIO)`.
+    fn set_nonblocking<'tcx>(&self, _nonblocking: bool) -> InterpResult<'tcx> {
+        throw_unsup_format!("cannot change the blocking mode of {}", self.name());
+    }
+
+    /// The address the data returned by the last successful `read` came from, for `recvfrom`.
+    fn last_read_source(&self) -> Option<SocketAddr> {
+        None
+    }
+
+    /// Called after a successful `write` with the clock of the writing thread, for FDs that pass
+    /// data between threads.
+    fn release_clock(&self, _clock: &VClock) {}
+
+    /// The clock a successful `read` synchronizes with, for FDs that pass data between threads.
+    fn acquire_clock(&self) -> Option<VClock> {
+        None
+    }
+
+    /// The current readiness of this FD, or `None` if it cannot be watched by `epoll`.
+    /// Readiness is recomputed after every `read`, `write` and `close`, so it may only change
+    /// through those.
+    fn readiness(&self) -> Option<Readiness> {
+        None
+    }
 }
 
 impl dyn FileDescriptor {
@@ -178,30 +245,87 @@ fn dup(&mut self) -> io::Result<Box<dyn FileDescriptor>> {
     }
 }
 
+/// For `recvfrom`, where the address the data came from and its length are written to.
+type SrcAddr = (Pointer<Option<Provenance>>, Pointer<Option<Provenance>>);
+
+/// A blocking `read` that is waiting for its FD to become readable.
+#[derive(Debug)]
+struct BlockedRead {
+    /// The thread that called `read`.
+    thread: ThreadId,
+    /// A `dup` of the FD being read from, so that the read is not affected by the original FD
+    /// being closed in the meantime.
+    file_descriptor: Box<dyn FileDescriptor>,
+    buf: Pointer<Option<Provenance>>,
+    count: u64,
+    src_addr: Option<SrcAddr>,
+    /// The place the return value of `read` is written to.
+    dest: Pointer<Option<Provenance>>,
+}
+
+/// Identifies an open FD. Unlike its number, which is reused once the FD is closed, an FD's
+/// id is never reused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FdId(u64);
+
 /// The file descriptor table
 #[derive(Debug)]
 pub struct FdTable {
     pub fds: BTreeMap<i32, Box<dyn FileDescriptor>>,
+    /// The id of each open FD.
+    ids: BTreeMap<i32, FdId>,
+    /// The id the next FD will get.
+    next_id: FdId,
+    /// Reads that are blocked until their FD becomes readable, in the order they were made.
+    blocked_reads: Vec<BlockedRead>,
+    /// `epoll_wait` calls that are blocked until an event is ready, in the order they were made.
+    pub(crate) blocked_epoll_waits: Vec<BlockedEpollWait>,
+    /// `accept` calls that are blocked until a connection arrives, in the order they were made.
+    pub(crate) blocked_accepts: Vec<BlockedAccept>,
+    /// The virtual network that `AF_INET` and `AF_INET6` sockets are bound to.
+    pub(crate) network: Rc<RefCell<Network>>,
 }
 
 impl VisitProvenance for FdTable {
-    fn visit_provenance(&self, _visit: &mut VisitWith<'_>) {
-        // All our FileDescriptor do not have any tags.
+    fn visit_provenance(&self, visit: &mut VisitWith<'_>) {
+        // All our FileDescriptor do not have any tags, but blocked reads hold pointers.
+        for blocked_read in &self.blocked_reads {
+            blocked_read.buf.visit_provenance(visit);
+            if let Some((addr, addrlen)) = blocked_read.src_addr {
+                addr.visit_provenance(visit);
+                addrlen.visit_provenance(visit);
+            }
+            blocked_read.dest.visit_provenance(visit);
+        }
+        for blocked_epoll_wait in &self.blocked_epoll_waits {
+            blocked_epoll_wait.visit_provenance(visit);
+        }
+        for blocked_accept in &self.blocked_accepts {
+            blocked_accept.visit_provenance(visit);
+        }
     }
 }
 
 impl FdTable {
     pub(crate) fn new(mute_stdout_stderr: bool) -> FdTable {
-        let mut fds: BTreeMap<_, Box<dyn FileDescriptor>> = BTreeMap::new();
-        fds.insert(0i32, Box::new(io::stdin()));
+        let mut fds = FdTable {
+            fds: BTreeMap::new(),
+            ids: BTreeMap::new(),
+            next_id: FdId(0),
+            blocked_reads: Vec::new(),
+            blocked_epoll_waits: Vec::new(),
+            blocked_accepts: Vec::new(),
+            network: Rc::default(),
+        };
+        fds.insert_fd(Box::new(io::stdin()));
         if mute_stdout_stderr {
-            fds.insert(1i32, Box::new(NullOutput));
-            fds.insert(2i32, Box::new(NullOutput));
+            fds.insert_fd(Box::new(NullOutput));
+            fds.insert_fd(Box::new(NullOutput));
         } else {
-            fds.insert(1i32, Box::new(io::stdout()));
-            fds.insert(2i32, Box::new(io::stderr()));
+            fds.insert_fd(Box::new(io::stdout()));
+            fds.insert_fd(Box::new(io::stderr()));
         }
-        FdTable { fds }
+        fds
     }
 
     pub fn insert_fd(&mut self, file_handle: Box<dyn FileDescriptor>) -> i32 {
@@ -236,6 +360,8 @@ pub fn insert_fd_with_min_fd(
         });
 
         self.fds.try_insert(new_fd, file_handle).unwrap();
+        self.ids.insert(new_fd, self.next_id);
+        self.next_id.0 += 1;
         new_fd
     }
 
@@ -247,7 +373,13 @@ pub fn get_mut(&mut self, fd: i32) -> Option<&mut dyn FileDescriptor> {
         Some(&mut **self.fds.get_mut(&fd)?)
     }
 
+    /// The id of an open FD.
+    pub fn id(&self, fd: i32) -> Option<FdId> {
+        self.ids.get(&fd).copied()
+    }
+
     pub fn remove(&mut self, fd: i32) -> Option<Box<dyn FileDescriptor>> {
+        self.ids.remove(&fd);
         self.fds.remove(&fd)
     }
 
@@ -323,20 +455,71 @@ fn fcntl(&mut self, args: &[OpTy<'tcx, Provenance>]) -> InterpResult<'tcx, i32>
         }
     }
 
+    fn ioctl(&mut self, args: &[OpTy<'tcx, Provenance>]) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        if args.len() < 2 {
+            throw_ub_format!(
+                "incorrect number of arguments for ioctl: got {}, expected at least 2",
+                args.len()
+            );
+        }
+        let fd = this.read_scalar(&args[0])?.to_i32()?;
+        // The type of the request differs between targets, and does not always match the
+        // type of the constants.
+        let request = this.read_scalar(&args[1])?.to_bits(args[1].layout.size)?;
+        let request_is = |name: &str| -> InterpResult<'tcx, bool> {
+            let value = this.eval_libc(name);
+            Ok(request == value.to_bits(value.size())?)
+        };
+
+        // We only support switching a descriptor between blocking and non-blocking mode, and
+        // setting close-on-exec, which std does on some targets.
+        if request_is("FIONBIO")? {
+            if args.len() < 3 {
+                throw_ub_format!(
+                    "incorrect number of arguments for ioctl with request=`FIONBIO`: got {}, expected at least 3",
+                    args.len()
+                );
+            }
+            let nonblocking = this.deref_pointer_as(&args[2], this.machine.layouts.i32)?;
+            let nonblocking = this.read_scalar(&nonblocking)?.to_i32()? != 0;
+            match this.machine.fds.get(fd) {
+                Some(file_descriptor) => {
+                    file_descriptor.set_nonblocking(nonblocking)?;
+                    Ok(0)
+                }
+                None => this.fd_not_found(),
+            }
+        } else if request_is("FIOCLEX")? || request_is("FIONCLEX")? {
+            // Since Miri doesn't support `exec`, close-on-exec has no effect.
+            match this.machine.fds.get(fd) {
+                Some(_) => Ok(0),
+                None => this.fd_not_found(),
+            }
+        } else {
+            throw_unsup_format!("the {:#x} request is not supported for `ioctl`", request);
+        }
+    }
+
     fn close(&mut self, fd_op: &OpTy<'tcx, Provenance>) -> InterpResult<'tcx, Scalar<Provenance>> {
         let this = self.eval_context_mut();
 
         let fd = this.read_scalar(fd_op)?.to_i32()?;
 
-        Ok(Scalar::from_i32(if let Some(file_descriptor) = this.machine.fds.remove(fd) {
+        let result = if let Some(file_descriptor) = this.machine.fds.remove(fd) {
             let result = file_descriptor.close(this.machine.communicate())?;
             this.try_unwrap_io_result(result)?
         } else {
             this.fd_not_found()?
-        }))
+        };
+        // Closing an FD can make reads from its peer hit EOF.
+        this.fd_readiness_changed()?;
+        Ok(Scalar::from_i32(result))
     }
 
-    /// Function used when a file descriptor does not exist. It returns `Ok(-1)`and sets
+    /// Function used when a file descriptor does not exist, or was not opened for the requested
+    /// operation. It returns `Ok(-1)`and sets
     /// the last OS error to `libc::EBADF` (invalid file descriptor). This function uses
     /// `T: From<i32>` instead of `i32` directly because some fs functions return different integer
     /// types (like `read`, that returns an `i64`).
@@ -347,12 +530,16 @@ fn fd_not_found<T: From<i32>>(&mut self) -> InterpResult<'tcx, T> {
         Ok((-1).into())
     }
 
+    /// Reads from `fd` and writes the result to `dest`. For `recvfrom`, `src_addr` is where the
+    /// address the data came from and its length are written to.
     fn read(
         &mut self,
         fd: i32,
         buf: Pointer<Option<Provenance>>,
         count: u64,
-    ) -> InterpResult<'tcx, i64> {
+        src_addr: Option<SrcAddr>,
+        dest: &MPlaceTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx> {
         let this = self.eval_context_mut();
 
         // Isolation check is done via `FileDescriptor` trait.
@@ -369,33 +556,131 @@ fn read(
             .min(u64::try_from(isize::MAX).unwrap());
         let communicate = this.machine.communicate();
 
-        if let Some(file_descriptor) = this.machine.fds.get_mut(fd) {
-            trace!("read: FD mapped to {:?}", file_descriptor);
-            // We want to read at most `count` bytes. We are sure that `count` is not negative
-            // because it was a target's `usize`. Also we are sure that its smaller than
-            // `usize::MAX` because it is bounded by the host's `isize`.
-            let mut bytes = vec![0; usize::try_from(count).unwrap()];
-            // `File::read` never returns a value larger than `count`,
-            // so this cannot fail.
-            let result = file_descriptor
-                .read(communicate, &mut bytes, *this.tcx)?
-                .map(|c| i64::try_from(c).unwrap());
-
-            match result {
-                Ok(read_bytes) => {
-                    // If reading to `bytes` did not fail, we write those bytes to the buffer.
-                    this.write_bytes_ptr(buf, bytes)?;
-                    Ok(read_bytes)
+        let Some(file_descriptor) = this.machine.fds.get_mut(fd) else {
+            trace!("read: FD not found");
+            let result: i64 = this.fd_not_found()?;
+            return this.write_scalar(Scalar::from_target_isize(result, this), dest);
+        };
+        trace!("read: FD mapped to {:?}", file_descriptor);
+        if !file_descriptor.is_readable() {
+            let result: i64 = this.fd_not_found()?;
+            return this.write_scalar(Scalar::from_target_isize(result, this), dest);
+        }
+        // We want to read at most `count` bytes. We are sure that `count` is not negative
+        // because it was a target's `usize`. Also we are sure that its smaller than
+        // `usize::MAX` because it is bounded by the host's `isize`.
+        let mut bytes = vec![0; usize::try_from(count).unwrap()];
+        let result = file_descriptor.read(communicate, &mut bytes, *this.tcx)?;
+
+        if matches!(&result, Err(e) if e.kind() == ErrorKind::WouldBlock)
+            && file_descriptor.is_blocking()
+        {
+            // Nothing to read yet: block until a `write` or `close` makes the read progress.
+            // The retry then completes the read on behalf of this thread.
+            match file_descriptor.dup() {
+                Ok(file_descriptor) => {
+                    let thread = this.get_active_thread();
+                    this.machine.fds.blocked_reads.push(BlockedRead {
+                        thread,
+                        file_descriptor,
+                        buf,
+                        count,
+                        src_addr,
+                        dest: dest.ptr(),
+                    });
+                    this.block_thread(thread, BlockReason::Read);
+                    return Ok(());
                 }
                 Err(e) => {
                     this.set_last_error_from_io_error(e.kind())?;
-                    Ok(-1)
+                    return this.write_scalar(Scalar::from_target_isize(-1, this), dest);
                 }
             }
-        } else {
-            trace!("read: FD not found");
-            this.fd_not_found()
         }
+
+        let clock = file_descriptor.acquire_clock();
+        let source = file_descriptor.last_read_source();
+        this.finish_read(result, &bytes, clock, buf, src_addr.map(|ptrs| (ptrs, source)), dest)?;
+        // Reading can make the FD not readable anymore, or its peer writable.
+        this.fd_readiness_changed()
+    }
+
+    /// Called after every operation that may change whether operations on some FD can make
+    /// progress: completes the blocked reads that can now make progress, and updates the
+    /// readiness reported by `epoll`.
+    fn fd_readiness_changed(&mut self) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        this.retry_blocked_reads()?;
+        this.retry_blocked_accepts()?;
+        this.epoll_update_readiness()
+    }
+
+    /// Writes the outcome of a `read` by the active thread to the program: the bytes that were
+    /// read, the source address for `recvfrom`, and the return value, or the error.
+    fn finish_read(
+        &mut self,
+        result: io::Result<usize>,
+        bytes: &[u8],
+        clock: Option<VClock>,
+        buf: Pointer<Option<Provenance>>,
+        src_addr: Option<(SrcAddr, Option<SocketAddr>)>,
+        dest: &MPlaceTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        match result {
+            Ok(read_bytes) => {
+                // The data was produced by a `write`, which happens-before this read.
+                if let (Some(data_race), Some(clock)) = (&this.machine.data_race, clock) {
+                    data_race.acquire_clock(&clock, this.get_active_thread());
+                }
+                // If reading to `bytes` did not fail, we write those bytes to the buffer.
+                // `read` never returns a value larger than `count`, so this cannot fail.
+                this.write_bytes_ptr(buf, bytes[..read_bytes].iter().copied())?;
+                if let Some(((addr, addrlen), source)) = src_addr {
+                    this.write_socket_addr(source, addr, addrlen)?;
+                }
+                let read_bytes = i64::try_from(read_bytes).unwrap();
+                this.write_scalar(Scalar::from_target_isize(read_bytes, this), dest)
+            }
+            Err(e) => {
+                this.set_last_error_from_io_error(e.kind())?;
+                this.write_scalar(Scalar::from_target_isize(-1, this), dest)
+            }
+        }
+    }
+
+    /// Retries all blocked reads, and completes and unblocks those that can now make progress.
+    fn retry_blocked_reads(&mut self) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        if this.machine.fds.blocked_reads.is_empty() {
+            return Ok(());
+        }
+        let communicate = this.machine.communicate();
+        let blocked_reads = std::mem::take(&mut this.machine.fds.blocked_reads);
+        for mut blocked_read in blocked_reads {
+            let mut bytes = vec![0; usize::try_from(blocked_read.count).unwrap()];
+            let result = blocked_read.file_descriptor.read(communicate, &mut bytes, *this.tcx)?;
+            if matches!(&result, Err(e) if e.kind() == ErrorKind::WouldBlock) {
+                this.machine.fds.blocked_reads.push(blocked_read);
+                continue;
+            }
+
+            let BlockedRead { thread, file_descriptor, buf, count: _, src_addr, dest } =
+                blocked_read;
+            let clock = file_descriptor.acquire_clock();
+            let source = file_descriptor.last_read_source();
+            let dest = this.ptr_to_mplace(dest, this.machine.layouts.isize);
+            this.unblock_thread(thread, BlockReason::Read);
+            // The read has to be completed as the blocked thread, since `errno` and data race
+            // clocks are per thread. Like `run_timeout_callback`, switch there and back.
+            let old_thread = this.set_active_thread(thread);
+            let src_addr = src_addr.map(|ptrs| (ptrs, source));
+            this.finish_read(result, &bytes, clock, buf, src_addr, &dest)?;
+            this.set_active_thread(old_thread);
+        }
+        Ok(())
     }
 
     fn write(
@@ -419,10 +704,24 @@ fn write(
         let communicate = this.machine.communicate();
 
         if let Some(file_descriptor) = this.machine.fds.get(fd) {
+            if !file_descriptor.is_writable() {
+                return this.fd_not_found();
+            }
             let bytes = this.read_bytes_ptr_strip_provenance(buf, Size::from_bytes(count))?;
             let result = file_descriptor
                 .write(communicate, bytes, *this.tcx)?
                 .map(|c| i64::try_from(c).unwrap());
+            if result.is_ok() {
+                // This write happens-before any read that observes the written data.
+                if let Some(data_race) = &this.machine.data_race {
+                    let current_span = this.machine.current_span();
+                    file_descriptor.release_clock(
+                        &data_race.release_clock(this.get_active_thread(), current_span),
+                    );
+                }
+                // The written data may unblock a reader.
+                this.fd_readiness_changed()?;
+            }
             this.try_unwrap_io_result(result)
         } else {
             this.fd_not_found()
@@ -83,8 +83,7 @@ fn emulate_foreign_item_inner(
                 let fd = this.read_scalar(fd)?.to_i32()?;
                 let buf = this.read_pointer(buf)?;
                 let count = this.read_target_usize(count)?;
-                let result = this.read(fd, buf, count)?;
-                this.write_scalar(Scalar::from_target_isize(result, this), dest)?;
+                this.read(fd, buf, count, None, dest)?;
             }
             "write" => {
                 let [fd, buf, n] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
@@ -101,6 +100,13 @@ fn emulate_foreign_item_inner(
                 let result = this.close(fd)?;
                 this.write_scalar(result, dest)?;
             }
+            "ioctl" => {
+                // `ioctl` is variadic. The argument count is checked based on the first argument
+                // in `this.ioctl()`, so we do not use `check_shim` here.
+                this.check_abi_and_shim_symbol_clash(abi, Abi::C { unwind: false }, link_name)?;
+                let result = this.ioctl(args)?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
             "fcntl" => {
                 // `fcntl` is variadic. The argument count is checked based on the first argument
                 // in `this.fcntl()`, so we do not use `check_shim` here.
@@ -227,6 +233,127 @@ fn emulate_foreign_item_inner(
                 let result = this.socketpair(domain, type_, protocol, sv)?;
                 this.write_scalar(result, dest)?;
             }
+            "recv" => {
+                let [fd, buf, len, flags] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let fd = this.read_scalar(fd)?.to_i32()?;
+                let buf = this.read_pointer(buf)?;
+                let len = this.read_target_usize(len)?;
+                let flags = this.read_scalar(flags)?.to_i32()?;
+                if flags != 0 {
+                    throw_unsup_format!("recv: flags {flags:#x} are unsupported");
+                }
+                // Without flags, `recv` is the same as `read`.
+                this.read(fd, buf, len, None, dest)?;
+            }
+            "send" => {
+                let [fd, buf, len, flags] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let fd = this.read_scalar(fd)?.to_i32()?;
+                let buf = this.read_pointer(buf)?;
+                let len = this.read_target_usize(len)?;
+                let flags = this.read_scalar(flags)?.to_i32()?;
+                let flags = this.remove_msg_nosignal(flags);
+                if flags != 0 {
+                    throw_unsup_format!("send: flags {flags:#x} are unsupported");
+                }
+                // Without flags, `send` is the same as `write`.
+                let result = this.write(fd, buf, len)?;
+                this.write_scalar(Scalar::from_target_isize(result, this), dest)?;
+            }
+            "recvfrom" => {
+                let [fd, buf, len, flags, addr, addrlen] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let fd = this.read_scalar(fd)?.to_i32()?;
+                let buf = this.read_pointer(buf)?;
+                let len = this.read_target_usize(len)?;
+                let flags = this.read_scalar(flags)?.to_i32()?;
+                let addr = this.read_pointer(addr)?;
+                let addrlen = this.read_pointer(addrlen)?;
+                if flags != 0 {
+                    throw_unsup_format!("recvfrom: flags {flags:#x} are unsupported");
+                }
+                this.read(fd, buf, len, Some((addr, addrlen)), dest)?;
+            }
+            "sendto" => {
+                let [fd, buf, len, flags, addr, addrlen] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.sendto(fd, buf, len, flags, addr, addrlen)?;
+                this.write_scalar(result, dest)?;
+            }
+            "socket" => {
+                let [domain, type_, protocol] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.socket(domain, type_, protocol)?;
+                this.write_scalar(result, dest)?;
+            }
+            "bind" => {
+                let [fd, addr, addrlen] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.bind(fd, addr, addrlen)?;
+                this.write_scalar(result, dest)?;
+            }
+            "listen" => {
+                let [fd, backlog] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.listen(fd, backlog)?;
+                this.write_scalar(result, dest)?;
+            }
+            "connect" => {
+                let [fd, addr, addrlen] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.connect(fd, addr, addrlen)?;
+                this.write_scalar(result, dest)?;
+            }
+            "accept" => {
+                let [fd, addr, addrlen] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                this.accept4(fd, addr, addrlen, None, dest)?;
+            }
+            "accept4" => {
+                let [fd, addr, addrlen, flags] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                this.accept4(fd, addr, addrlen, Some(flags), dest)?;
+            }
+            "getsockname" => {
+                let [fd, addr, addrlen] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.getsockname(fd, addr, addrlen)?;
+                this.write_scalar(result, dest)?;
+            }
+            "getpeername" => {
+                let [fd, addr, addrlen] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.getpeername(fd, addr, addrlen)?;
+                this.write_scalar(result, dest)?;
+            }
+            "shutdown" => {
+                let [fd, how] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.shutdown(fd, how)?;
+                this.write_scalar(result, dest)?;
+            }
+            "setsockopt" => {
+                let [fd, level, name, value, len] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.setsockopt(fd, level, name, value, len)?;
+                this.write_scalar(result, dest)?;
+            }
+            "getsockopt" => {
+                let [fd, level, name, value, len] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.getsockopt(fd, level, name, value, len)?;
+                this.write_scalar(result, dest)?;
+            }
+            "pipe" => {
+                let [pipefd] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.pipe2(pipefd, None)?;
+                this.write_scalar(result, dest)?;
+            }
+            "pipe2" => {
+                let [pipefd, flags] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.pipe2(pipefd, Some(flags))?;
+                this.write_scalar(result, dest)?;
+            }
 
             // Time
             "gettimeofday" => {
@@ -1,32 +1,123 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::io;
+use std::rc::Rc;
+use std::time::Duration;
 
-use rustc_data_structures::fx::FxHashMap;
-
+use crate::concurrency::thread::MachineCallback;
 use crate::shims::unix::*;
 use crate::*;
 
+/// The file descriptors an `Epoll` is watching, and what it is watching for.
+type Interests = Rc<RefCell<BTreeMap<Registration, EpollEvent>>>;
+
+/// An FD registered with an `Epoll`. Besides its number, the FD is identified by its id, so that
+/// an interest is not carried over to an unrelated FD that reuses the number of a closed one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Registration {
+    fd: i32,
+    id: FdId,
+}
+
 /// An `Epoll` file descriptor connects file handles and epoll events
 #[derive(Clone, Debug, Default)]
 struct Epoll {
     /// The file descriptors we are watching, and what we are watching for.
-    file_descriptors: FxHashMap<i32, EpollEvent>,
+    /// `dup`ed descriptors share this set.
+    file_descriptors: Interests,
 }
 
 /// Epoll Events associate events with data.
-/// These fields are currently unused by miri.
 /// This matches the `epoll_event` struct defined
-/// by the epoll_ctl man page. For more information
-/// see the man page:
+/// by the epoll_ctl man page, plus the readiness
+/// we track for the file descriptor. For more
+/// information see the man page:
 ///
 /// <https://man7.org/linux/man-pages/man2/epoll_ctl.2.html>
 #[derive(Clone, Debug)]
 struct EpollEvent {
-    #[allow(dead_code)]
+    /// The events we are watching for, including flags like `EPOLLET`.
     events: u32,
-    /// `Scalar<Provenance>` is used to represent the
-    /// `epoll_data` type union.
-    #[allow(dead_code)]
-    data: Scalar<Provenance>,
+    /// The `epoll_data` union, which is returned along with the ready events.
+    data: u64,
+    /// The events that were ready as of the last readiness update.
+    ready: u32,
+    /// The events that became ready since they were last returned. Edge-triggered
+    /// interests only report these.
+    pending: u32,
+}
+
+/// The `EPOLL*` flags of the target.
+struct EpollFlags {
+    epollin: u32,
+    epollout: u32,
+    epollrdhup: u32,
+    epollpri: u32,
+    epollhup: u32,
+    epollerr: u32,
+    epollet: u32,
+}
+
+impl EpollFlags {
+    fn new<'mir, 'tcx>(this: &MiriInterpCx<'mir, 'tcx>) -> Self {
+        EpollFlags {
+            epollin: this.eval_libc_u32("EPOLLIN"),
+            epollout: this.eval_libc_u32("EPOLLOUT"),
+            epollrdhup: this.eval_libc_u32("EPOLLRDHUP"),
+            epollpri: this.eval_libc_u32("EPOLLPRI"),
+            epollhup: this.eval_libc_u32("EPOLLHUP"),
+            epollerr: this.eval_libc_u32("EPOLLERR"),
+            epollet: this.eval_libc_u32("EPOLLET"),
+        }
+    }
+
+    /// The events that are ready for a file descriptor with the given readiness.
+    fn ready_events(&self, readiness: Readiness) -> u32 {
+        let mut events = 0;
+        if readiness.readable {
+            events |= self.epollin;
+        }
+        if readiness.writable {
+            events |= self.epollout;
+        }
+        if readiness.read_hangup {
+            events |= self.epollrdhup;
+        }
+        if readiness.hangup {
+            events |= self.epollhup;
+        }
+        if readiness.error {
+            events |= self.epollerr;
+        }
+        events
+    }
+
+    /// The events that are reported for an interest: the ones it asked for, plus `EPOLLHUP` and
+    /// `EPOLLERR`, which are always reported.
+    fn reported_events(&self, event: &EpollEvent) -> u32 {
+        let requested = self.epollin | self.epollout | self.epollrdhup | self.epollpri;
+        event.events & requested | self.epollhup | self.epollerr
+    }
+}
+
+/// An `epoll_wait` call that is blocked until an event is ready or the timeout expires.
+#[derive(Debug)]
+pub struct BlockedEpollWait {
+    /// The thread that called `epoll_wait`.
+    thread: ThreadId,
+    /// The interests of the `Epoll`, which keep being watched even if it is closed meanwhile.
+    file_descriptors: Interests,
+    events: Pointer<Option<Provenance>>,
+    maxevents: usize,
+    /// The place the return value of `epoll_wait` is written to.
+    dest: Pointer<Option<Provenance>>,
+}
+
+impl VisitProvenance for BlockedEpollWait {
+    fn visit_provenance(&self, visit: &mut VisitWith<'_>) {
+        self.events.visit_provenance(visit);
+        self.dest.visit_provenance(visit);
+    }
 }
 
 impl FileDescriptor for Epoll {
@@ -35,8 +126,6 @@ fn name(&self) -> &'static str {
     }
 
     fn dup(&mut self) -> io::Result<Box<dyn FileDescriptor>> {
-        // FIXME: this is probably wrong -- check if the `dup`ed descriptor truly uses an
-        // independent event set.
         Ok(Box::new(self.clone()))
     }
 
@@ -105,41 +194,69 @@ fn epoll_ctl(
         let epoll_ctl_mod = this.eval_libc_i32("EPOLL_CTL_MOD");
         let epoll_ctl_del = this.eval_libc_i32("EPOLL_CTL_DEL");
 
-        if op == epoll_ctl_add || op == epoll_ctl_mod {
+        let Some(epoll) = this.machine.fds.get(epfd) else {
+            return Ok(Scalar::from_i32(this.fd_not_found()?));
+        };
+        let interests = epoll
+            .downcast_ref::<Epoll>()
+            .ok_or_else(|| err_unsup_format!("non-epoll FD passed to `epoll_ctl`"))?
+            .file_descriptors
+            .clone();
+        let Some(file_descriptor) = this.machine.fds.get(fd) else {
+            return Ok(Scalar::from_i32(this.fd_not_found()?));
+        };
+        let readiness = file_descriptor.readiness();
+        let registration = Registration { fd, id: this.machine.fds.id(fd).unwrap() };
+
+        let error = if fd == epfd {
+            "EINVAL"
+        } else if op == epoll_ctl_add || op == epoll_ctl_mod {
             let event = this.deref_pointer_as(event, this.libc_ty_layout("epoll_event"))?;
 
             let events = this.project_field(&event, 0)?;
             let events = this.read_scalar(&events)?.to_u32()?;
             let data = this.project_field(&event, 1)?;
-            let data = this.read_scalar(&data)?;
-            let event = EpollEvent { events, data };
+            let data = this.read_scalar(&data)?.to_u64()?;
 
-            if let Some(epfd) = this.machine.fds.get_mut(epfd) {
-                let epfd = epfd
-                    .downcast_mut::<Epoll>()
-                    .ok_or_else(|| err_unsup_format!("non-epoll FD passed to `epoll_ctl`"))?;
+            let flags = EpollFlags::new(this);
+            let supported = flags.epollin
+                | flags.epollout
+                | flags.epollrdhup
+                | flags.epollpri
+                | flags.epollhup
+                | flags.epollerr
+                | flags.epollet;
+            if events & !supported != 0 {
+                throw_unsup_format!("epoll_ctl: events {:#x} are unsupported", events & !supported);
+            }
 
-                epfd.file_descriptors.insert(fd, event);
-                Ok(Scalar::from_i32(0))
+            let mut interests = interests.borrow_mut();
+            if let Some(readiness) = readiness {
+                if (op == epoll_ctl_add) == interests.contains_key(&registration) {
+                    if op == epoll_ctl_add { "EEXIST" } else { "ENOENT" }
+                } else {
+                    // Events that are ready right away are reported, also for edge-triggered
+                    // interests.
+                    let ready = flags.ready_events(readiness);
+                    interests
+                        .insert(registration, EpollEvent { events, data, ready, pending: ready });
+                    return Ok(Scalar::from_i32(0));
+                }
             } else {
-                Ok(Scalar::from_i32(this.fd_not_found()?))
+                // Like regular files, this FD does not support `epoll`.
+                "EPERM"
             }
         } else if op == epoll_ctl_del {
-            if let Some(epfd) = this.machine.fds.get_mut(epfd) {
-                let epfd = epfd
-                    .downcast_mut::<Epoll>()
-                    .ok_or_else(|| err_unsup_format!("non-epoll FD passed to `epoll_ctl`"))?;
-
-                epfd.file_descriptors.remove(&fd);
-                Ok(Scalar::from_i32(0))
-            } else {
-                Ok(Scalar::from_i32(this.fd_not_found()?))
+            if interests.borrow_mut().remove(&registration).is_some() {
+                return Ok(Scalar::from_i32(0));
             }
+            "ENOENT"
         } else {
-            let einval = this.eval_libc("EINVAL");
-            this.set_last_error(einval)?;
-            Ok(Scalar::from_i32(-1))
-        }
+            "EINVAL"
+        };
+        let error = this.eval_libc(error);
+        this.set_last_error(error)?;
+        Ok(Scalar::from_i32(-1))
     }
 
     /// The `epoll_wait()` system call waits for events on the `Epoll`
@@ -177,23 +294,175 @@ fn epoll_wait(
         events: &OpTy<'tcx, Provenance>,
         maxevents: &OpTy<'tcx, Provenance>,
         timeout: &OpTy<'tcx, Provenance>,
-    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        dest: &MPlaceTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx> {
         let this = self.eval_context_mut();
 
         let epfd = this.read_scalar(epfd)?.to_i32()?;
-        let _events = this.read_scalar(events)?.to_pointer(this)?;
-        let _maxevents = this.read_scalar(maxevents)?.to_i32()?;
-        let _timeout = this.read_scalar(timeout)?.to_i32()?;
+        let events = this.read_pointer(events)?;
+        let maxevents = this.read_scalar(maxevents)?.to_i32()?;
+        let timeout = this.read_scalar(timeout)?.to_i32()?;
 
-        if let Some(epfd) = this.machine.fds.get_mut(epfd) {
-            let _epfd = epfd
-                .downcast_mut::<Epoll>()
-                .ok_or_else(|| err_unsup_format!("non-epoll FD passed to `epoll_wait`"))?;
+        let Ok(maxevents @ 1..) = usize::try_from(maxevents) else {
+            let einval = this.eval_libc("EINVAL");
+            this.set_last_error(einval)?;
+            return this.write_scalar(Scalar::from_i32(-1), dest);
+        };
+        let Some(epoll) = this.machine.fds.get(epfd) else {
+            let result = this.fd_not_found()?;
+            return this.write_scalar(Scalar::from_i32(result), dest);
+        };
+        let interests = epoll
+            .downcast_ref::<Epoll>()
+            .ok_or_else(|| err_unsup_format!("non-epoll FD passed to `epoll_wait`"))?
+            .file_descriptors
+            .clone();
 
-            // FIXME return number of events ready when scheme for marking events ready exists
-            throw_unsup_format!("returning ready events from epoll_wait is not yet implemented");
-        } else {
-            Ok(Scalar::from_i32(this.fd_not_found()?))
+        let ready = this.epoll_return_events(&interests, events, maxevents)?;
+        if ready > 0 || timeout == 0 {
+            return this.write_scalar(Scalar::from_i32(ready), dest);
+        }
+
+        // Nothing is ready yet: block until `epoll_update_readiness` finds an event, or the
+        // timeout expires.
+        let thread = this.get_active_thread();
+        this.machine.fds.blocked_epoll_waits.push(BlockedEpollWait {
+            thread,
+            file_descriptors: interests,
+            events,
+            maxevents,
+            dest: dest.ptr(),
+        });
+        this.block_thread(thread, BlockReason::Epoll);
+        // A negative timeout means waiting forever.
+        if let Ok(timeout) = u64::try_from(timeout) {
+            struct Callback<'tcx> {
+                thread: ThreadId,
+                dest: MPlaceTy<'tcx, Provenance>,
+            }
+
+            impl<'tcx> VisitProvenance for Callback<'tcx> {
+                fn visit_provenance(&self, visit: &mut VisitWith<'_>) {
+                    let Callback { thread: _, dest } = self;
+                    dest.visit_provenance(visit);
+                }
+            }
+
+            impl<'mir, 'tcx: 'mir> MachineCallback<'mir, 'tcx> for Callback<'tcx> {
+                fn call(&self, this: &mut MiriInterpCx<'mir, 'tcx>) -> InterpResult<'tcx> {
+                    this.unblock_thread(self.thread, BlockReason::Epoll);
+                    this.machine.fds.blocked_epoll_waits.retain(|wait| wait.thread != self.thread);
+                    // The timeout expired without any event becoming ready.
+                    this.write_scalar(Scalar::from_i32(0), &self.dest)?;
+
+                    Ok(())
+                }
+            }
+
+            let timeout_time = CallbackTime::Monotonic(
+                this.machine.clock.now().checked_add(Duration::from_millis(timeout)).unwrap(),
+            );
+            this.register_timeout_callback(
+                thread,
+                timeout_time,
+                Box::new(Callback { thread, dest: dest.clone() }),
+            );
+        }
+        Ok(())
+    }
+
+    /// Writes up to `maxevents` ready events of `interests` to the `events` array, and returns
+    /// how many were written. Edge-triggered events are only returned once.
+    fn epoll_return_events(
+        &mut self,
+        interests: &RefCell<BTreeMap<Registration, EpollEvent>>,
+        events: Pointer<Option<Provenance>>,
+        maxevents: usize,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        let flags = EpollFlags::new(this);
+        let mut ready_events = Vec::new();
+        for event in interests.borrow_mut().values_mut() {
+            if ready_events.len() == maxevents {
+                break;
+            }
+            let ready = if event.events & flags.epollet != 0 { event.pending } else { event.ready };
+            let ready = ready & flags.reported_events(event);
+            if ready != 0 {
+                event.pending &= !ready;
+                ready_events.push((ready, event.data));
+            }
+        }
+
+        let event_layout = this.libc_ty_layout("epoll_event");
+        for (i, &(ready, data)) in ready_events.iter().enumerate() {
+            let offset = event_layout.size * u64::try_from(i).unwrap();
+            let event = this.ptr_to_mplace(events.offset(offset, this)?, event_layout);
+            this.write_scalar(Scalar::from_u32(ready), &this.project_field(&event, 0)?)?;
+            this.write_scalar(Scalar::from_u64(data), &this.project_field(&event, 1)?)?;
+        }
+        Ok(i32::try_from(ready_events.len()).unwrap())
+    }
+
+    /// Recomputes which events are ready for every FD watched by an `Epoll`, and completes the
+    /// `epoll_wait` calls that are blocked on an `Epoll` which now has ready events. FDs that
+    /// have been closed stop being watched.
+    fn epoll_update_readiness(&mut self) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let fds = &this.machine.fds;
+        let mut all_interests: Vec<Interests> = fds
+            .fds
+            .values()
+            .filter_map(|file_descriptor| file_descriptor.downcast_ref::<Epoll>())
+            .map(|epoll| epoll.file_descriptors.clone())
+            .collect();
+        all_interests
+            .extend(fds.blocked_epoll_waits.iter().map(|wait| wait.file_descriptors.clone()));
+        if all_interests.is_empty() {
+            // In particular, this avoids looking up `EPOLL*` flags on targets without epoll.
+            return Ok(());
+        }
+
+        let flags = EpollFlags::new(this);
+        for interests in &all_interests {
+            interests.borrow_mut().retain(|&Registration { fd, id }, event| {
+                // Closed FDs stop being watched, even if their number has been reused since.
+                if this.machine.fds.id(fd) != Some(id) {
+                    return false;
+                }
+                let Some(file_descriptor) = this.machine.fds.get(fd) else {
+                    return false;
+                };
+                let Some(readiness) = file_descriptor.readiness() else {
+                    return false;
+                };
+                let ready = flags.ready_events(readiness);
+                // Edge-triggered interests are notified of every event that was not ready before.
+                event.pending |= ready & !event.ready;
+                event.ready = ready;
+                true
+            });
+        }
+
+        let blocked_epoll_waits = std::mem::take(&mut this.machine.fds.blocked_epoll_waits);
+        for wait in blocked_epoll_waits {
+            // The events have to be returned as the blocked thread, for the data race detector.
+            // Like `run_timeout_callback`, switch there and back.
+            let old_thread = this.set_active_thread(wait.thread);
+            let ready =
+                this.epoll_return_events(&wait.file_descriptors, wait.events, wait.maxevents)?;
+            if ready > 0 {
+                this.unblock_thread(wait.thread, BlockReason::Epoll);
+                this.unregister_timeout_callback_if_exists(wait.thread);
+                let dest = this.ptr_to_mplace(wait.dest, this.machine.layouts.i32);
+                this.write_scalar(Scalar::from_i32(ready), &dest)?;
+            } else {
+                this.machine.fds.blocked_epoll_waits.push(wait);
+            }
+            this.set_active_thread(old_thread);
         }
+        Ok(())
     }
 }
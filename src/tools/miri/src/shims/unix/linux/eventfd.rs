@@ -1,16 +1,19 @@
 //! Linux `eventfd` implementation.
-//! Currently just a stub.
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::io;
+use std::rc::Rc;
 
 use rustc_middle::ty::TyCtxt;
 use rustc_target::abi::Endian;
 
+use crate::concurrency::VClock;
 use crate::shims::unix::*;
 use crate::*;
 
+/// The largest value the counter of an `Event` can hold.
+const MAX_COUNTER: u64 = u64::MAX - 1;
+
 /// A kind of file descriptor created by `eventfd`.
-/// The `Event` type isn't currently written to by `eventfd`.
 /// The interface is meant to keep track of objects associated
 /// with a file descriptor. For more information see the man
 /// page below:
@@ -20,7 +23,13 @@
 struct Event {
     /// The object contains an unsigned 64-bit integer (uint64_t) counter that is maintained by the
     /// kernel. This counter is initialized with the value specified in the argument initval.
-    val: Cell<u64>,
+    /// `dup`ed FDs share the counter.
+    val: Rc<Cell<u64>>,
+    /// The clocks of all writes since the counter was last read, so that reads can synchronize
+    /// with them.
+    clock: Rc<RefCell<VClock>>,
+    /// Whether the event was created with `EFD_NONBLOCK`.
+    is_nonblock: bool,
 }
 
 impl FileDescriptor for Event {
@@ -29,8 +38,11 @@ fn name(&self) -> &'static str {
     }
 
     fn dup(&mut self) -> io::Result<Box<dyn FileDescriptor>> {
-        // FIXME: this is wrong, the new and old FD should refer to the same event object!
-        Ok(Box::new(Event { val: self.val.clone() }))
+        Ok(Box::new(Event {
+            val: Rc::clone(&self.val),
+            clock: Rc::clone(&self.clock),
+            is_nonblock: self.is_nonblock,
+        }))
     }
 
     fn close<'tcx>(
@@ -40,6 +52,36 @@ fn close<'tcx>(
         Ok(Ok(0))
     }
 
+    /// A read call returns the 8-byte counter (in native endianness) and resets it to zero.
+    /// If the counter is zero, the read blocks until the counter becomes nonzero, or fails
+    /// with the error EAGAIN if the file descriptor has been made nonblocking.
+    ///
+    /// A read fails with the error EINVAL if the size of the supplied buffer is less than
+    /// 8 bytes.
+    fn read<'tcx>(
+        &mut self,
+        _communicate_allowed: bool,
+        bytes: &mut [u8],
+        tcx: TyCtxt<'tcx>,
+    ) -> InterpResult<'tcx, io::Result<usize>> {
+        let Some(bytes) = bytes.first_chunk_mut::<8>() else {
+            return Ok(Err(io::ErrorKind::InvalidInput.into()));
+        };
+        let val = self.val.get();
+        if val == 0 {
+            // Blocking events are woken up by the FD table once the counter changes, see
+            // `is_blocking`.
+            return Ok(Err(io::ErrorKind::WouldBlock.into()));
+        }
+        // Convert from host endianness to target endianness.
+        *bytes = match tcx.sess.target.endian {
+            Endian::Little => val.to_le_bytes(),
+            Endian::Big => val.to_be_bytes(),
+        };
+        self.val.set(0);
+        Ok(Ok(8))
+    }
+
     /// A write call adds the 8-byte integer value supplied in
     /// its buffer (in native endianness) to the counter.  The maximum value that may be
     /// stored in the counter is the largest unsigned 64-bit value
@@ -58,20 +100,41 @@ fn write<'tcx>(
         bytes: &[u8],
         tcx: TyCtxt<'tcx>,
     ) -> InterpResult<'tcx, io::Result<usize>> {
-        let v1 = self.val.get();
-        let bytes: [u8; 8] = bytes.try_into().unwrap(); // FIXME fail gracefully when this has the wrong size
+        let Some(bytes) = bytes.first_chunk::<8>() else {
+            return Ok(Err(io::ErrorKind::InvalidInput.into()));
+        };
         // Convert from target endianness to host endianness.
         let num = match tcx.sess.target.endian {
-            Endian::Little => u64::from_le_bytes(bytes),
-            Endian::Big => u64::from_be_bytes(bytes),
+            Endian::Little => u64::from_le_bytes(*bytes),
+            Endian::Big => u64::from_be_bytes(*bytes),
         };
-        // FIXME handle blocking when addition results in exceeding the max u64 value
-        // or fail with EAGAIN if the file descriptor is nonblocking.
-        let v2 = v1.checked_add(num).unwrap();
-        self.val.set(v2);
-        assert_eq!(8, bytes.len());
+        if num == u64::MAX {
+            return Ok(Err(io::ErrorKind::InvalidInput.into()));
+        }
+        match self.val.get().checked_add(num).filter(|&val| val <= MAX_COUNTER) {
+            Some(val) => self.val.set(val),
+            None if self.is_nonblock => return Ok(Err(io::ErrorKind::WouldBlock.into())),
+            None => throw_unsup_format!("eventfd: blocking on a full counter is unsupported"),
+        }
         Ok(Ok(8))
     }
+
+    fn is_blocking(&self) -> bool {
+        !self.is_nonblock
+    }
+
+    fn release_clock(&self, clock: &VClock) {
+        self.clock.borrow_mut().join(clock);
+    }
+
+    fn acquire_clock(&self) -> Option<VClock> {
+        Some(self.clock.borrow().clone())
+    }
+
+    fn readiness(&self) -> Option<Readiness> {
+        let val = self.val.get();
+        Some(Readiness { readable: val > 0, writable: val < MAX_COUNTER, ..Readiness::default() })
+    }
 }
 
 impl<'mir, 'tcx: 'mir> EvalContextExt<'mir, 'tcx> for crate::MiriInterpCx<'mir, 'tcx> {}
@@ -85,7 +148,6 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriInterpCxExt<'mir, 'tcx> {
     /// `select`, and `close` operations can be performed on the file descriptor. For more
     /// information on these operations, see the man page linked below.
     ///
-    /// The `flags` are not currently implemented for eventfd.
     /// The `flags` may be bitwise ORed to change the behavior of `eventfd`:
     /// `EFD_CLOEXEC` - Set the close-on-exec (`FD_CLOEXEC`) flag on the new file descriptor.
     /// `EFD_NONBLOCK` - Set the `O_NONBLOCK` file status flag on the new open file description.
@@ -112,14 +174,16 @@ fn eventfd(
         if flags & efd_cloexec == efd_cloexec {
             // cloexec does nothing as we don't support `exec`
         }
-        if flags & efd_nonblock == efd_nonblock {
-            // FIXME remember the nonblock flag
-        }
+        let is_nonblock = flags & efd_nonblock == efd_nonblock;
         if flags & efd_semaphore == efd_semaphore {
             throw_unsup_format!("eventfd: EFD_SEMAPHORE is unsupported");
         }
 
-        let fd = this.machine.fds.insert_fd(Box::new(Event { val: Cell::new(val.into()) }));
+        let fd = this.machine.fds.insert_fd(Box::new(Event {
+            val: Rc::new(Cell::new(val.into())),
+            clock: Rc::new(RefCell::new(VClock::default())),
+            is_nonblock,
+        }));
         Ok(Scalar::from_i32(fd))
     }
 }
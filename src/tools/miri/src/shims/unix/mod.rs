@@ -4,6 +4,7 @@
 mod fd;
 mod fs;
 mod mem;
+mod net;
 mod socket;
 mod sync;
 mod thread;
@@ -13,13 +14,14 @@
 mod macos;
 
 pub use env::UnixEnvVars;
-pub use fd::{FdTable, FileDescriptor};
+pub use fd::{FdId, FdTable, FileDescriptor, Readiness};
 pub use fs::DirTable;
 // All the Unix-specific extension traits
 pub use env::EvalContextExt as _;
 pub use fd::EvalContextExt as _;
 pub use fs::EvalContextExt as _;
 pub use mem::EvalContextExt as _;
+pub use net::EvalContextExt as _;
 pub use socket::EvalContextExt as _;
 pub use sync::EvalContextExt as _;
 pub use thread::EvalContextExt as _;
@@ -0,0 +1,1095 @@
+//! A virtual loopback network for `AF_INET` and `AF_INET6` sockets.
+//!
+//! Sockets can only talk to other sockets of the same program, over loopback addresses. The host
+//! network is never involved, so this works under isolation and is fully deterministic.
+
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{self, ErrorKind};
+use std::iter;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::rc::{Rc, Weak};
+
+use rustc_middle::ty::TyCtxt;
+use rustc_target::abi::Size;
+
+use crate::concurrency::VClock;
+use crate::shims::unix::socket::{new_pair, EndKind, SocketPair};
+use crate::shims::unix::*;
+use crate::*;
+
+/// The first port handed out to sockets that bind to port 0 or are bound implicitly. This is
+/// the start of the ephemeral port range assigned by IANA.
+const FIRST_EPHEMERAL_PORT: u16 = 49152;
+
+/// The addresses that sockets are bound to.
+#[derive(Debug)]
+pub struct Network {
+    /// Bound stream sockets. Closed sockets are removed lazily.
+    streams: BTreeMap<SocketAddr, Weak<Socket>>,
+    /// Bound datagram sockets. Closed sockets are removed lazily.
+    datagrams: BTreeMap<SocketAddr, Weak<Socket>>,
+    /// The next port to try when picking an ephemeral port.
+    next_port: u16,
+}
+
+impl Default for Network {
+    fn default() -> Self {
+        Network {
+            streams: BTreeMap::new(),
+            datagrams: BTreeMap::new(),
+            next_port: FIRST_EPHEMERAL_PORT,
+        }
+    }
+}
+
+impl Network {
+    fn sockets(&mut self, is_datagram: bool) -> &mut BTreeMap<SocketAddr, Weak<Socket>> {
+        if is_datagram { &mut self.datagrams } else { &mut self.streams }
+    }
+
+    /// Whether an open socket is bound to an address that conflicts with `addr`. An unspecified
+    /// address conflicts with all addresses of the same family and port.
+    fn in_use(&mut self, is_datagram: bool, addr: SocketAddr) -> bool {
+        let sockets = self.sockets(is_datagram);
+        sockets.retain(|_, socket| socket.strong_count() > 0);
+        sockets.keys().any(|bound| {
+            bound.is_ipv6() == addr.is_ipv6()
+                && bound.port() == addr.port()
+                && (bound.ip() == addr.ip()
+                    || bound.ip().is_unspecified()
+                    || addr.ip().is_unspecified())
+        })
+    }
+
+    /// Binds `socket` to `addr`, picking an ephemeral port if the port is 0. Only loopback and
+    /// unspecified addresses exist on this network.
+    fn bind(
+        &mut self,
+        socket: &Rc<Socket>,
+        mut addr: SocketAddr,
+    ) -> Result<SocketAddr, &'static str> {
+        if !addr.ip().is_loopback() && !addr.ip().is_unspecified() {
+            return Err("EADDRNOTAVAIL");
+        }
+        if addr.port() == 0 {
+            addr.set_port(self.ephemeral_port(socket.is_datagram, addr.ip()).ok_or("EADDRINUSE")?);
+        } else if self.in_use(socket.is_datagram, addr) {
+            return Err("EADDRINUSE");
+        }
+        self.sockets(socket.is_datagram).insert(addr, Rc::downgrade(socket));
+        Ok(addr)
+    }
+
+    /// Picks the next free ephemeral port for `ip`, deterministically.
+    fn ephemeral_port(&mut self, is_datagram: bool, ip: IpAddr) -> Option<u16> {
+        for _ in FIRST_EPHEMERAL_PORT..=u16::MAX {
+            let port = self.next_port;
+            self.next_port = port.checked_add(1).unwrap_or(FIRST_EPHEMERAL_PORT);
+            if !self.in_use(is_datagram, SocketAddr::new(ip, port)) {
+                return Some(port);
+            }
+        }
+        None
+    }
+
+    /// Finds the open socket that receives what is sent to `addr`: the one bound to exactly
+    /// `addr`, or else the one bound to the unspecified address of the same port.
+    fn find(&mut self, is_datagram: bool, addr: SocketAddr) -> Option<Rc<Socket>> {
+        let unspecified = SocketAddr::new(unspecified_ip(addr.is_ipv6()), addr.port());
+        let sockets = self.sockets(is_datagram);
+        [addr, unspecified].iter().find_map(|addr| sockets.get(addr)?.upgrade())
+    }
+}
+
+fn unspecified_ip(is_ipv6: bool) -> IpAddr {
+    if is_ipv6 { Ipv6Addr::UNSPECIFIED.into() } else { Ipv4Addr::UNSPECIFIED.into() }
+}
+
+fn loopback_ip(is_ipv6: bool) -> IpAddr {
+    if is_ipv6 { Ipv6Addr::LOCALHOST.into() } else { Ipv4Addr::LOCALHOST.into() }
+}
+
+/// Like on Linux, connecting or sending to the unspecified address reaches the loopback address.
+fn resolve_destination(mut addr: SocketAddr) -> SocketAddr {
+    if addr.ip().is_unspecified() {
+        addr.set_ip(loopback_ip(addr.is_ipv6()));
+    }
+    addr
+}
+
+/// A connection that has been made to a listening socket, but not accepted yet.
+#[derive(Debug)]
+struct PendingConnection {
+    /// The listener's end of the connection.
+    stream: SocketPair,
+    /// The address the client connected to.
+    local: SocketAddr,
+    /// The address of the client.
+    peer: SocketAddr,
+}
+
+#[derive(Debug)]
+enum State {
+    /// A stream socket that is neither listening nor connected.
+    Stream { local: Option<SocketAddr> },
+    /// A stream socket that `listen` was called on.
+    Listening { local: SocketAddr, backlog: VecDeque<PendingConnection> },
+    /// A connected stream socket. Data is passed through a socket pair, like for `socketpair`.
+    Connected { local: SocketAddr, peer: SocketAddr, stream: SocketPair },
+    /// A datagram socket.
+    Datagram {
+        local: Option<SocketAddr>,
+        /// The peer set by `connect`: where `send` sends to, and the only source datagrams are
+        /// received from.
+        peer: Option<SocketAddr>,
+        /// The datagrams received and not read yet, with their sources. There is no capacity
+        /// limit, so sending never blocks and no datagrams are lost.
+        queue: VecDeque<(Vec<u8>, SocketAddr)>,
+        /// The clocks of all sends that put datagrams into `queue`.
+        clock: VClock,
+    },
+}
+
+/// An `AF_INET` or `AF_INET6` socket.
+#[derive(Debug)]
+struct Socket {
+    network: Rc<RefCell<Network>>,
+    is_ipv6: bool,
+    is_datagram: bool,
+    /// Whether the socket is in non-blocking mode, set by `SOCK_NONBLOCK` or `ioctl(FIONBIO)`.
+    is_nonblock: Cell<bool>,
+    state: RefCell<State>,
+    /// The values set with `setsockopt`, by level and option name.
+    options: RefCell<BTreeMap<(i32, i32), i32>>,
+    /// The source of the datagram read last.
+    last_source: Cell<Option<SocketAddr>>,
+    /// The socket the last datagram was sent to, which the clock of the send is released to.
+    last_destination: RefCell<Weak<Socket>>,
+}
+
+impl Socket {
+    fn new(network: Rc<RefCell<Network>>, is_ipv6: bool, state: State, is_nonblock: bool) -> Self {
+        Socket {
+            network,
+            is_ipv6,
+            is_datagram: matches!(state, State::Datagram { .. }),
+            is_nonblock: Cell::new(is_nonblock),
+            state: RefCell::new(state),
+            options: RefCell::new(BTreeMap::new()),
+            last_source: Cell::new(None),
+            last_destination: RefCell::new(Weak::new()),
+        }
+    }
+
+    fn local_addr(&self) -> Option<SocketAddr> {
+        match &*self.state.borrow() {
+            State::Stream { local } | State::Datagram { local, .. } => *local,
+            State::Listening { local, .. } | State::Connected { local, .. } => Some(*local),
+        }
+    }
+
+    fn peer_addr(&self) -> Option<SocketAddr> {
+        match &*self.state.borrow() {
+            State::Connected { peer, .. } => Some(*peer),
+            State::Datagram { peer, .. } => *peer,
+            State::Stream { .. } | State::Listening { .. } => None,
+        }
+    }
+
+    /// Binds the socket to `addr`. Errors are returned as `errno` names.
+    fn bind(self: &Rc<Self>, addr: SocketAddr) -> Result<(), &'static str> {
+        if self.local_addr().is_some() {
+            return Err("EINVAL");
+        }
+        let local = self.network.borrow_mut().bind(self, addr)?;
+        match &mut *self.state.borrow_mut() {
+            State::Stream { local: unbound } | State::Datagram { local: unbound, .. } =>
+                *unbound = Some(local),
+            State::Listening { .. } | State::Connected { .. } => unreachable!(),
+        }
+        Ok(())
+    }
+
+    /// Returns the local address, after binding the socket to an ephemeral port on the loopback
+    /// address if it is not bound yet.
+    fn bind_implicitly(self: &Rc<Self>) -> Result<SocketAddr, &'static str> {
+        if let Some(local) = self.local_addr() {
+            return Ok(local);
+        }
+        self.bind(SocketAddr::new(loopback_ip(self.is_ipv6), 0))?;
+        Ok(self.local_addr().unwrap())
+    }
+
+    fn listen(self: &Rc<Self>) -> Result<(), &'static str> {
+        if self.is_datagram {
+            return Err("EOPNOTSUPP");
+        }
+        if matches!(*self.state.borrow(), State::Connected { .. }) {
+            return Err("EINVAL");
+        }
+        let local = self.bind_implicitly()?;
+        let mut state = self.state.borrow_mut();
+        if let State::Stream { .. } = *state {
+            *state = State::Listening { local, backlog: VecDeque::new() };
+        }
+        Ok(())
+    }
+
+    /// Connects a stream socket to the socket listening on `peer`, which can then accept the
+    /// connection. For a datagram socket, only sets the default peer.
+    fn connect(self: &Rc<Self>, peer: SocketAddr) -> Result<(), &'static str> {
+        let peer = resolve_destination(peer);
+        if self.is_datagram {
+            self.bind_implicitly()?;
+            if let State::Datagram { peer: default_peer, .. } = &mut *self.state.borrow_mut() {
+                *default_peer = Some(peer);
+            }
+            return Ok(());
+        }
+
+        match *self.state.borrow() {
+            State::Stream { .. } => {}
+            State::Connected { .. } => return Err("EISCONN"),
+            State::Listening { .. } => return Err("EINVAL"),
+            State::Datagram { .. } => unreachable!(),
+        }
+        let listener = self.network.borrow_mut().find(/* is_datagram */ false, peer);
+        let Some(listener) =
+            listener.filter(|listener| matches!(*listener.state.borrow(), State::Listening { .. }))
+        else {
+            return Err("ECONNREFUSED");
+        };
+        let local = self.bind_implicitly()?;
+
+        // Connections are established right away; there is no backlog limit.
+        let [client, server] = new_pair([EndKind::Socket, EndKind::Socket], false);
+        if let State::Listening { backlog, .. } = &mut *listener.state.borrow_mut() {
+            backlog.push_back(PendingConnection { stream: server, local: peer, peer: local });
+        }
+        *self.state.borrow_mut() = State::Connected { local, peer, stream: client };
+        Ok(())
+    }
+
+    /// Accepts the oldest pending connection, or returns `None` if there is none yet.
+    fn accept(&self, is_nonblock: bool) -> Result<Option<InetSocket>, &'static str> {
+        let mut state = self.state.borrow_mut();
+        let State::Listening { backlog, .. } = &mut *state else {
+            return Err("EINVAL");
+        };
+        Ok(backlog.pop_front().map(|PendingConnection { stream, local, peer }| {
+            let state = State::Connected { local, peer, stream };
+            let socket = Socket::new(Rc::clone(&self.network), self.is_ipv6, state, is_nonblock);
+            InetSocket { socket: Rc::new(socket) }
+        }))
+    }
+
+    /// Sends a datagram to `dest`. Like on a real network, a datagram that nobody receives is
+    /// dropped silently.
+    fn send_to(self: &Rc<Self>, bytes: &[u8], dest: SocketAddr) -> Result<usize, &'static str> {
+        let dest = resolve_destination(dest);
+        let local = self.bind_implicitly()?;
+        let target = self.network.borrow_mut().find(/* is_datagram */ true, dest);
+        let target = target.filter(|target| {
+            // A connected datagram socket only receives from its peer.
+            let State::Datagram { peer, .. } = *target.state.borrow() else { return false };
+            peer.map_or(true, |peer| peer == local)
+        });
+        let mut last_destination = self.last_destination.borrow_mut();
+        *last_destination = Weak::new();
+        if let Some(target) = target {
+            if let State::Datagram { queue, .. } = &mut *target.state.borrow_mut() {
+                queue.push_back((bytes.to_vec(), local));
+            }
+            *last_destination = Rc::downgrade(&target);
+        }
+        Ok(bytes.len())
+    }
+
+    fn release_clock(&self, clock: &VClock) {
+        if let State::Connected { stream, .. } = &*self.state.borrow() {
+            return stream.release_clock(clock);
+        }
+        // The last datagram may have been sent to this socket itself, so `state` must not be
+        // borrowed here.
+        if let Some(target) = self.last_destination.borrow().upgrade() {
+            if let State::Datagram { clock: target_clock, .. } = &mut *target.state.borrow_mut() {
+                target_clock.join(clock);
+            }
+        }
+    }
+
+    fn acquire_clock(&self) -> Option<VClock> {
+        match &*self.state.borrow() {
+            State::Connected { stream, .. } => stream.acquire_clock(),
+            State::Datagram { clock, .. } => Some(clock.clone()),
+            State::Stream { .. } | State::Listening { .. } => None,
+        }
+    }
+}
+
+/// An FD referring to an `AF_INET` or `AF_INET6` socket. `dup`ed FDs refer to the same socket.
+#[derive(Debug)]
+struct InetSocket {
+    socket: Rc<Socket>,
+}
+
+impl FileDescriptor for InetSocket {
+    fn name(&self) -> &'static str {
+        "socket"
+    }
+
+    fn read<'tcx>(
+        &mut self,
+        communicate_allowed: bool,
+        bytes: &mut [u8],
+        tcx: TyCtxt<'tcx>,
+    ) -> InterpResult<'tcx, io::Result<usize>> {
+        match &mut *self.socket.state.borrow_mut() {
+            State::Connected { stream, .. } => stream.read(communicate_allowed, bytes, tcx),
+            State::Datagram { queue, .. } => {
+                // Blocking sockets are woken up by the FD table once a datagram arrives.
+                let Some((datagram, source)) = queue.pop_front() else {
+                    return Ok(Err(ErrorKind::WouldBlock.into()));
+                };
+                // The part of the datagram that does not fit into the buffer is discarded.
+                let len = datagram.len().min(bytes.len());
+                bytes[..len].copy_from_slice(&datagram[..len]);
+                self.socket.last_source.set(Some(source));
+                Ok(Ok(len))
+            }
+            State::Stream { .. } | State::Listening { .. } =>
+                Ok(Err(ErrorKind::NotConnected.into())),
+        }
+    }
+
+    fn write<'tcx>(
+        &self,
+        communicate_allowed: bool,
+        bytes: &[u8],
+        tcx: TyCtxt<'tcx>,
+    ) -> InterpResult<'tcx, io::Result<usize>> {
+        let peer = match &*self.socket.state.borrow() {
+            State::Connected { stream, .. } =>
+                return stream.write(communicate_allowed, bytes, tcx),
+            State::Datagram { peer: Some(peer), .. } => *peer,
+            _ => return Ok(Err(ErrorKind::NotConnected.into())),
+        };
+        // Errors of `send_to` are for the implicit `bind`, which cannot fail on a connected
+        // socket since it is bound already.
+        Ok(Ok(self.socket.send_to(bytes, peer).unwrap()))
+    }
+
+    fn dup(&mut self) -> io::Result<Box<dyn FileDescriptor>> {
+        Ok(Box::new(InetSocket { socket: Rc::clone(&self.socket) }))
+    }
+
+    fn close<'tcx>(
+        self: Box<Self>,
+        _communicate_allowed: bool,
+    ) -> InterpResult<'tcx, io::Result<i32>> {
+        Ok(Ok(0))
+    }
+
+    fn is_blocking(&self) -> bool {
+        !self.socket.is_nonblock.get()
+    }
+
+    fn set_nonblocking<'tcx>(&self, nonblocking: bool) -> InterpResult<'tcx> {
+        self.socket.is_nonblock.set(nonblocking);
+        Ok(())
+    }
+
+    fn last_read_source(&self) -> Option<SocketAddr> {
+        // Like on Linux, `recvfrom` only reports the source address for datagram sockets.
+        if self.socket.is_datagram { self.socket.last_source.get() } else { None }
+    }
+
+    fn release_clock(&self, clock: &VClock) {
+        self.socket.release_clock(clock)
+    }
+
+    fn acquire_clock(&self) -> Option<VClock> {
+        self.socket.acquire_clock()
+    }
+
+    fn readiness(&self) -> Option<Readiness> {
+        Some(match &*self.socket.state.borrow() {
+            State::Connected { stream, .. } => return stream.readiness(),
+            State::Listening { backlog, .. } =>
+                Readiness { readable: !backlog.is_empty(), ..Readiness::default() },
+            State::Datagram { queue, .. } =>
+                Readiness { readable: !queue.is_empty(), writable: true, ..Readiness::default() },
+            // Like Linux, report a stream socket that is not connected as writable and hung up.
+            State::Stream { .. } =>
+                Readiness { writable: true, hangup: true, ..Readiness::default() },
+        })
+    }
+}
+
+/// A blocking `accept` that is waiting for a connection.
+#[derive(Debug)]
+pub struct BlockedAccept {
+    /// The thread that called `accept`.
+    thread: ThreadId,
+    /// The listening socket, which stays alive even if its FD is closed in the meantime.
+    socket: Rc<Socket>,
+    /// Whether the accepted socket is to be non-blocking.
+    is_nonblock: bool,
+    addr: Pointer<Option<Provenance>>,
+    addrlen: Pointer<Option<Provenance>>,
+    /// The place the return value of `accept` is written to.
+    dest: Pointer<Option<Provenance>>,
+}
+
+impl VisitProvenance for BlockedAccept {
+    fn visit_provenance(&self, visit: &mut VisitWith<'_>) {
+        self.addr.visit_provenance(visit);
+        self.addrlen.visit_provenance(visit);
+        self.dest.visit_provenance(visit);
+    }
+}
+
+/// Returns the socket `fd` refers to, or `None` if there is no such FD.
+fn inet_socket<'mir, 'tcx: 'mir>(
+    this: &MiriInterpCx<'mir, 'tcx>,
+    fd: i32,
+    name: &str,
+) -> InterpResult<'tcx, Option<Rc<Socket>>> {
+    let Some(file_descriptor) = this.machine.fds.get(fd) else {
+        return Ok(None);
+    };
+    let Some(inet_socket) = file_descriptor.downcast_ref::<InetSocket>() else {
+        throw_unsup_format!(
+            "`{name}` is only supported on AF_INET and AF_INET6 sockets, not on {}",
+            file_descriptor.name()
+        );
+    };
+    Ok(Some(Rc::clone(&inet_socket.socket)))
+}
+
+/// Sets `errno` to the error with the given name, and returns -1.
+fn set_errno<'mir, 'tcx: 'mir>(
+    this: &mut MiriInterpCx<'mir, 'tcx>,
+    name: &str,
+) -> InterpResult<'tcx, Scalar<Provenance>> {
+    let errno = this.eval_libc(name);
+    this.set_last_error(errno)?;
+    Ok(Scalar::from_i32(-1))
+}
+
+/// Writes the outcome of an `accept` by the active thread to the program: registers the
+/// accepted socket as a new FD and returns it, or returns the error.
+fn finish_accept<'mir, 'tcx: 'mir>(
+    this: &mut MiriInterpCx<'mir, 'tcx>,
+    result: Result<InetSocket, &'static str>,
+    addr: Pointer<Option<Provenance>>,
+    addrlen: Pointer<Option<Provenance>>,
+    dest: &MPlaceTy<'tcx, Provenance>,
+) -> InterpResult<'tcx> {
+    let result = match result {
+        Ok(accepted) => {
+            // The `connect` happens-before the `accept` that observes the connection.
+            if let (Some(data_race), Some(clock)) =
+                (&this.machine.data_race, accepted.socket.acquire_clock())
+            {
+                data_race.acquire_clock(&clock, this.get_active_thread());
+            }
+            let peer = accepted.socket.peer_addr();
+            this.write_socket_addr(peer, addr, addrlen)?;
+            Scalar::from_i32(this.machine.fds.insert_fd(Box::new(accepted)))
+        }
+        Err(errno) => set_errno(this, errno)?,
+    };
+    this.write_scalar(result, dest)
+}
+
+impl<'mir, 'tcx: 'mir> EvalContextExt<'mir, 'tcx> for crate::MiriInterpCx<'mir, 'tcx> {}
+pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriInterpCxExt<'mir, 'tcx> {
+    /// Creates an `AF_INET` or `AF_INET6` socket on the virtual network, which can only talk to
+    /// other sockets of the program. Stream and datagram sockets are supported.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/socket.2.html>
+    fn socket(
+        &mut self,
+        domain: &OpTy<'tcx, Provenance>,
+        type_: &OpTy<'tcx, Provenance>,
+        protocol: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let domain = this.read_scalar(domain)?.to_i32()?;
+        let type_ = this.read_scalar(type_)?.to_i32()?;
+        let protocol = this.read_scalar(protocol)?.to_i32()?;
+
+        let (type_, is_nonblock) = this.socket_type_flags(type_);
+        let is_ipv6 = if domain == this.eval_libc_i32("AF_INET") {
+            false
+        } else if domain == this.eval_libc_i32("AF_INET6") {
+            true
+        } else {
+            throw_unsup_format!("socket: domain {domain:#x} is unsupported");
+        };
+        let (state, default_protocol) = if type_ == this.eval_libc_i32("SOCK_STREAM") {
+            (State::Stream { local: None }, this.eval_libc_i32("IPPROTO_TCP"))
+        } else if type_ == this.eval_libc_i32("SOCK_DGRAM") {
+            let state = State::Datagram {
+                local: None,
+                peer: None,
+                queue: VecDeque::new(),
+                clock: VClock::default(),
+            };
+            (state, this.eval_libc_i32("IPPROTO_UDP"))
+        } else {
+            throw_unsup_format!("socket: type {type_:#x} is unsupported");
+        };
+        if protocol != 0 && protocol != default_protocol {
+            throw_unsup_format!("socket: protocol {protocol:#x} is unsupported");
+        }
+
+        let network = Rc::clone(&this.machine.fds.network);
+        let socket = Socket::new(network, is_ipv6, state, is_nonblock);
+        let fd = this.machine.fds.insert_fd(Box::new(InetSocket { socket: Rc::new(socket) }));
+        Ok(Scalar::from_i32(fd))
+    }
+
+    /// Binds a socket to a loopback or unspecified address. Port 0 picks an ephemeral port.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/bind.2.html>
+    fn bind(
+        &mut self,
+        fd: &OpTy<'tcx, Provenance>,
+        addr: &OpTy<'tcx, Provenance>,
+        addrlen: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let Some(socket) = inet_socket(this, fd, "bind")? else {
+            return Ok(Scalar::from_i32(this.fd_not_found()?));
+        };
+        let Some(addr) = this.read_socket_addr(addr, addrlen)? else {
+            return set_errno(this, "EINVAL");
+        };
+        if addr.is_ipv6() != socket.is_ipv6 {
+            return set_errno(this, "EINVAL");
+        }
+        match socket.bind(addr) {
+            Ok(()) => Ok(Scalar::from_i32(0)),
+            Err(errno) => set_errno(this, errno),
+        }
+    }
+
+    /// Makes a stream socket accept connections. There is no limit on pending connections, so
+    /// the backlog is ignored.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/listen.2.html>
+    fn listen(
+        &mut self,
+        fd: &OpTy<'tcx, Provenance>,
+        backlog: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let _backlog = this.read_scalar(backlog)?.to_i32()?;
+        let Some(socket) = inet_socket(this, fd, "listen")? else {
+            return Ok(Scalar::from_i32(this.fd_not_found()?));
+        };
+        match socket.listen() {
+            Ok(()) => Ok(Scalar::from_i32(0)),
+            Err(errno) => set_errno(this, errno),
+        }
+    }
+
+    /// Connects a stream socket to a listening socket, or sets the default peer of a datagram
+    /// socket. Connections are established right away, even for non-blocking sockets.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/connect.2.html>
+    fn connect(
+        &mut self,
+        fd: &OpTy<'tcx, Provenance>,
+        addr: &OpTy<'tcx, Provenance>,
+        addrlen: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let Some(socket) = inet_socket(this, fd, "connect")? else {
+            return Ok(Scalar::from_i32(this.fd_not_found()?));
+        };
+        let Some(addr) = this.read_socket_addr(addr, addrlen)? else {
+            return set_errno(this, "EINVAL");
+        };
+        if addr.is_ipv6() != socket.is_ipv6 {
+            return set_errno(this, "EINVAL");
+        }
+        if let Err(errno) = socket.connect(addr) {
+            return set_errno(this, errno);
+        }
+        // The connection is observed by the `accept` on the other end.
+        if let Some(data_race) = &this.machine.data_race {
+            let current_span = this.machine.current_span();
+            socket.release_clock(&data_race.release_clock(this.get_active_thread(), current_span));
+        }
+        // The new connection may unblock an `accept`.
+        this.fd_readiness_changed()?;
+        Ok(Scalar::from_i32(0))
+    }
+
+    /// Accepts a connection on a listening socket. `flags` may contain `SOCK_NONBLOCK` and
+    /// `SOCK_CLOEXEC`; `accept` is `accept4` with no flags.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/accept.2.html>
+    fn accept4(
+        &mut self,
+        fd: &OpTy<'tcx, Provenance>,
+        addr: &OpTy<'tcx, Provenance>,
+        addrlen: &OpTy<'tcx, Provenance>,
+        flags: Option<&OpTy<'tcx, Provenance>>,
+        dest: &MPlaceTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let addr = this.read_pointer(addr)?;
+        let addrlen = this.read_pointer(addrlen)?;
+        let flags = match flags {
+            Some(flags) => this.read_scalar(flags)?.to_i32()?,
+            None => 0,
+        };
+        let (flags, is_nonblock) = this.socket_type_flags(flags);
+        if flags != 0 {
+            throw_unsup_format!("accept4: flags {flags:#x} are unsupported");
+        }
+
+        let Some(socket) = inet_socket(this, fd, "accept")? else {
+            let result = this.fd_not_found()?;
+            return this.write_scalar(Scalar::from_i32(result), dest);
+        };
+        let result = match socket.accept(is_nonblock) {
+            Ok(Some(accepted)) => Ok(accepted),
+            Ok(None) if socket.is_nonblock.get() => Err("EAGAIN"),
+            Ok(None) => {
+                // No connection yet: block until a `connect` makes one. The retry then completes
+                // the `accept` on behalf of this thread.
+                let thread = this.get_active_thread();
+                this.machine.fds.blocked_accepts.push(BlockedAccept {
+                    thread,
+                    socket,
+                    is_nonblock,
+                    addr,
+                    addrlen,
+                    dest: dest.ptr(),
+                });
+                this.block_thread(thread, BlockReason::Accept);
+                return Ok(());
+            }
+            Err(errno) => Err(errno),
+        };
+        finish_accept(this, result, addr, addrlen, dest)?;
+        // Accepting empties the backlog.
+        this.fd_readiness_changed()
+    }
+
+    /// Retries all blocked `accept`s, and completes and unblocks those that now have a
+    /// connection.
+    fn retry_blocked_accepts(&mut self) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        if this.machine.fds.blocked_accepts.is_empty() {
+            return Ok(());
+        }
+        let blocked_accepts = std::mem::take(&mut this.machine.fds.blocked_accepts);
+        for blocked_accept in blocked_accepts {
+            let result = match blocked_accept.socket.accept(blocked_accept.is_nonblock) {
+                Ok(None) => {
+                    this.machine.fds.blocked_accepts.push(blocked_accept);
+                    continue;
+                }
+                Ok(Some(accepted)) => Ok(accepted),
+                Err(errno) => Err(errno),
+            };
+
+            let BlockedAccept { thread, addr, addrlen, dest, .. } = blocked_accept;
+            let dest = this.ptr_to_mplace(dest, this.machine.layouts.i32);
+            this.unblock_thread(thread, BlockReason::Accept);
+            // Like for blocked reads, the `accept` has to be completed as the blocked thread.
+            let old_thread = this.set_active_thread(thread);
+            finish_accept(this, result, addr, addrlen, &dest)?;
+            this.set_active_thread(old_thread);
+        }
+        Ok(())
+    }
+
+    /// Sends a datagram to the given address, or to the peer if `addr` is null.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/sendto.2.html>
+    fn sendto(
+        &mut self,
+        fd: &OpTy<'tcx, Provenance>,
+        buf: &OpTy<'tcx, Provenance>,
+        len: &OpTy<'tcx, Provenance>,
+        flags: &OpTy<'tcx, Provenance>,
+        addr: &OpTy<'tcx, Provenance>,
+        addrlen: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let buf = this.read_pointer(buf)?;
+        let len = this.read_target_usize(len)?;
+        let flags = this.read_scalar(flags)?.to_i32()?;
+        let flags = this.remove_msg_nosignal(flags);
+        if flags != 0 {
+            throw_unsup_format!("sendto: flags {flags:#x} are unsupported");
+        }
+
+        if this.ptr_is_null(this.read_pointer(addr)?)? {
+            // Without an address, `sendto` is the same as `send`.
+            let result = this.write(fd, buf, len)?;
+            return Ok(Scalar::from_target_isize(result, this));
+        }
+        let Some(socket) = inet_socket(this, fd, "sendto")? else {
+            let result: i64 = this.fd_not_found()?;
+            return Ok(Scalar::from_target_isize(result, this));
+        };
+        if !socket.is_datagram {
+            throw_unsup_format!(
+                "sendto: sending to an address is only supported on datagram sockets"
+            );
+        }
+        let Some(addr) = this.read_socket_addr(addr, addrlen)? else {
+            return set_errno(this, "EINVAL");
+        };
+        if addr.is_ipv6() != socket.is_ipv6 {
+            return set_errno(this, "EINVAL");
+        }
+
+        let bytes = this.read_bytes_ptr_strip_provenance(buf, Size::from_bytes(len))?;
+        let sent = match socket.send_to(bytes, addr) {
+            Ok(sent) => sent,
+            Err(errno) => return set_errno(this, errno),
+        };
+        // This send happens-before the read that receives the datagram.
+        if let Some(data_race) = &this.machine.data_race {
+            let current_span = this.machine.current_span();
+            socket.release_clock(&data_race.release_clock(this.get_active_thread(), current_span));
+        }
+        // The datagram may unblock a reader.
+        this.fd_readiness_changed()?;
+        Ok(Scalar::from_target_usize(u64::try_from(sent).unwrap(), this))
+    }
+
+    /// Writes the local address of a socket. Unbound sockets have the unspecified address and
+    /// port 0.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/getsockname.2.html>
+    fn getsockname(
+        &mut self,
+        fd: &OpTy<'tcx, Provenance>,
+        addr: &OpTy<'tcx, Provenance>,
+        addrlen: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let addr = this.read_pointer(addr)?;
+        let addrlen = this.read_pointer(addrlen)?;
+        let Some(socket) = inet_socket(this, fd, "getsockname")? else {
+            return Ok(Scalar::from_i32(this.fd_not_found()?));
+        };
+        let local = socket
+            .local_addr()
+            .unwrap_or_else(|| SocketAddr::new(unspecified_ip(socket.is_ipv6), 0));
+        this.write_socket_addr(Some(local), addr, addrlen)?;
+        Ok(Scalar::from_i32(0))
+    }
+
+    /// Writes the address of the peer of a connected socket.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/getpeername.2.html>
+    fn getpeername(
+        &mut self,
+        fd: &OpTy<'tcx, Provenance>,
+        addr: &OpTy<'tcx, Provenance>,
+        addrlen: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let addr = this.read_pointer(addr)?;
+        let addrlen = this.read_pointer(addrlen)?;
+        let Some(socket) = inet_socket(this, fd, "getpeername")? else {
+            return Ok(Scalar::from_i32(this.fd_not_found()?));
+        };
+        let Some(peer) = socket.peer_addr() else {
+            return set_errno(this, "ENOTCONN");
+        };
+        this.write_socket_addr(Some(peer), addr, addrlen)?;
+        Ok(Scalar::from_i32(0))
+    }
+
+    /// Shuts down reading from and/or writing to a connected stream socket. Also supports
+    /// sockets created by `socketpair`.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/shutdown.2.html>
+    fn shutdown(
+        &mut self,
+        fd: &OpTy<'tcx, Provenance>,
+        how: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let how = this.read_scalar(how)?.to_i32()?;
+        let (read, write) = if how == this.eval_libc_i32("SHUT_RD") {
+            (true, false)
+        } else if how == this.eval_libc_i32("SHUT_WR") {
+            (false, true)
+        } else if how == this.eval_libc_i32("SHUT_RDWR") {
+            (true, true)
+        } else {
+            return set_errno(this, "EINVAL");
+        };
+
+        let Some(file_descriptor) = this.machine.fds.get(fd) else {
+            return Ok(Scalar::from_i32(this.fd_not_found()?));
+        };
+        if let Some(socket_pair) = file_descriptor.downcast_ref::<SocketPair>() {
+            socket_pair.shutdown(read, write);
+        } else {
+            let Some(socket) = inet_socket(this, fd, "shutdown")? else { unreachable!() };
+            let state = socket.state.borrow();
+            let State::Connected { stream, .. } = &*state else {
+                return set_errno(this, "ENOTCONN");
+            };
+            stream.shutdown(read, write);
+        }
+        // Shutting down writing makes the peer hit EOF.
+        this.fd_readiness_changed()?;
+        Ok(Scalar::from_i32(0))
+    }
+
+    /// Sets a socket option. Only `int` options that make no difference on the virtual network
+    /// are supported; their values are just stored for `getsockopt`.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/setsockopt.2.html>
+    fn setsockopt(
+        &mut self,
+        fd: &OpTy<'tcx, Provenance>,
+        level: &OpTy<'tcx, Provenance>,
+        name: &OpTy<'tcx, Provenance>,
+        value: &OpTy<'tcx, Provenance>,
+        len: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let level = this.read_scalar(level)?.to_i32()?;
+        let name = this.read_scalar(name)?.to_i32()?;
+        let len = this.read_scalar(len)?.to_u32()?;
+        if !this.is_supported_sockopt(level, name) {
+            throw_unsup_format!("setsockopt: option {name:#x} at level {level:#x} is unsupported");
+        }
+        let Some(socket) = inet_socket(this, fd, "setsockopt")? else {
+            return Ok(Scalar::from_i32(this.fd_not_found()?));
+        };
+        if u64::from(len) != this.machine.layouts.i32.size.bytes() {
+            return set_errno(this, "EINVAL");
+        }
+        let value = this.deref_pointer_as(value, this.machine.layouts.i32)?;
+        let value = this.read_scalar(&value)?.to_i32()?;
+        socket.options.borrow_mut().insert((level, name), value);
+        Ok(Scalar::from_i32(0))
+    }
+
+    /// Gets a socket option: one set by `setsockopt`, or `SO_ERROR`, which is always 0 since
+    /// connections are established right away.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/getsockopt.2.html>
+    fn getsockopt(
+        &mut self,
+        fd: &OpTy<'tcx, Provenance>,
+        level: &OpTy<'tcx, Provenance>,
+        name: &OpTy<'tcx, Provenance>,
+        value: &OpTy<'tcx, Provenance>,
+        len: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let level = this.read_scalar(level)?.to_i32()?;
+        let name = this.read_scalar(name)?.to_i32()?;
+        let is_so_error =
+            level == this.eval_libc_i32("SOL_SOCKET") && name == this.eval_libc_i32("SO_ERROR");
+        if !is_so_error && !this.is_supported_sockopt(level, name) {
+            throw_unsup_format!("getsockopt: option {name:#x} at level {level:#x} is unsupported");
+        }
+        let Some(socket) = inet_socket(this, fd, "getsockopt")? else {
+            return Ok(Scalar::from_i32(this.fd_not_found()?));
+        };
+        let len = this.deref_pointer_as(len, this.libc_ty_layout("socklen_t"))?;
+        let int_size = this.machine.layouts.i32.size.bytes();
+        if u64::from(this.read_scalar(&len)?.to_u32()?) < int_size {
+            return set_errno(this, "EINVAL");
+        }
+        let default =
+            if level == this.eval_libc_i32("IPPROTO_IP") && name == this.eval_libc_i32("IP_TTL") {
+                64
+            } else {
+                0
+            };
+        let result = socket.options.borrow().get(&(level, name)).copied().unwrap_or(default);
+        let value = this.deref_pointer_as(value, this.machine.layouts.i32)?;
+        this.write_scalar(Scalar::from_i32(result), &value)?;
+        this.write_int(int_size, &len)?;
+        Ok(Scalar::from_i32(0))
+    }
+
+    /// Whether `setsockopt` and `getsockopt` support the given option.
+    fn is_supported_sockopt(&self, level: i32, name: i32) -> bool {
+        let this = self.eval_context_ref();
+
+        let mut options = vec![
+            ("SOL_SOCKET", "SO_REUSEADDR"),
+            ("SOL_SOCKET", "SO_BROADCAST"),
+            ("IPPROTO_TCP", "TCP_NODELAY"),
+            ("IPPROTO_IP", "IP_TTL"),
+            ("IPPROTO_IPV6", "IPV6_V6ONLY"),
+        ];
+        if this.tcx.sess.target.os == "macos" {
+            // std sets this on every socket, since macOS has no `MSG_NOSIGNAL`.
+            options.push(("SOL_SOCKET", "SO_NOSIGPIPE"));
+        }
+        options.into_iter().any(|(option_level, option_name)| {
+            level == this.eval_libc_i32(option_level) && name == this.eval_libc_i32(option_name)
+        })
+    }
+
+    /// Removes `MSG_NOSIGNAL` from the flags of `send` or `sendto`, on the targets that have it.
+    /// Miri never raises `SIGPIPE`, so it makes no difference.
+    fn remove_msg_nosignal(&self, flags: i32) -> i32 {
+        let this = self.eval_context_ref();
+
+        if matches!(this.tcx.sess.target.os.as_ref(), "linux" | "android" | "freebsd") {
+            flags & !this.eval_libc_i32("MSG_NOSIGNAL")
+        } else {
+            flags
+        }
+    }
+
+    /// Reads a `sockaddr_in` or `sockaddr_in6` of `addrlen` bytes. Returns `None` if the family
+    /// is neither `AF_INET` nor `AF_INET6`, or `addrlen` is too short.
+    fn read_socket_addr(
+        &self,
+        addr: &OpTy<'tcx, Provenance>,
+        addrlen: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Option<SocketAddr>> {
+        let this = self.eval_context_ref();
+
+        let ptr = this.read_pointer(addr)?;
+        let addrlen = u64::from(this.read_scalar(addrlen)?.to_u32()?);
+
+        // All `sockaddr` types start with the family.
+        let sockaddr = this.libc_ty_layout("sockaddr");
+        if addrlen < sockaddr.size.bytes() {
+            return Ok(None);
+        }
+        let family = this.project_field_named(&this.ptr_to_mplace(ptr, sockaddr), "sa_family")?;
+        let family = this.read_scalar(&family)?.to_uint(family.layout.size)?;
+
+        // Ports and addresses are in network byte order.
+        if family == u128::try_from(this.eval_libc_i32("AF_INET")).unwrap() {
+            let layout = this.libc_ty_layout("sockaddr_in");
+            if addrlen < layout.size.bytes() {
+                return Ok(None);
+            }
+            let sockaddr = this.ptr_to_mplace(ptr, layout);
+            let port = this.project_field_named(&sockaddr, "sin_port")?;
+            let port = this.read_bytes_ptr_strip_provenance(port.ptr(), Size::from_bytes(2))?;
+            let port = u16::from_be_bytes(port.try_into().unwrap());
+            let ip = this.project_field_named(&sockaddr, "sin_addr")?;
+            let ip = this.read_bytes_ptr_strip_provenance(ip.ptr(), Size::from_bytes(4))?;
+            let ip = Ipv4Addr::from(<[u8; 4]>::try_from(ip).unwrap());
+            Ok(Some(SocketAddrV4::new(ip, port).into()))
+        } else if family == u128::try_from(this.eval_libc_i32("AF_INET6")).unwrap() {
+            let layout = this.libc_ty_layout("sockaddr_in6");
+            if addrlen < layout.size.bytes() {
+                return Ok(None);
+            }
+            let sockaddr = this.ptr_to_mplace(ptr, layout);
+            let port = this.project_field_named(&sockaddr, "sin6_port")?;
+            let port = this.read_bytes_ptr_strip_provenance(port.ptr(), Size::from_bytes(2))?;
+            let port = u16::from_be_bytes(port.try_into().unwrap());
+            let ip = this.project_field_named(&sockaddr, "sin6_addr")?;
+            let ip = this.read_bytes_ptr_strip_provenance(ip.ptr(), Size::from_bytes(16))?;
+            let ip = Ipv6Addr::from(<[u8; 16]>::try_from(ip).unwrap());
+            let flowinfo = this.project_field_named(&sockaddr, "sin6_flowinfo")?;
+            let flowinfo = this.read_scalar(&flowinfo)?.to_u32()?;
+            let scope_id = this.project_field_named(&sockaddr, "sin6_scope_id")?;
+            let scope_id = this.read_scalar(&scope_id)?.to_u32()?;
+            Ok(Some(SocketAddrV6::new(ip, port, flowinfo, scope_id).into()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Writes `addr` as a `sockaddr_in` or `sockaddr_in6` to `addr_ptr`, truncated to the
+    /// length `*addrlen_ptr`, and sets that length to the size of the address. `None` only sets
+    /// the length to 0. Nothing is written if `addr_ptr` is null.
+    fn write_socket_addr(
+        &mut self,
+        addr: Option<SocketAddr>,
+        addr_ptr: Pointer<Option<Provenance>>,
+        addrlen_ptr: Pointer<Option<Provenance>>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        if this.ptr_is_null(addr_ptr)? {
+            return Ok(());
+        }
+        let addrlen = this.ptr_to_mplace(addrlen_ptr, this.libc_ty_layout("socklen_t"));
+        let Some(addr) = addr else {
+            return this.write_int(0, &addrlen);
+        };
+        let len = u64::from(this.read_scalar(&addrlen)?.to_u32()?);
+
+        // Build the whole address in a temporary allocation, and copy as much of it as fits.
+        let (layout, prefix) =
+            if addr.is_ipv4() { ("sockaddr_in", "sin") } else { ("sockaddr_in6", "sin6") };
+        let layout = this.libc_ty_layout(layout);
+        let sockaddr = this.allocate(layout, MiriMemoryKind::Machine.into())?;
+        this.write_bytes_ptr(sockaddr.ptr(), iter::repeat(0).take(layout.size.bytes_usize()))?;
+        if matches!(this.tcx.sess.target.os.as_ref(), "macos" | "ios" | "freebsd") {
+            let addr_len = this.project_field_named(&sockaddr, &format!("{prefix}_len"))?;
+            this.write_int(layout.size.bytes(), &addr_len)?;
+        }
+        let family = this.project_field_named(&sockaddr, &format!("{prefix}_family"))?;
+        let port = this.project_field_named(&sockaddr, &format!("{prefix}_port"))?;
+        let ip = this.project_field_named(&sockaddr, &format!("{prefix}_addr"))?;
+        // Ports and addresses are in network byte order.
+        this.write_bytes_ptr(port.ptr(), addr.port().to_be_bytes())?;
+        match addr {
+            SocketAddr::V4(addr) => {
+                this.write_int(this.eval_libc_i32("AF_INET"), &family)?;
+                this.write_bytes_ptr(ip.ptr(), addr.ip().octets())?;
+            }
+            SocketAddr::V6(addr) => {
+                this.write_int(this.eval_libc_i32("AF_INET6"), &family)?;
+                this.write_bytes_ptr(ip.ptr(), addr.ip().octets())?;
+                this.write_int_fields_named(
+                    &[
+                        ("sin6_flowinfo", addr.flowinfo().into()),
+                        ("sin6_scope_id", addr.scope_id().into()),
+                    ],
+                    &sockaddr,
+                )?;
+            }
+        }
+        let copied = Size::from_bytes(len.min(layout.size.bytes()));
+        this.mem_copy(sockaddr.ptr(), addr_ptr, copied, /*nonoverlapping*/ true)?;
+        this.deallocate_ptr(sockaddr.ptr(), None, MiriMemoryKind::Machine.into())?;
+        this.write_int(layout.size.bytes(), &addrlen)
+    }
+}
@@ -1,21 +1,119 @@
-use std::io;
+use std::cell::{Cell, OnceCell, RefCell};
+use std::collections::VecDeque;
+use std::io::{self, Read};
+use std::rc::{Rc, Weak};
 
+use rustc_middle::ty::TyCtxt;
+
+use crate::concurrency::VClock;
 use crate::shims::unix::*;
 use crate::*;
 
-/// Pair of connected sockets.
+/// What a `SocketEnd` is used as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum EndKind {
+    /// A socket created by `socketpair`, which can be read from and written to.
+    Socket,
+    /// The read end of a pipe.
+    PipeRead,
+    /// The write end of a pipe.
+    PipeWrite,
+}
+
+/// One end of a pair of connected sockets, or of a pipe.
+#[derive(Debug)]
+struct SocketEnd {
+    kind: EndKind,
+    /// Data written to the other end that has not been read from this end yet.
+    /// There is no capacity limit, so writes never block.
+    readbuf: RefCell<VecDeque<u8>>,
+    /// The clocks of all writes that put data into `readbuf`, so that reads can synchronize
+    /// with them.
+    clock: RefCell<VClock>,
+    /// The other end. Once every FD referring to it has been closed, reads from this end
+    /// return EOF when `readbuf` is empty and writes fail with `EPIPE`.
+    peer: OnceCell<Weak<SocketEnd>>,
+    /// Whether this end is in non-blocking mode, set by `SOCK_NONBLOCK`, `O_NONBLOCK` or
+    /// `ioctl(FIONBIO)`.
+    is_nonblock: Cell<bool>,
+    /// Whether reading from this end was shut down with `shutdown`.
+    read_shutdown: Cell<bool>,
+    /// Whether writing to this end was shut down with `shutdown`.
+    write_shutdown: Cell<bool>,
+}
+
+impl SocketEnd {
+    fn peer(&self) -> Option<Rc<SocketEnd>> {
+        self.peer.get().and_then(Weak::upgrade)
+    }
+
+    /// Whether no more data can arrive at this end: the peer is closed or stopped writing.
+    fn peer_write_done(&self) -> bool {
+        self.peer().map_or(true, |peer| peer.write_shutdown.get())
+    }
+}
+
+/// An FD referring to one end of a pair of connected sockets, or of a pipe.
 ///
-/// We currently don't allow sending any data through this pair, so this can be just a dummy.
+/// `dup`ed FDs refer to the same end, and the end is closed when the last of them is closed.
 #[derive(Debug)]
-struct SocketPair;
+pub(super) struct SocketPair {
+    end: Rc<SocketEnd>,
+}
 
 impl FileDescriptor for SocketPair {
     fn name(&self) -> &'static str {
-        "socketpair"
+        match self.end.kind {
+            EndKind::Socket => "socketpair",
+            EndKind::PipeRead => "read end of a pipe",
+            EndKind::PipeWrite => "write end of a pipe",
+        }
+    }
+
+    fn read<'tcx>(
+        &mut self,
+        _communicate_allowed: bool,
+        bytes: &mut [u8],
+        _tcx: TyCtxt<'tcx>,
+    ) -> InterpResult<'tcx, io::Result<usize>> {
+        if self.end.read_shutdown.get() {
+            return Ok(Ok(0));
+        }
+        let mut readbuf = self.end.readbuf.borrow_mut();
+        if readbuf.is_empty() && !bytes.is_empty() {
+            if self.end.peer_write_done() {
+                // The peer is closed or stopped writing, and no data is left: EOF.
+                return Ok(Ok(0));
+            }
+            // Blocking sockets are woken up by the FD table once data arrives, see `is_blocking`.
+            return Ok(Err(io::ErrorKind::WouldBlock.into()));
+        }
+        Ok(readbuf.read(bytes))
+    }
+
+    fn write<'tcx>(
+        &self,
+        _communicate_allowed: bool,
+        bytes: &[u8],
+        _tcx: TyCtxt<'tcx>,
+    ) -> InterpResult<'tcx, io::Result<usize>> {
+        let Some(peer) = self.end.peer().filter(|_| !self.end.write_shutdown.get()) else {
+            return Ok(Err(io::ErrorKind::BrokenPipe.into()));
+        };
+        peer.readbuf.borrow_mut().extend(bytes);
+        Ok(Ok(bytes.len()))
+    }
+
+    fn is_readable(&self) -> bool {
+        self.end.kind != EndKind::PipeWrite
+    }
+
+    fn is_writable(&self) -> bool {
+        self.end.kind != EndKind::PipeRead
     }
 
     fn dup(&mut self) -> io::Result<Box<dyn FileDescriptor>> {
-        Ok(Box::new(SocketPair))
+        Ok(Box::new(SocketPair { end: Rc::clone(&self.end) }))
     }
 
     fn close<'tcx>(
@@ -24,14 +122,90 @@ fn close<'tcx>(
     ) -> InterpResult<'tcx, io::Result<i32>> {
         Ok(Ok(0))
     }
+
+    fn is_blocking(&self) -> bool {
+        !self.end.is_nonblock.get()
+    }
+
+    fn set_nonblocking<'tcx>(&self, nonblocking: bool) -> InterpResult<'tcx> {
+        self.end.is_nonblock.set(nonblocking);
+        Ok(())
+    }
+
+    fn release_clock(&self, clock: &VClock) {
+        if let Some(peer) = self.end.peer() {
+            peer.clock.borrow_mut().join(clock);
+        }
+    }
+
+    fn acquire_clock(&self) -> Option<VClock> {
+        Some(self.end.clock.borrow().clone())
+    }
+
+    fn readiness(&self) -> Option<Readiness> {
+        let peer_closed = self.end.peer().is_none();
+        let read_hangup = self.end.peer_write_done() || self.end.read_shutdown.get();
+        let readable = !self.end.readbuf.borrow().is_empty() || read_hangup;
+        // The buffers are unbounded, so writes never block. Writing to a closed peer fails
+        // right away, too.
+        Some(match self.end.kind {
+            EndKind::Socket =>
+                Readiness {
+                    readable,
+                    writable: true,
+                    read_hangup,
+                    // Like Linux, only report a hangup once both directions are shut down.
+                    hangup: read_hangup && (peer_closed || self.end.write_shutdown.get()),
+                    error: false,
+                },
+            EndKind::PipeRead =>
+                Readiness { readable, hangup: peer_closed, ..Readiness::default() },
+            EndKind::PipeWrite =>
+                Readiness { writable: true, error: peer_closed, ..Readiness::default() },
+        })
+    }
+}
+
+impl SocketPair {
+    /// Shuts down reading from and/or writing to this end. After shutting down reading, reads
+    /// return EOF right away; after shutting down writing, writes fail with `EPIPE` and the
+    /// peer reads EOF once it has read all data written before.
+    pub(super) fn shutdown(&self, read: bool, write: bool) {
+        if read {
+            self.end.read_shutdown.set(true);
+        }
+        if write {
+            self.end.write_shutdown.set(true);
+        }
+    }
+}
+
+/// Creates a connected pair of ends of the given kinds, such that data written to one of them
+/// can be read from the other.
+pub(super) fn new_pair(kinds: [EndKind; 2], is_nonblock: bool) -> [SocketPair; 2] {
+    let new_end = |kind| {
+        Rc::new(SocketEnd {
+            kind,
+            readbuf: RefCell::new(VecDeque::new()),
+            clock: RefCell::new(VClock::default()),
+            peer: OnceCell::new(),
+            is_nonblock: Cell::new(is_nonblock),
+            read_shutdown: Cell::new(false),
+            write_shutdown: Cell::new(false),
+        })
+    };
+    let end0 = new_end(kinds[0]);
+    let end1 = new_end(kinds[1]);
+    end0.peer.set(Rc::downgrade(&end1)).unwrap();
+    end1.peer.set(Rc::downgrade(&end0)).unwrap();
+    [SocketPair { end: end0 }, SocketPair { end: end1 }]
 }
 
 impl<'mir, 'tcx: 'mir> EvalContextExt<'mir, 'tcx> for crate::MiriInterpCx<'mir, 'tcx> {}
 pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriInterpCxExt<'mir, 'tcx> {
-    /// Currently this function this function is a stub. Eventually we need to
-    /// properly implement an FD type for sockets and have this function create
-    /// two sockets and associated FDs such that writing to one will produce
-    /// data that can be read from the other.
+    /// Creates a pair of connected stream sockets, such that data written to one of them can be
+    /// read from the other. Only `AF_UNIX` stream sockets are supported, with the `SOCK_NONBLOCK`
+    /// and `SOCK_CLOEXEC` flags on targets that have them.
     ///
     /// For more information on the arguments see the socketpair manpage:
     /// <https://linux.die.net/man/2/socketpair>
@@ -44,17 +218,28 @@ fn socketpair(
     ) -> InterpResult<'tcx, Scalar<Provenance>> {
         let this = self.eval_context_mut();
 
-        let _domain = this.read_scalar(domain)?.to_i32()?;
-        let _type_ = this.read_scalar(type_)?.to_i32()?;
-        let _protocol = this.read_scalar(protocol)?.to_i32()?;
+        let domain = this.read_scalar(domain)?.to_i32()?;
+        let type_ = this.read_scalar(type_)?.to_i32()?;
+        let protocol = this.read_scalar(protocol)?.to_i32()?;
         let sv = this.deref_pointer(sv)?;
 
-        // FIXME: fail on unsupported inputs
+        let (type_, is_nonblock) = this.socket_type_flags(type_);
+
+        if domain != this.eval_libc_i32("AF_UNIX") {
+            throw_unsup_format!("socketpair: domain {domain:#x} is unsupported");
+        }
+        if type_ != this.eval_libc_i32("SOCK_STREAM") {
+            throw_unsup_format!("socketpair: type {type_:#x} is unsupported");
+        }
+        if protocol != 0 {
+            throw_unsup_format!("socketpair: protocol {protocol:#x} is unsupported");
+        }
 
+        let [end0, end1] = new_pair([EndKind::Socket, EndKind::Socket], is_nonblock);
         let fds = &mut this.machine.fds;
-        let sv0 = fds.insert_fd(Box::new(SocketPair));
+        let sv0 = fds.insert_fd(Box::new(end0));
         let sv0 = Scalar::try_from_int(sv0, sv.layout.size).unwrap();
-        let sv1 = fds.insert_fd(Box::new(SocketPair));
+        let sv1 = fds.insert_fd(Box::new(end1));
         let sv1 = Scalar::try_from_int(sv1, sv.layout.size).unwrap();
 
         this.write_scalar(sv0, &sv)?;
@@ -62,4 +247,74 @@ fn socketpair(
 
         Ok(Scalar::from_i32(0))
     }
+
+    /// Removes the `SOCK_NONBLOCK` and `SOCK_CLOEXEC` flags, which are passed as part of the
+    /// socket type on the targets that have them. Returns the remaining type and whether
+    /// `SOCK_NONBLOCK` was set.
+    fn socket_type_flags(&self, mut type_: i32) -> (i32, bool) {
+        let this = self.eval_context_ref();
+
+        let mut is_nonblock = false;
+        if matches!(
+            this.tcx.sess.target.os.as_ref(),
+            "linux" | "android" | "freebsd" | "illumos" | "solaris"
+        ) {
+            let sock_nonblock = this.eval_libc_i32("SOCK_NONBLOCK");
+            let sock_cloexec = this.eval_libc_i32("SOCK_CLOEXEC");
+            if type_ & sock_nonblock == sock_nonblock {
+                is_nonblock = true;
+                type_ &= !sock_nonblock;
+            }
+            if type_ & sock_cloexec == sock_cloexec {
+                // cloexec does nothing as we don't support `exec`
+                type_ &= !sock_cloexec;
+            }
+        }
+        (type_, is_nonblock)
+    }
+
+    /// Creates a pipe: `pipefd[0]` is the read end and `pipefd[1]` the write end. `flags` may
+    /// contain `O_NONBLOCK` and `O_CLOEXEC`; `pipe` is `pipe2` with no flags.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/pipe.2.html>
+    fn pipe2(
+        &mut self,
+        pipefd: &OpTy<'tcx, Provenance>,
+        flags: Option<&OpTy<'tcx, Provenance>>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let pipefd = this.deref_pointer(pipefd)?;
+        let mut flags = match flags {
+            Some(flags) => this.read_scalar(flags)?.to_i32()?,
+            None => 0,
+        };
+
+        let mut is_nonblock = false;
+        let o_nonblock = this.eval_libc_i32("O_NONBLOCK");
+        let o_cloexec = this.eval_libc_i32("O_CLOEXEC");
+        if flags & o_nonblock == o_nonblock {
+            is_nonblock = true;
+            flags &= !o_nonblock;
+        }
+        if flags & o_cloexec == o_cloexec {
+            // cloexec does nothing as we don't support `exec`
+            flags &= !o_cloexec;
+        }
+        if flags != 0 {
+            throw_unsup_format!("pipe2: flags {flags:#x} are unsupported");
+        }
+
+        let [read_end, write_end] = new_pair([EndKind::PipeRead, EndKind::PipeWrite], is_nonblock);
+        let fds = &mut this.machine.fds;
+        let fd0 = fds.insert_fd(Box::new(read_end));
+        let fd0 = Scalar::try_from_int(fd0, pipefd.layout.size).unwrap();
+        let fd1 = fds.insert_fd(Box::new(write_end));
+        let fd1 = Scalar::try_from_int(fd1, pipefd.layout.size).unwrap();
+
+        this.write_scalar(fd0, &pipefd)?;
+        this.write_scalar(fd1, &pipefd.offset(pipefd.layout.size, pipefd.layout, this)?)?;
+
+        Ok(Scalar::from_i32(0))
+    }
 }
@@ -0,0 +1,252 @@
+//@only-target-linux
+// We use `yield` to test specific interleavings, so disable automatic preemption.
+//@compile-flags: -Zmiri-preemption-rate=0
+
+use std::thread;
+
+fn main() {
+    test_epoll_socketpair();
+    test_epoll_edge_triggered();
+    test_epoll_eventfd();
+    test_epoll_pipe();
+    test_epoll_ctl_errors();
+    test_epoll_wait_blocks();
+    test_epoll_wait_timeout();
+    test_epoll_fd_reuse();
+}
+
+fn errno() -> i32 {
+    std::io::Error::last_os_error().raw_os_error().unwrap()
+}
+
+const EMPTY: libc::epoll_event = libc::epoll_event { events: 0, u64: 0 };
+
+fn epoll_add(epfd: i32, fd: i32, events: i32, data: u64) {
+    let mut event = libc::epoll_event { events: events as u32, u64: data };
+    let res = unsafe { libc::epoll_ctl(epfd, libc::EPOLL_CTL_ADD, fd, &mut event) };
+    assert_eq!(res, 0);
+}
+
+/// Calls `epoll_wait` and returns the ready events and their data.
+fn epoll_wait(epfd: i32, timeout: i32) -> Vec<(u32, u64)> {
+    let mut events = [EMPTY; 8];
+    let res = unsafe { libc::epoll_wait(epfd, events.as_mut_ptr(), 8, timeout) };
+    assert!(res >= 0);
+    events[..res as usize].iter().map(|event| (event.events, event.u64)).collect()
+}
+
+fn socketpair() -> [i32; 2] {
+    let mut fds = [-1, -1];
+    let res = unsafe { libc::socketpair(libc::AF_UNIX, libc::SOCK_STREAM, 0, fds.as_mut_ptr()) };
+    assert_eq!(res, 0);
+    fds
+}
+
+fn test_epoll_socketpair() {
+    let epfd = unsafe { libc::epoll_create1(0) };
+    assert!(epfd >= 0);
+    let fds = socketpair();
+    epoll_add(epfd, fds[1], libc::EPOLLIN | libc::EPOLLOUT | libc::EPOLLRDHUP, 7);
+
+    // Level-triggered: writable, and reported again as long as that stays true.
+    assert_eq!(epoll_wait(epfd, 0), [(libc::EPOLLOUT as u32, 7)]);
+    assert_eq!(epoll_wait(epfd, 0), [(libc::EPOLLOUT as u32, 7)]);
+
+    let data = b"abc";
+    let res = unsafe { libc::write(fds[0], data.as_ptr().cast(), 3) };
+    assert_eq!(res, 3);
+    assert_eq!(epoll_wait(epfd, 0), [((libc::EPOLLIN | libc::EPOLLOUT) as u32, 7)]);
+
+    let mut buf = [0u8; 3];
+    let res = unsafe { libc::read(fds[1], buf.as_mut_ptr().cast(), 3) };
+    assert_eq!(res, 3);
+    assert_eq!(epoll_wait(epfd, 0), [(libc::EPOLLOUT as u32, 7)]);
+
+    // Closing the peer reports a hangup, and makes the socket readable (at EOF).
+    assert_eq!(unsafe { libc::close(fds[0]) }, 0);
+    let hangup = libc::EPOLLIN | libc::EPOLLOUT | libc::EPOLLRDHUP | libc::EPOLLHUP;
+    assert_eq!(epoll_wait(epfd, 0), [(hangup as u32, 7)]);
+
+    // Closed FDs are removed from the interest list.
+    assert_eq!(unsafe { libc::close(fds[1]) }, 0);
+    assert!(epoll_wait(epfd, 0).is_empty());
+    assert_eq!(unsafe { libc::close(epfd) }, 0);
+}
+
+fn test_epoll_edge_triggered() {
+    let epfd = unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) };
+    assert!(epfd >= 0);
+    let fds = socketpair();
+    epoll_add(epfd, fds[1], libc::EPOLLIN | libc::EPOLLOUT | libc::EPOLLET, 1);
+
+    // Edge-triggered: events are only reported once after they become ready.
+    assert_eq!(epoll_wait(epfd, 0), [(libc::EPOLLOUT as u32, 1)]);
+    assert!(epoll_wait(epfd, 0).is_empty());
+
+    let data = b"a";
+    let res = unsafe { libc::write(fds[0], data.as_ptr().cast(), 1) };
+    assert_eq!(res, 1);
+    assert_eq!(epoll_wait(epfd, 0), [(libc::EPOLLIN as u32, 1)]);
+    assert!(epoll_wait(epfd, 0).is_empty());
+
+    // After draining the data, new data is a new edge.
+    let mut buf = [0u8; 1];
+    let res = unsafe { libc::read(fds[1], buf.as_mut_ptr().cast(), 1) };
+    assert_eq!(res, 1);
+    let res = unsafe { libc::write(fds[0], data.as_ptr().cast(), 1) };
+    assert_eq!(res, 1);
+    assert_eq!(epoll_wait(epfd, 0), [(libc::EPOLLIN as u32, 1)]);
+
+    // `EPOLL_CTL_MOD` re-arms the interest.
+    let mut event = libc::epoll_event { events: (libc::EPOLLIN | libc::EPOLLET) as u32, u64: 2 };
+    let res = unsafe { libc::epoll_ctl(epfd, libc::EPOLL_CTL_MOD, fds[1], &mut event) };
+    assert_eq!(res, 0);
+    assert_eq!(epoll_wait(epfd, 0), [(libc::EPOLLIN as u32, 2)]);
+
+    for fd in [fds[0], fds[1], epfd] {
+        assert_eq!(unsafe { libc::close(fd) }, 0);
+    }
+}
+
+fn test_epoll_eventfd() {
+    let epfd = unsafe { libc::epoll_create1(0) };
+    assert!(epfd >= 0);
+    let fd = unsafe { libc::eventfd(0, libc::EFD_NONBLOCK) };
+    assert!(fd >= 0);
+    epoll_add(epfd, fd, libc::EPOLLIN | libc::EPOLLET, 3);
+    assert!(epoll_wait(epfd, 0).is_empty());
+
+    let mut buf = [0u8; 8];
+    let res = unsafe { libc::read(fd, buf.as_mut_ptr().cast(), 8) };
+    assert_eq!(res, -1);
+    assert_eq!(errno(), libc::EAGAIN);
+
+    let one = 1u64.to_ne_bytes();
+    for _ in 0..2 {
+        let res = unsafe { libc::write(fd, one.as_ptr().cast(), 8) };
+        assert_eq!(res, 8);
+    }
+    assert_eq!(epoll_wait(epfd, 0), [(libc::EPOLLIN as u32, 3)]);
+
+    // Reading returns and resets the counter.
+    let res = unsafe { libc::read(fd, buf.as_mut_ptr().cast(), 8) };
+    assert_eq!(res, 8);
+    assert_eq!(u64::from_ne_bytes(buf), 2);
+    let res = unsafe { libc::read(fd, buf.as_mut_ptr().cast(), 8) };
+    assert_eq!(res, -1);
+    assert_eq!(errno(), libc::EAGAIN);
+
+    assert_eq!(unsafe { libc::close(fd) }, 0);
+    assert_eq!(unsafe { libc::close(epfd) }, 0);
+}
+
+fn test_epoll_pipe() {
+    let epfd = unsafe { libc::epoll_create1(0) };
+    assert!(epfd >= 0);
+    let mut fds = [-1, -1];
+    let res = unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_NONBLOCK | libc::O_CLOEXEC) };
+    assert_eq!(res, 0);
+    epoll_add(epfd, fds[0], libc::EPOLLIN, 0);
+    epoll_add(epfd, fds[1], libc::EPOLLOUT, 1);
+    assert_eq!(epoll_wait(epfd, 0), [(libc::EPOLLOUT as u32, 1)]);
+
+    let data = b"ab";
+    let res = unsafe { libc::write(fds[1], data.as_ptr().cast(), 2) };
+    assert_eq!(res, 2);
+    assert_eq!(epoll_wait(epfd, 0), [(libc::EPOLLIN as u32, 0), (libc::EPOLLOUT as u32, 1)]);
+
+    // Closing the write end is reported as a hangup on the read end.
+    assert_eq!(unsafe { libc::close(fds[1]) }, 0);
+    assert_eq!(epoll_wait(epfd, 0), [((libc::EPOLLIN | libc::EPOLLHUP) as u32, 0)]);
+    let mut buf = [0u8; 4];
+    let res = unsafe { libc::read(fds[0], buf.as_mut_ptr().cast(), 4) };
+    assert_eq!(res, 2);
+    let res = unsafe { libc::read(fds[0], buf.as_mut_ptr().cast(), 4) };
+    assert_eq!(res, 0);
+
+    assert_eq!(unsafe { libc::close(fds[0]) }, 0);
+    assert_eq!(unsafe { libc::close(epfd) }, 0);
+}
+
+fn test_epoll_ctl_errors() {
+    let epfd = unsafe { libc::epoll_create1(0) };
+    assert!(epfd >= 0);
+    let fds = socketpair();
+    let mut event = libc::epoll_event { events: libc::EPOLLIN as u32, u64: 0 };
+
+    let res = unsafe { libc::epoll_ctl(epfd, libc::EPOLL_CTL_MOD, fds[0], &mut event) };
+    assert_eq!(res, -1);
+    assert_eq!(errno(), libc::ENOENT);
+    epoll_add(epfd, fds[0], libc::EPOLLIN, 0);
+    let res = unsafe { libc::epoll_ctl(epfd, libc::EPOLL_CTL_ADD, fds[0], &mut event) };
+    assert_eq!(res, -1);
+    assert_eq!(errno(), libc::EEXIST);
+    let res = unsafe { libc::epoll_ctl(epfd, libc::EPOLL_CTL_DEL, fds[0], &mut event) };
+    assert_eq!(res, 0);
+    let res = unsafe { libc::epoll_ctl(epfd, libc::EPOLL_CTL_DEL, fds[0], &mut event) };
+    assert_eq!(res, -1);
+    assert_eq!(errno(), libc::ENOENT);
+
+    for fd in [fds[0], fds[1], epfd] {
+        assert_eq!(unsafe { libc::close(fd) }, 0);
+    }
+}
+
+fn test_epoll_wait_blocks() {
+    let epfd = unsafe { libc::epoll_create1(0) };
+    assert!(epfd >= 0);
+    let fd = unsafe { libc::eventfd(0, 0) };
+    assert!(fd >= 0);
+    epoll_add(epfd, fd, libc::EPOLLIN, 5);
+
+    let waiter = thread::spawn(move || {
+        assert_eq!(epoll_wait(epfd, -1), [(libc::EPOLLIN as u32, 5)]);
+    });
+
+    // Let the waiter block first.
+    thread::yield_now();
+    let one = 1u64.to_ne_bytes();
+    let res = unsafe { libc::write(fd, one.as_ptr().cast(), 8) };
+    assert_eq!(res, 8);
+    waiter.join().unwrap();
+
+    assert_eq!(unsafe { libc::close(fd) }, 0);
+    assert_eq!(unsafe { libc::close(epfd) }, 0);
+}
+
+fn test_epoll_wait_timeout() {
+    let epfd = unsafe { libc::epoll_create1(0) };
+    assert!(epfd >= 0);
+    let fd = unsafe { libc::eventfd(0, 0) };
+    assert!(fd >= 0);
+    epoll_add(epfd, fd, libc::EPOLLIN, 0);
+
+    let start = std::time::Instant::now();
+    assert!(epoll_wait(epfd, 10).is_empty());
+    assert!(start.elapsed().as_millis() >= 10);
+
+    assert_eq!(unsafe { libc::close(fd) }, 0);
+    assert_eq!(unsafe { libc::close(epfd) }, 0);
+}
+
+fn test_epoll_fd_reuse() {
+    let epfd = unsafe { libc::epoll_create1(0) };
+    assert!(epfd >= 0);
+    let fd = unsafe { libc::eventfd(1, 0) };
+    assert!(fd >= 0);
+    epoll_add(epfd, fd, libc::EPOLLIN, 1);
+
+    // An FD that reuses the number of a closed one is not watched in its place.
+    assert_eq!(unsafe { libc::close(fd) }, 0);
+    let new_fd = unsafe { libc::eventfd(1, 0) };
+    assert_eq!(new_fd, fd);
+    assert!(epoll_wait(epfd, 0).is_empty());
+
+    // It can be added as a new interest.
+    epoll_add(epfd, new_fd, libc::EPOLLIN, 2);
+    assert_eq!(epoll_wait(epfd, 0), [(libc::EPOLLIN as u32, 2)]);
+
+    for fd in [new_fd, epfd] {
+        assert_eq!(unsafe { libc::close(fd) }, 0);
+    }
+}
@@ -281,6 +281,25 @@ fn test_isatty() {
     }
 }
 
+fn test_ioctl_cloexec() {
+    let path = utils::tmp().join("miri_test_libc_ioctl_cloexec.txt");
+    // Cleanup before test.
+    remove_file(&path).ok();
+    let file = File::create(&path).unwrap();
+
+    // There is no `exec` in Miri, so these just need to succeed on open FDs.
+    unsafe {
+        assert_eq!(libc::ioctl(file.as_raw_fd(), libc::FIOCLEX), 0);
+        assert_eq!(libc::ioctl(file.as_raw_fd(), libc::FIONCLEX), 0);
+        assert_eq!(libc::ioctl(-1, libc::FIOCLEX), -1);
+        assert_eq!(std::io::Error::last_os_error().raw_os_error().unwrap(), libc::EBADF);
+    }
+
+    // Cleanup after test.
+    drop(file);
+    remove_file(&path).unwrap();
+}
+
 fn test_memcpy() {
     unsafe {
         let src = [1i8, 2, 3];
@@ -412,6 +431,7 @@ fn main() {
     test_localtime_r();
 
     test_isatty();
+    test_ioctl_cloexec();
 
     test_clocks();
 
@@ -0,0 +1,190 @@
+//@ignore-target-windows: No libc on Windows
+//@ignore-target-apple: SOCK_NONBLOCK does not exist on macOS
+// We use `yield` to test specific interleavings, so disable automatic preemption.
+//@compile-flags: -Zmiri-preemption-rate=0
+
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::thread;
+
+fn main() {
+    test_socketpair();
+    test_socketpair_nonblock();
+    test_socketpair_peer_closed();
+    test_socketpair_blocking_read();
+    test_socketpair_read_synchronizes();
+    test_unix_stream_pair();
+    test_pipe_wrong_end();
+}
+
+fn errno() -> i32 {
+    std::io::Error::last_os_error().raw_os_error().unwrap()
+}
+
+fn socketpair(flags: i32) -> [i32; 2] {
+    let mut fds = [-1, -1];
+    let res =
+        unsafe { libc::socketpair(libc::AF_UNIX, libc::SOCK_STREAM | flags, 0, fds.as_mut_ptr()) };
+    assert_eq!(res, 0);
+    fds
+}
+
+fn test_socketpair() {
+    let fds = socketpair(0);
+
+    // Data flows in both directions, and is read back in order.
+    let data = b"abcde";
+    let res = unsafe { libc::write(fds[0], data.as_ptr().cast(), 5) };
+    assert_eq!(res, 5);
+    let data = b"fgh";
+    let res = unsafe { libc::write(fds[0], data.as_ptr().cast(), 3) };
+    assert_eq!(res, 3);
+    let mut buf = [0u8; 6];
+    let res = unsafe { libc::read(fds[1], buf.as_mut_ptr().cast(), 6) };
+    assert_eq!(res, 6);
+    assert_eq!(&buf, b"abcdef");
+    let res = unsafe { libc::read(fds[1], buf.as_mut_ptr().cast(), 6) };
+    assert_eq!(res, 2);
+    assert_eq!(&buf[..2], b"gh");
+
+    let data = b"12";
+    let res = unsafe { libc::write(fds[1], data.as_ptr().cast(), 2) };
+    assert_eq!(res, 2);
+    let mut buf = [0u8; 2];
+    let res = unsafe { libc::read(fds[0], buf.as_mut_ptr().cast(), 2) };
+    assert_eq!(res, 2);
+    assert_eq!(&buf, b"12");
+
+    // A `dup`ed FD refers to the same end.
+    let dup = unsafe { libc::fcntl(fds[1], libc::F_DUPFD_CLOEXEC, 0) };
+    assert!(dup >= 0);
+    let res = unsafe { libc::write(fds[0], data.as_ptr().cast(), 2) };
+    assert_eq!(res, 2);
+    let res = unsafe { libc::read(dup, buf.as_mut_ptr().cast(), 2) };
+    assert_eq!(res, 2);
+    assert_eq!(&buf, b"12");
+
+    for fd in [fds[0], fds[1], dup] {
+        assert_eq!(unsafe { libc::close(fd) }, 0);
+    }
+}
+
+fn test_socketpair_nonblock() {
+    let fds = socketpair(libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC);
+
+    let mut buf = [0u8; 4];
+    let res = unsafe { libc::read(fds[1], buf.as_mut_ptr().cast(), 4) };
+    assert_eq!(res, -1);
+    assert_eq!(errno(), libc::EAGAIN);
+
+    let data = b"ab";
+    let res = unsafe { libc::write(fds[0], data.as_ptr().cast(), 2) };
+    assert_eq!(res, 2);
+    let res = unsafe { libc::read(fds[1], buf.as_mut_ptr().cast(), 4) };
+    assert_eq!(res, 2);
+    assert_eq!(&buf[..2], b"ab");
+
+    assert_eq!(unsafe { libc::close(fds[0]) }, 0);
+    assert_eq!(unsafe { libc::close(fds[1]) }, 0);
+}
+
+fn test_socketpair_peer_closed() {
+    let fds = socketpair(0);
+
+    let data = b"abc";
+    let res = unsafe { libc::write(fds[0], data.as_ptr().cast(), 3) };
+    assert_eq!(res, 3);
+    assert_eq!(unsafe { libc::close(fds[0]) }, 0);
+
+    // Data written before the peer was closed can still be read, then the socket is at EOF.
+    let mut buf = [0u8; 4];
+    let res = unsafe { libc::read(fds[1], buf.as_mut_ptr().cast(), 4) };
+    assert_eq!(res, 3);
+    assert_eq!(&buf[..3], b"abc");
+    let res = unsafe { libc::read(fds[1], buf.as_mut_ptr().cast(), 4) };
+    assert_eq!(res, 0);
+
+    // Writing to a closed peer fails.
+    let res = unsafe { libc::write(fds[1], data.as_ptr().cast(), 3) };
+    assert_eq!(res, -1);
+    assert_eq!(errno(), libc::EPIPE);
+
+    assert_eq!(unsafe { libc::close(fds[1]) }, 0);
+}
+
+fn test_socketpair_blocking_read() {
+    let fds = socketpair(0);
+
+    // The reader blocks until data arrives, and then until the peer is closed.
+    let reader = thread::spawn(move || {
+        let mut buf = [0u8; 4];
+        let res = unsafe { libc::read(fds[1], buf.as_mut_ptr().cast(), 4) };
+        assert_eq!(res, 2);
+        assert_eq!(&buf[..2], b"ab");
+        let res = unsafe { libc::read(fds[1], buf.as_mut_ptr().cast(), 4) };
+        assert_eq!(res, 0);
+        assert_eq!(unsafe { libc::close(fds[1]) }, 0);
+    });
+
+    thread::yield_now();
+    let data = b"ab";
+    let res = unsafe { libc::write(fds[0], data.as_ptr().cast(), 2) };
+    assert_eq!(res, 2);
+    thread::yield_now();
+    assert_eq!(unsafe { libc::close(fds[0]) }, 0);
+
+    reader.join().unwrap();
+}
+
+fn test_socketpair_read_synchronizes() {
+    static mut VAL: u32 = 0;
+    let fds = socketpair(0);
+
+    // Sending a byte after a non-atomic write, and reading after receiving it, is not a data race.
+    let reader = thread::spawn(move || {
+        let mut buf = [0u8; 1];
+        let res = unsafe { libc::read(fds[1], buf.as_mut_ptr().cast(), 1) };
+        assert_eq!(res, 1);
+        assert_eq!(unsafe { VAL }, 1);
+    });
+
+    unsafe { VAL = 1 };
+    let res = unsafe { libc::write(fds[0], [0u8].as_ptr().cast(), 1) };
+    assert_eq!(res, 1);
+    reader.join().unwrap();
+
+    assert_eq!(unsafe { libc::close(fds[0]) }, 0);
+    assert_eq!(unsafe { libc::close(fds[1]) }, 0);
+}
+
+fn test_unix_stream_pair() {
+    let (mut a, mut b) = UnixStream::pair().unwrap();
+
+    let echo = thread::spawn(move || {
+        let mut buf = Vec::new();
+        b.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, b"hello");
+    });
+
+    a.write_all(b"hello").unwrap();
+    drop(a);
+    echo.join().unwrap();
+}
+
+fn test_pipe_wrong_end() {
+    let mut fds = [-1, -1];
+    let res = unsafe { libc::pipe(fds.as_mut_ptr()) };
+    assert_eq!(res, 0);
+
+    // Each end of a pipe only goes one way.
+    let mut buf = [0u8; 4];
+    let res = unsafe { libc::read(fds[1], buf.as_mut_ptr().cast(), 4) };
+    assert_eq!(res, -1);
+    assert_eq!(errno(), libc::EBADF);
+    let res = unsafe { libc::write(fds[0], buf.as_ptr().cast(), 4) };
+    assert_eq!(res, -1);
+    assert_eq!(errno(), libc::EBADF);
+
+    assert_eq!(unsafe { libc::close(fds[0]) }, 0);
+    assert_eq!(unsafe { libc::close(fds[1]) }, 0);
+}
@@ -0,0 +1,145 @@
+//@ignore-target-windows: Sockets are not implemented on Windows
+
+// The network is virtual, so all of this works with isolation enabled.
+
+use std::io::{ErrorKind, Read, Write};
+use std::net::{Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::thread;
+
+fn main() {
+    test_tcp(Ipv4Addr::LOCALHOST.into());
+    test_tcp(Ipv6Addr::LOCALHOST.into());
+    test_tcp_unspecified();
+    test_tcp_errors();
+    test_tcp_nonblocking();
+    test_udp();
+    test_udp_connected();
+}
+
+fn test_tcp(ip: std::net::IpAddr) {
+    let listener = TcpListener::bind(SocketAddr::new(ip, 0)).unwrap();
+    let addr = listener.local_addr().unwrap();
+    assert_eq!(addr.ip(), ip);
+    assert_ne!(addr.port(), 0);
+
+    // The client sends a request and shuts down writing; the server replies to it.
+    let client = thread::spawn(move || {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        assert_eq!(stream.peer_addr().unwrap(), addr);
+        stream.set_nodelay(true).unwrap();
+        assert!(stream.nodelay().unwrap());
+        stream.write_all(b"ping").unwrap();
+        stream.shutdown(Shutdown::Write).unwrap();
+        let mut reply = String::new();
+        stream.read_to_string(&mut reply).unwrap();
+        assert_eq!(reply, "pong");
+        stream.local_addr().unwrap()
+    });
+
+    let (mut stream, peer) = listener.accept().unwrap();
+    assert_eq!(stream.local_addr().unwrap(), addr);
+    let mut request = String::new();
+    stream.read_to_string(&mut request).unwrap();
+    assert_eq!(request, "ping");
+    stream.write_all(b"pong").unwrap();
+    drop(stream);
+    assert_eq!(client.join().unwrap(), peer);
+}
+
+fn test_tcp_unspecified() {
+    // A listener on the unspecified address accepts connections to the loopback address.
+    let listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, 0)).unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let mut client = TcpStream::connect((Ipv4Addr::LOCALHOST, port)).unwrap();
+    let (mut server, _) = listener.accept().unwrap();
+    assert_eq!(server.local_addr().unwrap(), SocketAddr::from((Ipv4Addr::LOCALHOST, port)));
+
+    client.write_all(b"abc").unwrap();
+    let mut buf = [0; 3];
+    server.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"abc");
+
+    // Closing one end makes the other one read EOF.
+    drop(client);
+    assert_eq!(server.read(&mut buf).unwrap(), 0);
+}
+
+fn test_tcp_errors() {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let addr = listener.local_addr().unwrap();
+    assert_eq!(TcpListener::bind(addr).unwrap_err().kind(), ErrorKind::AddrInUse);
+    drop(listener);
+
+    // Nobody listens anymore, and the port can be reused.
+    assert_eq!(TcpStream::connect(addr).unwrap_err().kind(), ErrorKind::ConnectionRefused);
+    TcpListener::bind(addr).unwrap();
+
+    // Only loopback addresses exist.
+    let err = TcpListener::bind((Ipv4Addr::new(192, 0, 2, 1), 0)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::AddrNotAvailable);
+}
+
+fn test_tcp_nonblocking() {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    listener.set_nonblocking(true).unwrap();
+    assert_eq!(listener.accept().unwrap_err().kind(), ErrorKind::WouldBlock);
+
+    let _client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (server, _) = listener.accept().unwrap();
+    server.set_nonblocking(true).unwrap();
+    assert_eq!((&server).read(&mut [0; 4]).unwrap_err().kind(), ErrorKind::WouldBlock);
+}
+
+fn test_udp() {
+    let a = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let b = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let a_addr = a.local_addr().unwrap();
+    let b_addr = b.local_addr().unwrap();
+
+    // Datagrams keep their boundaries and report their source.
+    assert_eq!(a.send_to(b"hello", b_addr).unwrap(), 5);
+    assert_eq!(a.send_to(b"world!", b_addr).unwrap(), 6);
+    let mut buf = [0; 16];
+    assert_eq!(b.recv_from(&mut buf).unwrap(), (5, a_addr));
+    assert_eq!(&buf[..5], b"hello");
+    // The rest of a datagram that does not fit is discarded.
+    let mut small = [0; 3];
+    assert_eq!(b.recv_from(&mut small).unwrap(), (3, a_addr));
+    assert_eq!(&small, b"wor");
+
+    // Datagrams to an address nobody is bound to are dropped.
+    let unbound = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let unbound_addr = unbound.local_addr().unwrap();
+    drop(unbound);
+    assert_eq!(a.send_to(b"lost", unbound_addr).unwrap(), 4);
+
+    // Blocking receives wait for a datagram.
+    let receiver = thread::spawn(move || {
+        let mut buf = [0; 16];
+        let (len, _) = b.recv_from(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"late");
+    });
+    a.send_to(b"late", b_addr).unwrap();
+    receiver.join().unwrap();
+}
+
+fn test_udp_connected() {
+    let a = UdpSocket::bind((Ipv6Addr::LOCALHOST, 0)).unwrap();
+    let b = UdpSocket::bind((Ipv6Addr::LOCALHOST, 0)).unwrap();
+    let other = UdpSocket::bind((Ipv6Addr::LOCALHOST, 0)).unwrap();
+    a.connect(b.local_addr().unwrap()).unwrap();
+    assert_eq!(a.peer_addr().unwrap(), b.local_addr().unwrap());
+
+    // A connected socket only receives from its peer.
+    other.send_to(b"ignored", a.local_addr().unwrap()).unwrap();
+    b.send_to(b"reply", a.local_addr().unwrap()).unwrap();
+    a.send(b"request").unwrap();
+
+    let mut buf = [0; 16];
+    let len = b.recv(&mut buf).unwrap();
+    assert_eq!(&buf[..len], b"request");
+    let len = a.recv(&mut buf).unwrap();
+    assert_eq!(&buf[..len], b"reply");
+    a.set_nonblocking(true).unwrap();
+    assert_eq!(a.recv(&mut buf).unwrap_err().kind(), ErrorKind::WouldBlock);
+}
@@ -17,6 +17,7 @@
 
 pub use object;
 pub use regex;
+pub use serde_json;
 pub use wasmparser;
 
 pub use cc::{cc, extra_c_flags, extra_cxx_flags, Cc};
@@ -0,0 +1,19 @@
+#![crate_type = "lib"]
+#![feature(rustc_attrs)]
+// The compilation from the second checkout reuses the object file of the first one.
+#![rustc_partition_reused(module = "lib", cfg = "second")]
+
+pub fn add(a: u32, b: u32) -> u32 {
+    a + b
+}
+
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Point {
+    pub fn manhattan_distance(&self, other: &Point) -> u32 {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+}
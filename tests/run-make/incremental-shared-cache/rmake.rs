@@ -0,0 +1,36 @@
+// A session published to the shared incremental cache (`-Z incremental-shared-cache`) should be
+// picked up by a compilation of the same crate from another checkout, which starts with an empty
+// incremental directory, as long as both checkouts are remapped to the same path. The codegen unit
+// is then reused, as checked by `#![rustc_partition_reused]` in `lib.rs`.
+
+extern crate run_make_support;
+
+use run_make_support::{rustc, tmp_dir};
+use std::fs;
+
+fn compile(name: &str) -> String {
+    let checkout = tmp_dir().join(name);
+    fs::create_dir_all(&checkout).unwrap();
+    fs::copy("lib.rs", checkout.join("lib.rs")).unwrap();
+
+    let output = rustc()
+        .input(checkout.join("lib.rs"))
+        .incremental(checkout.join("incr"))
+        .arg(format!("--remap-path-prefix={}=/checkout", checkout.display()))
+        .arg(format!("-Zincremental-shared-cache={}", tmp_dir().join("shared").display()))
+        .arg("-Zincremental-info")
+        .arg("-Zquery-dep-graph")
+        // Lets the attributes of `lib.rs` know which checkout they are compiled from.
+        .cfg(name)
+        .run();
+    String::from_utf8(output.stderr).unwrap()
+}
+
+fn main() {
+    let first = compile("first");
+    assert!(!first.contains("[incremental] shared cache"), "{first}");
+    assert!(fs::read_dir(tmp_dir().join("shared").join("sessions")).unwrap().count() == 1);
+
+    let second = compile("second");
+    assert!(second.contains("[incremental] shared cache"), "{second}");
+}
@@ -0,0 +1,8 @@
+#[inline(never)]
+fn generic<T: Default>() -> T {
+    T::default()
+}
+
+pub fn bar() -> (u8, u64) {
+    (generic::<u8>(), generic::<u64>())
+}
@@ -0,0 +1,36 @@
+//! Checks that `--print=mono-items-size` lists the instantiations of the
+//! generic functions of the crate along with their size.
+
+//@ ignore-cross-compile
+
+extern crate run_make_support;
+
+use run_make_support::rustc;
+
+fn main() {
+    let output = rustc()
+        .input("foo.rs")
+        .crate_type("lib")
+        .arg("-Zunstable-options")
+        .print("mono-items-size")
+        .run();
+    let report = String::from_utf8(output.stdout).unwrap();
+
+    assert!(report.contains(r#"{"crate":"foo","#), "{report}");
+    assert!(report.contains(r#""item":"foo::generic","#), "{report}");
+    for args in ["u8", "u64"] {
+        let size = instantiation_size(&report, args);
+        assert!(size > 0, "`generic::<{args}>` has no size:\n{report}");
+    }
+}
+
+/// Returns the size reported for the instantiation of `generic` with `args`.
+fn instantiation_size(report: &str, args: &str) -> u64 {
+    let prefix = format!(r#"{{"args":["{args}"],"size":"#);
+    let Some(start) = report.find(&prefix) else {
+        panic!("no instantiation with args `{args}`:\n{report}");
+    };
+    let size = &report[start + prefix.len()..];
+    let end = size.find(|c: char| !c.is_ascii_digit()).unwrap_or(size.len());
+    size[..end].parse().unwrap()
+}
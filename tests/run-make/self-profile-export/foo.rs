@@ -0,0 +1,7 @@
+pub fn generic<T: Default>() -> T {
+    T::default()
+}
+
+pub fn bar() -> (u8, u64) {
+    (generic(), generic())
+}
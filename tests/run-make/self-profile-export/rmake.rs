@@ -0,0 +1,67 @@
+//! Checks that `-Zself-profile-export` writes a Chrome trace and a JSON summary of the
+//! self-profile, named after the crate and the process id, and that it warns when the
+//! self-profiler isn't enabled.
+
+extern crate run_make_support;
+
+use run_make_support::serde_json::{self, Value};
+use run_make_support::{rustc, tmp_dir};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn main() {
+    let profile_dir = tmp_dir().join("profile");
+    rustc()
+        .input("foo.rs")
+        .crate_type("lib")
+        .arg(format!("-Zself-profile={}", profile_dir.display()))
+        .arg("-Zself-profile-export=chrome-trace,json-summary")
+        .run();
+
+    let trace = read_json(export_path(&profile_dir, ".trace.json"));
+    let events = trace["traceEvents"].as_array().unwrap();
+    let names: Vec<&str> = events.iter().filter_map(|event| event["name"].as_str()).collect();
+    for query in ["typeck", "optimized_mir"] {
+        assert!(names.contains(&query), "no `{query}` event in the trace: {names:?}");
+    }
+    let process_name = events.iter().find(|event| event["name"] == "process_name").unwrap();
+    assert_eq!(process_name["args"]["name"], "foo");
+
+    let summary = read_json(export_path(&profile_dir, ".summary.json"));
+    let queries = summary["queries"].as_array().unwrap();
+    let typeck = queries.iter().find(|query| query["query"] == "typeck").unwrap();
+    assert!(typeck["invocations"].as_u64().unwrap() > 0, "{typeck}");
+    let passes = summary["passes"].as_array().unwrap();
+    assert!(passes.iter().any(|pass| pass["pass"] == "codegen_crate"), "{passes:?}");
+
+    let output = rustc()
+        .input("foo.rs")
+        .crate_type("lib")
+        .arg("-Zself-profile-export=json-summary")
+        .run();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("warning: using -Z self-profile-export without enabling the self-profiler"),
+        "{stderr}"
+    );
+}
+
+/// Returns the path of the export ending with `suffix`, which is named `{crate}-{pid}{suffix}`.
+fn export_path(profile_dir: &Path, suffix: &str) -> PathBuf {
+    let mut paths = fs::read_dir(profile_dir).unwrap().map(|entry| entry.unwrap().path());
+    let Some(path) = paths.find(|path| {
+        let name = path.file_name().unwrap().to_str().unwrap();
+        let Some(pid) = name.strip_prefix("foo-").and_then(|name| name.strip_suffix(suffix)) else {
+            return false;
+        };
+        pid.bytes().all(|b| b.is_ascii_digit())
+    }) else {
+        panic!("no `foo-{{pid}}{suffix}` in {}", profile_dir.display());
+    };
+    path
+}
+
+fn read_json(path: PathBuf) -> Value {
+    let contents = fs::read_to_string(&path).unwrap();
+    serde_json::from_str(&contents).unwrap_or_else(|e| panic!("{}: {e}", path.display()))
+}
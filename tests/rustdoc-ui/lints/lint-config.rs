@@ -0,0 +1,7 @@
+// Checks that `--lint-config` sets the levels of rustdoc's tool lints.
+
+//@ compile-flags: -Zunstable-options --lint-config {{src-base}}/lints/lint-config.toml
+
+/// Links to [`Missing`].
+//~^ ERROR unresolved link to `Missing`
+pub fn broken_link() {}
@@ -0,0 +1 @@
+pub fn undocumented_in_tests() {}
@@ -0,0 +1,6 @@
+// ignore-tidy-linelength
+// Checks the errors for an invalid `--lint-config` file, which point into the file.
+
+//@ compile-flags: -Zunstable-options --lint-config {{src-base}}/lint/lint-config/invalid-lint-config.toml
+
+fn main() {}
@@ -0,0 +1,18 @@
+//! Checks the lint levels of a `--lint-config` file: the levels of the file are applied by
+//! priority, unknown lints point into the file and the overrides apply to the modules whose
+//! file is under their paths.
+
+//@ compile-flags: -Zunstable-options --lint-config {{src-base}}/lint/lint-config/lint-config.toml
+
+#![crate_type = "lib"]
+
+pub fn undocumented() {} //~ ERROR missing documentation for a function
+
+/// `unused_variables` has a higher priority than the `unused` group.
+pub fn unused_variable() {
+    let x = 1;
+}
+
+/// The override allows `missing_docs` in this module.
+#[path = "auxiliary/lint-config-tests.rs"]
+pub mod tests;
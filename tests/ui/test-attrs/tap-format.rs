@@ -0,0 +1,33 @@
+//@ compile-flags: --test
+//@ run-fail
+//@ run-flags: --test-threads=1 -Zunstable-options --format=tap
+//@ check-run-results
+//@ exec-env:RUST_BACKTRACE=0
+//@ normalize-stdout-test "finished in \d+\.\d+s" -> "finished in $$TIME"
+//@ ignore-emscripten no threads support
+//@ needs-unwind
+
+#[test]
+fn a_passes() {}
+
+#[test]
+fn b_fails() {
+    println!("some output\nover two lines");
+    panic!();
+}
+
+#[test]
+#[should_panic]
+fn c_should_panic() {
+    panic!();
+}
+
+#[test]
+#[should_panic(expected = "other")]
+fn d_wrong_panic() {
+    panic!("boom");
+}
+
+#[test]
+#[ignore = "not # now"]
+fn e_ignored() {}
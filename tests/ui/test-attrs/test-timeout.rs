@@ -0,0 +1,38 @@
+//@ no-prefer-dynamic
+//@ compile-flags: --test
+//@ run-flags: --test-threads=1 -Zunstable-options --test-timeout=60
+//@ run-fail
+//@ check-run-results
+//@ normalize-stdout-test "finished in \d+\.\d+s" -> "finished in $$TIME"
+//@ needs-unwind
+
+//@ ignore-wasm no subprocess support
+//@ ignore-emscripten no subprocess support
+//@ ignore-sgx no subprocess support
+
+#![cfg(test)]
+#![feature(test_timeout)]
+
+use std::panic;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+#[test_timeout = "1"]
+fn it_hangs() {
+    loop {
+        thread::sleep(Duration::from_secs(60));
+    }
+}
+
+#[test]
+fn it_works() {
+    assert_eq!(1 + 1, 2);
+}
+
+#[test]
+#[test_timeout = "60"]
+fn it_catches_its_own_panic() {
+    // The child process must not treat a caught panic as a failure.
+    assert!(panic::catch_unwind(|| panic!("caught")).is_err());
+}